@@ -0,0 +1,32 @@
+//! This is an example of a Criterion.rs benchmark of a shader, which requires the `criterion`
+//! feature: `cargo bench --features criterion`.
+
+#[macro_use]
+extern crate criterion;
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use criterion::Criterion;
+use vulkanology::criterion_adapter::bench_dispatch;
+
+const NUM_INVOCATIONS: usize = 640000;
+
+/// Measures the example shader.
+fn bench_example(c: &mut Criterion) {
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS],
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader,
+        repeat_command: execute_shader_n
+    };
+
+    bench_dispatch(c, "example", execute_shader_n);
+}
+
+criterion_group!(benches, bench_example);
+criterion_main!(benches);
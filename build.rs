@@ -1,12 +1,106 @@
 extern crate vulkano_shaders;
 
+use std::env;
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
 use vulkano_shaders::ShaderType;
 
+// The build script cannot depend on `vulkanology`, as it builds `vulkanology` itself, so it
+// includes the build utilities and the reflection which they use as modules.
+#[allow(dead_code)]
+#[path = "src/build_utils.rs"]
+mod build_utils;
+#[allow(dead_code)]
+#[path = "src/reflection.rs"]
+mod reflection;
+
+/// The built-in segments in `segments/` which are tested by `tests/segments.rs`.
+const TESTED_SEGMENTS: &'static [&'static str] = &["debug_log", "hash", "invocation_count", "pcg",
+                                                     "reduce", "scan", "xoroshiro"];
+
+/// Concatenates the test header, the built-in segment `name` and the test main into
+/// `<out_dir>/test_shaders/segment_<name>.comp` and returns the path relative to `out_dir`. The
+/// test headers do not enable `#line` directives, so `build_utils::concatenate_files` is not
+/// used.
+fn concatenate_segment_test(name: &str, out_dir: &Path) -> String {
+    let mut source = String::new();
+    let parts = [format!("tests/shaders/segments/{}_header.comp", name),
+                 format!("segments/{}.comp", name),
+                 format!("tests/shaders/segments/{}_main.comp", name)];
+    for part in &parts {
+        println!("cargo:rerun-if-changed={}", part);
+        File::open(part)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .expect(format!("Failed to read {}", part).as_ref());
+        source.push('\n');
+    }
+
+    let output = format!("test_shaders/segment_{}.comp", name);
+    create_dir_all(out_dir.join("test_shaders")).expect("Failed to create target directory.");
+    File::create(out_dir.join(&output))
+        .and_then(|mut file| file.write_all(source.as_bytes()))
+        .expect(format!("Failed to write {}", output).as_ref());
+    output
+}
+
 fn main() {
+    let out_dir = env::var("OUT_DIR").expect("The OUT_DIR of the build script is not set.");
+    let out_dir = Path::new(&out_dir);
+    let segment_tests = TESTED_SEGMENTS.iter()
+        .map(|name| concatenate_segment_test(name, out_dir))
+        .collect::<Vec<_>>();
+
     let shader_list = [
+        ("tests/shaders/buffer_layouts.comp", ShaderType::Compute),
+        ("tests/shaders/builtins.comp", ShaderType::Compute),
+        ("tests/shaders/cross_device.comp", ShaderType::Compute),
+        ("tests/shaders/descriptor_sets.comp", ShaderType::Compute),
+        ("tests/shaders/double_precision.comp", ShaderType::Compute),
         ("tests/shaders/example.comp", ShaderType::Compute),
+        ("tests/shaders/example_variant.comp", ShaderType::Compute),
+        ("tests/shaders/explicit_bindings.comp", ShaderType::Compute),
+        ("tests/shaders/glsl_types.comp", ShaderType::Compute),
+        ("tests/shaders/half_floats.comp", ShaderType::Compute),
+        ("tests/shaders/images.comp", ShaderType::Compute),
+        ("tests/shaders/multipass_reduce.comp", ShaderType::Compute),
+        ("tests/shaders/multipass_scatter.comp", ShaderType::Compute),
+        ("tests/shaders/optional_buffers.comp", ShaderType::Compute),
+        ("tests/shaders/packed_bytes.comp", ShaderType::Compute),
+        ("tests/shaders/ping_pong.comp", ShaderType::Compute),
+        ("tests/shaders/precision.comp", ShaderType::Compute),
         ("tests/shaders/push_constants.comp", ShaderType::Compute),
-        ("tests/shaders/random.comp", ShaderType::Compute)
+        ("tests/shaders/push_constant_ranges.comp", ShaderType::Compute),
+        ("tests/shaders/push_constant_structs.comp", ShaderType::Compute),
+        ("tests/shaders/random.comp", ShaderType::Compute),
+        ("tests/shaders/sampled_images.comp", ShaderType::Compute),
+        ("tests/shaders/shape_independent.comp", ShaderType::Compute),
+        ("tests/shaders/spec_constants.comp", ShaderType::Compute),
+        ("tests/shaders/special_values.comp", ShaderType::Compute),
+        ("tests/shaders/std430_buffers.comp", ShaderType::Compute),
+        ("tests/shaders/storage_block.comp", ShaderType::Compute),
+        ("tests/shaders/texel_buffers.comp", ShaderType::Compute),
+        ("tests/shaders/uniforms.comp", ShaderType::Compute),
+        ("tests/shaders/workgroup_count.comp", ShaderType::Compute)
     ];
     vulkano_shaders::build_glsl_shaders(shader_list.iter().cloned());
+
+    // `vulkano_shaders` opens the shaders relative to the working directory, and names their
+    // modules by the path as given, so the segment tests are built from within `OUT_DIR`. Their
+    // modules are then at `<OUT_DIR>/shaders/test_shaders/segment_<name>.comp`.
+    let manifest_dir = env::current_dir().expect("Failed to get the working directory.");
+    env::set_current_dir(out_dir).expect("Failed to enter the OUT_DIR.");
+    let segment_list = segment_tests.iter().map(|path| (path.as_str(), ShaderType::Compute));
+    vulkano_shaders::build_glsl_shaders(segment_list);
+    env::set_current_dir(manifest_dir).expect("Failed to leave the OUT_DIR.");
+
+    // Append the reflected buffer layouts and capabilities, which `pipeline!` checks, and turn
+    // the shaders which the tests reference without building them into readable errors.
+    let shaders = shader_list.iter()
+        .map(|&(path, _)| path)
+        .chain(segment_tests.iter().map(|path| path.as_str()))
+        .collect::<Vec<_>>();
+    build_utils::reflect_buffer_layouts(&shaders, out_dir);
+    build_utils::report_missing_shaders("tests", &shaders, out_dir);
 }
@@ -0,0 +1,252 @@
+//! This module compares two kernels which compute the same outputs, e.g. an optimized kernel
+//! against its reference implementation.
+//!
+//! `ab_compare!` creates a pipeline for each of the two shaders from the same parameters, so
+//! both operate on buffers with the same contents, and measures their execution commands like
+//! `benchmark!`. Afterwards, it compares the output buffers of the two pipelines elementwise:
+//!
+//! ```ignore
+//! let comparison = ab_compare!{
+//!     reference: "tests/shaders/example.comp",
+//!     candidate: "tests/shaders/example_variant.comp",
+//!     pipeline: {
+//!         workgroup_count: [100, 100, 1],
+//!         buffers: {
+//!             data: [u32] from input_vec,
+//!             result: [u32; NUM_INVOCATIONS]
+//!         }
+//!     },
+//!     outputs: [result],
+//!     warm_up: 3,
+//!     samples: 10
+//! };
+//! comparison.assert_equal_outputs();
+//! ```
+//!
+//! The inputs must therefore be given in the `buffers` with their contents, e.g. with `from` or
+//! `=`, instead of being written after the pipeline was created. With a fixed `warm_up`, both
+//! kernels are executed `warm_up + samples` times before their outputs are read, so kernels
+//! which update their buffers in place are compared after the same number of executions. A
+//! warm-up until the device is steady, `benchmark::WarmUp::steady()`, may execute them a
+//! different number of times.
+//!
+//! The outputs are compared exactly. Kernels whose floating point results differ by their
+//! rounding, e.g. because they sum in a different order, can be verified against each other
+//! with the [`compare`] module instead.
+//!
+//! [`compare`]: ../compare/index.html
+
+use std::any::Any;
+use std::fmt::{self, Debug};
+
+use benchmark::Stats;
+
+/// The difference between an output buffer of the reference and of the candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The name of the buffer.
+    pub buffer: String,
+    /// The index of the first differing element.
+    pub first: usize,
+    /// The element of the reference at `first`, formatted with `Debug`.
+    pub reference: String,
+    /// The element of the candidate at `first`, formatted with `Debug`.
+    pub candidate: String,
+    /// The number of differing elements, including the elements beyond the end of the shorter
+    /// buffer.
+    pub mismatches: usize,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "`{}` differs in {} elements, first at index {}: {} (reference) != {} \
+                (candidate)",
+               self.buffer,
+               self.mismatches,
+               self.first,
+               self.reference,
+               self.candidate)
+    }
+}
+
+/// Compares the output `candidate` of the buffer `buffer` against the output `reference`.
+/// Returns `None` if they are equal.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::ab::divergence;
+///
+/// assert_eq!(divergence("result", &[1, 2, 3], &[1, 2, 3]), None);
+///
+/// let divergence = divergence("result", &[1, 2, 3, 4], &[1, 5, 3, 6]).unwrap();
+/// assert_eq!(divergence.first, 1);
+/// assert_eq!(divergence.mismatches, 2);
+/// assert_eq!(divergence.to_string(),
+///            "`result` differs in 2 elements, first at index 1: 2 (reference) != 5 (candidate)");
+/// # }
+/// ```
+///
+pub fn divergence<T>(buffer: &str, reference: &[T], candidate: &[T]) -> Option<Divergence>
+    where T: PartialEq + Debug
+{
+    let mismatches = reference.iter()
+        .zip(candidate.iter())
+        .enumerate()
+        .filter(|&(_, (reference, candidate))| reference != candidate)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    let len = reference.len().min(candidate.len());
+    let excess = reference.len().max(candidate.len()) - len;
+    let first = match mismatches.first() {
+        Some(&first) => first,
+        None if excess > 0 => len,
+        None => return None,
+    };
+    let format = |output: &[T]| {
+        output.get(first).map_or_else(|| "nothing".to_owned(), |element| format!("{:?}", element))
+    };
+    Some(Divergence {
+        buffer: buffer.to_owned(),
+        first: first,
+        reference: format(reference),
+        candidate: format(candidate),
+        mismatches: mismatches.len() + excess,
+    })
+}
+
+/// Copies the output `buffer` of the reference, so it can be compared with `compare_output`
+/// once the candidate was executed.
+pub fn record_output<T: Any + Clone>(buffer: &[T]) -> Box<Any> {
+    Box::new(buffer.to_vec())
+}
+
+/// Compares the output `candidate` of the buffer `buffer` against the output which
+/// `record_output` copied from the reference.
+///
+/// # Panics
+///
+/// If the recorded output has another element type.
+pub fn compare_output<T>(buffer: &str, reference: &Any, candidate: &[T]) -> Option<Divergence>
+    where T: Any + PartialEq + Debug
+{
+    match reference.downcast_ref::<Vec<T>>() {
+        Some(reference) => divergence(buffer, reference, candidate),
+        None => panic!("The output `{}` of the reference has a different type.", buffer),
+    }
+}
+
+/// The result of `ab_compare!`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// The path of the reference shader.
+    pub reference_path: String,
+    /// The path of the candidate shader.
+    pub candidate_path: String,
+    /// The statistics of the executions of the reference.
+    pub reference: Stats,
+    /// The statistics of the executions of the candidate.
+    pub candidate: Stats,
+    /// The output buffers in which the candidate differs from the reference.
+    pub divergences: Vec<Divergence>,
+}
+
+impl Comparison {
+    /// Returns how many times faster the candidate is than the reference, given the medians of
+    /// their samples.
+    pub fn speedup(&self) -> f64 {
+        self.reference.median / self.candidate.median
+    }
+
+    /// Asserts that the candidate computed the same outputs as the reference.
+    ///
+    /// # Panics
+    ///
+    /// If an output diverges, with every divergence.
+    pub fn assert_equal_outputs(&self) {
+        if !self.divergences.is_empty() {
+            let divergences = self.divergences
+                .iter()
+                .map(|divergence| divergence.to_string())
+                .collect::<Vec<_>>();
+            panic!("The outputs of `{}` diverge from `{}`:\n{}",
+                   self.candidate_path,
+                   self.reference_path,
+                   divergences.join("\n"));
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "reference `{}`: {}", self.reference_path, self.reference)?;
+        writeln!(f, "candidate `{}`: {}", self.candidate_path, self.candidate)?;
+        write!(f, "speedup {:.2}x, ", self.speedup())?;
+        if self.divergences.is_empty() {
+            write!(f, "the outputs are equal")
+        } else {
+            write!(f, "{} outputs diverge", self.divergences.len())?;
+            for divergence in &self.divergences {
+                write!(f, "\n  {}", divergence)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Compares the kernels `candidate` and `reference`, see the [`ab`] module. The `pipeline`
+/// parameters are passed to [`pipeline!`] for both shaders, together with their `shader_path`
+/// and an `execution_command`. Every execution command is warmed up with `warm_up` like by
+/// `benchmark!` and then measured `samples` times, after which the buffers named in `outputs`
+/// are compared.
+///
+/// Prints the comparison to stdout and evaluates to it as an `ab::Comparison`.
+///
+/// [`ab`]: ab/index.html
+/// [`pipeline!`]: macro.pipeline.html
+///
+#[macro_export]
+macro_rules! ab_compare {
+    {
+        reference: $reference:expr,
+        candidate: $candidate:expr,
+        pipeline: { $( $pipeline_args:tt )* },
+        outputs: [ $( $output:ident ),* $(,)* ],
+        warm_up: $warm_up:expr,
+        samples: $samples:expr $(,)*
+    } => ({
+        use vulkanology::ab::{self, Comparison};
+        use vulkanology::benchmark::{self, Stats};
+
+        let (reference, reference_outputs) = {
+            pipeline!{ shader_path: $reference, $( $pipeline_args )*, execution_command: execute }
+            let samples = benchmark::measure($warm_up, $samples, || execute());
+            let outputs: Vec<_> = vec![ $( ab::record_output(&read_buffer!($output)) ),* ];
+            (Stats::new(&samples, None), outputs)
+        };
+        let (candidate, divergences) = {
+            pipeline!{ shader_path: $candidate, $( $pipeline_args )*, execution_command: execute }
+            let samples = benchmark::measure($warm_up, $samples, || execute());
+            let mut reference_outputs = reference_outputs.iter();
+            let divergences = vec![ $(
+                ab::compare_output(stringify!($output),
+                                   &**reference_outputs.next().unwrap(),
+                                   &read_buffer!($output))
+            ),* ];
+            (Stats::new(&samples, None), divergences)
+        };
+        let comparison = Comparison {
+            reference_path: $reference.to_owned(),
+            candidate_path: $candidate.to_owned(),
+            reference: reference,
+            candidate: candidate,
+            divergences: divergences.into_iter().filter_map(|divergence| divergence).collect(),
+        };
+        println!("ab_compare:\n{}", comparison);
+        comparison
+    });
+}
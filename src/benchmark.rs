@@ -0,0 +1,266 @@
+//! This module measures the execution command of `pipeline!` and summarizes the samples.
+//!
+//! `benchmark!` runs the execution command a number of times without measuring it, to warm up
+//! the caches and clocks of the device, and then measures every further execution:
+//!
+//! ```ignore
+//! pipeline!{
+//!     // ...
+//!     execution_command: execute_shader
+//! };
+//! let stats = benchmark!(execute_shader, warm_up: 10, samples: 100, elements: NUM_INVOCATIONS);
+//! ```
+//!
+//! The first executions are slower, as the driver may still compile the shader and the clocks of
+//! the device ramp up. Instead of a fixed number of runs, the warm-up can run until the device
+//! reached a steady state, in which the last few executions vary by at most a tolerance:
+//!
+//! ```ignore
+//! use vulkanology::benchmark::WarmUp;
+//!
+//! let stats = benchmark!(execute_shader, warm_up: WarmUp::steady(), samples: 100);
+//! ```
+//!
+//! If the executions do not settle within the maximum number of runs, the measurement starts
+//! anyway, noting it on stderr and in the report of the [`harness`].
+//!
+//! The summary is printed to stdout, and the statistics are returned for further checks. As GPU
+//! timestamps cannot be recorded with the version of `vulkano` used by `pipeline!`, a sample is
+//! the CPU time of one execution, which includes the submission and the wait for its fence.
+//! Compare variants of a kernel with workloads large enough to hide this overhead, or measure a
+//! `repeat_command` with Criterion.rs, see the [`criterion_adapter`] module.
+//!
+//! [`criterion_adapter`]: ../criterion_adapter/index.html
+//! [`harness`]: ../harness/index.html
+
+use std::fmt;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use harness;
+
+/// The statistics of the samples of a benchmark, in seconds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stats {
+    /// The number of samples.
+    pub samples: usize,
+    /// The shortest sample.
+    pub min: f64,
+    /// The median of the samples.
+    pub median: f64,
+    /// The mean of the samples.
+    pub mean: f64,
+    /// The standard deviation of the samples.
+    pub stddev: f64,
+    /// The number of processed elements per second, given the median and the number of elements
+    /// of an execution.
+    pub throughput: Option<f64>,
+}
+
+impl Stats {
+    /// Summarizes the samples `samples` of executions which process `elements` elements each.
+    ///
+    /// # Panics
+    ///
+    /// If there are no samples.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use std::time::Duration;
+    /// use vulkanology::benchmark::Stats;
+    ///
+    /// let samples = [3, 1, 2, 6].iter().map(|&s| Duration::from_secs(s)).collect::<Vec<_>>();
+    /// let stats = Stats::new(&samples, Some(1000));
+    /// assert_eq!(stats.samples, 4);
+    /// assert_eq!(stats.min, 1.0);
+    /// assert_eq!(stats.median, 2.5);
+    /// assert_eq!(stats.mean, 3.0);
+    /// assert_eq!(stats.throughput, Some(400.0));
+    /// # }
+    /// ```
+    ///
+    pub fn new(samples: &[Duration], elements: Option<usize>) -> Stats {
+        if samples.is_empty() {
+            panic!("A benchmark needs at least one sample.");
+        }
+        let mut seconds = samples.iter()
+            .map(|sample| sample.as_secs() as f64 + sample.subsec_nanos() as f64 * 1e-9)
+            .collect::<Vec<_>>();
+        seconds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let len = seconds.len();
+        let median = if len % 2 == 0 {
+            (seconds[len / 2 - 1] + seconds[len / 2]) / 2.0
+        } else {
+            seconds[len / 2]
+        };
+        let mean = seconds.iter().sum::<f64>() / len as f64;
+        let variance = seconds.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() /
+                       len as f64;
+        Stats {
+            samples: len,
+            min: seconds[0],
+            median: median,
+            mean: mean,
+            stddev: variance.sqrt(),
+            throughput: elements.map(|elements| elements as f64 / median),
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "{} samples, min {:.3} ms, median {:.3} ms, mean {:.3} ms, stddev {:.3} ms",
+               self.samples,
+               self.min * 1e3,
+               self.median * 1e3,
+               self.mean * 1e3,
+               self.stddev * 1e3)?;
+        if let Some(throughput) = self.throughput {
+            write!(f, ", {:.2} Melements/s", throughput * 1e-6)?;
+        }
+        Ok(())
+    }
+}
+
+/// The executions which precede the measured ones.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WarmUp {
+    /// A fixed number of executions.
+    Runs(usize),
+    /// Executions until the last `window` of them are steady, see `is_steady`, but at most
+    /// `max_runs`.
+    Steady {
+        /// The number of executions whose variation is checked.
+        window: usize,
+        /// The largest coefficient of variation of a steady window.
+        tolerance: f64,
+        /// The largest number of executions.
+        max_runs: usize,
+    },
+}
+
+impl WarmUp {
+    /// Returns a warm-up until the last 5 executions vary by at most 5 %, but for at most 100
+    /// executions.
+    pub fn steady() -> WarmUp {
+        WarmUp::Steady {
+            window: 5,
+            tolerance: 0.05,
+            max_runs: 100,
+        }
+    }
+}
+
+impl From<usize> for WarmUp {
+    fn from(runs: usize) -> WarmUp {
+        WarmUp::Runs(runs)
+    }
+}
+
+/// Returns whether the durations `window` are steady, i.e. whether their standard deviation is
+/// at most `tolerance` times their mean.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::time::Duration;
+/// use vulkanology::benchmark::is_steady;
+///
+/// let ramping = [40, 20, 11, 10].iter().map(|&ms| Duration::from_millis(ms)).collect::<Vec<_>>();
+/// let steady = [10, 10, 11, 10].iter().map(|&ms| Duration::from_millis(ms)).collect::<Vec<_>>();
+/// assert!(!is_steady(&ramping, 0.05));
+/// assert!(is_steady(&steady, 0.05));
+/// # }
+/// ```
+///
+pub fn is_steady(window: &[Duration], tolerance: f64) -> bool {
+    if window.is_empty() {
+        return false;
+    }
+    let stats = Stats::new(window, None);
+    stats.stddev <= tolerance * stats.mean
+}
+
+/// Calls `exec` for the warm-up `warm_up` and returns the number of calls.
+pub fn warm_up<F: FnMut()>(warm_up: WarmUp, exec: &mut F) -> usize {
+    match warm_up {
+        WarmUp::Runs(runs) => {
+            for _ in 0..runs {
+                exec();
+            }
+            runs
+        }
+        WarmUp::Steady { window, tolerance, max_runs } => {
+            let mut durations = Vec::new();
+            while durations.len() < max_runs {
+                let start = Instant::now();
+                exec();
+                durations.push(start.elapsed());
+                if durations.len() >= window &&
+                   is_steady(&durations[durations.len() - window..], tolerance) {
+                    return durations.len();
+                }
+            }
+            let note = format!("The benchmark did not reach a steady state within {} runs.",
+                               max_runs);
+            let _ = writeln!(io::stderr(), "note: {}", note);
+            harness::record_note(note);
+            max_runs
+        }
+    }
+}
+
+/// Calls `exec` for the warm-up `warm_up`, a number of runs or a `WarmUp`, and then measures
+/// `samples` further calls. Returns the measured durations.
+pub fn measure<W, F>(warm_up: W, samples: usize, mut exec: F) -> Vec<Duration>
+    where W: Into<WarmUp>,
+          F: FnMut()
+{
+    self::warm_up(warm_up.into(), &mut exec);
+    (0..samples)
+        .map(|_| {
+            let start = Instant::now();
+            exec();
+            start.elapsed()
+        })
+        .collect()
+}
+
+/// Measures the execution command `$exec_cmd` of `pipeline!`, see the [`benchmark`] module.
+/// It is executed `warm_up` times, or until it is steady with `warm_up: WarmUp::steady()`, and
+/// then measured `samples` times. Given `elements`, the
+/// number of elements processed by one execution, the throughput is reported as well.
+///
+/// Prints the statistics to stdout and evaluates to them as a `benchmark::Stats`.
+///
+/// [`benchmark`]: benchmark/index.html
+///
+#[macro_export]
+macro_rules! benchmark {
+    ($exec_cmd:ident, warm_up: $warm_up:expr, samples: $samples:expr) => {
+        benchmark!(__run__ $exec_cmd, $warm_up, $samples, None)
+    };
+    ($exec_cmd:ident,
+     warm_up: $warm_up:expr,
+     samples: $samples:expr,
+     elements: $elements:expr) => {
+        benchmark!(__run__ $exec_cmd, $warm_up, $samples, Some($elements as usize))
+    };
+    (__run__ $exec_cmd:ident, $warm_up:expr, $samples:expr, $elements:expr) => ({
+        use vulkanology::benchmark::{self, Stats};
+
+        let samples = benchmark::measure($warm_up, $samples, || $exec_cmd());
+        let stats = Stats::new(&samples, $elements);
+        println!("benchmark `{}`: {}", stringify!($exec_cmd), stats);
+        stats
+    });
+}
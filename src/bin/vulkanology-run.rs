@@ -0,0 +1,80 @@
+//! Runs a compute shader with the buffers of a JSON description and reports the results, see the
+//! `runner` module of `vulkanology`. With `--watch`, the shader is run again whenever it changes.
+
+extern crate vulkanology;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use vulkanology::runner::{load_shader, Description, Runner};
+
+const USAGE: &'static str = "usage: vulkanology-run <shader> <description.json> [--watch]";
+
+/// The number of mismatches which are printed.
+const MAX_MISMATCHES: usize = 16;
+
+/// The interval in which the shader is checked for changes in watch mode.
+const POLL_INTERVAL_MS: u64 = 250;
+
+/// Runs the shader and prints the mismatches. Returns whether all elements match.
+fn run(runner: &Runner, shader: &str) -> bool {
+    runner.run(shader, &load_shader(Path::new(shader)));
+    let mismatches = runner.report();
+    for mismatch in mismatches.iter().take(MAX_MISMATCHES) {
+        let _ = writeln!(io::stderr(), "mismatch: {}", mismatch);
+    }
+    if mismatches.len() > MAX_MISMATCHES {
+        let _ = writeln!(io::stderr(),
+                         "... and {} more mismatches",
+                         mismatches.len() - MAX_MISMATCHES);
+    }
+    mismatches.is_empty()
+}
+
+/// Returns the modification time of the file `path`, or `None` while it cannot be read, e.g.
+/// while an editor replaces it.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Keeps the device and the buffers of `runner`, and runs the shader whenever it changes. A
+/// shader which fails to compile or to run is reported by the panic hook.
+fn watch(runner: &Runner, shader: &str) -> ! {
+    let mut last_modified = None;
+    loop {
+        let current = modified(Path::new(shader));
+        if current.is_some() && current != last_modified {
+            last_modified = current;
+            if let Ok(true) = panic::catch_unwind(AssertUnwindSafe(|| run(runner, shader))) {
+                println!("All expected elements match.");
+            }
+            println!("Waiting for changes of `{}`...", shader);
+        }
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let watch_mode = args.iter().any(|arg| arg == "--watch");
+    args.retain(|arg| arg != "--watch");
+    if args.len() != 2 {
+        let _ = writeln!(io::stderr(), "{}", USAGE);
+        process::exit(2);
+    }
+    let (shader, description) = (&args[0], Path::new(&args[1]));
+
+    let runner = Runner::new(Description::load(description));
+    if watch_mode {
+        watch(&runner, shader);
+    }
+    if !run(&runner, shader) {
+        process::exit(1);
+    }
+}
@@ -1,10 +1,127 @@
 //! This module exports shader building tools which simplify the test shader building process.
+//!
+//! Relative paths passed to these tools are resolved with [`resolve_path`], i.e. against the
+//! directory of the crate manifest rather than the directory the build was invoked from, or
+//! against the shader root given with `VULKANOLOGY_SHADER_ROOT`. The shader root only applies to
+//! these tools: the shaders given to `vulkano_shaders` and the `shader_path` of `pipeline!` are
+//! always relative to the crate manifest.
+//!
+//! [`resolve_path`]: fn.resolve_path.html
 
-use std::path::Path;
+use std::env;
+use std::path::{Path, PathBuf};
 use std::io::{Read, Write};
-use std::fs::File;
+use std::fs::{File, OpenOptions, read_dir};
 use std::fs::create_dir_all;
 
+use reflection::{self, BufferLayout};
+
+/// The environment variable which overrides the root directory of relative shader paths.
+pub const SHADER_ROOT_VAR: &'static str = "VULKANOLOGY_SHADER_ROOT";
+
+/// Resolves a relative path against the shader root directory.
+///
+/// The shader root is the value of the `VULKANOLOGY_SHADER_ROOT` environment variable if it is
+/// set, otherwise `CARGO_MANIFEST_DIR`. Thus paths work identically in workspaces and no matter
+/// which directory cargo was invoked from. If neither variable is set, or `path` is absolute,
+/// `path` is returned unchanged.
+///
+/// Note that `vulkano_shaders` opens shaders relative to the working directory of the build
+/// script, which cargo sets to `CARGO_MANIFEST_DIR`, and that it uses the path as given to name
+/// the generated module. The override therefore only affects the utilities of this module.
+///
+/// The other functions of this module tell cargo to rerun the build script when the override
+/// changes, once per call. This function does not, so it can be called for every path.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use std::path::Path;
+/// use vulkanology::build_utils::{resolve_path, SHADER_ROOT_VAR};
+///
+/// env::set_var(SHADER_ROOT_VAR, "/some/crate");
+/// assert_eq!(resolve_path("tests/shaders/example.comp"),
+///            Path::new("/some/crate/tests/shaders/example.comp"));
+/// assert_eq!(resolve_path("/absolute/example.comp"), Path::new("/absolute/example.comp"));
+/// # }
+/// ```
+///
+pub fn resolve_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+
+    match env::var_os(SHADER_ROOT_VAR).or_else(|| env::var_os("CARGO_MANIFEST_DIR")) {
+        Some(root) => Path::new(&root).join(path),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Reruns the build script if the shader root of `resolve_path` changes.
+fn rerun_if_shader_root_changed() {
+    println!("cargo:rerun-if-env-changed={}", SHADER_ROOT_VAR);
+}
+
+/// The directory containing the GLSL segments shipped with this crate.
+pub const SEGMENT_DIR: &'static str = concat!(env!("CARGO_MANIFEST_DIR"), "/segments");
+
+/// Returns the path of the GLSL segment `name` shipped with this crate, which can be passed to
+/// [`concatenate_files`] or `gen_simple_test_shader!` like the segments of the user.
+///
+/// The available segments are:
+///
+/// * `atomic_float`: enables `GL_EXT_shader_atomic_float`, see the `compare` module for
+/// verifying the results.
+/// * `debug_log`: `debug_log(tag, values)`, which logs tagged values into a buffer at
+/// `DEBUG_LOG_SET` and `DEBUG_LOG_BINDING`, see the `debug_log` module.
+/// * `hash`: the integer hashes `pcg_hash`, `wang_hash` and `murmur3_fmix32`.
+/// * `invocation_count`: `count_invocation()`, which counts the invocations of a dispatch in a
+/// buffer at `INVOCATION_COUNT_SET` and `INVOCATION_COUNT_BINDING`, see the `invocations` module.
+/// * `pcg`: the PCG32 PRNG `pcg32_next`, which requires `GL_ARB_gpu_shader_int64`.
+/// * `reduce`: the workgroup sum `workgroup_reduce_add`, which requires `WORKGROUP_SIZE` to be
+/// defined as the number of invocations of a workgroup.
+/// * `scan`: the workgroup prefix sums `workgroup_inclusive_scan_add` and
+/// `workgroup_exclusive_scan_add`, which require `WORKGROUP_SIZE` like `reduce`. See the `scan`
+/// module for verifying the results.
+/// * `shader_clock`: timing helpers based on `GL_EXT_shader_realtime_clock`, see the
+/// `shader_clock` module.
+/// * `xoroshiro`: the xoroshiro128+ PRNG `xoroshiro128plus_next`, which requires
+/// `GL_ARB_gpu_shader_int64`.
+///
+/// Each of these segments, except for `atomic_float` and `shader_clock`, is tested against a Rust
+/// implementation by `tests/segments.rs`.
+///
+/// # Panics
+///
+/// If there is no segment called `name`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::build_utils::builtin_segment;
+///
+/// assert!(builtin_segment("shader_clock").ends_with("segments/shader_clock.comp"));
+/// # }
+/// ```
+///
+/// [`concatenate_files`]: fn.concatenate_files.html
+///
+pub fn builtin_segment(name: &str) -> PathBuf {
+    let path = Path::new(SEGMENT_DIR).join(name).with_extension("comp");
+    if !path.is_file() {
+        panic!("There is no built-in segment called `{}` in {}.", name, SEGMENT_DIR);
+    }
+    path
+}
+
 /// Concatenates GLSL source files inserting `#line` statements where necessary.
 ///
 /// # Motivation
@@ -112,7 +229,8 @@ pub fn concatenate_files<PI, PO>(file_names: &[PI], write_to: PO)
     let line_pragma = b"#line 1 \"";
     let quotes = b"\"\n";
 
-    let write_to = write_to.as_ref();
+    rerun_if_shader_root_changed();
+    let write_to = &resolve_path(write_to);
     let target_dir = write_to.parent().unwrap();
     create_dir_all(target_dir).expect("Failed to create target directory.");
     let mut file_out = File::create(write_to)
@@ -120,6 +238,7 @@ pub fn concatenate_files<PI, PO>(file_names: &[PI], write_to: PO)
     let mut file_names_iter = file_names.iter();
 
     fn append_file(file_out: &mut File, file_name: &Path) {
+        let file_name = &resolve_path(file_name);
         let mut file_in = match File::open(file_name) {
             Ok(file) => file,
             Err(err) => {
@@ -151,6 +270,222 @@ pub fn concatenate_files<PI, PO>(file_names: &[PI], write_to: PO)
     }
 }
 
+/// Turns references to unbuilt shaders into readable compile errors.
+///
+/// Scans all `.rs` files in `test_dir` (recursively) for shaders given as macro parameters, i.e.
+/// for relative `.comp` paths in string literals which follow a `:`, like `shader_path: "<path>"`
+/// or the `reference` and `candidate` of `ab_compare!`. For every referenced path which is not
+/// contained in `registered`, a shader module stub is written to `<out_dir>/shaders/<path>`, the
+/// location where `pipeline!` expects the compiled shader. The stub only contains a
+/// `compile_error!` naming the path and the expected build script entry. Without it the test
+/// would fail with an obscure `include!` error deep inside the `pipeline!` expansion.
+///
+/// Call this function in the build script after building the registered shaders, passing the
+/// same list of paths.
+///
+/// # Panics
+///
+/// * If `test_dir` cannot be read.
+/// * If a stub cannot be written.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::fs::{create_dir_all, File};
+/// use std::io::{Read, Write};
+/// use std::path::Path;
+/// use vulkanology::build_utils::report_missing_shaders;
+///
+/// // Working directory with a test which references a shader unknown to the build script.
+/// let target = Path::new("target/report_missing_shaders");
+/// let test_dir = target.join("tests");
+/// let out_dir = target.join("out");
+/// create_dir_all(&test_dir).unwrap();
+/// File::create(test_dir.join("some_test.rs"))
+///     .unwrap()
+///     .write_all(b"pipeline!{\n    shader_path :\n        \"tests/shaders/unknown.comp\",\n}")
+///     .unwrap();
+///
+/// // Only `tests/shaders/example.comp` was built.
+/// report_missing_shaders(&test_dir, &["tests/shaders/example.comp"], &out_dir);
+///
+/// // The stub will fail the compilation with a readable message.
+/// let mut stub = String::new();
+/// File::open(out_dir.join("shaders/tests/shaders/unknown.comp"))
+///     .unwrap()
+///     .read_to_string(&mut stub)
+///     .unwrap();
+/// assert!(stub.starts_with("compile_error!"));
+/// assert!(stub.contains("tests/shaders/unknown.comp"));
+/// # }
+/// ```
+///
+pub fn report_missing_shaders<PT, PO>(test_dir: PT, registered: &[&str], out_dir: PO)
+    where PT: AsRef<Path>,
+          PO: AsRef<Path>
+{
+    fn collect_shader_paths(dir: &Path, shader_paths: &mut Vec<String>) {
+        // Rerun the build script if a test is added or changed.
+        println!("cargo:rerun-if-changed={}", dir.display());
+        let entries = read_dir(dir)
+            .expect(format!("Failed to read test directory: {}", dir.display()).as_ref());
+        for entry in entries {
+            let path = entry.expect("Failed to read directory entry.").path();
+            if path.is_dir() {
+                collect_shader_paths(&path, shader_paths);
+            } else if path.extension().map_or(false, |extension| extension == "rs") {
+                println!("cargo:rerun-if-changed={}", path.display());
+                let mut source = String::new();
+                File::open(&path)
+                    .and_then(|mut file| file.read_to_string(&mut source))
+                    .expect(format!("Failed to read test file: {}", path.display()).as_ref());
+
+                // Extract the shader paths in the string literals following a `:`.
+                for (position, _) in source.match_indices(':') {
+                    let rest = &source[position + 1..];
+                    let rest = match rest.find(|c: char| !c.is_whitespace()) {
+                        Some(start) if rest[start..].starts_with('"') => &rest[start + 1..],
+                        _ => continue,
+                    };
+                    if let Some(end) = rest.find('"') {
+                        let literal = &rest[..end];
+                        if literal.ends_with(".comp") && Path::new(literal).is_relative() {
+                            shader_paths.push(literal.to_owned());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    rerun_if_shader_root_changed();
+    let mut shader_paths = Vec::new();
+    collect_shader_paths(&resolve_path(test_dir), &mut shader_paths);
+
+    for shader_path in shader_paths {
+        if registered.contains(&shader_path.as_ref()) {
+            continue;
+        }
+
+        let reason = if resolve_path(&shader_path).exists() {
+            "is not built by the build script"
+        } else {
+            "does not exist"
+        };
+        let message = format!("The shader `{0}` {1}. Make sure the file exists and add \
+                               `(\"{0}\", ShaderType::Compute)` to the shader list in `build.rs`.",
+                              shader_path,
+                              reason);
+
+        let stub_path = resolve_path(&out_dir).join("shaders").join(&shader_path);
+        create_dir_all(stub_path.parent().unwrap()).expect("Failed to create stub directory.");
+        let mut stub = File::create(&stub_path)
+            .expect(format!("Failed to open stub file: {}", stub_path.display()).as_ref());
+        write!(stub, "compile_error!({:?});\n", message).expect("Failed to write stub.");
+    }
+}
+
+/// Appends the reflected buffer layouts to the shader modules generated by `vulkano_shaders`.
+///
+/// For every path in `shaders`, the SPIR-V code embedded in `<out_dir>/shaders/<path>` is
+/// reflected and a `BUFFER_LAYOUTS` and a `CAPABILITIES` constant are appended to the module.
+/// `pipeline!` uses the constants to check the element types of the declared buffers and the
+/// capabilities of the shader at pipeline setup, see the [`reflection`] module.
+///
+/// Call this function in the build script after building the shaders, passing the same list of
+/// paths.
+///
+/// # Panics
+///
+/// * If a shader module cannot be read or written.
+/// * If a shader module does not contain SPIR-V code.
+///
+/// [`reflection`]: ../reflection/index.html
+///
+pub fn reflect_buffer_layouts<PO: AsRef<Path>>(shaders: &[&str], out_dir: PO) {
+    rerun_if_shader_root_changed();
+    for shader_path in shaders {
+        let module_path = resolve_path(&out_dir).join("shaders").join(shader_path);
+        let mut module = String::new();
+        File::open(&module_path)
+            .and_then(|mut file| file.read_to_string(&mut module))
+            .expect(format!("Failed to read shader module: {}", module_path.display()).as_ref());
+        if module.contains("BUFFER_LAYOUTS") {
+            continue;
+        }
+
+        // `vulkano_shaders` embeds the SPIR-V code as `let data = [<bytes>];`.
+        let start = module.find("let data = [")
+            .map(|start| start + "let data = [".len())
+            .expect(format!("No SPIR-V code found in: {}", module_path.display()).as_ref());
+        let end = start + module[start..].find(']').unwrap();
+        let bytes = module[start..end]
+            .split(',')
+            .map(|byte| byte.trim().parse::<u8>().expect("Invalid SPIR-V byte."))
+            .collect::<Vec<_>>();
+        let words = bytes.chunks(4)
+            .map(|word| word.iter().rev().fold(0, |word, &byte| word << 8 | byte as u32))
+            .collect::<Vec<u32>>();
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&module_path)
+            .expect(format!("Failed to open shader module: {}", module_path.display()).as_ref());
+        write!(file,
+               "\n/// The buffer layouts of the shader, see `vulkanology::reflection`.\n\
+                #[allow(dead_code)]\n\
+                pub const BUFFER_LAYOUTS: &'static [::vulkanology::reflection::BufferLayout] = \
+                &[{}];\n",
+               reflection::buffer_layouts(&words)
+                   .iter()
+                   .map(buffer_layout_source)
+                   .collect::<Vec<_>>()
+                   .join(", "))
+            .expect("Failed to write buffer layouts.");
+        write!(file,
+               "\n/// The SPIR-V capabilities of the shader, see `vulkanology::reflection`.\n\
+                #[allow(dead_code)]\n\
+                pub const CAPABILITIES: &'static [u32] = &{:?};\n",
+               reflection::capabilities(&words))
+            .expect("Failed to write capabilities.");
+    }
+}
+
+/// Returns the Rust source of a constant `BufferLayout` expression.
+fn buffer_layout_source(layout: &BufferLayout) -> String {
+    let element = match layout.element {
+        Some(ref element) => {
+            let fields = element.fields
+                .iter()
+                .map(|field| {
+                    format!("::vulkanology::reflection::FieldLayout {{ name: \
+                             ::std::borrow::Cow::Borrowed({:?}), offset: {}, size: {:?} }}",
+                            field.name,
+                            field.offset,
+                            field.size)
+                })
+                .collect::<Vec<_>>();
+            format!("Some(::vulkanology::reflection::ElementLayout {{ member: \
+                     ::std::borrow::Cow::Borrowed({:?}), stride: {}, fields: \
+                     ::std::borrow::Cow::Borrowed(&[{}]) }})",
+                    element.member,
+                    element.stride,
+                    fields.join(", "))
+        }
+        None => "None".to_owned(),
+    };
+    format!("::vulkanology::reflection::BufferLayout {{ set: {}, binding: {}, block: \
+             ::std::borrow::Cow::Borrowed({:?}), size: {:?}, element: {} }}",
+            layout.set,
+            layout.binding,
+            layout.block,
+            layout.size,
+            element)
+}
+
 /// A simple macro for generating the build code which concatenates the correct test shader
 /// segments.
 ///
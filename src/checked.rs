@@ -0,0 +1,329 @@
+//! This module contains checked conversions for the parameters of the generated code.
+//!
+//! Vulkan takes workgroup counts as `u32` and limits the range of a storage buffer descriptor to
+//! `u32::MAX` bytes. Instead of silently dispatching a wrapped-around size, the conversions panic
+//! with the offending value and the name of the parameter. The specialization constants are
+//! checked in the same way before they are passed to the pipeline, and the size of the push
+//! constants against the limit of the device.
+
+use std::fmt::Display;
+use std::mem;
+use std::ops::Range;
+
+/// An integer which can be converted into a `u32` if it is in range.
+pub trait CheckedU32: Copy + Display {
+    /// Returns the value as a `u32`, or `None` if it is out of range.
+    fn checked_u32(self) -> Option<u32>;
+}
+
+macro_rules! impl_checked_u32_unsigned {
+    ($( $ty:ty ),*) => {
+        $(
+            impl CheckedU32 for $ty {
+                fn checked_u32(self) -> Option<u32> {
+                    if self as u64 <= u32::max_value() as u64 {
+                        Some(self as u32)
+                    } else {
+                        None
+                    }
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! impl_checked_u32_signed {
+    ($( $ty:ty ),*) => {
+        $(
+            impl CheckedU32 for $ty {
+                fn checked_u32(self) -> Option<u32> {
+                    if self >= 0 && self as u64 <= u32::max_value() as u64 {
+                        Some(self as u32)
+                    } else {
+                        None
+                    }
+                }
+            }
+        )*
+    }
+}
+
+impl_checked_u32_unsigned!(u8, u16, u32, u64, usize);
+impl_checked_u32_signed!(i8, i16, i32, i64, isize);
+
+/// Converts the parameter `name` into a `u32`.
+///
+/// # Panics
+///
+/// If `value` is negative or larger than `u32::MAX`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::to_u32;
+///
+/// let count: usize = 640000;
+/// assert_eq!(to_u32("count", count), 640000u32);
+/// # }
+/// ```
+///
+pub fn to_u32<T: CheckedU32>(name: &str, value: T) -> u32 {
+    match value.checked_u32() {
+        Some(value) => value,
+        None => panic!("The value of `{}` ({}) does not fit into a u32.", name, value),
+    }
+}
+
+/// Converts a workgroup count of any integer type into the `[u32; 3]` taken by Vulkan.
+///
+/// # Panics
+///
+/// If one of the dimensions does not fit into a `u32`.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::workgroup_count;
+///
+/// let num_invocations: u64 = 1 << 40;
+/// workgroup_count([num_invocations / 64, 1, 1]);
+/// # }
+/// ```
+///
+pub fn workgroup_count<T: CheckedU32>(count: [T; 3]) -> [u32; 3] {
+    [to_u32("workgroup_count[0]", count[0]),
+     to_u32("workgroup_count[1]", count[1]),
+     to_u32("workgroup_count[2]", count[2])]
+}
+
+/// Returns the buffer length `len`, given by the expression `name`, after checking that a buffer
+/// of `len` elements of type `T` can be bound as a storage buffer.
+///
+/// # Panics
+///
+/// If the size of the buffer in bytes exceeds `u32::MAX`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::buffer_len;
+///
+/// assert_eq!(buffer_len::<u32>("len", 1024), 1024);
+/// # }
+/// ```
+///
+pub fn buffer_len<T>(name: &str, len: usize) -> usize {
+    let element_size = mem::size_of::<T>();
+    match len.checked_mul(element_size) {
+        Some(size) if size as u64 <= u32::max_value() as u64 => len,
+        _ => {
+            panic!("The buffer length `{}` ({}) exceeds the maximum of {} elements of {} bytes.",
+                   name,
+                   len,
+                   u32::max_value() as usize / element_size,
+                   element_size)
+        }
+    }
+}
+
+/// Returns the range of elements of the buffer `name` which is bound to the shader, starting at
+/// `offset` and spanning `len` elements, or the rest of the buffer if `len` is `None`. The
+/// buffer has `buf_len` elements of `element_size` bytes, and `alignment` is the
+/// `minStorageBufferOffsetAlignment` of the device in bytes.
+///
+/// # Panics
+///
+/// * If the range exceeds the buffer.
+/// * If the offset in bytes is not a multiple of `alignment`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::buffer_range;
+///
+/// assert_eq!(buffer_range("view", 64, Some(32), 1024, 4, 256), 64..96);
+/// assert_eq!(buffer_range("view", 64, None, 1024, 4, 256), 64..1024);
+/// # }
+/// ```
+///
+/// An offset of 16 elements of 4 bytes is not a multiple of 256 bytes:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::buffer_range;
+///
+/// buffer_range("view", 16, None, 1024, 4, 256);
+/// # }
+/// ```
+///
+pub fn buffer_range(name: &str,
+                    offset: usize,
+                    len: Option<usize>,
+                    buf_len: usize,
+                    element_size: usize,
+                    alignment: u64)
+                    -> Range<usize> {
+    let end = len.map_or(Some(buf_len), |len| offset.checked_add(len));
+    let end = match end {
+        Some(end) if offset <= end && end <= buf_len => end,
+        _ => {
+            panic!("The bound range of the buffer `{}` (offset {}, length {:?}) exceeds its {} \
+                    elements.",
+                   name,
+                   offset,
+                   len,
+                   buf_len)
+        }
+    };
+    let byte_offset = offset as u64 * element_size as u64;
+    if alignment != 0 && byte_offset % alignment != 0 {
+        panic!("The bound range of the buffer `{}` starts at byte {}, which is not a multiple of \
+                the minimum storage buffer offset alignment of {} bytes.",
+               name,
+               byte_offset,
+               alignment);
+    }
+    offset..end
+}
+
+/// Checks that the initializer `name` of a buffer of `len` elements has `data_len` elements.
+///
+/// # Panics
+///
+/// If the lengths differ.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::initializer_len;
+///
+/// initializer_len("input_vec", 4, 4);
+/// # }
+/// ```
+///
+/// An initializer of 3 elements does not fill a buffer of 4:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::initializer_len;
+///
+/// initializer_len("input_vec", 4, 3);
+/// # }
+/// ```
+///
+pub fn initializer_len(name: &str, len: usize, data_len: usize) {
+    if len != data_len {
+        panic!("The initializer `{}` has {} elements, but the buffer has {}.",
+               name,
+               data_len,
+               len);
+    }
+}
+
+/// Checks the `(name, constant_id, size)` triples of the specialization constants of a pipeline.
+///
+/// # Panics
+///
+/// * If two constants share a `constant_id`.
+/// * If a constant is neither 4 bytes (`bool`, `int`, `uint`, `float`) nor 8 bytes (`double`,
+///   `int64_t`, `uint64_t`) large. Note that a GLSL `bool` is given as a `u32`.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::spec_constants;
+///
+/// spec_constants(&[("size", 0, 4), ("scale", 1, 8)]);
+/// # }
+/// ```
+///
+/// A `u8` is not a valid specialization constant:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::spec_constants;
+///
+/// spec_constants(&[("size", 0, 4), ("enabled", 1, 1)]);
+/// # }
+/// ```
+///
+pub fn spec_constants(constants: &[(&str, u32, usize)]) {
+    for (index, &(name, id, size)) in constants.iter().enumerate() {
+        if let Some(&(other_name, _, _)) = constants[..index].iter().find(|other| other.1 == id) {
+            panic!("The specialization constants `{}` and `{}` both have the constant_id {}.",
+                   other_name,
+                   name,
+                   id);
+        }
+        if size != 4 && size != 8 {
+            panic!("The specialization constant `{}` has {} bytes, but specialization constants \
+                    have 4 or 8 bytes.",
+                   name,
+                   size);
+        }
+    }
+}
+
+/// Checks that the push constant block of a pipeline, which takes `size` bytes, fits into the
+/// limit `max_size` of the device, i.e. its `maxPushConstantsSize`.
+///
+/// # Panics
+///
+/// If the block is larger than the limit.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::push_constants_size;
+///
+/// push_constants_size(128, 128);
+/// # }
+/// ```
+///
+/// A block of 160 bytes does not fit into the minimum limit of 128 bytes:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::checked::push_constants_size;
+///
+/// push_constants_size(160, 128);
+/// # }
+/// ```
+///
+pub fn push_constants_size(size: usize, max_size: u32) {
+    if size > max_size as usize {
+        panic!("The push constants take {} bytes, but the device supports at most {} bytes \
+                (`maxPushConstantsSize`).",
+               size,
+               max_size);
+    }
+}
@@ -0,0 +1,541 @@
+//! This module contains comparisons of floating point results which are only reproducible within
+//! a tolerance.
+//!
+//! This is the case for float atomics (`GL_EXT_shader_atomic_float`), whose results depend on the
+//! order in which the invocations happen to execute, and in general for results computed by
+//! different devices. The tolerances are adjusted by the [`quirks`] of the device.
+//!
+//! Exact results are compared with `assert_buffers_eq!`, which reports the first differing
+//! elements with their neighbours instead of both buffers. `assert_buffers_approx_eq!` reports
+//! float buffers in the same way, given a tolerance in ULP, or as an absolute or relative error,
+//! or by a profile of the [`tolerances`] module, which gives the tolerance per device.
+//!
+//! [`quirks`]: ../quirks/index.html
+//! [`tolerances`]: ../tolerances/index.html
+
+use std::cmp;
+use std::f32;
+use std::fmt::{self, Debug};
+
+use quirks;
+
+/// Returns whether `a` and `b` differ by at most `tolerance` relative to the larger magnitude of
+/// the two, or absolutely, if both magnitudes are below 1.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::approx_eq;
+///
+/// assert!(approx_eq(1000.0, 1000.1, 1e-3));
+/// assert!(!approx_eq(0.001, 0.002, 1e-4));
+/// # }
+/// ```
+///
+pub fn approx_eq(a: f32, b: f32, tolerance: f32) -> bool {
+    if a == b {
+        return true;
+    }
+    let magnitude = a.abs().max(b.abs()).max(1.0);
+    (a - b).abs() <= tolerance * magnitude
+}
+
+/// Compares `actual` elementwise against `expected` with `approx_eq`.
+///
+/// # Panics
+///
+/// If the lengths differ, or if an element does not match. The message names the first
+/// mismatching index and the number of mismatches.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::assert_approx_eq;
+///
+/// assert_approx_eq(&[1.0, 2.0, 3.0], &[1.0, 2.0000002, 3.0], 1e-6);
+/// # }
+/// ```
+///
+pub fn assert_approx_eq(expected: &[f32], actual: &[f32], tolerance: f32) {
+    if let Some(tolerance) = quirks::tolerance("assert_approx_eq", tolerance) {
+        check_approx_eq(expected, actual, tolerance);
+    }
+}
+
+/// Compares `actual` elementwise against `expected` without applying quirks.
+fn check_approx_eq(expected: &[f32], actual: &[f32], tolerance: f32) {
+    if expected.len() != actual.len() {
+        panic!("The result has {} elements, expected {}.",
+               actual.len(),
+               expected.len());
+    }
+    let mut mismatches = expected.iter()
+        .zip(actual)
+        .enumerate()
+        .filter(|&(_, (&expected, &actual))| !approx_eq(expected, actual, tolerance));
+    if let Some((index, (expected, actual))) = mismatches.next() {
+        panic!("The result differs at index {}: {} != {} (tolerance {}), {} mismatches in total.",
+               index,
+               actual,
+               expected,
+               tolerance,
+               mismatches.count() + 1);
+    }
+}
+
+/// Compares `actual` against `expected` with `assert_approx_eq`, ignoring the order of the
+/// elements, e.g. for results appended to a buffer by an atomic counter.
+///
+/// # Panics
+///
+/// If there is no order in which the elements match.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::assert_unordered_approx_eq;
+///
+/// assert_unordered_approx_eq(&[1.0, 2.0, 3.0], &[3.0, 1.0, 2.0], 0.0);
+/// # }
+/// ```
+///
+pub fn assert_unordered_approx_eq(expected: &[f32], actual: &[f32], tolerance: f32) {
+    let mut expected = expected.to_vec();
+    let mut actual = actual.to_vec();
+    sort(&mut expected);
+    sort(&mut actual);
+    if let Some(tolerance) = quirks::tolerance("assert_unordered_approx_eq", tolerance) {
+        check_approx_eq(&expected, &actual, tolerance);
+    }
+}
+
+/// Checks the result of accumulating `contributions` with float atomics, e.g. `atomicAdd`.
+///
+/// The invocations may add their contributions in any order, so the result is only accurate up
+/// to the rounding error of summing them in the worst order, which is
+/// `(n - 1) * f32::EPSILON * sum(|contribution|)`.
+///
+/// # Panics
+///
+/// If `actual` is outside of the error bound of the exact sum.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::assert_atomic_sum;
+///
+/// let contributions = (0..1000).map(|i| i as f32 * 0.1).collect::<Vec<_>>();
+/// let reversed = contributions.iter().rev().fold(0.0, |sum, x| sum + x);
+/// assert_atomic_sum(&contributions, reversed);
+/// # }
+/// ```
+///
+pub fn assert_atomic_sum(contributions: &[f32], actual: f32) {
+    let exact = contributions.iter().map(|&x| x as f64).sum::<f64>();
+    let magnitude = contributions.iter().map(|&x| x.abs() as f64).sum::<f64>();
+    let steps = contributions.len().saturating_sub(1) as f64;
+    let bound = match quirks::tolerance("assert_atomic_sum", f32::EPSILON) {
+        Some(epsilon) => steps * epsilon as f64 * magnitude,
+        None => return,
+    };
+    let error = (actual as f64 - exact).abs();
+    if error.is_nan() || error > bound {
+        panic!("The atomic sum of {} contributions is {}, but the exact sum is {} with an error \
+                bound of {}.",
+               contributions.len(),
+               actual,
+               exact,
+               bound);
+    }
+}
+
+/// The number of differing elements which `assert_buffers_eq!` reports, unless it is given a
+/// `limit`.
+pub const DEFAULT_DIFF_LIMIT: usize = 8;
+
+/// The number of neighbours on each side of a differing element which are reported with it.
+pub const DIFF_CONTEXT: usize = 2;
+
+/// Compares `actual` elementwise against `expected`. Returns `None` if they are equal, and
+/// otherwise a report of the first `limit` differing indices, each with the elements around it,
+/// naming the buffers `actual_name` and `expected_name`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::buffer_diff;
+///
+/// assert_eq!(buffer_diff("result", &[1, 2, 3], "expected", &[1, 2, 3], 8), None);
+/// assert_eq!(buffer_diff("result", &[0, 1, 2, 9, 4, 5, 6], "expected", &[0, 1, 2, 3, 4, 5, 6], 8)
+///                .unwrap(),
+///            "`result` differs from `expected` in 1 of 7 elements:\n\
+///             \x20 index 3: 9 != 3\n\
+///             \x20   `result`[1..6]: [1, 2, 9, 4, 5]\n\
+///             \x20   `expected`[1..6]: [1, 2, 3, 4, 5]");
+/// # }
+/// ```
+///
+pub fn buffer_diff<T>(actual_name: &str,
+                      actual: &[T],
+                      expected_name: &str,
+                      expected: &[T],
+                      limit: usize)
+                      -> Option<String>
+    where T: PartialEq + Debug
+{
+    diff_by(actual_name,
+            actual,
+            expected_name,
+            expected,
+            limit,
+            "",
+            |actual, expected| actual == expected)
+}
+
+/// Compares `actual` elementwise against `expected` with `equal`, and reports the differences
+/// like `buffer_diff`, which `condition` describes.
+fn diff_by<T, F>(actual_name: &str,
+                 actual: &[T],
+                 expected_name: &str,
+                 expected: &[T],
+                 limit: usize,
+                 condition: &str,
+                 equal: F)
+                 -> Option<String>
+    where T: Debug,
+          F: Fn(&T, &T) -> bool
+{
+    let mismatches = actual.iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|&(_, (actual, expected))| !equal(actual, expected))
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    if mismatches.is_empty() && actual.len() == expected.len() {
+        return None;
+    }
+
+    let len = cmp::min(actual.len(), expected.len());
+    let mut report = if actual.len() == expected.len() {
+        format!("`{}` differs from `{}`{} in {} of {} elements:",
+                actual_name,
+                expected_name,
+                condition,
+                mismatches.len(),
+                len)
+    } else {
+        format!("`{}` has {} elements, but `{}` has {}, and the first {} differ{} in {}:",
+                actual_name,
+                actual.len(),
+                expected_name,
+                expected.len(),
+                len,
+                condition,
+                mismatches.len())
+    };
+    for &index in mismatches.iter().take(limit) {
+        let start = index.saturating_sub(DIFF_CONTEXT);
+        let end = cmp::min(index + DIFF_CONTEXT + 1, len);
+        report.push_str(&format!("\n  index {}: {:?} != {:?}",
+                                 index,
+                                 actual[index],
+                                 expected[index]));
+        report.push_str(&format!("\n    `{}`[{}..{}]: {:?}",
+                                 actual_name,
+                                 start,
+                                 end,
+                                 &actual[start..end]));
+        report.push_str(&format!("\n    `{}`[{}..{}]: {:?}",
+                                 expected_name,
+                                 start,
+                                 end,
+                                 &expected[start..end]));
+    }
+    if mismatches.len() > limit {
+        report.push_str(&format!("\n  and {} more", mismatches.len() - limit));
+    }
+    Some(report)
+}
+
+/// The tolerance of `assert_buffers_approx_eq!`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tolerance {
+    /// The largest absolute difference.
+    Epsilon(f64),
+    /// The largest difference relative to the larger magnitude of the two elements.
+    Relative(f64),
+    /// The largest distance in units in the last place, i.e. the number of representable floats
+    /// between the two elements. Both zeros are equal.
+    Ulps(u64),
+}
+
+impl Tolerance {
+    /// Returns the tolerance of the comparison helper `check` on the device of the current
+    /// thread, or `None` if the comparison is disabled by a quirk, see `quirks::tolerance`.
+    pub fn with_quirks(self, check: &str) -> Option<Tolerance> {
+        quirks::tolerance(check, 1.0).map(|factor| {
+            let factor = factor as f64;
+            match self {
+                Tolerance::Epsilon(epsilon) => Tolerance::Epsilon(epsilon * factor),
+                Tolerance::Relative(relative) => Tolerance::Relative(relative * factor),
+                Tolerance::Ulps(ulps) => Tolerance::Ulps((ulps as f64 * factor).ceil() as u64),
+            }
+        })
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Tolerance::Epsilon(epsilon) => write!(f, "an absolute error of {:e}", epsilon),
+            Tolerance::Relative(relative) => write!(f, "a relative error of {:e}", relative),
+            Tolerance::Ulps(ulps) => write!(f, "{} ULP", ulps),
+        }
+    }
+}
+
+/// The floating point element types which are compared by `assert_buffers_approx_eq!`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::f64;
+/// use vulkanology::compare::{ApproxEq, Tolerance};
+///
+/// let tiny = f64::from_bits(1);
+/// assert!(tiny.approx_eq(-tiny, Tolerance::Ulps(2)));
+/// assert!(!tiny.approx_eq(-tiny, Tolerance::Ulps(1)));
+/// assert!(0.0f32.approx_eq(-0.0, Tolerance::Ulps(0)));
+/// assert!(!std::f32::NAN.approx_eq(std::f32::NAN, Tolerance::Epsilon(1.0)));
+/// # }
+/// ```
+///
+pub trait ApproxEq: Copy + Debug {
+    /// Returns whether `self` and `other` are equal within `tolerance`. NaNs are never equal.
+    fn approx_eq(self, other: Self, tolerance: Tolerance) -> bool;
+}
+
+macro_rules! impl_approx_eq {
+    ($float:ty, $signed:ty) => {
+        impl ApproxEq for $float {
+            fn approx_eq(self, other: $float, tolerance: Tolerance) -> bool {
+                if self == other {
+                    return true;
+                }
+                if self.is_nan() || other.is_nan() {
+                    return false;
+                }
+                match tolerance {
+                    Tolerance::Epsilon(epsilon) => ((self - other).abs() as f64) <= epsilon,
+                    Tolerance::Relative(relative) => {
+                        let magnitude = self.abs().max(other.abs()) as f64;
+                        ((self - other).abs() as f64) <= relative * magnitude
+                    }
+                    Tolerance::Ulps(ulps) => {
+                        // Maps the sign and magnitude representation of the floats onto a
+                        // monotonic integer scale, on which adjacent floats differ by one.
+                        let ordered = |float: $float| {
+                            let bits = float.to_bits() as $signed;
+                            if bits < 0 {
+                                (bits & <$signed>::max_value()).wrapping_neg() as i64
+                            } else {
+                                bits as i64
+                            }
+                        };
+                        let (low, high) = if ordered(self) < ordered(other) {
+                            (ordered(self), ordered(other))
+                        } else {
+                            (ordered(other), ordered(self))
+                        };
+                        // The distance of two `i64` always fits into a `u64`.
+                        (high as u64).wrapping_sub(low as u64) <= ulps
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_approx_eq!(f32, i32);
+impl_approx_eq!(f64, i64);
+
+/// Compares `actual` elementwise against `expected` within `tolerance`, and reports the first
+/// `limit` differing indices like `buffer_diff`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::f32;
+/// use vulkanology::compare::{buffer_approx_diff, Tolerance};
+///
+/// let next = f32::from_bits(1.0f32.to_bits() + 1);
+/// assert_eq!(buffer_approx_diff("result", &[1.0, next], "expected", &[1.0, 1.0],
+///                               Tolerance::Ulps(1), 8),
+///            None);
+/// assert!(buffer_approx_diff("result", &[1.0, 1.01], "expected", &[1.0, 1.0],
+///                            Tolerance::Relative(1e-3), 8)
+///     .unwrap()
+///     .starts_with("`result` differs from `expected` beyond a relative error of 1e-3 in 1 of 2"));
+/// # }
+/// ```
+///
+pub fn buffer_approx_diff<T>(actual_name: &str,
+                             actual: &[T],
+                             expected_name: &str,
+                             expected: &[T],
+                             tolerance: Tolerance,
+                             limit: usize)
+                             -> Option<String>
+    where T: ApproxEq
+{
+    diff_by(actual_name,
+            actual,
+            expected_name,
+            expected,
+            limit,
+            &format!(" beyond {}", tolerance),
+            |&actual, &expected| actual.approx_eq(expected, tolerance))
+}
+
+/// Sorts floats, placing NaNs last.
+fn sort(values: &mut [f32]) {
+    values.sort_by(|a, b| match a.partial_cmp(b) {
+        Some(ordering) => ordering,
+        None => a.is_nan().cmp(&b.is_nan()),
+    });
+}
+
+/// Asserts that the buffer `$actual` equals `$expected` elementwise. Both can be anything which
+/// can be sliced, e.g. the `ReadLock` of a buffer, a `Vec` or an array.
+///
+/// # Panics
+///
+/// If the buffers differ, with the report of `compare::buffer_diff`. It lists the first
+/// `DEFAULT_DIFF_LIMIT` differing indices, or the first `$limit` with `limit: $limit`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let result = vec![0, 2, 4, 6];
+/// let expected = (0..4).map(|i| i * 2).collect::<Vec<_>>();
+/// assert_buffers_eq!(result, expected);
+/// assert_buffers_eq!(result, [0, 2, 4, 6], limit: 1);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! assert_buffers_eq {
+    ($actual:expr, $expected:expr) => {
+        assert_buffers_eq!($actual, $expected, limit: ::vulkanology::compare::DEFAULT_DIFF_LIMIT)
+    };
+    ($actual:expr, $expected:expr, limit: $limit:expr) => {
+        if let Some(report) = ::vulkanology::compare::buffer_diff(stringify!($actual),
+                                                                  &$actual[..],
+                                                                  stringify!($expected),
+                                                                  &$expected[..],
+                                                                  $limit) {
+            panic!("{}", report);
+        }
+    };
+}
+
+/// Asserts that the float buffer `$actual` equals `$expected` elementwise within a tolerance,
+/// given as the units in the last place with `ulps = $ulps`, as an absolute error with
+/// `epsilon = $epsilon` or as a relative error with `relative = $relative`, see
+/// `compare::Tolerance`. A `compare::Tolerance` can also be given with `tolerance = $tolerance`,
+/// or the name of a profile of the [`tolerances`] module with `profile = $name`, whose tolerance
+/// depends on the device. The buffers are given like to `assert_buffers_eq!`, and their elements
+/// are `f32` or `f64`. The tolerance is adjusted by the quirks of the device.
+///
+/// # Panics
+///
+/// If the buffers differ, with the report of `compare::buffer_approx_diff`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let result = vec![0.1f32 + 0.2, 1.0 / 3.0];
+/// let expected = [0.3f32, 0.33333334];
+/// assert_buffers_approx_eq!(result, expected, ulps = 4);
+/// assert_buffers_approx_eq!(result, expected, epsilon = 1e-6);
+/// assert_buffers_approx_eq!(result, expected, relative = 1e-6);
+///
+/// use vulkanology::compare::Tolerance;
+/// use vulkanology::tolerances::{self, Device, Profile, ARM};
+/// let profile = Profile::new(Tolerance::Ulps(1)).on(Device::Vendor(ARM), Tolerance::Ulps(3));
+/// tolerances::register("division", profile);
+/// assert_buffers_approx_eq!(result, expected, profile = "division");
+/// assert_buffers_approx_eq!(result, expected, tolerance = Tolerance::Ulps(1));
+/// # }
+/// ```
+///
+/// [`tolerances`]: tolerances/index.html
+///
+#[macro_export]
+macro_rules! assert_buffers_approx_eq {
+    ($actual:expr, $expected:expr, ulps = $ulps:expr) => {
+        assert_buffers_approx_eq!(__check__ $actual, $expected, Ulps($ulps as u64))
+    };
+    ($actual:expr, $expected:expr, epsilon = $epsilon:expr) => {
+        assert_buffers_approx_eq!(__check__ $actual, $expected, Epsilon($epsilon as f64))
+    };
+    ($actual:expr, $expected:expr, relative = $relative:expr) => {
+        assert_buffers_approx_eq!(__check__ $actual, $expected, Relative($relative as f64))
+    };
+    ($actual:expr, $expected:expr, profile = $profile:expr) => {
+        assert_buffers_approx_eq!(__check__ $actual,
+                                  $expected,
+                                  ::vulkanology::tolerances::tolerance($profile))
+    };
+    ($actual:expr, $expected:expr, tolerance = $tolerance:expr) => {
+        assert_buffers_approx_eq!(__check__ $actual, $expected, $tolerance)
+    };
+    (__check__ $actual:expr, $expected:expr, $kind:ident($value:expr)) => {
+        assert_buffers_approx_eq!(__check__ $actual,
+                                  $expected,
+                                  ::vulkanology::compare::Tolerance::$kind($value))
+    };
+    (__check__ $actual:expr, $expected:expr, $tolerance:expr) => {{
+        use vulkanology::compare::{self, Tolerance};
+        let tolerance: Tolerance = $tolerance;
+        if let Some(tolerance) = tolerance.with_quirks("assert_buffers_approx_eq") {
+            if let Some(report) = compare::buffer_approx_diff(stringify!($actual),
+                                                              &$actual[..],
+                                                              stringify!($expected),
+                                                              &$expected[..],
+                                                              tolerance,
+                                                              compare::DEFAULT_DIFF_LIMIT) {
+                panic!("{}", report);
+            }
+        }
+    }};
+}
@@ -0,0 +1,474 @@
+//! This module checks the GLSL built-in math functions of a device against CPU references within
+//! the precision which the Vulkan specification requires, e.g. to qualify the drivers which an
+//! application ships on.
+//!
+//! A `Builtin` describes a function, e.g. `conformance::SIN`, by its reference in `f64`, the
+//! bound of its error from the section "Precision and Operation of SPIR-V Instructions" of the
+//! specification, and a range of inputs to sweep. The shader under test applies the function to
+//! every element of an input buffer:
+//!
+//! ```ignore
+//! let input_vec = conformance::SIN.inputs(NUM_ELEMENTS);
+//! assert_conformant!{
+//!     builtin: conformance::SIN,
+//!     pipeline: {
+//!         shader_path: "tests/shaders/builtins.comp",
+//!         workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+//!         buffers: {
+//!             data: [f32] from input_vec,
+//!             result: [f32; NUM_ELEMENTS]
+//!         }
+//!     },
+//!     input: data,
+//!     output: result
+//! };
+//! ```
+//!
+//! Inputs outside of the domain of a bound, e.g. `sin` outside of `[-π, π]` or `log` of a
+//! negative number, are not checked, as their results are undefined. Devices may flush
+//! denormals to zero, so denormal inputs are outside of every domain, and a zero is accepted
+//! wherever the reference is a denormal. The functions whose precision the specification
+//! inherits from others, e.g. `sqrt` from `1.0 / inversesqrt(x)` or `tan` from
+//! `sin(x) / cos(x)`, are not described, but can be checked with a `Builtin` of their own.
+//!
+//! Besides asserting the bounds, the check notes the [`precision`] report of the function.
+//!
+//! [`precision`]: ../precision/index.html
+
+use std::f32;
+use std::f64::consts::PI;
+use std::fmt;
+
+use compare::DEFAULT_DIFF_LIMIT;
+use precision::{self, Report};
+
+/// The largest error which the specification allows for a result.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Bound {
+    /// An error in units in the last place of the result, see `precision::ulp_error`.
+    Ulps(f64),
+    /// An absolute error.
+    Absolute(f64),
+}
+
+impl Bound {
+    /// Returns whether the result `actual` is within the bound of the exact result `reference`.
+    /// A zero is within the bound of a denormal reference, which devices may flush to zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use vulkanology::conformance::Bound;
+    ///
+    /// assert!(Bound::Ulps(2.0).admits(1.0 + 2.0 * ::std::f32::EPSILON, 1.0));
+    /// assert!(!Bound::Ulps(2.0).admits(1.0 + 3.0 * ::std::f32::EPSILON, 1.0));
+    /// assert!(Bound::Absolute(1e-3).admits(0.0, 1e-4));
+    /// assert!(Bound::Ulps(0.0).admits(0.0, 1e-40));
+    /// # }
+    /// ```
+    ///
+    pub fn admits(&self, actual: f32, reference: f64) -> bool {
+        if actual == 0.0 && reference.abs() < f32::MIN_POSITIVE as f64 {
+            return true;
+        }
+        match *self {
+            Bound::Ulps(ulps) => precision::ulp_error(actual, reference) <= ulps,
+            Bound::Absolute(error) => (actual as f64 - reference).abs() <= error,
+        }
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Bound::Ulps(ulps) => write!(f, "{} ULP", ulps),
+            Bound::Absolute(error) => write!(f, "an absolute error of {:e}", error),
+        }
+    }
+}
+
+/// A GLSL built-in function of one float argument.
+#[derive(Copy, Clone)]
+pub struct Builtin {
+    /// The name of the function in GLSL.
+    pub name: &'static str,
+    /// The exact result of the function.
+    pub reference: fn(f64) -> f64,
+    /// The bound of the error of the result for an input, or `None` if the input is outside of
+    /// the domain in which the specification bounds the result.
+    pub bound: fn(f64) -> Option<Bound>,
+    /// The first and the last input of the sweep of `inputs`.
+    pub range: (f32, f32),
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Builtin").field("name", &self.name).field("range", &self.range).finish()
+    }
+}
+
+impl Builtin {
+    /// Returns `count` inputs from the `range` of the function, see `spaced`.
+    pub fn inputs(&self, count: usize) -> Vec<f32> {
+        spaced(self.range.0, self.range.1, count)
+    }
+}
+
+/// Returns whether `x` is a normal `f32`, or zero.
+fn is_normal_or_zero(x: f64) -> bool {
+    x == 0.0 || x.abs() >= f32::MIN_POSITIVE as f64
+}
+
+fn trigonometric_bound(x: f64) -> Option<Bound> {
+    if is_normal_or_zero(x) && x.abs() <= PI {
+        Some(Bound::Absolute(2f64.powi(-11)))
+    } else {
+        None
+    }
+}
+
+fn exp_bound(x: f64) -> Option<Bound> {
+    if is_normal_or_zero(x) {
+        Some(Bound::Ulps(3.0 + 2.0 * x.abs()))
+    } else {
+        None
+    }
+}
+
+fn log_bound(x: f64) -> Option<Bound> {
+    if x < f32::MIN_POSITIVE as f64 {
+        None
+    } else if 0.5 <= x && x <= 2.0 {
+        Some(Bound::Absolute(2f64.powi(-21)))
+    } else {
+        Some(Bound::Ulps(3.0))
+    }
+}
+
+fn inversesqrt_bound(x: f64) -> Option<Bound> {
+    if x < f32::MIN_POSITIVE as f64 {
+        None
+    } else {
+        Some(Bound::Ulps(2.0))
+    }
+}
+
+fn sin(x: f64) -> f64 {
+    x.sin()
+}
+
+fn cos(x: f64) -> f64 {
+    x.cos()
+}
+
+fn exp(x: f64) -> f64 {
+    x.exp()
+}
+
+fn exp2(x: f64) -> f64 {
+    x.exp2()
+}
+
+fn log(x: f64) -> f64 {
+    x.ln()
+}
+
+fn log2(x: f64) -> f64 {
+    x.log2()
+}
+
+fn inversesqrt(x: f64) -> f64 {
+    1.0 / x.sqrt()
+}
+
+/// `sin(x)`, within an absolute error of 2<sup>-11</sup> in `[-π, π]`.
+pub const SIN: Builtin = Builtin {
+    name: "sin",
+    reference: sin,
+    bound: trigonometric_bound,
+    range: (-3.1415925, 3.1415925),
+};
+
+/// `cos(x)`, within an absolute error of 2<sup>-11</sup> in `[-π, π]`.
+pub const COS: Builtin = Builtin {
+    name: "cos",
+    reference: cos,
+    bound: trigonometric_bound,
+    range: (-3.1415925, 3.1415925),
+};
+
+/// `exp(x)`, within `3 + 2 * |x|` ULP.
+pub const EXP: Builtin = Builtin {
+    name: "exp",
+    reference: exp,
+    bound: exp_bound,
+    range: (-87.0, 88.0),
+};
+
+/// `exp2(x)`, within `3 + 2 * |x|` ULP.
+pub const EXP2: Builtin = Builtin {
+    name: "exp2",
+    reference: exp2,
+    bound: exp_bound,
+    range: (-126.0, 127.0),
+};
+
+/// `log(x)`, within an absolute error of 2<sup>-21</sup> in `[0.5, 2]` and within 3 ULP
+/// elsewhere.
+pub const LOG: Builtin = Builtin {
+    name: "log",
+    reference: log,
+    bound: log_bound,
+    range: (f32::MIN_POSITIVE, f32::MAX),
+};
+
+/// `log2(x)`, within an absolute error of 2<sup>-21</sup> in `[0.5, 2]` and within 3 ULP
+/// elsewhere.
+pub const LOG2: Builtin = Builtin {
+    name: "log2",
+    reference: log2,
+    bound: log_bound,
+    range: (f32::MIN_POSITIVE, f32::MAX),
+};
+
+/// `inversesqrt(x)`, within 2 ULP.
+pub const INVERSESQRT: Builtin = Builtin {
+    name: "inversesqrt",
+    reference: inversesqrt,
+    bound: inversesqrt_bound,
+    range: (f32::MIN_POSITIVE, f32::MAX),
+};
+
+/// All described functions.
+pub const ALL: [Builtin; 7] = [SIN, COS, EXP, EXP2, LOG, LOG2, INVERSESQRT];
+
+/// Returns the position of `x` among the ordered floats, where both zeros are 0.
+fn ordinal(x: f32) -> i64 {
+    let bits = x.to_bits();
+    if bits >> 31 == 0 {
+        bits as i64
+    } else {
+        -((bits & 0x7fff_ffff) as i64)
+    }
+}
+
+/// Returns the float at the position `ordinal`, see `ordinal`.
+fn from_ordinal(ordinal: i64) -> f32 {
+    if ordinal >= 0 {
+        f32::from_bits(ordinal as u32)
+    } else {
+        f32::from_bits((-ordinal) as u32 | 0x8000_0000)
+    }
+}
+
+/// Returns `count` floats from `start` to `end`, which are evenly spaced among all floats in
+/// between, so every order of magnitude of the range gets the same number of inputs. If there
+/// are at most `count` floats in the range, every float is returned once.
+///
+/// # Panics
+///
+/// If `start` or `end` is NaN, or `start` is larger than `end`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::conformance::spaced;
+///
+/// // Roughly one input per two orders of magnitude.
+/// assert_eq!(spaced(1.0, 1e6, 4), vec![1.0, 104.6901, 10416.667, 1e6]);
+/// assert_eq!(spaced(-0.0, 0.0, 8), vec![0.0]);
+/// assert_eq!(spaced(1.0, 1.0 + 2.0 * ::std::f32::EPSILON, 100).len(), 3);
+/// # }
+/// ```
+///
+pub fn spaced(start: f32, end: f32, count: usize) -> Vec<f32> {
+    if start.is_nan() || end.is_nan() || start > end {
+        panic!("The floats from {} to {} are not a range.", start, end);
+    }
+    let (first, last) = (ordinal(start), ordinal(end));
+    let floats = (last - first + 1) as u64;
+    if floats <= count as u64 {
+        return (first..last + 1).map(from_ordinal).collect();
+    }
+    match count {
+        0 => Vec::new(),
+        1 => vec![start],
+        _ => {
+            let step = (last - first) as f64 / (count - 1) as f64;
+            (0..count)
+                .map(|index| from_ordinal(first + (index as f64 * step).round() as i64))
+                .collect()
+        }
+    }
+}
+
+/// A result which exceeds the bound of its input.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Violation {
+    /// The index of the element.
+    pub index: usize,
+    /// The input.
+    pub input: f32,
+    /// The result of the device.
+    pub actual: f32,
+    /// The exact result.
+    pub reference: f64,
+    /// The bound of the result.
+    pub bound: Bound,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "index {}: {:?} for the input {:?}, the reference is {:?} within {}",
+               self.index,
+               self.actual,
+               self.input,
+               self.reference,
+               self.bound)
+    }
+}
+
+/// The conformance of the results of a built-in function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conformance {
+    /// The name of the function.
+    pub builtin: &'static str,
+    /// The number of inputs in the domain of the bounds, which were checked.
+    pub checked: usize,
+    /// The results which exceed their bounds.
+    pub violations: Vec<Violation>,
+    /// The precision of the checked results.
+    pub report: Report,
+}
+
+impl Conformance {
+    /// Asserts that no result exceeds its bound.
+    ///
+    /// # Panics
+    ///
+    /// If a result exceeds its bound, with the first `DEFAULT_DIFF_LIMIT` violations and their
+    /// number.
+    pub fn assert_conformant(&self) {
+        if !self.violations.is_empty() {
+            let listed = self.violations
+                .iter()
+                .take(DEFAULT_DIFF_LIMIT)
+                .map(|violation| format!("  {}", violation))
+                .collect::<Vec<_>>();
+            panic!("{} of {} results of `{}` exceed the precision required by Vulkan:\n{}{}",
+                   self.violations.len(),
+                   self.checked,
+                   self.builtin,
+                   listed.join("\n"),
+                   if self.violations.len() > DEFAULT_DIFF_LIMIT { "\n  ..." } else { "" });
+        }
+    }
+}
+
+/// Returns the conformance of the results `output` of the function `builtin` for the `inputs`,
+/// whose elements correspond by their index.
+///
+/// # Panics
+///
+/// If there are fewer inputs than results.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::conformance::{check, INVERSESQRT};
+///
+/// let inputs = [-1.0f32, 0.25, 4.0, 16.0];
+/// let output = [0.0f32, 2.0, 0.5, 0.2501];
+/// let conformance = check(&INVERSESQRT, &inputs, &output);
+/// assert_eq!(conformance.checked, 3);
+/// assert_eq!(conformance.violations.len(), 1);
+/// assert_eq!(conformance.violations[0].to_string(),
+///            "index 3: 0.2501 for the input 16.0, the reference is 0.25 within 2 ULP");
+/// # }
+/// ```
+///
+pub fn check(builtin: &Builtin, inputs: &[f32], output: &[f32]) -> Conformance {
+    if inputs.len() < output.len() {
+        panic!("The output of `{}` has {} elements, but only {} inputs were given.",
+               builtin.name,
+               output.len(),
+               inputs.len());
+    }
+
+    let mut checked_inputs = Vec::new();
+    let mut checked_output = Vec::new();
+    let mut violations = Vec::new();
+    for (index, (&input, &actual)) in inputs.iter().zip(output).enumerate() {
+        let bound = match (builtin.bound)(input as f64) {
+            Some(bound) => bound,
+            None => continue,
+        };
+        let reference = (builtin.reference)(input as f64);
+        checked_inputs.push(input);
+        checked_output.push(actual);
+        if !bound.admits(actual, reference) {
+            violations.push(Violation {
+                index: index,
+                input: input,
+                actual: actual,
+                reference: reference,
+                bound: bound,
+            });
+        }
+    }
+    Conformance {
+        builtin: builtin.name,
+        checked: checked_inputs.len(),
+        violations: violations,
+        report: precision::report(builtin.name,
+                                  &checked_output,
+                                  &checked_inputs,
+                                  |&x| (builtin.reference)(x as f64),
+                                  None),
+    }
+}
+
+/// Executes a shader once and asserts that its output is within the precision which Vulkan
+/// requires of the built-in function `builtin`, a `conformance::Builtin`, for the elements of
+/// the `input` buffer, see the [`conformance`] module. The precision report of the results is
+/// noted on stderr and in the report of the harness. Returns the `conformance::Conformance`.
+///
+/// The `pipeline` takes the parameters of `pipeline!` without the execution command.
+///
+/// # Panics
+///
+/// If a result exceeds its bound, with the first violations, or like `pipeline!`.
+///
+/// [`conformance`]: conformance/index.html
+///
+#[macro_export]
+macro_rules! assert_conformant {
+    {
+        builtin: $builtin:expr,
+        pipeline: { $( $pipeline_args:tt )* },
+        input: $input:ident,
+        output: $output:ident $(,)*
+    } => ({
+        use vulkanology::{conformance, precision};
+
+        pipeline!{ $( $pipeline_args )*, execution_command: execute }
+        execute();
+
+        let conformance = conformance::check(&$builtin,
+                                             &read_buffer!($input)[..],
+                                             &read_buffer!($output)[..]);
+        precision::note(&conformance.report);
+        conformance.assert_conformant();
+        conformance
+    });
+}
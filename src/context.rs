@@ -0,0 +1,136 @@
+//! This module describes the device which the current test runs on, to identify it in failure
+//! reports from other machines.
+//!
+//! `pipeline!` records the context after selecting the physical device. From then on, every
+//! panic of the test thread, whether it comes from the generated code or from an assertion of
+//! the test, is followed by a note naming the device, its vendor, its driver and its Vulkan
+//! version on stderr, which the test runner shows together with the panic message. The reports
+//! of the [`harness`] append the context to the failure message.
+//!
+//! Assertions can also include it in their message themselves:
+//!
+//! ```ignore
+//! assert_eq!(result, expected, "{}", context::describe());
+//! ```
+//!
+//! [`harness`]: ../harness/index.html
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::panic;
+use std::sync::Once;
+
+/// The PCI vendor id of NVIDIA, whose drivers encode their version differently.
+const NVIDIA: u32 = 0x10de;
+
+thread_local! {
+    static CONTEXT: RefCell<Option<DeviceContext>> = RefCell::new(None);
+}
+
+static HOOK: Once = Once::new();
+
+/// The properties of a physical device which identify it in a failure report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceContext {
+    /// The name of the device.
+    pub name: String,
+    /// The PCI vendor id.
+    pub vendor_id: u32,
+    /// The PCI device id.
+    pub device_id: u32,
+    /// The version of the driver, in the encoding of the vendor.
+    pub driver_version: u32,
+    /// The Vulkan version supported by the device, as `[major, minor, patch]`.
+    pub api_version: [u16; 3],
+}
+
+impl DeviceContext {
+    /// Returns the version of the driver. NVIDIA encodes it in 10, 8, 8 and 6 bits, other
+    /// vendors like the Vulkan version.
+    pub fn driver(&self) -> String {
+        let version = self.driver_version;
+        if self.vendor_id == NVIDIA {
+            format!("{}.{}.{}.{}",
+                    version >> 22,
+                    (version >> 14) & 0xff,
+                    (version >> 6) & 0xff,
+                    version & 0x3f)
+        } else {
+            format!("{}.{}.{}", version >> 22, (version >> 12) & 0x3ff, version & 0xfff)
+        }
+    }
+}
+
+impl fmt::Display for DeviceContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "`{}` (vendor {:#06x}, device {:#06x}, driver {}, Vulkan {}.{}.{})",
+               self.name,
+               self.vendor_id,
+               self.device_id,
+               self.driver(),
+               self.api_version[0],
+               self.api_version[1],
+               self.api_version[2])
+    }
+}
+
+/// Records the device which the current test runs on, and installs the panic hook which notes
+/// it after the messages of panics.
+pub fn set(context: DeviceContext) {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            // The context of a thread which is being destroyed cannot be accessed any more.
+            let context = CONTEXT.try_with(|current| current.borrow().clone());
+            if let Ok(Some(context)) = context {
+                let _ = writeln!(io::stderr(), "note: The test ran on the device {}.", context);
+            }
+        }));
+    });
+    CONTEXT.with(|current| *current.borrow_mut() = Some(context));
+}
+
+/// Forgets the device of the current test, e.g. before the next test runs on the same thread.
+pub fn clear() {
+    CONTEXT.with(|current| *current.borrow_mut() = None);
+}
+
+/// Returns the device which the current test runs on, if it was recorded.
+pub fn current() -> Option<DeviceContext> {
+    CONTEXT.with(|current| current.borrow().clone())
+}
+
+/// Returns a sentence naming the device of the current test, for the messages of assertions.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::context::{self, DeviceContext};
+///
+/// assert_eq!(context::describe(), "The device of the test is unknown.");
+///
+/// context::set(DeviceContext {
+///     name: "NVIDIA GeForce RTX 3060".to_owned(),
+///     vendor_id: 0x10de,
+///     device_id: 0x2503,
+///     driver_version: 535 << 22 | 104 << 14 | 5 << 6,
+///     api_version: [1, 3, 242],
+/// });
+/// assert_eq!(context::describe(),
+///            "The test ran on the device `NVIDIA GeForce RTX 3060` (vendor 0x10de, device \
+///             0x2503, driver 535.104.5.0, Vulkan 1.3.242).");
+/// # }
+/// ```
+///
+pub fn describe() -> String {
+    match current() {
+        Some(context) => format!("The test ran on the device {}.", context),
+        None => "The device of the test is unknown.".to_owned(),
+    }
+}
@@ -0,0 +1,48 @@
+//! This module runs the dispatches of `pipeline!` as [Criterion.rs] benchmarks. It requires the
+//! optional `criterion` feature.
+//!
+//! Criterion measures a routine by running it for a growing number of iterations. Submitting
+//! and waiting for one execution per iteration would add the latency of a fence to every
+//! sample. `bench_dispatch` instead makes an iteration a `repeat_command` of `pipeline!`, which
+//! submits a chunk of `CHUNK` executions that are waited for together, and lets Criterion handle
+//! the warm-up and the statistics. The reported time is therefore the time of a whole chunk:
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate criterion;
+//! #[macro_use]
+//! extern crate vulkanology;
+//!
+//! use criterion::Criterion;
+//! use vulkanology::criterion_adapter::bench_dispatch;
+//!
+//! fn bench_example(c: &mut Criterion) {
+//!     pipeline!{
+//!         // ...
+//!         execution_command: execute_shader,
+//!         repeat_command: execute_shader_n
+//!     };
+//!     bench_dispatch(c, "example", execute_shader_n);
+//! }
+//!
+//! criterion_group!(benches, bench_example);
+//! criterion_main!(benches);
+//! ```
+//!
+//! The bench target must set `harness = false`, see `benches/criterion.rs`.
+//!
+//! [Criterion.rs]: https://github.com/bheisler/criterion.rs
+
+use criterion::Criterion;
+
+/// The number of submissions of one iteration, which are in flight at the same time. Every
+/// submission holds a fence and a semaphore until it is waited for.
+pub const CHUNK: u32 = 256;
+
+/// Adds the benchmark `name` to `criterion`, which measures the repeat command `repeat_command`
+/// of `pipeline!`. An iteration is a chunk of `CHUNK` submissions of the command buffer.
+pub fn bench_dispatch<F>(criterion: &mut Criterion, name: &str, mut repeat_command: F)
+    where F: FnMut(u32)
+{
+    criterion.bench_function(name, move |bencher| bencher.iter(|| repeat_command(CHUNK)));
+}
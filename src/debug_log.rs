@@ -0,0 +1,158 @@
+//! This module decodes and prints the records written by the `debug_log` GLSL segment, see
+//! `build_utils::builtin_segment`.
+//!
+//! `debugPrintfEXT` requires `VK_KHR_shader_non_semantic_info`, which cannot be enabled with the
+//! version of `vulkano` used by `pipeline!`, and the GLSL compiler of `vulkano-shaders` predates
+//! `GL_EXT_debug_printf`. Instead, the segment appends the values passed to `debug_log(tag,
+//! values)` to a buffer, together with the global invocation id:
+//!
+//! ```glsl
+//! debug_log(__LINE__, vec4(position, weight));
+//! ```
+//!
+//! After the execution, `print` writes the records to stdout, which the test harness shows in
+//! the output of failing tests. The buffer is declared with `buffer_len`, and its first element
+//! must be reset to zero before every further execution.
+
+use std::fmt;
+
+/// The number of `u32` of a single record.
+pub const RECORD_LEN: usize = 8;
+
+/// Returns the length of a log buffer which holds `records` records.
+pub fn buffer_len(records: usize) -> usize {
+    1 + records * RECORD_LEN
+}
+
+/// The values of a record, in the type they were logged with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Values {
+    /// A `uvec4`.
+    Uint([u32; 4]),
+    /// An `ivec4`.
+    Int([i32; 4]),
+    /// A `vec4`.
+    Float([f32; 4]),
+}
+
+/// A record of the log.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LogRecord {
+    /// The global invocation id of the invocation which wrote the record.
+    pub invocation: [u32; 3],
+    /// The tag passed to `debug_log`.
+    pub tag: u32,
+    /// The values passed to `debug_log`.
+    pub values: Values,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "invocation ({}, {}, {}), tag {}:",
+               self.invocation[0],
+               self.invocation[1],
+               self.invocation[2],
+               self.tag)?;
+        match self.values {
+            Values::Uint(values) => write!(f, " {:?}", values),
+            Values::Int(values) => write!(f, " {:?}", values),
+            Values::Float(values) => write!(f, " {:?}", values),
+        }
+    }
+}
+
+/// The decoded content of a log buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLog {
+    /// The records, ordered by their invocation. The records of one invocation are in the order
+    /// in which it wrote them.
+    pub records: Vec<LogRecord>,
+    /// The number of records which did not fit into the buffer.
+    pub dropped: usize,
+}
+
+/// Decodes the log buffer `data`.
+///
+/// # Panics
+///
+/// If `data` is empty, or if a record has an unknown kind of values.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::debug_log::{decode, Values};
+///
+/// // Three records were written, the second one with the tag 7 and the values of an `ivec4`.
+/// // The third one did not fit into the buffer.
+/// let data = [3, 5, 0, 0, 2 << 24 | 7, 0, 0, 0, 0, 1, 0, 0, 1 << 24 | 7, !0, 1, 2, 3];
+/// let log = decode(&data);
+/// assert_eq!(log.dropped, 1);
+/// assert_eq!(log.records[0].invocation, [1, 0, 0]);
+/// assert_eq!(log.records[0].values, Values::Int([-1, 1, 2, 3]));
+/// assert_eq!(log.records[1].values, Values::Float([0.0; 4]));
+/// # }
+/// ```
+///
+pub fn decode(data: &[u32]) -> DebugLog {
+    let (&len, data) = data.split_first().expect("The log buffer is empty.");
+    let len = len as usize;
+    let mut records = data.chunks(RECORD_LEN)
+        .filter(|record| record.len() == RECORD_LEN)
+        .take(len)
+        .map(|record| {
+            let words = [record[4], record[5], record[6], record[7]];
+            let values = match record[3] >> 24 {
+                0 => Values::Uint(words),
+                1 => {
+                    Values::Int([words[0] as i32,
+                                 words[1] as i32,
+                                 words[2] as i32,
+                                 words[3] as i32])
+                }
+                2 => {
+                    Values::Float([f32::from_bits(words[0]),
+                                   f32::from_bits(words[1]),
+                                   f32::from_bits(words[2]),
+                                   f32::from_bits(words[3])])
+                }
+                kind => panic!("The log record has the unknown kind {}.", kind),
+            };
+            LogRecord {
+                invocation: [record[0], record[1], record[2]],
+                tag: record[3] & 0xff_ffff,
+                values: values,
+            }
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable, so the records of an invocation stay in the order of the counter.
+    records.sort_by_key(|record| {
+        (record.invocation[2], record.invocation[1], record.invocation[0])
+    });
+    let dropped = len - records.len();
+    DebugLog {
+        records: records,
+        dropped: dropped,
+    }
+}
+
+/// Prints the records of the log buffer `data` of the shader `shader` to stdout, one line per
+/// record.
+///
+/// # Panics
+///
+/// Like `decode`.
+pub fn print(shader: &str, data: &[u32]) {
+    let log = decode(data);
+    for record in &log.records {
+        println!("debug_log `{}`: {}", shader, record);
+    }
+    if log.dropped > 0 {
+        println!("debug_log `{}`: {} records did not fit into the buffer.",
+                 shader,
+                 log.dropped);
+    }
+}
@@ -0,0 +1,158 @@
+//! This module checks that a shader computes the same outputs every time it is executed on the
+//! same inputs. Nondeterministic outputs usually come from a data race, e.g. a missing
+//! `barrier()` between the writes and the reads of shared memory, or from reading shared memory
+//! or buffer elements which were never written.
+//!
+//! `assert_deterministic!` creates the pipeline anew for every run from the same parameters,
+//! so every run starts from buffers with the same contents, executes it once, and compares the
+//! output buffers of every run against those of the first run:
+//!
+//! ```ignore
+//! assert_deterministic!{
+//!     shader_path: "tests/shaders/example.comp",
+//!     pipeline: {
+//!         workgroup_count: [100, 100, 1],
+//!         buffers: {
+//!             data: [u32] from input_vec,
+//!             result: [u32; NUM_INVOCATIONS]
+//!         }
+//!     },
+//!     outputs: [result],
+//!     runs: 4
+//! };
+//! ```
+//!
+//! The inputs must therefore be given in the `buffers` with their contents, e.g. with `from`,
+//! instead of being written after the pipeline was created. Races often only show under load,
+//! so more runs, or a larger workgroup count, find them more reliably. The outputs are compared
+//! exactly, with the `ab` module.
+
+use std::fmt;
+
+use ab::Divergence;
+
+/// The default number of runs of `assert_deterministic!`.
+pub const DEFAULT_RUNS: usize = 2;
+
+/// An output buffer of a run which differs from the output of the first run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nondeterminism {
+    /// The number of the run, starting at 1 for the first run.
+    pub run: usize,
+    /// The difference of the buffer from the first run, whose `reference` is the first run and
+    /// whose `candidate` is the run `run`.
+    pub divergence: Divergence,
+}
+
+impl fmt::Display for Nondeterminism {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "`{}` differs between the runs 1 and {} in {} elements, first at index {}: {} != {}",
+               self.divergence.buffer,
+               self.run,
+               self.divergence.mismatches,
+               self.divergence.first,
+               self.divergence.reference,
+               self.divergence.candidate)
+    }
+}
+
+/// Asserts that none of `runs` runs of the shader `shader_path` computed outputs which differ
+/// from the first run.
+///
+/// # Panics
+///
+/// If any output differs, with every difference.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::ab::divergence;
+/// use vulkanology::determinism::{assert_deterministic, Nondeterminism};
+///
+/// let found = divergence("result", &[1, 2, 3], &[1, 2, 4])
+///     .map(|divergence| Nondeterminism { run: 2, divergence: divergence });
+/// assert_eq!(found.as_ref().unwrap().to_string(),
+///            "`result` differs between the runs 1 and 2 in 1 elements, first at index 2: 3 != 4");
+/// assert_deterministic("race.comp", 2, &[]);
+/// # }
+/// ```
+///
+pub fn assert_deterministic(shader_path: &str, runs: usize, found: &[Nondeterminism]) {
+    if !found.is_empty() {
+        let found = found.iter().map(|found| format!("  {}", found)).collect::<Vec<_>>();
+        panic!("The outputs of `{}` are nondeterministic in {} runs, which suggests a data race \
+                or uninitialized memory:\n{}",
+               shader_path,
+               runs,
+               found.join("\n"));
+    }
+}
+
+/// Executes a shader several times on the same inputs and asserts that its outputs are the
+/// same every time, see the [`determinism`] module. The `pipeline` parameters are passed to
+/// [`pipeline!`] for every run together with the `shader_path` and an execution command, and
+/// the buffers named in `outputs` are compared after every execution. The shader runs `runs`
+/// times, or `determinism::DEFAULT_RUNS` times if it is not given.
+///
+/// # Panics
+///
+/// If an output of a run differs from the first run, with every difference, or if `runs` is
+/// less than 2.
+///
+/// [`determinism`]: determinism/index.html
+/// [`pipeline!`]: macro.pipeline.html
+///
+#[macro_export]
+macro_rules! assert_deterministic {
+    {
+        shader_path: $shader_path:expr,
+        pipeline: { $( $pipeline_args:tt )* },
+        outputs: [ $( $output:ident ),* $(,)* ] $(,)*
+    } => {
+        assert_deterministic!{
+            shader_path: $shader_path,
+            pipeline: { $( $pipeline_args )* },
+            outputs: [ $( $output ),* ],
+            runs: ::vulkanology::determinism::DEFAULT_RUNS
+        }
+    };
+    {
+        shader_path: $shader_path:expr,
+        pipeline: { $( $pipeline_args:tt )* },
+        outputs: [ $( $output:ident ),* $(,)* ],
+        runs: $runs:expr $(,)*
+    } => ({
+        use vulkanology::{ab, determinism};
+
+        let runs: usize = $runs;
+        if runs < 2 {
+            panic!("The determinism of a shader needs at least 2 runs, not {}.", runs);
+        }
+        let first_outputs: Vec<_> = {
+            pipeline!{ shader_path: $shader_path, $( $pipeline_args )*, execution_command: execute }
+            execute();
+            // The locks must be released before the buffers of the block are dropped.
+            let outputs = vec![ $( ab::record_output(&read_buffer!($output)) ),* ];
+            outputs
+        };
+        let mut found = Vec::new();
+        for run in 2..runs + 1 {
+            pipeline!{ shader_path: $shader_path, $( $pipeline_args )*, execution_command: execute }
+            execute();
+            let mut first_outputs = first_outputs.iter();
+            $(
+                let divergence = ab::compare_output(stringify!($output),
+                                                    &**first_outputs.next().unwrap(),
+                                                    &read_buffer!($output));
+                if let Some(divergence) = divergence {
+                    found.push(determinism::Nondeterminism { run: run, divergence: divergence });
+                }
+            )*
+        }
+        determinism::assert_deterministic($shader_path, runs, &found);
+    });
+}
@@ -0,0 +1,285 @@
+//! This module checks the output of a shader against a reference implementation in Rust, which
+//! computes the expected value of every output element from the inputs of the shader.
+//!
+//! `assert_shader_matches!` creates the pipeline, executes the shader once, and evaluates the
+//! reference closure for every index of the output buffer. The closure receives the inputs,
+//! whose fields are the contents of the input buffers named after them, and the index:
+//!
+//! ```ignore
+//! assert_shader_matches!{
+//!     pipeline: {
+//!         shader_path: "tests/shaders/example.comp",
+//!         workgroup_count: [100, 100, 1],
+//!         buffers: {
+//!             data: [u32] from input_vec,
+//!             result: [u32; NUM_INVOCATIONS]
+//!         }
+//!     },
+//!     inputs: [data],
+//!     output: result,
+//!     expected: |inputs, i| inputs.data[i].wrapping_mul(i as u32),
+//!     tolerance: 0,
+//!     workgroup_count: [100, 100, 1],
+//!     local_size: [8, 8, 1]
+//! };
+//! ```
+//!
+//! The input buffers must be given with their contents, e.g. with `from`. The elements are
+//! those of the [`export`] module, which are compared scalar by scalar: an element matches if
+//! none of its scalars differs from the expected one by more than the absolute tolerance, and
+//! NaN only matches NaN. The scalars are compared as `f64`, so 64-bit integers which differ only
+//! beyond 53 bits are not told apart.
+//!
+//! Given the workgroup count and the local size of the dispatch, every mismatch is reported
+//! with the global invocation id which wrote it, assuming that the invocations write their
+//! elements in the order of their flattened global id, see `non_finite::Layout`.
+//!
+//! [`export`]: ../export/index.html
+
+use std::f64;
+use std::fmt::{self, Debug};
+
+use compare::DEFAULT_DIFF_LIMIT;
+use export::{Element, Scalar};
+use non_finite::Layout;
+
+/// An output element which does not match the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The index of the element.
+    pub index: usize,
+    /// The global invocation id which wrote the element, if the layout of the buffer is known.
+    pub invocation: Option<[u32; 3]>,
+    /// The element of the output.
+    pub actual: String,
+    /// The element computed by the reference.
+    pub expected: String,
+    /// The largest difference between the scalars of the two elements.
+    pub difference: f64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {}", self.index)?;
+        if let Some(invocation) = self.invocation {
+            write!(f, " (invocation {:?})", invocation)?;
+        }
+        write!(f, ": {}, expected {}", self.actual, self.expected)
+    }
+}
+
+/// Returns the largest difference between the scalars of `actual` and `expected`, which is
+/// infinite if only one of them is NaN.
+fn difference<T: Element>(actual: &T, expected: &T) -> f64 {
+    let mut actual_scalars = Vec::new();
+    let mut expected_scalars = Vec::new();
+    actual.scalars(&mut actual_scalars);
+    expected.scalars(&mut expected_scalars);
+    actual_scalars.iter()
+        .zip(&expected_scalars)
+        .map(|(actual, expected)| {
+            let (actual, expected) = (actual.to_f64(), expected.to_f64());
+            match (actual.is_nan(), expected.is_nan()) {
+                (true, true) => 0.0,
+                (false, false) if actual == expected => 0.0,
+                (false, false) => (actual - expected).abs(),
+                _ => f64::INFINITY,
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Returns the elements of `output` which differ from `expected(inputs, index)` by more than
+/// `tolerance`, with the invocations which wrote them given the `layout` of the buffer.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::differential::mismatches;
+/// use vulkanology::non_finite::Layout;
+///
+/// let input = [1.0f32, 2.0, 3.0, 4.0];
+/// let output = [2.0f32, 4.0, 6.5, 8.0];
+/// let mismatches = mismatches(&output,
+///                             &input,
+///                             |input, i| input[i] * 2.0,
+///                             0.1,
+///                             Some(Layout::new([2, 1, 1], [2, 1, 1], 1)));
+/// assert_eq!(mismatches.len(), 1);
+/// assert_eq!(mismatches[0].to_string(), "index 2 (invocation [2, 0, 0]): 6.5, expected 6.0");
+/// # }
+/// ```
+///
+pub fn mismatches<T, I, F>(output: &[T],
+                           inputs: &I,
+                           expected: F,
+                           tolerance: f64,
+                           layout: Option<Layout>)
+                           -> Vec<Mismatch>
+    where T: Element + Debug,
+          I: ?Sized,
+          F: Fn(&I, usize) -> T
+{
+    output.iter()
+        .enumerate()
+        .filter_map(|(index, actual)| {
+            let expected = expected(inputs, index);
+            let difference = difference(actual, &expected);
+            if difference <= tolerance {
+                return None;
+            }
+            Some(Mismatch {
+                index: index,
+                invocation: layout.and_then(|layout| layout.invocation(index)),
+                actual: format!("{:?}", actual),
+                expected: format!("{:?}", expected),
+                difference: difference,
+            })
+        })
+        .collect()
+}
+
+/// Asserts that every element of the output buffer `name` matches the reference `expected`
+/// within `tolerance`, see `mismatches`.
+///
+/// # Panics
+///
+/// If an element does not match, with the first `DEFAULT_DIFF_LIMIT` mismatches and their
+/// number.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::differential::assert_matches;
+///
+/// let input = [1u32, 2, 3];
+/// let output = [1u32, 4, 9];
+/// assert_matches("output", &output, &input, |input, i| input[i] * input[i], 0.0, None);
+/// # }
+/// ```
+///
+pub fn assert_matches<T, I, F>(name: &str,
+                               output: &[T],
+                               inputs: &I,
+                               expected: F,
+                               tolerance: f64,
+                               layout: Option<Layout>)
+    where T: Element + Debug,
+          I: ?Sized,
+          F: Fn(&I, usize) -> T
+{
+    let mismatches = mismatches(output, inputs, expected, tolerance, layout);
+    if !mismatches.is_empty() {
+        let listed = mismatches.iter()
+            .take(DEFAULT_DIFF_LIMIT)
+            .map(|mismatch| format!("  {}", mismatch))
+            .collect::<Vec<_>>();
+        let largest = mismatches.iter().map(|mismatch| mismatch.difference).fold(0.0, f64::max);
+        panic!("{} of {} elements of `{}` differ from the reference by more than {}, by up to \
+                {}:\n{}{}",
+               mismatches.len(),
+               output.len(),
+               name,
+               tolerance,
+               largest,
+               listed.join("\n"),
+               if mismatches.len() > DEFAULT_DIFF_LIMIT { "\n  ..." } else { "" });
+    }
+}
+
+/// Executes a shader once and asserts that its output matches a reference closure, see the
+/// [`differential`] module.
+///
+/// The `pipeline` takes the parameters of `pipeline!` without the execution command. The
+/// closure `expected` is called with the `inputs`, whose fields are named after the input
+/// buffers and hold their contents, and the index of an element of the `output` buffer. It
+/// returns the expected element, which may differ from the output by at most the absolute
+/// `tolerance`. Given the `workgroup_count` and the `local_size` of the dispatch, the
+/// mismatches are reported with the invocations which wrote them.
+///
+/// # Panics
+///
+/// If an element of the output does not match, with the first mismatches, or like `pipeline!`.
+///
+/// # Example
+///
+/// ```ignore
+/// assert_shader_matches!{
+///     pipeline: {
+///         shader_path: "tests/shaders/example.comp",
+///         workgroup_count: [100, 100, 1],
+///         buffers: {
+///             data: [u32] from input_vec,
+///             result: [u32; NUM_INVOCATIONS]
+///         }
+///     },
+///     inputs: [data],
+///     output: result,
+///     expected: |inputs, i| inputs.data[i].wrapping_mul(i as u32),
+///     tolerance: 0
+/// };
+/// ```
+///
+/// [`differential`]: differential/index.html
+///
+#[macro_export]
+macro_rules! assert_shader_matches {
+    {
+        pipeline: { $( $pipeline_args:tt )* },
+        inputs: [ $( $input:ident ),* $(,)* ],
+        output: $output:ident,
+        expected: $expected:expr,
+        tolerance: $tolerance:expr,
+        workgroup_count: $workgroup_count:expr,
+        local_size: $local_size:expr $(,)*
+    } => {
+        assert_shader_matches!{
+            __check__ { $( $pipeline_args )* } [ $( $input ),* ] $output, $expected, $tolerance,
+            Some(::vulkanology::non_finite::Layout::new(
+                ::vulkanology::checked::workgroup_count($workgroup_count),
+                ::vulkanology::checked::workgroup_count($local_size),
+                1))
+        }
+    };
+    {
+        pipeline: { $( $pipeline_args:tt )* },
+        inputs: [ $( $input:ident ),* $(,)* ],
+        output: $output:ident,
+        expected: $expected:expr,
+        tolerance: $tolerance:expr $(,)*
+    } => {
+        assert_shader_matches!{
+            __check__ { $( $pipeline_args )* } [ $( $input ),* ] $output, $expected, $tolerance,
+            None
+        }
+    };
+    (__check__ { $( $pipeline_args:tt )* } [ $( $input:ident ),* ] $output:ident,
+     $expected:expr, $tolerance:expr, $layout:expr) => ({
+        use std::marker::PhantomData;
+        use vulkanology::differential;
+
+        pipeline!{ $( $pipeline_args )*, execution_command: execute }
+        execute();
+
+        $( let $input = read_buffer!($input); )*
+        // The fields of the inputs are named after the buffers, and so are their types.
+        #[allow(non_camel_case_types)]
+        struct Inputs<'a, $( $input: 'a + ?Sized ),*> {
+            $( $input: &'a $input, )*
+            _lifetime: PhantomData<&'a ()>,
+        }
+        let inputs = Inputs { $( $input: &*$input, )* _lifetime: PhantomData };
+        differential::assert_matches(stringify!($output),
+                                     &read_buffer!($output)[..],
+                                     &inputs,
+                                     $expected,
+                                     $tolerance as f64,
+                                     $layout);
+    });
+}
@@ -0,0 +1,356 @@
+//! This module exports buffers to CSV and NumPy `.npy` files, so the results of a test can be
+//! inspected with other tools, e.g. pandas or NumPy:
+//!
+//! ```ignore
+//! let output: ReadLock<[ArrayVec4<f32>]> = read_buffer!(result);
+//! export_buffer!(output, "target/result.npy");
+//! ```
+//!
+//! ```python
+//! import numpy
+//! result = numpy.load("target/result.npy")  # An array of the shape (len, 4).
+//! ```
+//!
+//! The elements are scalars, arrays of scalars, or the vectors and matrices of the [`glsl`]
+//! module. A CSV file has a row per element with its index and a column per scalar. A `.npy`
+//! file holds an array of the buffer length followed by the shape of an element, in which the
+//! matrices are indexed by their column first.
+//!
+//! [`glsl`]: ../glsl/index.html
+
+use std::fs::{create_dir_all, File};
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::Path;
+
+use glsl::{ArrayDVec2, ArrayDVec3, ArrayDVec4, ArrayMat2, ArrayMat3, ArrayMat4, ArrayVec2,
+           ArrayVec3, ArrayVec4};
+
+/// The scalar types of the exported and imported elements.
+pub trait Scalar: Copy + ToString {
+    /// The NumPy type of the scalar, e.g. `<f4`.
+    const DTYPE: &'static str;
+
+    /// The size of the scalar in bytes.
+    const SIZE: usize;
+
+    /// Appends the little endian bytes of the scalar to `out`.
+    fn write_le(&self, out: &mut Vec<u8>);
+
+    /// Reads the scalar from its `SIZE` little endian bytes.
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Returns the scalar as a `f64`, which may round 64-bit integers.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! scalars {
+    ( $( $scalar:ty: $dtype:expr,
+         |$value:ident| $to_bits:expr,
+         |$bits:ident| $from_bits:expr; )* ) => {
+        $(
+            impl Scalar for $scalar {
+                const DTYPE: &'static str = $dtype;
+                const SIZE: usize = mem::size_of::<$scalar>();
+
+                fn write_le(&self, out: &mut Vec<u8>) {
+                    let $value = *self;
+                    let bits: u64 = $to_bits;
+                    out.extend((0..Self::SIZE).map(|index| (bits >> (8 * index)) as u8));
+                }
+
+                fn read_le(bytes: &[u8]) -> $scalar {
+                    let $bits = bytes[..Self::SIZE]
+                        .iter()
+                        .rev()
+                        .fold(0u64, |bits, &byte| bits << 8 | byte as u64);
+                    $from_bits
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+scalars!{
+    u8: "|u1", |value| value as u64, |bits| bits as u8;
+    i8: "|i1", |value| value as u8 as u64, |bits| bits as i8;
+    u16: "<u2", |value| value as u64, |bits| bits as u16;
+    i16: "<i2", |value| value as u16 as u64, |bits| bits as i16;
+    u32: "<u4", |value| value as u64, |bits| bits as u32;
+    i32: "<i4", |value| value as u32 as u64, |bits| bits as i32;
+    u64: "<u8", |value| value, |bits| bits;
+    i64: "<i8", |value| value as u64, |bits| bits as i64;
+    f32: "<f4", |value| value.to_bits() as u64, |bits| f32::from_bits(bits as u32);
+    f64: "<f8", |value| value.to_bits(), |bits| f64::from_bits(bits);
+}
+
+/// The element types of the exported and imported buffers.
+pub trait Element {
+    /// The type of the scalars of the element.
+    type Scalar: Scalar;
+
+    /// The shape of the element, which is empty for a scalar.
+    const SHAPE: &'static [usize];
+
+    /// Appends the scalars of the element to `out`, with the last index of the shape changing
+    /// fastest.
+    fn scalars(&self, out: &mut Vec<Self::Scalar>);
+
+    /// Returns the element of the scalars `scalars`, which are as many as the shape holds and
+    /// ordered like by `scalars`.
+    fn from_scalars(scalars: &[Self::Scalar]) -> Self;
+}
+
+macro_rules! scalar_elements {
+    ( $( $scalar:ty ),* ) => {
+        $(
+            impl Element for $scalar {
+                type Scalar = $scalar;
+                const SHAPE: &'static [usize] = &[];
+
+                fn scalars(&self, out: &mut Vec<$scalar>) {
+                    out.push(*self);
+                }
+
+                fn from_scalars(scalars: &[$scalar]) -> $scalar {
+                    scalars[0]
+                }
+            }
+        )*
+    };
+}
+
+scalar_elements!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+macro_rules! array_elements {
+    ( $( $len:expr ),* ) => {
+        $(
+            impl<T: Scalar> Element for [T; $len] {
+                type Scalar = T;
+                const SHAPE: &'static [usize] = &[$len];
+
+                fn scalars(&self, out: &mut Vec<T>) {
+                    out.extend_from_slice(self);
+                }
+
+                fn from_scalars(scalars: &[T]) -> [T; $len] {
+                    let mut array = [scalars[0]; $len];
+                    array.copy_from_slice(&scalars[..$len]);
+                    array
+                }
+            }
+        )*
+    };
+}
+
+array_elements!(1, 2, 3, 4, 8, 16);
+
+macro_rules! vector_elements {
+    ( $( $vector:ident: $len:expr ),* ) => {
+        $(
+            impl<T: Scalar> Element for $vector<T> {
+                type Scalar = T;
+                const SHAPE: &'static [usize] = &[$len];
+
+                fn scalars(&self, out: &mut Vec<T>) {
+                    out.extend_from_slice(&self.0);
+                }
+
+                fn from_scalars(scalars: &[T]) -> $vector<T> {
+                    $vector(<[T; $len]>::from_scalars(scalars))
+                }
+            }
+        )*
+    };
+}
+
+vector_elements!(ArrayVec2: 2, ArrayVec3: 3, ArrayVec4: 4,
+                 ArrayDVec2: 2, ArrayDVec3: 3, ArrayDVec4: 4);
+
+macro_rules! matrix_elements {
+    ( $( $matrix:ident: $len:expr ),* ) => {
+        $(
+            impl Element for $matrix {
+                type Scalar = f32;
+                const SHAPE: &'static [usize] = &[$len, $len];
+
+                fn scalars(&self, out: &mut Vec<f32>) {
+                    for column in &self.0 {
+                        out.extend_from_slice(&column.0);
+                    }
+                }
+
+                fn from_scalars(scalars: &[f32]) -> $matrix {
+                    let mut matrix = $matrix(Default::default());
+                    for (column, scalars) in matrix.0.iter_mut().zip(scalars.chunks($len)) {
+                        column.0.copy_from_slice(scalars);
+                    }
+                    matrix
+                }
+            }
+        )*
+    };
+}
+
+matrix_elements!(ArrayMat2: 2, ArrayMat3: 3, ArrayMat4: 4);
+
+/// Returns the indices of the scalars of an element of the shape `shape`, e.g. `[1][0]`.
+fn scalar_indices(shape: &[usize]) -> Vec<String> {
+    shape.iter().fold(vec![String::new()], |prefixes, &len| {
+        prefixes.iter()
+            .flat_map(|prefix| (0..len).map(move |index| format!("{}[{}]", prefix, index)))
+            .collect()
+    })
+}
+
+/// Writes `data` as CSV to `out`. The header names the columns after the buffer `name`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::export::write_csv;
+///
+/// let mut csv = Vec::new();
+/// write_csv(&mut csv, "result", &[[1u32, 2], [3, 4]]).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(),
+///            "index,result[0],result[1]\n0,1,2\n1,3,4\n");
+/// # }
+/// ```
+///
+pub fn write_csv<W: Write, T: Element>(out: &mut W, name: &str, data: &[T]) -> io::Result<()> {
+    write!(out, "index")?;
+    for index in scalar_indices(T::SHAPE) {
+        write!(out, ",{}{}", name, index)?;
+    }
+    writeln!(out)?;
+
+    let mut scalars = Vec::new();
+    for (index, element) in data.iter().enumerate() {
+        scalars.clear();
+        element.scalars(&mut scalars);
+        write!(out, "{}", index)?;
+        for scalar in &scalars {
+            write!(out, ",{}", scalar.to_string())?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes `data` as a NumPy `.npy` file of the format version 1.0 to `out`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::export::write_npy;
+///
+/// let mut npy = Vec::new();
+/// write_npy(&mut npy, &[1.0f32, 2.0, 3.0]).unwrap();
+/// assert_eq!(&npy[..6], b"\x93NUMPY");
+/// assert_eq!(npy.len(), 128 + 3 * 4);
+/// assert!(String::from_utf8_lossy(&npy[10..128])
+///     .starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (3,), }"));
+/// # }
+/// ```
+///
+pub fn write_npy<W: Write, T: Element>(out: &mut W, data: &[T]) -> io::Result<()> {
+    let shape = Some(data.len())
+        .into_iter()
+        .chain(T::SHAPE.iter().cloned())
+        .map(|len| format!("{},", len))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': ({}), }}",
+                             T::Scalar::DTYPE,
+                             shape);
+    // The magic string, the version and the length of the header take 10 bytes, and the data
+    // starts at a multiple of 64 bytes after the header, which ends with a newline.
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    out.write_all(b"\x93NUMPY\x01\x00")?;
+    let header_len = header.len() as u16;
+    out.write_all(&[header_len as u8, (header_len >> 8) as u8])?;
+    out.write_all(header.as_bytes())?;
+
+    let mut scalars = Vec::new();
+    let mut bytes = Vec::new();
+    for element in data {
+        scalars.clear();
+        bytes.clear();
+        element.scalars(&mut scalars);
+        for scalar in &scalars {
+            scalar.write_le(&mut bytes);
+        }
+        out.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Exports `data` to the file `path`, as a NumPy `.npy` file if the path has the extension
+/// `npy`, and otherwise as CSV whose columns are named after the buffer `name`. Creates the
+/// parent directories of the file.
+///
+/// # Panics
+///
+/// If the file cannot be written.
+pub fn export<T: Element>(path: &Path, name: &str, data: &[T]) {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).expect("Failed to create the export directory.");
+    }
+    File::create(path)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            if path.extension().map_or(false, |extension| extension == "npy") {
+                write_npy(&mut out, data)?;
+            } else {
+                write_csv(&mut out, name, data)?;
+            }
+            out.flush()
+        })
+        .expect(format!("Failed to export the buffer `{}` to {}", name, path.display()).as_ref());
+}
+
+/// Exports the buffer `$buffer` to the file `$path`, see the [`export`] module. The buffer can
+/// be anything which can be sliced, e.g. the `ReadLock` of a buffer. It is written as a NumPy
+/// `.npy` file if the path ends with `.npy`, and otherwise as CSV.
+///
+/// # Panics
+///
+/// If the file cannot be written.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let result = vec![0.5f32, 1.5, 2.5];
+/// export_buffer!(result, "target/doc_export/result.csv");
+/// export_buffer!(result, "target/doc_export/result.npy");
+/// # }
+/// ```
+///
+/// [`export`]: export/index.html
+///
+#[macro_export]
+macro_rules! export_buffer {
+    ($buffer:expr, $path:expr) => {
+        ::vulkanology::export::export(::std::path::Path::new(&$path),
+                                      stringify!($buffer),
+                                      &$buffer[..])
+    };
+}
@@ -0,0 +1,171 @@
+//! This module contains the GLSL vector and matrix types with the memory layout of a `std430`
+//! buffer, so they can be the element type of a buffer in `pipeline!`:
+//!
+//! `buffers: { positions: [ArrayVec3<f32>; 1024], transforms: [ArrayMat4; 16] },`
+//!
+//! In contrast to the vectors of the [`push_constants`] module, which are packed into the following
+//! members like in a GLSL struct, the vectors of this module are aligned and padded like the
+//! elements of a GLSL array: an `ArrayVec3` has a size of 16 bytes, and the columns of an
+//! `ArrayMat3` are 16 bytes apart. The `Array` prefix tells them apart from the packed types, which
+//! carry the plain GLSL names. The vectors have 32-bit components, the `ArrayDVec` types have
+//! 64-bit components.
+//!
+//! The types convert from and into arrays, which is also the way to convert them from and into
+//! the types of a math library, e.g. `ArrayVec3::from(position.to_array())`.
+//!
+//! [`push_constants`]: ../push_constants/index.html
+
+use push_constants::Std430;
+
+macro_rules! glsl_vectors {
+    ( $( $(#[$attr:meta])* $vector:ident: $len:expr, align $alignment:expr,
+         [$( $scalar:ty ),*]; )* ) => {
+        $(
+            $(#[$attr])*
+            #[repr(C, align($alignment))]
+            #[derive(Debug, Copy, Clone, Default, PartialEq)]
+            pub struct $vector<T>(pub [T; $len]);
+
+            impl<T> From<[T; $len]> for $vector<T> {
+                fn from(components: [T; $len]) -> $vector<T> {
+                    $vector(components)
+                }
+            }
+
+            // The alignment is fixed by `repr`, so the layout is only implemented for the scalars
+            // of the matching size.
+            $(
+                impl From<$vector<$scalar>> for [$scalar; $len] {
+                    fn from(vector: $vector<$scalar>) -> [$scalar; $len] {
+                        vector.0
+                    }
+                }
+
+                // The padding of a three-component vector is part of the type, so its size is
+                // the size of the Rust type, which is its alignment.
+                impl Std430 for $vector<$scalar> {
+                    const ALIGNMENT: usize = $alignment;
+                    const SIZE: usize = $alignment;
+
+                    fn write(&self, out: &mut [u8]) {
+                        self.0.write(out);
+                    }
+                }
+            )*
+        )*
+    };
+}
+
+glsl_vectors!{
+    /// A GLSL `vec2`, `ivec2` or `uvec2` with the components of type `T`.
+    ArrayVec2: 2, align 8, [f32, i32, u32];
+    /// A GLSL `vec3`, `ivec3` or `uvec3` with the components of type `T`, which is padded to the
+    /// size of an `ArrayVec4`. Unlike in GLSL, a scalar which follows it in a struct is placed
+    /// behind the padding.
+    ArrayVec3: 3, align 16, [f32, i32, u32];
+    /// A GLSL `vec4`, `ivec4` or `uvec4` with the components of type `T`.
+    ArrayVec4: 4, align 16, [f32, i32, u32];
+    /// A GLSL `dvec2`, `i64vec2` or `u64vec2` with the components of type `T`.
+    ArrayDVec2: 2, align 16, [f64, i64, u64];
+    /// A GLSL `dvec3`, `i64vec3` or `u64vec3` with the components of type `T`, which is padded to
+    /// the size of an `ArrayDVec4`. Unlike in GLSL, a scalar which follows it in a struct is placed
+    /// behind the padding.
+    ArrayDVec3: 3, align 32, [f64, i64, u64];
+    /// A GLSL `dvec4`, `i64vec4` or `u64vec4` with the components of type `T`.
+    ArrayDVec4: 4, align 32, [f64, i64, u64];
+}
+
+macro_rules! glsl_matrices {
+    ( $( $(#[$attr:meta])* $matrix:ident: [$column:ident; $len:expr]; )* ) => {
+        $(
+            $(#[$attr])*
+            #[repr(C)]
+            #[derive(Debug, Copy, Clone, Default, PartialEq)]
+            pub struct $matrix(pub [$column<f32>; $len]);
+
+            impl $matrix {
+                /// Returns the identity matrix.
+                pub fn identity() -> $matrix {
+                    let mut matrix = $matrix::default();
+                    for (index, column) in matrix.0.iter_mut().enumerate() {
+                        column.0[index] = 1.0;
+                    }
+                    matrix
+                }
+            }
+
+            impl From<[[f32; $len]; $len]> for $matrix {
+                fn from(columns: [[f32; $len]; $len]) -> $matrix {
+                    let mut matrix = $matrix::default();
+                    for (column, values) in matrix.0.iter_mut().zip(columns.iter()) {
+                        column.0 = *values;
+                    }
+                    matrix
+                }
+            }
+
+            impl From<$matrix> for [[f32; $len]; $len] {
+                fn from(matrix: $matrix) -> [[f32; $len]; $len] {
+                    let mut columns = [[0.0; $len]; $len];
+                    for (values, column) in columns.iter_mut().zip(matrix.0.iter()) {
+                        *values = column.0;
+                    }
+                    columns
+                }
+            }
+
+            impl Std430 for $matrix {
+                const ALIGNMENT: usize = <$column<f32> as Std430>::ALIGNMENT;
+                const SIZE: usize = <[$column<f32>; $len] as Std430>::SIZE;
+
+                fn write(&self, out: &mut [u8]) {
+                    self.0.write(out);
+                }
+            }
+        )*
+    };
+}
+
+glsl_matrices!{
+    /// A GLSL `mat2` given as its columns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use std::mem;
+    /// use vulkanology::glsl::ArrayMat2;
+    ///
+    /// let matrix = ArrayMat2::from([[1.0, 2.0], [3.0, 4.0]]);
+    /// assert_eq!(mem::size_of::<ArrayMat2>(), 16);
+    /// assert_eq!((matrix.0[1]).0, [3.0, 4.0]);
+    /// # }
+    /// ```
+    ///
+    ArrayMat2: [ArrayVec2; 2];
+    /// A GLSL `mat3` given as its columns, each of which is padded to 16 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use std::mem;
+    /// use vulkanology::glsl::{ArrayMat3, ArrayVec3};
+    /// use vulkanology::push_constants::Std430;
+    ///
+    /// assert_eq!(mem::size_of::<ArrayVec3<f32>>(), 16);
+    /// assert_eq!(<ArrayVec3<f32> as Std430>::SIZE, 16);
+    /// assert_eq!(mem::size_of::<ArrayMat3>(), 48);
+    /// let columns: [[f32; 3]; 3] = ArrayMat3::identity().into();
+    /// assert_eq!(columns, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    /// # }
+    /// ```
+    ///
+    ArrayMat3: [ArrayVec3; 3];
+    /// A GLSL `mat4` given as its columns.
+    ArrayMat4: [ArrayVec4; 4];
+}
@@ -0,0 +1,145 @@
+//! This module converts between `f32` and half floats, which buffers store as `u16`.
+//!
+//! The arithmetic types and buffer accesses of half floats require the `shaderFloat16` feature of
+//! `VK_KHR_shader_float16_int8` and the features of `VK_KHR_16bit_storage`, which cannot be
+//! enabled with the version of `vulkano` used by `pipeline!`. It therefore rejects shaders
+//! declaring the `Float16` capability or one of the 16-bit storage capabilities, e.g.
+//! `StorageBuffer16BitAccess`.
+//!
+//! Half float kernels can still be validated with the `packHalf2x16` and `unpackHalf2x16`
+//! functions of GLSL 4.50, which convert a `uint` to and from a `vec2` of two half floats. A
+//! buffer of `u16` with an even length is bound to a `uint` array in this way, the low bits of
+//! every `uint` holding the element with the even index:
+//!
+//! ```glsl
+//! layout(set = 0, binding = 0, std430) buffer Data { uint data[]; };
+//!
+//! vec2 pair = unpackHalf2x16(data[gl_GlobalInvocationID.x]);
+//! data[gl_GlobalInvocationID.x] = packHalf2x16(pair * 2.0);
+//! ```
+//!
+//! The shader computes with `f32` in between, so the results only match a half float kernel if
+//! every intermediate result is rounded with `packHalf2x16`. Compare them with the [`compare`]
+//! module and a multiple of `EPSILON`.
+//!
+//! [`compare`]: ../compare/index.html
+
+/// The difference between 1.0 and the next larger half float. Rounding to a half float has a
+/// relative error of at most half of it.
+pub const EPSILON: f32 = 0.0009765625;
+
+/// The largest finite half float.
+pub const MAX: f32 = 65504.0;
+
+/// Converts `value` to the bits of the nearest half float, rounding ties to even. Values beyond
+/// `MAX` become infinite, and NaNs stay NaNs.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::half::f32_to_f16;
+///
+/// assert_eq!(f32_to_f16(1.0), 0x3c00);
+/// assert_eq!(f32_to_f16(-2.5), 0xc100);
+/// assert_eq!(f32_to_f16(1e6), 0x7c00);
+/// # }
+/// ```
+///
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinities keep a zero mantissa, NaNs keep a non-zero one.
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan | (mantissa >> 13) as u16;
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // Subnormal half floats have the implicit leading bit in their mantissa, and lose the bits of
+    // the difference of their exponent to the smallest normal exponent in addition.
+    let (exponent_bits, significand, shift) = if exponent > 0 {
+        ((exponent as u32) << 10, mantissa, 13)
+    } else if exponent >= -10 {
+        (0, mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        return sign;
+    };
+    let half = exponent_bits | (significand >> shift);
+    let remainder = significand & ((1 << shift) - 1);
+    let tie = 1 << (shift - 1);
+    // A carry out of the mantissa correctly increments the exponent, up to infinity.
+    if remainder > tie || (remainder == tie && half & 1 == 1) {
+        sign | (half + 1) as u16
+    } else {
+        sign | half as u16
+    }
+}
+
+/// Converts the bits of a half float to the equal `f32`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::half::f16_to_f32;
+///
+/// assert_eq!(f16_to_f32(0x3c00), 1.0);
+/// assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+/// assert!(f16_to_f32(0x7e00).is_nan());
+/// # }
+/// ```
+///
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = (half as u32 & 0x8000) << 16;
+    let exponent = (half as u32 >> 10) & 0x1f;
+    let mantissa = half as u32 & 0x3ff;
+
+    let bits = match exponent {
+        0 => {
+            // Subnormal half floats are normal in `f32`, their value is exact in both.
+            let magnitude = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -magnitude } else { magnitude };
+        }
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// Converts `values` to half floats with `f32_to_f16`, e.g. to initialize a buffer of `u16`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::half::{from_f16, to_f16};
+///
+/// let values = [0.5, 1.0 / 3.0, 100.0];
+/// let halves = from_f16(&to_f16(&values));
+/// assert_eq!(halves[0], 0.5);
+/// assert_eq!(halves[1], 0.33325195);
+/// # }
+/// ```
+///
+pub fn to_f16(values: &[f32]) -> Vec<u16> {
+    values.iter().map(|&value| f32_to_f16(value)).collect()
+}
+
+/// Converts the half floats `values` to `f32` with `f16_to_f32`, e.g. to compare the content of
+/// a buffer of `u16`.
+pub fn from_f16(values: &[u16]) -> Vec<f32> {
+    values.iter().map(|&value| f16_to_f32(value)).collect()
+}
@@ -0,0 +1,55 @@
+//! This module reports executions of `pipeline!` which do not finish in time.
+//!
+//! A shader with an infinite loop never signals the fence of its submission, and waiting for it
+//! would freeze the test binary until the operating system resets the driver. The execution
+//! command therefore waits at most for the timeout, 60 seconds unless it is overridden with
+//! `VULKANOLOGY_TIMEOUT`, see the [`overrides`] module. If the shader does not finish in time,
+//! the test panics with a report of the probable hang.
+//!
+//! The submissions of a hanging shader are leaked, as destroying the objects which they use
+//! would wait for the shader as well.
+//!
+//! [`overrides`]: ../overrides/index.html
+
+use std::time::Duration;
+
+use context;
+use overrides;
+
+/// The seconds which the execution command waits for a submission, unless it is overridden.
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Returns the report of a probable hang of the shader `shader`, which was dispatched with the
+/// workgroup count `workgroup_count` and did not finish within `timeout`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::time::Duration;
+/// use vulkanology::hang::report;
+///
+/// assert_eq!(report("tests/shaders/example.comp", [100, 100, 1], Duration::from_secs(60)),
+///            "Probable shader hang: the execution of `tests/shaders/example.comp` with the \
+///             workgroup count [100, 100, 1] did not finish within 60 s. The device of the \
+///             test is unknown. Set VULKANOLOGY_TIMEOUT to wait longer.");
+/// # }
+/// ```
+///
+pub fn report(shader: &str, workgroup_count: [u32; 3], timeout: Duration) -> String {
+    format!("Probable shader hang: the execution of `{}` with the workgroup count {:?} did not \
+             finish within {} s. {} Set {} to wait longer.",
+            shader,
+            workgroup_count,
+            timeout.as_secs(),
+            context::describe(),
+            overrides::TIMEOUT_VAR)
+}
+
+/// Panics with the report of a probable hang. The execution command calls this function if a
+/// submission did not finish in time.
+pub fn probable_hang(shader: &str, workgroup_count: [u32; 3], timeout: Duration) -> ! {
+    panic!("{}", report(shader, workgroup_count, timeout))
+}
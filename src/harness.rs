@@ -0,0 +1,263 @@
+//! This module contains a minimal test harness for test targets with `harness = false`.
+//!
+//! The harness understands the subset of the libtest command line which is used by `cargo test`
+//! and [cargo-nextest]: listing the tests with `--list --format terse`, and running the tests
+//! which match a filter and no `--skip` filter, optionally `--exact`. It runs the tests one after
+//! another, so tests of one binary never compete for the GPU, while nextest takes care of running
+//! binaries concurrently, of timeouts and of retries.
+//!
+//! Register the test target in `Cargo.toml`:
+//!
+//! ```toml
+//! [[test]]
+//! name = "gpu"
+//! harness = false
+//! ```
+//!
+//! and generate its `main` with `test_harness!`:
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate vulkanology;
+//!
+//! fn test_example() {
+//!     // ...
+//! }
+//!
+//! test_harness!{ test_example }
+//! ```
+//!
+//! If `VULKANOLOGY_JUNIT` is set, the harness writes a JUnit XML report of the tests to the path
+//! given by the variable, see the [`junit`] module. The failure messages of the report name the
+//! device of the test, see the [`context`] module.
+//!
+//! [cargo-nextest]: https://nexte.st
+//! [`context`]: ../context/index.html
+//! [`junit`]: ../junit/index.html
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::panic;
+use std::process;
+use std::time::Instant;
+
+use context;
+use junit::{self, TestResult};
+
+thread_local! {
+    static DEVICE: RefCell<Option<String>> = RefCell::new(None);
+    static NOTES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    static SKIPPED: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Records the name of the physical device which the current test runs on, so it appears in the
+/// report. `pipeline!` calls this function after selecting the device.
+pub fn record_device(name: &str) {
+    DEVICE.with(|device| *device.borrow_mut() = Some(name.to_owned()));
+}
+
+/// Records a note about the current test, e.g. an applied quirk, so it appears in the report.
+pub fn record_note(note: String) {
+    NOTES.with(|notes| notes.borrow_mut().push(note));
+}
+
+/// Notes on stderr and in the report that the test of the shader `shader` is skipped, as the
+/// device `device` does not support the `missing` features, and reports the test as skipped.
+/// `pipeline!` calls this function before it returns from the test.
+pub fn note_skipped(shader: &str, device: &str, missing: &[&str]) {
+    let features = missing.iter().map(|feature| format!("`{}`", feature)).collect::<Vec<_>>();
+    let note = format!("Skipped `{}`: the device `{}` does not support {}.",
+                       shader,
+                       device,
+                       features.join(", "));
+    let _ = writeln!(io::stderr(), "note: {}", note);
+    SKIPPED.with(|skipped| *skipped.borrow_mut() = Some(note.clone()));
+    record_note(note);
+}
+
+/// Notes on stderr and in the report that the test at `location` is skipped, as Vulkan is not
+/// available for the reason `reason`, and reports the test as skipped. `instance!` and
+/// `pipeline!` call this function with `unavailable: skip` before they return from the test.
+pub fn note_unavailable(location: &str, reason: &str) {
+    let note = format!("Skipped the test at {}: Vulkan is not available, {}.", location, reason);
+    let _ = writeln!(io::stderr(), "note: {}", note);
+    SKIPPED.with(|skipped| *skipped.borrow_mut() = Some(note.clone()));
+    record_note(note);
+}
+
+/// A test of the harness.
+#[derive(Debug, Copy, Clone)]
+pub struct Test {
+    /// The name of the test, usually the name of the test function.
+    pub name: &'static str,
+    /// The test function, which fails by panicking.
+    pub run: fn(),
+}
+
+/// Runs `tests` with the command line arguments of the process and exits with the result.
+pub fn main(tests: &[Test]) -> ! {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    process::exit(run(tests, &args))
+}
+
+/// Runs `tests` with the command line arguments `args` and returns the exit code, which is 0 if
+/// all selected tests passed.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::harness::{run, Test};
+///
+/// fn passes() {}
+/// fn fails() {
+///     panic!("failed");
+/// }
+///
+/// let tests = [Test { name: "passes", run: passes }, Test { name: "fails", run: fails }];
+/// let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+///
+/// assert_eq!(run(&tests, &args(&["--list", "--format", "terse"])), 0);
+/// assert_eq!(run(&tests, &args(&["passes", "--exact", "--nocapture"])), 0);
+/// assert_eq!(run(&tests, &args(&["fails"])), 101);
+/// assert_eq!(run(&tests, &args(&["--skip", "fails"])), 0);
+/// # }
+/// ```
+///
+pub fn run(tests: &[Test], args: &[String]) -> i32 {
+    let mut list = false;
+    let mut exact = false;
+    let mut ignored = false;
+    let mut filters = Vec::new();
+    let mut skips = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_ref() {
+            "--list" => list = true,
+            "--exact" => exact = true,
+            "--ignored" => ignored = true,
+            "--skip" => skips.extend(args.next().cloned()),
+            // Options with a value, whose value does not matter to this harness.
+            "--format" | "--test-threads" | "--color" | "--logfile" => {
+                args.next();
+            }
+            arg if arg.starts_with('-') => {}
+            filter => filters.push(filter.to_owned()),
+        }
+    }
+
+    let matches = |name: &str, filter: &String| if exact {
+        name == filter
+    } else {
+        name.contains(filter.as_str())
+    };
+
+    // None of the tests is ignored, so `--ignored` selects no test.
+    let selected = tests.iter()
+        .filter(|test| {
+            let included = filters.is_empty() ||
+                           filters.iter().any(|filter| matches(test.name, filter));
+            let skipped = skips.iter().any(|skip| matches(test.name, skip));
+            !ignored && included && !skipped
+        })
+        .collect::<Vec<_>>();
+
+    if list {
+        for test in selected {
+            println!("{}: test", test.name);
+        }
+        return 0;
+    }
+
+    println!("\nrunning {} tests", selected.len());
+    let mut failed = Vec::new();
+    let mut results = Vec::new();
+    for test in &selected {
+        DEVICE.with(|device| *device.borrow_mut() = None);
+        NOTES.with(|notes| notes.borrow_mut().clear());
+        SKIPPED.with(|skipped| *skipped.borrow_mut() = None);
+        context::clear();
+        let start = Instant::now();
+        let result = panic::catch_unwind(test.run);
+        let duration = start.elapsed();
+        let failure = match result {
+            Ok(()) => {
+                println!("test {} ... ok", test.name);
+                None
+            }
+            Err(payload) => {
+                println!("test {} ... FAILED", test.name);
+                failed.push(test.name);
+                let message = panic_message(&*payload);
+                Some(match context::current() {
+                    Some(_) => format!("{}\n{}", message, context::describe()),
+                    None => message,
+                })
+            }
+        };
+        results.push(TestResult {
+            name: test.name.to_owned(),
+            device: DEVICE.with(|device| device.borrow_mut().take()),
+            duration: duration,
+            failure: failure,
+            skipped: SKIPPED.with(|skipped| skipped.borrow_mut().take()),
+            notes: NOTES.with(|notes| notes.borrow_mut().split_off(0)),
+        });
+    }
+
+    if let Some(path) = env::var_os(junit::JUNIT_VAR) {
+        let suite = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "tests".to_owned());
+        File::create(&path)
+            .and_then(|mut file| junit::write_report(&mut file, &suite, &results))
+            .expect("Failed to write the JUnit report.");
+    }
+
+    if failed.is_empty() {
+        println!("\ntest result: ok. {} passed; 0 failed\n", selected.len());
+        0
+    } else {
+        println!("\nfailures:");
+        for name in &failed {
+            println!("    {}", name);
+        }
+        println!("\ntest result: FAILED. {} passed; {} failed\n",
+                 selected.len() - failed.len(),
+                 failed.len());
+        101
+    }
+}
+
+/// Returns the message of a panic.
+fn panic_message(payload: &(Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => {
+            payload.downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "The test panicked.".to_owned())
+        }
+    }
+}
+
+/// Generates the `main` function of a test target with `harness = false`, which runs the given
+/// test functions with the [harness] of this crate.
+///
+/// [harness]: harness/index.html
+///
+#[macro_export]
+macro_rules! test_harness {
+    ( $( $test:ident ),* $(,)* ) => {
+        fn main() {
+            use vulkanology::harness::{self, Test};
+            harness::main(&[ $( Test { name: stringify!($test), run: $test } ),* ])
+        }
+    };
+}
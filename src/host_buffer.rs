@@ -0,0 +1,533 @@
+//! This module contains a host visible buffer whose memory type is chosen by its properties.
+//!
+//! vulkano allocates every `CpuAccessibleBuffer` in the first host visible memory type which
+//! supports it, which is host coherent on most drivers. The driver paths of cached and of
+//! non-coherent memory, i.e. the flushes after the host writes and the invalidations before the
+//! host reads, are thus never exercised. A `HostBuffer` is allocated in the first host visible
+//! memory type which has the requested `MemoryProperties`, and is otherwise locked, read and
+//! written like a `CpuAccessibleBuffer`. It requires the optional `vulkano` feature.
+//!
+//! `cpu_array_buffer!` creates a `HostBuffer` with `memory: $properties`:
+//!
+//! ```ignore
+//! use vulkanology::host_buffer::MemoryProperties;
+//!
+//! let cached = MemoryProperties { host_cached: true, ..MemoryProperties::none() };
+//! let buffer = cpu_array_buffer!(device, queue, memory: cached, u32, from input_vec);
+//! assert!(buffer.memory_type().is_host_cached());
+//! ```
+//!
+//! The buffers of `pipeline!` are always `CpuAccessibleBuffer`s, so a `HostBuffer` is only used
+//! by the command buffers which a test builds itself, e.g. to copy it to another buffer.
+//!
+//! The whole allocation is mapped by every lock, so the flushed and invalidated range of
+//! non-coherent memory ends at the end of the allocation, as Vulkan requires of ranges which are
+//! not a multiple of `nonCoherentAtomSize`.
+
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut, Range};
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
+use std::time::Duration;
+
+use vulkano::OomError;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::sys::{BufferCreationError, SparseLevel, UnsafeBuffer};
+use vulkano::buffer::traits::{AccessRange, Buffer, CommandBufferState, CommandListState,
+                              GpuAccessResult, PipelineBarrierRequest,
+                              PipelineMemoryBarrierRequest, SubmitInfos, TrackedBuffer,
+                              TypedBuffer};
+use vulkano::command_buffer::Submission;
+use vulkano::device::{Device, Queue};
+use vulkano::instance::{MemoryType, QueueFamily};
+use vulkano::memory::{Content, CpuAccess, DeviceMemory, MappedDeviceMemory};
+use vulkano::sync::{AccessFlagBits, Fence, FenceWaitError, PipelineStages, Sharing};
+
+/// The properties which the memory type of a `HostBuffer` must have besides being host visible.
+/// The properties which are `false` are not restricted.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::host_buffer::MemoryProperties;
+///
+/// let cached = MemoryProperties { host_cached: true, ..MemoryProperties::none() };
+/// assert!(cached.host_cached);
+/// assert!(!cached.host_coherent);
+/// # }
+/// ```
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryProperties {
+    /// The memory is cached on the host, which makes host reads fast.
+    pub host_cached: bool,
+    /// The memory is coherent, so host writes and device writes need not be flushed and
+    /// invalidated.
+    pub host_coherent: bool,
+}
+
+impl MemoryProperties {
+    /// Returns the properties which every host visible memory type has.
+    pub fn none() -> MemoryProperties {
+        MemoryProperties {
+            host_cached: false,
+            host_coherent: false,
+        }
+    }
+
+    /// Returns whether the memory type `memory_type` is host visible and has the properties.
+    pub fn matches(&self, memory_type: MemoryType) -> bool {
+        memory_type.is_host_visible() && (!self.host_cached || memory_type.is_host_cached()) &&
+        (!self.host_coherent || memory_type.is_host_coherent())
+    }
+}
+
+/// A buffer in host visible memory with the requested `MemoryProperties`, see the [module].
+///
+/// [module]: index.html
+#[derive(Debug)]
+pub struct HostBuffer<T: ?Sized> {
+    inner: UnsafeBuffer,
+    memory: MappedDeviceMemory,
+    queue_families: Vec<u32>,
+    // The latest submissions which use the buffer. The lock also blocks any submission while the
+    // host accesses the buffer.
+    latest_submission: RwLock<LatestSubmission>,
+    marker: PhantomData<Box<T>>,
+}
+
+#[derive(Debug)]
+struct LatestSubmission {
+    read_submissions: Mutex<Vec<Weak<Submission>>>,
+    write_submission: Option<Weak<Submission>>,
+}
+
+impl<T> HostBuffer<T> {
+    /// Creates a buffer holding the value `data`.
+    pub fn from_data<'a, I>(device: &Arc<Device>,
+                            usage: &BufferUsage,
+                            memory: &MemoryProperties,
+                            queue_families: I,
+                            data: T)
+                            -> Result<Arc<HostBuffer<T>>, OomError>
+        where I: IntoIterator<Item = QueueFamily<'a>>,
+              T: Content + 'static
+    {
+        unsafe {
+            let buffer = HostBuffer::uninitialized(device, usage, memory, queue_families)?;
+            {
+                let mut mapping = buffer.write(Duration::new(0, 0)).unwrap();
+                ptr::write::<T>(&mut *mapping, data);
+            }
+            Ok(buffer)
+        }
+    }
+
+    /// Creates a buffer holding a single uninitialized value.
+    pub unsafe fn uninitialized<'a, I>(device: &Arc<Device>,
+                                       usage: &BufferUsage,
+                                       memory: &MemoryProperties,
+                                       queue_families: I)
+                                       -> Result<Arc<HostBuffer<T>>, OomError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        HostBuffer::raw(device, mem::size_of::<T>(), usage, memory, queue_families)
+    }
+}
+
+impl<T> HostBuffer<[T]> {
+    /// Creates a buffer holding the elements of the iterator `data`.
+    ///
+    /// # Panics
+    ///
+    /// If the iterator yields fewer elements than its `len()`.
+    pub fn from_iter<'a, I, Q>(device: &Arc<Device>,
+                               usage: &BufferUsage,
+                               memory: &MemoryProperties,
+                               queue_families: Q,
+                               data: I)
+                               -> Result<Arc<HostBuffer<[T]>>, OomError>
+        where I: ExactSizeIterator<Item = T>,
+              T: Content + 'static,
+              Q: IntoIterator<Item = QueueFamily<'a>>
+    {
+        unsafe {
+            let buffer =
+                HostBuffer::uninitialized_array(device, data.len(), usage, memory, queue_families)?;
+            {
+                let mut mapping = buffer.write(Duration::new(0, 0)).unwrap();
+                let len = mapping.len();
+                let mut written = 0;
+                for (element, slot) in data.zip(mapping.iter_mut()) {
+                    ptr::write(slot, element);
+                    written += 1;
+                }
+                if written != len {
+                    panic!("The iterator yielded {} elements, but its length is {}.",
+                           written,
+                           len);
+                }
+            }
+            Ok(buffer)
+        }
+    }
+
+    /// Creates a buffer holding `len` uninitialized elements.
+    pub unsafe fn uninitialized_array<'a, I>(device: &Arc<Device>,
+                                             len: usize,
+                                             usage: &BufferUsage,
+                                             memory: &MemoryProperties,
+                                             queue_families: I)
+                                             -> Result<Arc<HostBuffer<[T]>>, OomError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        HostBuffer::raw(device, len * mem::size_of::<T>(), usage, memory, queue_families)
+    }
+}
+
+impl<T: ?Sized> HostBuffer<T> {
+    /// Creates a buffer of `size` bytes, which must be a valid size of `T`, in the first memory
+    /// type of the device which supports the buffer and has the properties `memory`.
+    ///
+    /// # Panics
+    ///
+    /// If no such memory type exists.
+    pub unsafe fn raw<'a, I>(device: &Arc<Device>,
+                             size: usize,
+                             usage: &BufferUsage,
+                             memory: &MemoryProperties,
+                             queue_families: I)
+                             -> Result<Arc<HostBuffer<T>>, OomError>
+        where I: IntoIterator<Item = QueueFamily<'a>>
+    {
+        let queue_families = queue_families.into_iter()
+            .map(|family| family.id())
+            .collect::<Vec<_>>();
+        let (buffer, requirements) = {
+            let sharing = if queue_families.len() >= 2 {
+                Sharing::Concurrent(queue_families.iter().cloned())
+            } else {
+                Sharing::Exclusive
+            };
+            match UnsafeBuffer::new(device, size, usage, sharing, SparseLevel::none()) {
+                Ok(buffer) => buffer,
+                Err(BufferCreationError::OomError(error)) => return Err(error),
+                // The buffer is not sparse, so no other error can occur.
+                Err(_) => unreachable!(),
+            }
+        };
+
+        let memory_type = device.physical_device()
+            .memory_types()
+            .filter(|memory_type| requirements.memory_type_bits & (1 << memory_type.id()) != 0)
+            .find(|&memory_type| memory.matches(memory_type));
+        let memory_type = match memory_type {
+            Some(memory_type) => memory_type,
+            None => {
+                panic!("The device has no host visible memory type which supports the buffer and \
+                        has the properties {:?}.",
+                       memory)
+            }
+        };
+        let mapped = DeviceMemory::alloc_and_map(device, memory_type, requirements.size)?;
+        buffer.bind_memory(mapped.memory(), 0)?;
+
+        Ok(Arc::new(HostBuffer {
+            inner: buffer,
+            memory: mapped,
+            queue_families: queue_families,
+            latest_submission: RwLock::new(LatestSubmission {
+                read_submissions: Mutex::new(Vec::new()),
+                write_submission: None,
+            }),
+            marker: PhantomData,
+        }))
+    }
+
+    /// Returns the device of the buffer.
+    pub fn device(&self) -> &Arc<Device> {
+        self.inner.device()
+    }
+
+    /// Returns the memory type in which the buffer is allocated.
+    pub fn memory_type(&self) -> MemoryType {
+        self.memory.memory().memory_type()
+    }
+}
+
+impl<T: ?Sized> HostBuffer<T>
+    where T: Content + 'static
+{
+    /// Locks the buffer for reading, after waiting up to `timeout` for the last submission which
+    /// writes it. Submissions which write the buffer block until the lock is dropped.
+    pub fn read(&self, timeout: Duration) -> Result<ReadLock<T>, FenceWaitError> {
+        let submission = self.latest_submission.read().unwrap();
+        if let Some(submission) = submission.write_submission.clone().and_then(|s| s.upgrade()) {
+            submission.wait(timeout)?;
+        }
+        Ok(ReadLock {
+            inner: unsafe { self.map() },
+            lock: submission,
+        })
+    }
+
+    /// Locks the buffer for writing, after waiting up to `timeout` for the last submissions
+    /// which use it. Submissions which use the buffer block until the lock is dropped.
+    pub fn write(&self, timeout: Duration) -> Result<WriteLock<T>, FenceWaitError> {
+        let mut submission = self.latest_submission.write().unwrap();
+        for read in submission.read_submissions.get_mut().unwrap().drain(..) {
+            if let Some(read) = read.upgrade() {
+                read.wait(timeout)?;
+            }
+        }
+        if let Some(write) = submission.write_submission.take().and_then(|s| s.upgrade()) {
+            write.wait(timeout)?;
+        }
+        Ok(WriteLock {
+            inner: unsafe { self.map() },
+            lock: submission,
+        })
+    }
+
+    /// Maps the whole allocation and returns the content of the buffer at its start.
+    unsafe fn map(&self) -> CpuAccess<T> {
+        let size = self.inner.size();
+        self.memory
+            .read_write::<[u8]>(0..self.memory.memory().size())
+            .map(|bytes| T::ref_from_ptr(bytes as *mut u8 as *mut c_void, size).unwrap())
+    }
+}
+
+unsafe impl<T: ?Sized> Buffer for HostBuffer<T>
+    where T: 'static + Send + Sync
+{
+    fn inner(&self) -> &UnsafeBuffer {
+        &self.inner
+    }
+
+    fn blocks(&self, _: Range<usize>) -> Vec<usize> {
+        vec![0]
+    }
+
+    fn block_memory_range(&self, _: usize) -> Range<usize> {
+        0..self.size()
+    }
+
+    fn needs_fence(&self, _: bool, _: Range<usize>) -> Option<bool> {
+        Some(true)
+    }
+
+    fn host_accesses(&self, _: usize) -> bool {
+        true
+    }
+
+    unsafe fn gpu_access(&self,
+                         ranges: &mut Iterator<Item = AccessRange>,
+                         submission: &Arc<Submission>)
+                         -> GpuAccessResult {
+        let queue_id = submission.queue().family().id();
+        if !self.queue_families.contains(&queue_id) {
+            panic!("The buffer is submitted to the queue family {}, but only supports the queue \
+                    families {:?}.",
+                   queue_id,
+                   self.queue_families);
+        }
+
+        let mut written = false;
+        for range in ranges {
+            written |= range.write;
+        }
+
+        let dependencies = if written {
+            let mut submissions = self.latest_submission.write().unwrap();
+            let write = mem::replace(&mut submissions.write_submission,
+                                     Some(Arc::downgrade(submission)));
+            let reads = mem::replace(&mut *submissions.read_submissions.get_mut().unwrap(),
+                                     Vec::new());
+            reads.into_iter().chain(write).filter_map(|s| s.upgrade()).collect()
+        } else {
+            let submissions = self.latest_submission.read().unwrap();
+            submissions.read_submissions.lock().unwrap().push(Arc::downgrade(submission));
+            submissions.write_submission.clone().and_then(|s| s.upgrade()).into_iter().collect()
+        };
+
+        GpuAccessResult {
+            dependencies: dependencies,
+            additional_wait_semaphore: None,
+            additional_signal_semaphore: None,
+        }
+    }
+}
+
+unsafe impl<T: ?Sized> TypedBuffer for HostBuffer<T>
+    where T: 'static + Send + Sync
+{
+    type Content = T;
+}
+
+unsafe impl<T: ?Sized> TrackedBuffer for HostBuffer<T>
+    where T: 'static + Send + Sync
+{
+    type CommandListState = HostBufferListState;
+    type FinishedState = HostBufferFinishedState;
+
+    fn initial_state(&self) -> HostBufferListState {
+        // The host may write the buffer at any time before the submission.
+        HostBufferListState {
+            size: self.size(),
+            stages: PipelineStages { host: true, ..PipelineStages::none() },
+            access: AccessFlagBits { host_write: true, ..AccessFlagBits::none() },
+            write: true,
+            earliest_previous_transition: 0,
+            needs_flush_at_the_end: false,
+        }
+    }
+}
+
+/// The state of a `HostBuffer` in a command buffer which is being built. It requests the same
+/// barriers as the state of a `CpuAccessibleBuffer`.
+#[derive(Debug)]
+pub struct HostBufferListState {
+    size: usize,
+    stages: PipelineStages,
+    access: AccessFlagBits,
+    write: bool,
+    earliest_previous_transition: usize,
+    needs_flush_at_the_end: bool,
+}
+
+impl HostBufferListState {
+    /// Returns the barrier from the previous accesses to the access `access` in the stages
+    /// `stages`.
+    fn barrier(&self,
+               stages: PipelineStages,
+               access: AccessFlagBits,
+               memory_barrier: bool)
+               -> PipelineBarrierRequest {
+        PipelineBarrierRequest {
+            after_command_num: self.earliest_previous_transition,
+            source_stage: self.stages,
+            destination_stages: stages,
+            by_region: true,
+            memory_barrier: if memory_barrier {
+                Some(PipelineMemoryBarrierRequest {
+                    offset: 0,
+                    size: self.size,
+                    source_access: self.access,
+                    destination_access: access,
+                })
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl CommandListState for HostBufferListState {
+    type FinishedState = HostBufferFinishedState;
+
+    fn transition(self,
+                  num_command: usize,
+                  _: &UnsafeBuffer,
+                  _: usize,
+                  _: usize,
+                  write: bool,
+                  stage: PipelineStages,
+                  access: AccessFlagBits)
+                  -> (HostBufferListState, Option<PipelineBarrierRequest>) {
+        debug_assert!(!stage.host && !access.host_read && !access.host_write);
+
+        if write || self.write {
+            // Write after read or write, and read after write.
+            let barrier = self.barrier(stage, access, self.write);
+            let state = HostBufferListState {
+                stages: stage,
+                access: access,
+                write: write,
+                earliest_previous_transition: num_command,
+                needs_flush_at_the_end: write || self.needs_flush_at_the_end,
+                ..self
+            };
+            (state, Some(barrier))
+        } else {
+            // Read after read.
+            let state = HostBufferListState {
+                stages: self.stages | stage,
+                access: self.access | access,
+                ..self
+            };
+            (state, None)
+        }
+    }
+
+    fn finish(self) -> (HostBufferFinishedState, Option<PipelineBarrierRequest>) {
+        let barrier = if self.needs_flush_at_the_end {
+            Some(self.barrier(PipelineStages { host: true, ..PipelineStages::none() },
+                              AccessFlagBits { host_read: true, ..AccessFlagBits::none() },
+                              true))
+        } else {
+            None
+        };
+        (HostBufferFinishedState, barrier)
+    }
+}
+
+/// The state of a `HostBuffer` in a built command buffer. The submission needs no semaphores or
+/// barriers, since the host waits for the submissions when it locks the buffer.
+#[derive(Debug)]
+pub struct HostBufferFinishedState;
+
+impl CommandBufferState for HostBufferFinishedState {
+    fn on_submit<B, F>(&self, _: &B, _: &Arc<Queue>, _: F) -> SubmitInfos
+        where B: Buffer,
+              F: FnOnce() -> Arc<Fence>
+    {
+        SubmitInfos {
+            pre_semaphore: None,
+            post_semaphore: None,
+            pre_barrier: None,
+            post_barrier: None,
+        }
+    }
+}
+
+/// A lock for reading the content of a `HostBuffer`.
+pub struct ReadLock<'a, T: ?Sized + 'a> {
+    inner: CpuAccess<'a, T>,
+    #[allow(dead_code)]
+    lock: RwLockReadGuard<'a, LatestSubmission>,
+}
+
+impl<'a, T: ?Sized + 'a> Deref for ReadLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// A lock for writing the content of a `HostBuffer`.
+pub struct WriteLock<'a, T: ?Sized + 'a> {
+    inner: CpuAccess<'a, T>,
+    #[allow(dead_code)]
+    lock: RwLockWriteGuard<'a, LatestSubmission>,
+}
+
+impl<'a, T: ?Sized + 'a> Deref for WriteLock<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<'a, T: ?Sized + 'a> DerefMut for WriteLock<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
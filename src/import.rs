@@ -0,0 +1,189 @@
+//! This module loads the contents of input buffers from binary files, so datasets captured from
+//! an application can drive a shader test deterministically. A buffer of `pipeline!` takes its
+//! length and contents from a file with `from_file`, relative to the working directory of the
+//! test, which is the crate root under `cargo test`:
+//!
+//! `buffers: { positions: [ArrayVec4<f32>] from_file "tests/data/positions.npy" },`
+//!
+//! The format of a file is detected from its content:
+//!
+//! - NumPy `.npy` files, whose type must match the scalars of the element type and whose shape
+//!   must be the number of elements followed by the shape of an element, e.g. `(1024, 4)` for a
+//!   buffer of `ArrayVec4<f32>`. Arrays in Fortran order are rejected.
+//! - Files recorded by the [`snapshots`] module, which hold the raw elements after a small
+//!   header with their size.
+//! - Any other file is read as the raw little endian scalars of the elements, without padding.
+//!
+//! The element types are those of the [`export`] module, so a buffer exported with
+//! `export_buffer!` can be loaded again.
+//!
+//! [`export`]: ../export/index.html
+//! [`snapshots`]: ../snapshots/index.html
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use export::{Element, Scalar};
+use snapshots;
+
+/// Returns the elements stored in the file `path`.
+///
+/// # Panics
+///
+/// * If the file cannot be read.
+/// * If its content does not match the element type `T`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::path::Path;
+/// use vulkanology::export::export;
+/// use vulkanology::import::load;
+///
+/// let path = Path::new("target/doc_import/points.npy");
+/// export(path, "points", &[[1.0f32, 2.0], [3.0, 4.0]]);
+/// assert_eq!(load::<[f32; 2]>(path), [[1.0, 2.0], [3.0, 4.0]]);
+/// # }
+/// ```
+///
+pub fn load<T: Element + Copy>(path: &Path) -> Vec<T> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .expect(format!("Failed to read the test vectors: {}", path.display()).as_ref());
+    let name = path.display().to_string();
+    if bytes.starts_with(b"\x93NUMPY") {
+        parse_npy(&name, &bytes)
+    } else if snapshots::is_snapshot(&bytes) {
+        snapshots::read(path)
+    } else {
+        parse_raw(&name, &bytes)
+    }
+}
+
+/// Returns the scalars of an element of the type `T`.
+fn scalars_per_element<T: Element>() -> usize {
+    T::SHAPE.iter().product()
+}
+
+/// Returns the elements of the raw little endian scalars `bytes` of the file `name`.
+///
+/// # Panics
+///
+/// If the bytes are not a whole number of elements.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::import::parse_raw;
+///
+/// assert_eq!(parse_raw::<u16>("data.bin", &[1, 0, 0, 1]), [1, 256]);
+/// # }
+/// ```
+///
+pub fn parse_raw<T: Element>(name: &str, bytes: &[u8]) -> Vec<T> {
+    let element_size = scalars_per_element::<T>() * T::Scalar::SIZE;
+    if bytes.len() % element_size != 0 {
+        panic!("The test vectors {} have {} bytes, which is not a multiple of the element size \
+                {}.",
+               name,
+               bytes.len(),
+               element_size);
+    }
+    let scalars = bytes.chunks(T::Scalar::SIZE).map(T::Scalar::read_le).collect::<Vec<_>>();
+    scalars.chunks(scalars_per_element::<T>()).map(T::from_scalars).collect()
+}
+
+/// Returns the value of the entry `key` in the header of a `.npy` file.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}':", key))? + key.len() + 3;
+    let value = header[start..].trim();
+    let end = if value.starts_with('(') {
+        value.find(')')? + 1
+    } else {
+        value.find(',').unwrap_or(value.len())
+    };
+    Some(value[..end].trim())
+}
+
+/// Returns the elements of the `.npy` file `name` with the content `bytes`.
+///
+/// # Panics
+///
+/// If the file is malformed, or if its type or shape does not match the element type `T`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::export::write_npy;
+/// use vulkanology::glsl::ArrayVec3;
+/// use vulkanology::import::parse_npy;
+///
+/// let mut npy = Vec::new();
+/// write_npy(&mut npy, &[ArrayVec3([1.0f32, 2.0, 3.0])]).unwrap();
+/// assert_eq!(parse_npy::<ArrayVec3<f32>>("data.npy", &npy), [ArrayVec3([1.0, 2.0, 3.0])]);
+/// # }
+/// ```
+///
+pub fn parse_npy<T: Element>(name: &str, bytes: &[u8]) -> Vec<T> {
+    let malformed = || -> ! { panic!("The test vectors {} are not a valid `.npy` file.", name) };
+    if bytes.len() < 10 {
+        malformed();
+    }
+    // Version 1 stores the length of the header in 2 bytes, later versions in 4 bytes.
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, bytes[8] as usize | (bytes[9] as usize) << 8),
+        2 | 3 if bytes.len() >= 12 => {
+            (12, bytes[8..12].iter().rev().fold(0, |len, &byte| len << 8 | byte as usize))
+        }
+        _ => malformed(),
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start {
+        malformed();
+    }
+    let header = String::from_utf8_lossy(&bytes[header_start..data_start]);
+
+    let descr = header_value(&header, "descr").unwrap_or_else(|| malformed());
+    let dtype = descr.trim_matches(|c| c == '\'' || c == '"');
+    if dtype != T::Scalar::DTYPE {
+        panic!("The test vectors {} have the type `{}`, expected `{}`.",
+               name,
+               dtype,
+               T::Scalar::DTYPE);
+    }
+    if header_value(&header, "fortran_order") != Some("False") {
+        panic!("The test vectors {} are stored in Fortran order.", name);
+    }
+    let shape = header_value(&header, "shape")
+        .unwrap_or_else(|| malformed())
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|len| len.trim())
+        .filter(|len| !len.is_empty())
+        .map(|len| len.parse::<usize>().unwrap_or_else(|_| malformed()))
+        .collect::<Vec<_>>();
+    if shape.is_empty() || &shape[1..] != T::SHAPE {
+        panic!("The test vectors {} have the shape {:?}, expected [len] followed by {:?}.",
+               name,
+               shape,
+               T::SHAPE);
+    }
+
+    let data = &bytes[data_start..];
+    let len = shape[0] * scalars_per_element::<T>() * T::Scalar::SIZE;
+    if data.len() < len {
+        malformed();
+    }
+    parse_raw(name, &data[..len])
+}
@@ -0,0 +1,104 @@
+//! This module checks the invocation counts written by the `invocation_count` GLSL segment, see
+//! `build_utils::builtin_segment`.
+//!
+//! Pipeline statistics queries, which count the compute shader invocations of a dispatch,
+//! cannot be recorded with the version of `vulkano` used by `pipeline!`. Instead, every
+//! invocation of the shader calls `count_invocation()`, which counts it in a buffer of
+//! `BUFFER_LEN` elements:
+//!
+//! ```glsl
+//! void main(void) {
+//!     count_invocation();
+//!     // ...
+//! }
+//! ```
+//!
+//! A dispatch whose dimensions do not match the data, e.g. because the workgroup count was given
+//! in elements instead of workgroups, leaves parts of the output untouched without any error.
+//! `assert_invocations` catches it after the execution, given the dispatch and the local size of
+//! the shader. The buffer must be reset to zero before every further execution.
+
+use std::fmt;
+
+/// The length of the count buffer.
+pub const BUFFER_LEN: usize = 4;
+
+/// The invocations counted by the segment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvocationCount {
+    /// The number of invocations which called `count_invocation()`.
+    pub invocations: u32,
+    /// The largest global invocation id plus one in every dimension.
+    pub extent: [u32; 3],
+}
+
+impl InvocationCount {
+    /// Returns the count of a dispatch of `workgroup_count` workgroups of the size `local_size`
+    /// in which every invocation was counted.
+    pub fn expected(workgroup_count: [u32; 3], local_size: [u32; 3]) -> InvocationCount {
+        let extent = [workgroup_count[0] * local_size[0],
+                      workgroup_count[1] * local_size[1],
+                      workgroup_count[2] * local_size[2]];
+        // The counter of the segment wraps around like the product.
+        InvocationCount {
+            invocations: extent[0].wrapping_mul(extent[1]).wrapping_mul(extent[2]),
+            extent: extent,
+        }
+    }
+}
+
+impl fmt::Display for InvocationCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} invocations in the extent {:?}", self.invocations, self.extent)
+    }
+}
+
+/// Decodes the count buffer.
+///
+/// # Panics
+///
+/// If the buffer does not have `BUFFER_LEN` elements.
+pub fn decode(buffer: &[u32]) -> InvocationCount {
+    if buffer.len() != BUFFER_LEN {
+        panic!("The invocation count buffer has {} elements instead of {}.",
+               buffer.len(),
+               BUFFER_LEN);
+    }
+    InvocationCount {
+        invocations: buffer[0],
+        extent: [buffer[1], buffer[2], buffer[3]],
+    }
+}
+
+/// Asserts that the count buffer `buffer` counted every invocation of a dispatch of
+/// `workgroup_count` workgroups of the size `local_size`, exactly once.
+///
+/// # Panics
+///
+/// If the counts differ, with both counts.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::invocations::assert_invocations;
+///
+/// // The count buffer after a dispatch of 100 workgroups of 64 invocations each.
+/// assert_invocations(&[6400, 6400, 1, 1], [100, 1, 1], [64, 1, 1]);
+/// # }
+/// ```
+///
+pub fn assert_invocations(buffer: &[u32], workgroup_count: [u32; 3], local_size: [u32; 3]) {
+    let counted = decode(buffer);
+    let expected = InvocationCount::expected(workgroup_count, local_size);
+    if counted != expected {
+        panic!("The shader counted {}, but the workgroup count {:?} and the local size {:?} \
+                dispatch {}.",
+               counted,
+               workgroup_count,
+               local_size,
+               expected);
+    }
+}
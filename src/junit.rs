@@ -0,0 +1,140 @@
+//! This module writes test results as JUnit XML, which is understood by the test dashboards of
+//! most CI servers, e.g. Jenkins and GitLab.
+//!
+//! The [`harness`] writes a report to the path given by `VULKANOLOGY_JUNIT` after running the
+//! tests.
+//!
+//! [`harness`]: ../harness/index.html
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// The environment variable which sets the path of the JUnit report written by the harness.
+pub const JUNIT_VAR: &'static str = "VULKANOLOGY_JUNIT";
+
+/// The result of a single test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    /// The name of the test.
+    pub name: String,
+    /// The name of the physical device the test ran on, if it is known.
+    pub device: Option<String>,
+    /// The duration of the test.
+    pub duration: Duration,
+    /// The failure message, or `None` if the test passed.
+    pub failure: Option<String>,
+    /// The reason why the test was skipped, e.g. as Vulkan is not available, or `None` if it ran.
+    pub skipped: Option<String>,
+    /// Notes about the test, e.g. the applied quirks.
+    pub notes: Vec<String>,
+}
+
+/// Writes the JUnit XML report of the test suite `suite` with the results `results`.
+///
+/// A test which was skipped and did not fail is reported with a `<skipped>` element, so that
+/// dashboards do not count it as passed.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::time::Duration;
+/// use vulkanology::junit::{write_report, TestResult};
+///
+/// let results = [TestResult {
+///     name: "test_example".to_owned(),
+///     device: Some("llvmpipe".to_owned()),
+///     duration: Duration::from_millis(1500),
+///     failure: Some("1 < 2".to_owned()),
+///     skipped: None,
+///     notes: vec!["\u{1b}[33mwarning\u{1b}[0m: validation layer".to_owned()],
+/// }, TestResult {
+///     name: "test_double_precision".to_owned(),
+///     device: Some("llvmpipe".to_owned()),
+///     duration: Duration::from_millis(2),
+///     failure: None,
+///     skipped: Some("The device does not support `shader_f3264`.".to_owned()),
+///     notes: vec![],
+/// }];
+///
+/// let mut report = Vec::new();
+/// write_report(&mut report, "gpu", &results).unwrap();
+/// let report = String::from_utf8(report).unwrap();
+/// assert!(report.contains("<testcase name=\"test_example\" classname=\"gpu\" time=\"1.500\">"));
+/// assert!(report.contains("<failure message=\"1 &lt; 2\">1 &lt; 2</failure>"));
+/// assert!(report.contains("<system-out>[33mwarning[0m: validation layer</system-out>"));
+/// assert!(report.contains("tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"1\""));
+/// assert!(report.contains("<skipped message=\"The device does not support `shader_f3264`.\"/>"));
+/// # }
+/// ```
+///
+pub fn write_report<W: Write>(out: &mut W, suite: &str, results: &[TestResult]) -> io::Result<()> {
+    let failures = results.iter().filter(|result| result.failure.is_some()).count();
+    let skipped = results.iter()
+        .filter(|result| result.failure.is_none() && result.skipped.is_some())
+        .count();
+    let total = results.iter().fold(Duration::new(0, 0), |sum, result| sum + result.duration);
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out,
+             "<testsuites>\n<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" \
+              errors=\"0\" skipped=\"{}\" time=\"{}\">",
+             escape(suite),
+             results.len(),
+             failures,
+             skipped,
+             seconds(total))?;
+    for result in results {
+        writeln!(out,
+                 "<testcase name=\"{}\" classname=\"{}\" time=\"{}\">",
+                 escape(&result.name),
+                 escape(suite),
+                 seconds(result.duration))?;
+        if let Some(ref device) = result.device {
+            writeln!(out,
+                     "<properties><property name=\"device\" value=\"{}\"/></properties>",
+                     escape(device))?;
+        }
+        if let Some(ref failure) = result.failure {
+            // The message attribute is shown in overviews, so it only contains the first line.
+            let message = failure.lines().next().unwrap_or("");
+            writeln!(out,
+                     "<failure message=\"{}\">{}</failure>",
+                     escape(message),
+                     escape(failure))?;
+        } else if let Some(ref reason) = result.skipped {
+            writeln!(out, "<skipped message=\"{}\"/>", escape(reason))?;
+        }
+        if !result.notes.is_empty() {
+            writeln!(out, "<system-out>{}</system-out>", escape(&result.notes.join("\n")))?;
+        }
+        writeln!(out, "</testcase>")?;
+    }
+    writeln!(out, "</testsuite>\n</testsuites>")
+}
+
+/// Formats a duration in seconds with millisecond precision.
+fn seconds(duration: Duration) -> String {
+    format!("{}.{:03}", duration.as_secs(), duration.subsec_nanos() / 1_000_000)
+}
+
+/// Escapes the XML special characters of `text`, and drops the characters which XML 1.0 forbids,
+/// e.g. the color codes of panic messages.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < '\u{20}' || c == '\u{fffe}' || c == '\u{ffff}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
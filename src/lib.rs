@@ -13,11 +13,10 @@
 //!
 //! ## Import (Usage in integration test modules)
 //!
-//! Due to the reexport of utility function from the [`vulkano`] crate (which you don't need to access,
-//! unless you want to) you need to use the following crates in your test module header:
+//! The expanded macros refer to the [`vulkano`] crate (which you don't need to access, unless you
+//! want to), so you need to use the following crates in your test module header:
 //!
 //! ```
-//! #[macro_use]
 //! extern crate vulkano;
 //! #[macro_use]
 //! extern crate vulkanology;
@@ -27,6 +26,17 @@
 //! # }
 //! ```
 //!
+//! Alternatively, enable the optional `vulkano` feature of this crate, which re-exports the
+//! required version of [`vulkano`]. Importing the re-export at the root of the test module is then
+//! sufficient for the expanded macros to resolve:
+//!
+//! ```ignore
+//! #[macro_use]
+//! extern crate vulkanology;
+//!
+//! use vulkanology::vulkano;
+//! ```
+//!
 //! For basic usage of the library you can refer to the doc-tests and `tests/shaders/example.comp`.
 //! For a working example of a fairly elaborate shader test please refer to: `tests/random.rs`
 //! and `tests/shaders/random.comp`.
@@ -36,25 +46,242 @@
 //! This utility pack is built around the [`vulkano`] library, which also provides `vulkano-shaders`,
 //! a library which compiles GLSL shaders into Rust interface modules.
 //! For examples on how to build shaders with `vulkano-shaders` see `build.rs` and [this].
+//! Calling `build_utils::report_missing_shaders` afterwards turns references to shaders which
+//! were not built into readable compile errors, and `build_utils::reflect_buffer_layouts` lets
+//! `pipeline!` check the buffer element types against the layouts declared in the shaders.
 //!
 //! ## Composite shader tests
 //!
 //! `vulkanology` also provides some build utilities for working with segmented shaders.
 //! `src/build_utils.rs` contains working examples on how to use these utilities in your `build.rs`.
 //!
+//! ## Versioned macros
+//!
+//! `pipeline!` gains new parameters and checks as the crate evolves. `pipeline_v1!` is frozen at
+//! the first version of `pipeline!`: it rejects everything `pipeline!` has learned since, and its
+//! expansion is the code of that first version. Large test suites can therefore pin the version
+//! they were written against and migrate one test at a time.
+//!
+//! ## Scope
+//!
+//! `vulkanology` is a library for tests, the shaders are compiled by the build script and the
+//! pipelines are assembled at compile time. For quick experiments with a kernel, the
+//! `vulkanology-run` binary of the optional `runner` feature runs a `.comp` or `.spv` file
+//! without a test. It compiles the shader at runtime and assembles its pipeline from a JSON
+//! description of the buffers, the push constants and the workgroup count, which is checked
+//! against the reflection of the shader. Like those of a test, its device, workgroup count and
+//! timeout can be overridden with the [`overrides`]. See the [`runner`] module.
+//!
+//! For an edit-run loop, `vulkanology-run --watch` keeps the device and the buffers alive and runs
+//! the shader again whenever its file changes, printing the configured slices of the results and
+//! the elements which differ from the expected ones.
+//!
+//! Concurrency, timeouts and retries of GPU tests are left to the test runner. The [`harness`]
+//! module provides a `main` for test targets with `harness = false` which can be run by
+//! cargo-nextest and runs the tests of a target sequentially.
+//!
+//! ## Backends
+//!
+//! The code generated by `pipeline!` is written against the [`vulkano`] API and always executes
+//! on a Vulkan device. There is no alternative backend: [wgpu] expects WGSL or SPIR-V which
+//! passes its validation, binds resources by its own layout rules and requires a far newer
+//! toolchain than this crate, so the test definitions cannot be executed through it unchanged.
+//!
+//! Machines without a GPU can run the tests on a CPU implementation of Vulkan, such as lavapipe
+//! or SwiftShader, by selecting its driver with `VK_ICD_FILENAMES`. The shader is then compiled
+//! to machine code by the driver, so the results confirm its correctness, but not its
+//! performance. `pipeline!` prints a note to stderr whenever it runs on such a device. Machines
+//! without any Vulkan implementation can skip the tests which are given `unavailable: skip`,
+//! see `instance!`.
+//!
+//! ## Unsupported Vulkan features
+//!
+//! The generated code is bound to the API of [`vulkano`] 0.3, which only knows the extensions and
+//! features of Vulkan 1.0 and creates pipelines without extension structures. The following
+//! features can therefore not be used from `pipeline!` yet:
+//!
+//! - Subgroup size control (`VK_EXT_subgroup_size_control`): pinning the subgroup size requires
+//!   chaining `VkPipelineShaderStageRequiredSubgroupSizeCreateInfoEXT` to the shader stage of the
+//!   compute pipeline. The subgroup size is always chosen by the driver.
+//! - Cooperative matrices (`VK_KHR_cooperative_matrix`): the extension can neither be enabled nor
+//!   can its supported configurations be queried. Matrix multiplication kernels without
+//!   cooperative matrices can be verified against the CPU reference in [`matrix`].
+//! - Buffer device addresses (`VK_KHR_buffer_device_address`): buffers cannot be created with the
+//!   `SHADER_DEVICE_ADDRESS` usage and their addresses cannot be queried, so shaders using
+//!   `GL_EXT_buffer_reference` cannot be given valid pointers.
+//! - Timeline semaphores (`VK_KHR_timeline_semaphore`): only binary semaphores and fences exist.
+//!   The execution command submits one command buffer per iteration to a single queue and waits
+//!   for it, so dependencies between submissions are always implicit.
+//! - Variable pointers (`VK_KHR_variable_pointers`): `pipeline!` panics with the name of the
+//!   capability if a shader declares `VariablePointers` or `VariablePointersStorageBuffer`, given
+//!   that the capabilities were reflected by `build_utils::reflect_buffer_layouts`.
+//! - Shader clocks (`VK_KHR_shader_clock`): shaders declaring `ShaderClockKHR` are rejected in
+//!   the same way. The `shader_clock` segment and the [`shader_clock`] module decode the timings
+//!   once the extension is available.
+//! - Float atomics (`VK_EXT_shader_atomic_float`): shaders declaring `AtomicFloat32AddEXT` or
+//!   `AtomicFloat64AddEXT` are rejected in the same way. The `atomic_float` segment enables the
+//!   GLSL extension, and the [`compare`] module verifies results within the rounding error of an
+//!   arbitrary execution order.
+//! - `LocalSizeId` (SPIR-V 1.6, `VK_KHR_maintenance4`): the shaders are compiled to SPIR-V 1.0, in
+//!   which the workgroup size is given by `LocalSize` or by the `WorkgroupSize` built-in. For
+//!   size sweeps, declare `layout(local_size_x_id = 0) in;` and set the size with the
+//!   `spec_constants` of `pipeline!`.
+//! - Explicit workgroup memory layouts (`VK_KHR_workgroup_memory_explicit_layout`): shaders
+//!   declaring one of the `WorkgroupMemoryExplicitLayout*KHR` capabilities are rejected. The bank
+//!   conflicts of padded shared memory layouts can be checked on the host with
+//!   [`shared_memory`].
+//! - Half floats (`VK_KHR_shader_float16_int8`, `VK_KHR_16bit_storage`): shaders declaring
+//!   `Float16` or one of the 16-bit storage capabilities are rejected. Buffers of half floats are
+//!   given as `u16` and accessed with `unpackHalf2x16` and `packHalf2x16`, the [`half`] module
+//!   converts them on the host.
+//! - 8-bit and 16-bit storage (`VK_KHR_8bit_storage`, `VK_KHR_16bit_storage`): shaders declaring
+//!   `Int8` or one of the 8-bit or 16-bit storage capabilities are rejected in the same way. As
+//!   no device can enable the extensions, such tests fail instead of being skipped. Buffers of
+//!   `u8`, `i8`, `u16` and `i16` are instead bound to `uint` arrays, whose elements the shader
+//!   unpacks with `bitfieldExtract`, and the layout check accepts such packed buffers.
+//! - Shader printf (`VK_KHR_shader_non_semantic_info`): `debugPrintfEXT` cannot be used, and the
+//!   GLSL compiler of `vulkano-shaders` predates `GL_EXT_debug_printf`. The `debug_log` segment
+//!   logs tagged values into a buffer instead, which the [`debug_log`] module prints.
+//! - Object names (`VK_EXT_debug_utils`, `VK_EXT_debug_marker`): the objects of a pipeline
+//!   cannot be named for the driver, so they stay anonymous in captures. With strict validation,
+//!   the [`validation`] module names them in the reported messages instead.
+//! - Device groups (`VK_KHR_device_group`): a device is always created from a single physical
+//!   device, and dispatches cannot be given a device mask or a base workgroup.
+//! - Timestamp queries: [`vulkano`] creates query pools, but cannot record
+//!   `vkCmdWriteTimestamp` or read the results of queries, so the GPU time of a dispatch cannot
+//!   be measured. CPU timings of the execution command include the submission and the wait for
+//!   its fence. To compare kernel variants, time a `repeat_command` with many submissions, which
+//!   amortizes that overhead, or compare the durations of the dispatches in a [`renderdoc`]
+//!   capture.
+//! - Pipeline statistics queries: queries cannot be begun or ended either, so the invocations of
+//!   a dispatch cannot be counted by the device. The `invocation_count` segment counts them in a
+//!   buffer instead, and [`invocations`] compares the count with the dispatch.
+//! - Pipeline caches: [`vulkano`] creates compute pipelines without a `VkPipelineCache`, so the
+//!   shaders cannot be cached under `target/` and are compiled by the driver whenever a test
+//!   creates its pipeline. Most drivers keep their own cache of compiled shaders on disk, which
+//!   makes repeated runs of unchanged shaders fast, as long as it is not disabled, e.g. with
+//!   `MESA_SHADER_CACHE_DISABLE` or `__GL_SHADER_DISK_CACHE=0`. Its location can be moved with
+//!   `MESA_SHADER_CACHE_DIR` and `__GL_SHADER_DISK_CACHE_PATH` respectively.
+//! - Queue family ownership transfers: [`vulkano`] records every barrier with
+//!   `VK_QUEUE_FAMILY_IGNORED`, so an exclusive buffer cannot be released by a transfer queue
+//!   and acquired by a compute queue. The uploads and downloads of `pipeline!` therefore always
+//!   run on the queue of the dispatches. Tests of concurrent submissions can request a dedicated
+//!   transfer queue with `device_and_queue!(physical_device, queues: [compute, transfer])` and
+//!   share their buffers between both families, see the [`queues`] module.
+//! - Robustness2 (`VK_EXT_robustness2`): only the `robust_buffer_access` feature of Vulkan 1.0 is
+//!   enabled, see `device_and_queue!`. Null descriptors are not available, so every buffer
+//!   declared in `pipeline!` is bound. An optional buffer without data is bound to a dummy
+//!   buffer with a single element.
+//!
+//! [`compare`]: compare/index.html
+//! [`debug_log`]: debug_log/index.html
+//! [`half`]: half/index.html
+//! [`harness`]: harness/index.html
+//! [`invocations`]: invocations/index.html
+//! [`matrix`]: matrix/index.html
+//! [`overrides`]: overrides/index.html
+//! [`queues`]: queues/index.html
+//! [`renderdoc`]: renderdoc/index.html
+//! [`runner`]: runner/index.html
+//! [`shader_clock`]: shader_clock/index.html
+//! [`shared_memory`]: shared_memory/index.html
+//! [`validation`]: validation/index.html
+//! [wgpu]: https://github.com/gfx-rs/wgpu
 //! [this]: https://github.com/tomaka/vulkano/blob/master/examples/build.rs
 //! [`vulkano`]: https://github.com/tomaka/vulkano
 //!
 #![deny(missing_docs)]
-#![feature(macro_reexport)]
+#![feature(macro_reexport, thread_local_state)]
+
+#[macro_use]
+extern crate lazy_static;
+#[cfg(feature = "vulkano")]
+pub extern crate vulkano;
+#[cfg(feature = "criterion")]
+extern crate criterion;
+#[cfg(feature = "glsl-to-spirv")]
+extern crate glsl_to_spirv;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
 
+#[macro_use]
+pub mod ab;
+#[macro_use]
+pub mod benchmark;
 pub mod build_utils;
+pub mod checked;
+#[macro_use]
+pub mod compare;
+#[macro_use]
+pub mod conformance;
+pub mod context;
+#[cfg(feature = "criterion")]
+pub mod criterion_adapter;
+pub mod debug_log;
+#[macro_use]
+pub mod determinism;
+#[macro_use]
+pub mod differential;
+#[macro_use]
+pub mod export;
+pub mod glsl;
+pub mod half;
+pub mod hang;
+pub mod harness;
+#[cfg(feature = "vulkano")]
+pub mod host_buffer;
+pub mod import;
+pub mod invocations;
+pub mod junit;
+pub mod limits;
+#[macro_use]
+pub mod locks;
+pub mod matrix;
+#[macro_use]
+pub mod non_finite;
+pub mod overrides;
+pub mod ping_pong;
+#[macro_use]
+pub mod png;
+#[macro_use]
+pub mod precision;
+pub mod push_constants;
+#[cfg(feature = "quickcheck")]
+pub mod quickcheck_adapter;
+pub mod queues;
+pub mod quirks;
+pub mod randomness;
+pub mod reflection;
+pub mod renderdoc;
+pub mod runner;
+pub mod scan;
+pub mod shader_clock;
+#[macro_use]
+pub mod shapes;
+pub mod shared;
+pub mod shared_memory;
+#[macro_use]
+pub mod snapshots;
+pub mod sort;
+pub mod special_values;
+pub mod stats;
+pub mod tolerances;
+pub mod validation;
 
-/// Creates a [`vulkano`] [`Instance`]. Does not enable any instance extensions.
+/// Creates a [`vulkano`] [`Instance`]. Does not enable any instance extensions or layers,
+/// unless they are given: `extensions: [$extension, ...]` enables the instance extensions named
+/// like the fields of `vulkano::instance::InstanceExtensions`, e.g. `ext_debug_report`, and
+/// `layers: [$layer, ...]` the layers with the given names. Either list can be omitted. Note that
+/// `VK_EXT_debug_utils` is not known to the version of `vulkano` used by this crate.
+///
+/// On machines without a GPU, `unavailable: skip` returns from the enclosing function instead if
+/// there is no Vulkan loader or no physical device which supports compute shaders, and notes on
+/// stderr that the test was skipped. The enclosing function must therefore return `()`.
 ///
 /// # Panics
 ///
-/// Panics if the instance loading procedure fails.
+/// Panics if the instance loading procedure fails, e.g. because a layer or an extension is not
+/// available.
 ///
 /// # Example
 ///
@@ -72,25 +299,105 @@ pub mod build_utils;
 /// # }
 /// ```
 ///
+/// ```no_run
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # #[allow(unused_variables)]
+/// # fn main() {
+/// // Opt into the debug tooling.
+/// let instance = instance!(extensions: [ext_debug_report],
+///                          layers: ["VK_LAYER_KHRONOS_validation"]);
+/// # }
+/// ```
+///
+/// ```
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # #[allow(unused_variables)]
+/// # fn main() {
+/// // Skip the test on a machine without Vulkan.
+/// let instance = instance!(unavailable: skip);
+/// # }
+/// ```
+///
 /// [`vulkano`]: https://github.com/tomaka/vulkano
 /// [`Instance`]: https://docs.rs/vulkano/0.3.1/vulkano/instance/struct.Instance.html
 ///
 #[macro_export]
 macro_rules! instance {
-    () => ({
+    () => {
+        instance!(extensions: [], layers: [])
+    };
+    (unavailable: skip) => ({
+        if let Some(reason) = instance!(__unavailable__) {
+            ::vulkanology::harness::note_unavailable(concat!(file!(), ":", line!()), &reason);
+            return;
+        }
+        instance!()
+    });
+    // Returns why Vulkan cannot be used, if there is no loader, no driver or no device.
+    (__unavailable__) => ({
+        use vulkano::instance::{Instance, InstanceCreationError, InstanceExtensions,
+                                PhysicalDevice};
+        match Instance::new(None, &InstanceExtensions::none(), None) {
+            Ok(instance) => {
+                if PhysicalDevice::enumerate(&instance).next().is_none() {
+                    Some("no physical devices are available".to_owned())
+                } else if !PhysicalDevice::enumerate(&instance)
+                    .any(|p| p.queue_families().any(|family| family.supports_compute())) {
+                    Some("no physical device supports compute shaders".to_owned())
+                } else {
+                    None
+                }
+            }
+            Err(error) => {
+                match error {
+                    InstanceCreationError::LoadingError(_) |
+                    InstanceCreationError::IncompatibleDriver => Some(error.to_string()),
+                    // Other errors are reported when the instance is created again.
+                    _ => None,
+                }
+            }
+        }
+    });
+    (extensions: $extensions:tt $(,)*) => {
+        instance!(extensions: $extensions, layers: [])
+    };
+    (layers: $layers:tt $(,)*) => {
+        instance!(extensions: [], layers: $layers)
+    };
+    (extensions: [ $( $extension:ident ),* $(,)* ],
+     layers: [ $( $layer:expr ),* $(,)* ] $(,)*) => ({
         use vulkano::instance::{Instance, InstanceExtensions};
-        let extensions = &InstanceExtensions::none();
-        Instance::new(None, extensions, None).expect("Failed to initialize vulkano.")
+        let extensions = &InstanceExtensions {
+            $( $extension: true, )*
+            ..InstanceExtensions::none()
+        };
+        let layers: &[&str] = &[ $( $layer ),* ];
+        Instance::new(None, extensions, layers).expect("Failed to initialize vulkano.")
     })
 }
 
 /// This macro generates code for loading a [`PhysicalDevice`]. It takes
 /// the instance variable name and an optional list of features which the device
 /// should support. All available features are defined [here].
+/// Alternatively, `index: $index` selects the device at the position `$index` of the
+/// enumeration, and `prefer: [$type, ...]` selects a device by its [`PhysicalDeviceType`]. The
+/// types are given in the order of preference, devices of other types are only selected if none
+/// of the given types is available. `limits: [$limit >= $min, ...]` selects the first device
+/// whose limits are at least the given values, which are compared by the [`limits`] module.
+///
+/// Except for `index`, only the device given by `VULKANOLOGY_DEVICE` or
+/// `VULKANOLOGY_DEVICE_NAME` is considered if one of them is set, see the [`overrides`] module.
 ///
 /// # Panics
 ///
-/// Panics if no device matching the requirements has been found.
+/// Panics if no device matching the requirements has been found. With `limits`, the message
+/// lists the limits which each device failed.
 ///
 /// # Example
 ///
@@ -116,18 +423,103 @@ macro_rules! instance {
 ///         robust_buffer_access,
 ///         full_draw_index_uint32);
 /// }
+/// {
+///     // By position, which is identical to no features for the first device:
+///     let physical_device = physical_device!(instance, index: 0);
+/// }
+/// {
+///     // By type, e.g. the discrete GPU of a laptop with an integrated one:
+///     let physical_device = physical_device!(instance, prefer: DiscreteGpu);
+///     // With a fallback order:
+///     let physical_device = physical_device!(instance, prefer: [DiscreteGpu, IntegratedGpu]);
+/// }
+/// {
+///     // With some limits:
+///     let physical_device = physical_device!(instance, limits: [
+///         max_compute_work_group_invocations >= 256,
+///         max_compute_work_group_size >= [256, 1, 1],
+///         max_storage_buffer_range >= 1 << 27
+///     ]);
+/// }
 /// # }
 /// ```
 ///
 /// [here]: https://github.com/tomaka/vulkano/blob/master/vulkano/src/features.rs
 /// [`PhysicalDevice`]: https://docs.rs/vulkano/0.3.1/vulkano/instance/struct.PhysicalDevice.html
+/// [`PhysicalDeviceType`]:
+///     https://docs.rs/vulkano/0.3.1/vulkano/instance/enum.PhysicalDeviceType.html
+/// [`limits`]: limits/index.html
+/// [`overrides`]: overrides/index.html
 ///
 #[macro_export]
 macro_rules! physical_device {
+    // The devices which can be selected, all of them unless the device is overridden from the
+    // environment.
+    (__candidates__ $instance:ident) => ({
+        use vulkano::instance::{PhysicalDevice};
+        use vulkanology::overrides;
+        let names = PhysicalDevice::enumerate(&$instance).map(|p| p.name()).collect::<Vec<_>>();
+        let selected = overrides::device(&names);
+        PhysicalDevice::enumerate(&$instance)
+            .filter(move |p| selected.map_or(true, |index| p.index() == index))
+    });
+
+    // Rule for selecting the device of a pipeline, see `overrides::DeviceIndex`.
+    ($instance:ident, device: $device:expr) => ({
+        use vulkano::instance::{PhysicalDevice};
+        use vulkanology::overrides::DeviceIndex;
+        let names = PhysicalDevice::enumerate(&$instance).map(|p| p.name()).collect::<Vec<_>>();
+        physical_device!($instance, index: DeviceIndex::device_index($device, &names))
+    });
+
+    // Rule for selecting a device by its position in the enumeration.
+    ($instance:ident, index: $index:expr) => ({
+        use vulkano::instance::{PhysicalDevice};
+        let index: usize = $index;
+        PhysicalDevice::enumerate(&$instance).nth(index)
+            .expect(format!("The physical device {} is not available, there are {} devices.",
+                            index,
+                            PhysicalDevice::enumerate(&$instance).count()).as_ref())
+    });
+
+    // Rules for selecting a device by its type. Among devices of the same type, the first one
+    // in the enumeration is selected.
+    ($instance:ident, prefer: $ty:ident) => {
+        physical_device!($instance, prefer: [$ty])
+    };
+    ($instance:ident, prefer: [ $( $ty:ident ),+ ]) => ({
+        use vulkano::instance::PhysicalDeviceType;
+        let preference = [ $( PhysicalDeviceType::$ty ),+ ];
+        physical_device!(__candidates__ $instance)
+            .min_by_key(|p| {
+                preference.iter().position(|&ty| ty == p.ty()).unwrap_or(preference.len())
+            })
+            .expect("No physical devices are available.")
+    });
+
+    // Rule for selecting a device with specific limits.
+    ($instance:ident, limits: [ $( $limit:ident >= $min:expr ),+ ]) => ({
+        use vulkanology::limits;
+        let mut failures = Vec::new();
+        let device = physical_device!(__candidates__ $instance).find(|p| {
+            let device_limits = p.limits();
+            let failed = vec![
+                $( limits::check(stringify!($limit), device_limits.$limit(), $min) ),+
+            ];
+            let failed = failed.into_iter().filter_map(|failure| failure).collect::<Vec<_>>();
+            let found = failed.is_empty();
+            failures.push((p.name(), failed));
+            found
+        });
+        match device {
+            Some(device) => device,
+            None => limits::unmet(&failures),
+        }
+    });
+
     // Rule for selecting a device with specific features.
     ($instance:ident, $($feature:ident),+) => ({
-        use vulkano::instance::{PhysicalDevice};
-        PhysicalDevice::enumerate(&$instance).find(|p| {
+        physical_device!(__candidates__ $instance).find(|p| {
             let supported_features = p.supported_features();
             true $( && supported_features.$feature )*
         }).expect("No physical devices are available.")
@@ -136,18 +528,135 @@ macro_rules! physical_device {
     // Rule for selecting the first available physical
     // device when no features are required.
     ($instance:ident) => ({
-        use vulkano::instance::{PhysicalDevice};
-        PhysicalDevice::enumerate(&$instance).next()
+        physical_device!(__candidates__ $instance).next()
             .expect("No physical devices are available.")
     })
 }
 
+/// Skips the test if no physical device supports compute shaders, e.g. on a machine without a
+/// Vulkan implementation.
+///
+/// The guard belongs at the top of a test, which it returns from after noting on stderr that the
+/// test was skipped. The enclosing function must therefore return `()`. See also
+/// `require_features!`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// require_gpu!();
+///
+/// let instance = instance!();
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! require_gpu {
+    () => {
+        if let Some(reason) = instance!(__unavailable__) {
+            ::vulkanology::harness::note_unavailable(concat!(file!(), ":", line!()), &reason);
+            return;
+        }
+    };
+}
+
+/// Skips the test if the device of the test does not support the given features, or if the
+/// Vulkan implementation does not support the given instance extensions.
+///
+/// The features are named like the fields of `vulkano::instance::Features`, e.g. `shader_f3264`
+/// for `shaderFloat64`, and the extensions like the fields of
+/// `vulkano::instance::InstanceExtensions`. The device is the one which `pipeline!` selects
+/// without a `device` parameter, i.e. the first one unless it is overridden from the
+/// environment, see the [`overrides`] module. The version of `vulkano` used by this crate cannot
+/// query the extensions of a device.
+///
+/// Like `require_gpu!`, which it includes, the guard returns from the enclosing function, which
+/// must therefore return `()`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// // A test of a double precision shader.
+/// require_features!(shader_f3264);
+///
+/// // A test which additionally requires an instance extension.
+/// require_features!(features: [shader_f3264, shader_int64], extensions: [ext_debug_report]);
+/// # }
+/// ```
+///
+/// [`overrides`]: overrides/index.html
+///
+#[macro_export]
+macro_rules! require_features {
+    ( $( $feature:ident ),+ $(,)* ) => {
+        require_features!(features: [ $( $feature ),+ ], extensions: [])
+    };
+    (features: [ $( $feature:ident ),* $(,)* ] $(,)*) => {
+        require_features!(features: [ $( $feature ),* ], extensions: [])
+    };
+    (extensions: [ $( $extension:ident ),* $(,)* ] $(,)*) => {
+        require_features!(features: [], extensions: [ $( $extension ),* ])
+    };
+    (features: [ $( $feature:ident ),* $(,)* ],
+     extensions: [ $( $extension:ident ),* $(,)* ] $(,)*) => {
+        require_gpu!();
+        {
+            use vulkano::instance::InstanceExtensions;
+            use vulkanology::harness;
+            use vulkanology::overrides::DefaultDevice;
+            let instance = instance!();
+            let physical_device = physical_device!(instance, device: DefaultDevice);
+            #[allow(unused_variables)]
+            let supported = physical_device.supported_features();
+            #[allow(unused_variables)]
+            let extensions = InstanceExtensions::supported_by_core()
+                .expect("Failed to load the Vulkan library.");
+            #[allow(unused_mut)]
+            let mut missing: Vec<&str> = Vec::new();
+            $( if !supported.$feature { missing.push(stringify!($feature)); } )*
+            $( if !extensions.$extension { missing.push(stringify!($extension)); } )*
+            if !missing.is_empty() {
+                harness::note_skipped(concat!(file!(), ":", line!()),
+                                      &physical_device.name(),
+                                      &missing);
+                return;
+            }
+        }
+    };
+}
+
 /// Creates a [`Device`] and a [`Queue`] for compute operations.
 ///
+/// All features supported by the physical device are enabled. In particular, out-of-bounds
+/// buffer accesses are well-defined if the device supports `robust_buffer_access`, which can be
+/// required with `physical_device!`.
+///
+/// The queue is taken from the first family which supports compute operations, with a priority
+/// of 0.5. `family: $kind` selects the family by the kind of queue instead, e.g. `compute_only`
+/// for the asynchronous compute queue of a GPU, and `priority: $priority` sets the priority of
+/// the queue, between 0.0 and 1.0. If the device has no family which matches the kind exactly,
+/// the family which fits it best is used, and a note is printed to stderr and added to the
+/// report of the [`harness`]. The kinds are `compute`, `compute_only` and `transfer`, which are
+/// assigned to queue families by the [`queues`] module.
+///
+/// With `queues: [$kind, ...]`, it creates one queue of every kind instead, and returns them as
+/// a tuple in the same order, e.g. to test concurrent submissions or the synchronization
+/// between queues of different families.
+///
 /// # Panics
 ///
 /// Panics if no compute-compatible queue has been found, or the
-/// device could not be initialized.
+/// device could not be initialized. It also panics if the priority is out of range, or with
+/// `queues`, if the device does not have enough queues.
 ///
 /// # Example
 ///
@@ -164,8 +673,28 @@ macro_rules! physical_device {
 /// # }
 /// ```
 ///
+/// ```no_run
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # #[allow(unused_variables)]
+/// # fn main() {
+/// let instance = instance!();
+/// let physical_device = physical_device!(instance);
+/// let (device, (compute, transfer)) =
+///     device_and_queue!(physical_device, queues: [compute, transfer]);
+/// println!("The transfer queue is of the family {}.", transfer.family().id());
+///
+/// // Test on the asynchronous compute queue, with the highest priority.
+/// let (device, queue) = device_and_queue!(physical_device, priority: 1.0, family: compute_only);
+/// # }
+/// ```
+///
 /// [`Device`]: https://docs.rs/vulkano/0.3.1/vulkano/device/struct.Device.html
 /// [`Queue`]: https://docs.rs/vulkano/0.3.1/vulkano/device/struct.Queue.html
+/// [`harness`]: harness/index.html
+/// [`queues`]: queues/index.html
 ///
 #[macro_export]
 macro_rules! device_and_queue {
@@ -187,14 +716,139 @@ macro_rules! device_and_queue {
 
         // We only requested one queue, so `queues` is an array with only one element.
         (device, queues.next().unwrap())
-    })
+    });
+
+    ($physical_device:ident, priority: $priority:expr) => {
+        device_and_queue!(__single__ $physical_device, $priority, compute)
+    };
+    ($physical_device:ident, family: $kind:ident) => {
+        device_and_queue!(__single__ $physical_device, 0.5, $kind)
+    };
+    ($physical_device:ident, priority: $priority:expr, family: $kind:ident) => {
+        device_and_queue!(__single__ $physical_device, $priority, $kind)
+    };
+    ($physical_device:ident, family: $kind:ident, priority: $priority:expr) => {
+        device_and_queue!(__single__ $physical_device, $priority, $kind)
+    };
+
+    // Rule for creating several queues, possibly of different families.
+    ($physical_device:ident, queues: [ $( $kind:ident ),+ $(,)* ]) => ({
+        use vulkano::device::{Device, DeviceExtensions};
+        use vulkanology::queues;
+
+        let families = $physical_device.queue_families().collect::<Vec<_>>();
+        let capabilities = device_and_queue!(__capabilities__ families);
+        let assigned = queues::assign(&capabilities,
+                                      &[ $( device_and_queue!(__kind__ $kind) ),+ ]);
+
+        let device_extensions = DeviceExtensions::none();
+        let requests = assigned.iter().map(|&index| (families[index], 0.5));
+        let (device, mut created) = Device::new(&$physical_device,
+                                                &$physical_device.supported_features(),
+                                                &device_extensions,
+                                                requests)
+            .expect("Failed to create device.");
+
+        // The queues are returned in the order in which they were requested. The trailing comma
+        // makes a single queue a tuple as well.
+        (device, ( $( device_and_queue!(__queue__ created $kind), )+ ))
+    });
+    (__single__ $physical_device:ident, $priority:expr, $kind:ident) => ({
+        use std::io::{self, Write};
+        use vulkano::device::{Device, DeviceExtensions};
+        use vulkanology::{harness, queues};
+
+        let kind = device_and_queue!(__kind__ $kind);
+        let families = $physical_device.queue_families().collect::<Vec<_>>();
+        let capabilities = device_and_queue!(__capabilities__ families);
+        let index = queues::assign(&capabilities, &[kind])[0];
+        if queues::is_fallback(&capabilities[index], kind) {
+            let note = format!("`{}` has no `{}` queue family, the queue is of the family {}.",
+                               $physical_device.name(),
+                               stringify!($kind),
+                               families[index].id());
+            let _ = writeln!(io::stderr(), "note: {}", note);
+            harness::record_note(note);
+        }
+
+        let device_extensions = DeviceExtensions::none();
+        let (device, mut queues) = Device::new(&$physical_device,
+                                               &$physical_device.supported_features(),
+                                               &device_extensions,
+                                               Some((families[index], $priority)))
+            .expect("Failed to create device.");
+        (device, queues.next().unwrap())
+    });
+    (__capabilities__ $families:ident) => {
+        $families.iter()
+            .map(|family| {
+                ::vulkanology::queues::QueueFamily {
+                    graphics: family.supports_graphics(),
+                    compute: family.supports_compute(),
+                    transfer: family.supports_transfers(),
+                    queues: family.queues_count(),
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    (__kind__ compute) => { ::vulkanology::queues::QueueKind::Compute };
+    (__kind__ compute_only) => { ::vulkanology::queues::QueueKind::ComputeOnly };
+    (__kind__ transfer) => { ::vulkanology::queues::QueueKind::Transfer };
+    (__queue__ $created:ident $kind:ident) => { $created.next().unwrap() };
 }
 
 /// Creates a new uninitialized [buffer] of type `$buf_type` of length `$buf_len`.
 ///
+/// Alternatively, `from $data` creates a buffer which takes its length and initial contents
+/// from `$data`, which can be anything with an `iter()` method yielding `&$buf_type`,
+/// e.g. a `Vec` or a slice.
+///
+/// With `const $buf_len` the length must be a constant expression. It is then encoded in the
+/// buffer type, i.e. the buffer content is `[$buf_type; $buf_len]` instead of `[$buf_type]`. Thus
+/// the locks returned by `read` and `write` dereference to a fixed-size array, and comparisons
+/// against an expected array of a different length fail to compile. The buffer is still a
+/// `CpuAccessibleBuffer`, there is no separate handle type with a length parameter.
+///
+/// The length should be a `const` item at module level, or a path to one. The buffer type is
+/// then valid in every scope, whereas a `const` declared inside the test function is not in scope
+/// of the items nested in it, e.g. of the module which vulkano's `pipeline_layout!` generates
+/// around a layout naming the buffer type. `pipeline!` only uses the buffer type in the scope of
+/// its invocation, but a layout written by hand would fail with "cannot find value".
+///
+/// With `from_file $path`, the buffer takes its length and initial contents from the binary file
+/// at `$path`, see the [`import`] module.
+///
+/// With `optional from $data`, `$data` is an `Option` of such a container. If it is `None`, a
+/// dummy buffer holding a single uninitialized element is created instead.
+///
+/// With `single` the buffer holds a single uninitialized value of type `$buf_type`, e.g. a struct
+/// matching a block of scalars, instead of an array. `single from $data` initializes it with the
+/// value `$data`.
+///
+/// With `$buf_len, |$index| $init` the buffer is initialized with the value of `$init` at every
+/// `$index: usize`, and with `$buf_len, from $data` with the contents of `$data`, which must have
+/// `$buf_len` elements.
+///
+/// The buffer can be used for anything by default. `usage: $usage` before the type restricts it
+/// to the `BufferUsage` `$usage`, e.g. to exercise the driver paths of a specific usage without
+/// the validation layers warning about unneeded flags.
+///
+/// The buffer is allocated in the first host visible memory type which supports it. With
+/// `memory: $properties` after the usage, the memory type must also have the
+/// [`MemoryProperties`] `$properties`, e.g. be host cached to exercise the flushes and
+/// invalidations of non-coherent memory, and the buffer is a [`HostBuffer`] instead. This
+/// requires the optional `vulkano` feature, see the [`host_buffer`] module. `pipeline!` cannot
+/// bind a `HostBuffer`, it is only used by the command buffers which a test builds itself.
+///
 /// # Panics
 ///
-/// If the array fails to be initialized.
+/// * If the array fails to be initialized.
+/// * If the size of the buffer in bytes exceeds `u32::MAX`, the maximum range of a storage
+///   buffer descriptor.
+/// * If `from $data` is given together with `$buf_len`, and `$data` has a different length.
+/// * If the file given with `from_file` cannot be read, or does not hold elements of the type
+///   `$buf_type`.
+/// * If no memory type which supports the buffer has the properties given with `memory`.
 ///
 /// # Examples
 ///
@@ -211,24 +865,214 @@ macro_rules! device_and_queue {
 ///
 /// // Initialize a buffer.
 /// let buffer = cpu_array_buffer!(device, queue, u32, 13*31);
+///
+/// // Initialize a buffer with the contents of a `Vec`.
+/// let input_vec: Vec<u32> = (0..42).collect();
+/// let filled_buffer = cpu_array_buffer!(device, queue, u32, from input_vec);
+///
+/// // Initialize a buffer from data which may not be present.
+/// let maybe_input: Option<Vec<u32>> = None;
+/// let dummy_buffer = cpu_array_buffer!(device, queue, u32, optional from maybe_input);
+///
+/// // Initialize a buffer with the element at every index, or with the contents of a `Vec` of
+/// // the given length.
+/// let indexed_buffer = cpu_array_buffer!(device, queue, u32, 42, |i| i as u32 * 3);
+/// let checked_buffer = cpu_array_buffer!(device, queue, u32, 42, from input_vec);
+///
+/// // Initialize a buffer holding a single value.
+/// let counter = cpu_array_buffer!(device, queue, [u32; 2], single from [0, 0]);
+///
+/// // Initialize a buffer which can only be copied from.
+/// use vulkano::buffer::BufferUsage;
+/// let usage = BufferUsage { transfer_source: true, ..BufferUsage::none() };
+/// let source_buffer = cpu_array_buffer!(device, queue, usage: usage, u32, from input_vec);
+///
+/// // Initialize a buffer with a compile-time constant length.
+/// const LEN: usize = 16;
+/// let fixed_buffer = cpu_array_buffer!(device, queue, u32, const LEN);
+/// {
+///     use std::time::Duration;
+///     use vulkano::buffer::cpu_access::WriteLock;
+///     let mut mapping: WriteLock<[u32; LEN]> = fixed_buffer.write(Duration::new(1, 0)).unwrap();
+///     *mapping = [0; LEN];
+/// }
 /// # }
 /// ```
 ///
 /// [buffer]: https://docs.rs/vulkano/0.3.1/vulkano/buffer/cpu_access/struct.CpuAccessibleBuffer.html
+/// [`import`]: import/index.html
+/// [`MemoryProperties`]: host_buffer/struct.MemoryProperties.html
+/// [`HostBuffer`]: host_buffer/struct.HostBuffer.html
+/// [`host_buffer`]: host_buffer/index.html
 ///
 #[macro_export]
 macro_rules! cpu_array_buffer {
-    ($device:ident, $queue:ident, $buf_type:ty, $buf_len:expr) => ({
-        use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+    ($device:ident, $queue:ident, usage: $usage:expr, memory: $memory:expr, $( $rest:tt )*) => {
+        cpu_array_buffer!(__buffer__ ($memory) $device, $queue, $usage, $( $rest )*)
+    };
+    ($device:ident, $queue:ident, memory: $memory:expr, $( $rest:tt )*) => {
+        cpu_array_buffer!(__buffer__ ($memory)
+                          $device,
+                          $queue,
+                          ::vulkano::buffer::BufferUsage::all(),
+                          $( $rest )*)
+    };
+    ($device:ident, $queue:ident, usage: $usage:expr, $( $rest:tt )*) => {
+        cpu_array_buffer!(__buffer__ default $device, $queue, $usage, $( $rest )*)
+    };
+    ($device:ident, $queue:ident, $( $rest:tt )*) => {
+        cpu_array_buffer!(__buffer__ default
+                          $device,
+                          $queue,
+                          ::vulkano::buffer::BufferUsage::all(),
+                          $( $rest )*)
+    };
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     from $data:expr) => ({
+        use vulkanology::checked;
+        let data = &$data;
+        checked::buffer_len::<$buf_type>(stringify!($data), data.iter().len());
+        cpu_array_buffer!(__from_iter__ $memory, $device, $queue, $usage, $buf_type,
+                          data.iter().cloned())
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     from_file $path:expr) => ({
+        use std::path::Path;
+        use vulkanology::import;
+        let data = import::load::<$buf_type>(Path::new(&$path));
+        cpu_array_buffer!(__buffer__ $memory $device, $queue, $usage, $buf_type, from data)
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     optional from $data:expr) => ({
+        use vulkanology::checked;
+        let data = &$data;
+        match *data {
+            Some(ref data) => {
+                checked::buffer_len::<$buf_type>(stringify!($data), data.iter().len());
+                cpu_array_buffer!(__from_iter__ $memory, $device, $queue, $usage, $buf_type,
+                                  data.iter().cloned())
+            }
+            None => cpu_array_buffer!(__buffer__ $memory $device, $queue, $usage, $buf_type, 1),
+        }
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     single from $data:expr) => {
+        cpu_array_buffer!(__from_data__ $memory, $device, $queue, $usage, $buf_type, $data)
+    };
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty, single) => {
+        cpu_array_buffer!(__uninitialized__ $memory, $device, $queue, $usage, $buf_type)
+    };
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     const $buf_len:expr) => ({
+        use vulkanology::checked;
+        checked::buffer_len::<$buf_type>(stringify!($buf_len), $buf_len);
+        cpu_array_buffer!(__uninitialized__ $memory, $device, $queue, $usage,
+                          [$buf_type; $buf_len])
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty, $buf_len:expr,
+     | $index:ident | $init:expr) => ({
+        use vulkanology::checked;
+        let buf_len = checked::buffer_len::<$buf_type>(stringify!($buf_len), $buf_len);
+        cpu_array_buffer!(__from_iter__ $memory, $device, $queue, $usage, $buf_type,
+                          (0..buf_len).map(|$index: usize| -> $buf_type { $init }))
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty, $buf_len:expr,
+     from $data:expr) => ({
+        use vulkanology::checked;
+        let buf_len = checked::buffer_len::<$buf_type>(stringify!($buf_len), $buf_len);
+        let data = &$data;
+        checked::initializer_len(stringify!($data), buf_len, data.iter().len());
+        cpu_array_buffer!(__from_iter__ $memory, $device, $queue, $usage, $buf_type,
+                          data.iter().cloned())
+    });
+    (__buffer__ $memory:tt $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $buf_len:expr) => ({
+        use vulkanology::checked;
+        let buf_len = checked::buffer_len::<$buf_type>(stringify!($buf_len), $buf_len);
+        cpu_array_buffer!(__uninitialized_array__ $memory, $device, $queue, $usage, $buf_type,
+                          buf_len)
+    });
+    (__from_iter__ default, $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $data:expr) => {
+        ::vulkano::buffer::CpuAccessibleBuffer::<[$buf_type]>::from_iter(
+            $device,
+            &$usage,
+            Some($queue.family()),
+            $data)
+            .expect("Failed to create a cpu accessible buffer.")
+    };
+    (__from_iter__ ($memory:expr), $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $data:expr) => {
+        ::vulkanology::host_buffer::HostBuffer::<[$buf_type]>::from_iter(
+            $device,
+            &$usage,
+            &$memory,
+            Some($queue.family()),
+            $data)
+            .expect("Failed to create a host buffer.")
+    };
+    (__from_data__ default, $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $data:expr) => {
+        ::vulkano::buffer::CpuAccessibleBuffer::<$buf_type>::from_data(
+            $device,
+            &$usage,
+            Some($queue.family()),
+            $data)
+            .expect("Failed to create a cpu accessible buffer.")
+    };
+    (__from_data__ ($memory:expr), $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $data:expr) => {
+        ::vulkanology::host_buffer::HostBuffer::<$buf_type>::from_data(
+            $device,
+            &$usage,
+            &$memory,
+            Some($queue.family()),
+            $data)
+            .expect("Failed to create a host buffer.")
+    };
+    (__uninitialized__ default, $device:ident, $queue:ident, $usage:expr, $content:ty) => {
+        unsafe {
+            ::vulkano::buffer::CpuAccessibleBuffer::<$content>::uninitialized(
+                $device,
+                &$usage,
+                Some($queue.family()))
+                .expect("Failed to create a cpu accessible buffer.")
+        }
+    };
+    (__uninitialized__ ($memory:expr), $device:ident, $queue:ident, $usage:expr,
+     $content:ty) => {
+        unsafe {
+            ::vulkanology::host_buffer::HostBuffer::<$content>::uninitialized(
+                $device,
+                &$usage,
+                &$memory,
+                Some($queue.family()))
+                .expect("Failed to create a host buffer.")
+        }
+    };
+    (__uninitialized_array__ default, $device:ident, $queue:ident, $usage:expr, $buf_type:ty,
+     $buf_len:expr) => {
         unsafe {
-            CpuAccessibleBuffer::<[$buf_type]>::uninitialized_array(
+            ::vulkano::buffer::CpuAccessibleBuffer::<[$buf_type]>::uninitialized_array(
                 $device,
                 $buf_len,
-                &BufferUsage::all(),
+                &$usage,
                 Some($queue.family()))
                 .expect("Failed to create a cpu accessible buffer.")
         }
-    })
+    };
+    (__uninitialized_array__ ($memory:expr), $device:ident, $queue:ident, $usage:expr,
+     $buf_type:ty, $buf_len:expr) => {
+        unsafe {
+            ::vulkanology::host_buffer::HostBuffer::<[$buf_type]>::uninitialized_array(
+                $device,
+                $buf_len,
+                &$usage,
+                &$memory,
+                Some($queue.family()))
+                .expect("Failed to create a host buffer.")
+        }
+    };
 }
 
 /// This macro is the core of the shader-testing framework.
@@ -237,17 +1081,29 @@ macro_rules! cpu_array_buffer {
 /// it sets up a [`ComputePipeline`] and provides a function
 /// for executing the shader.
 ///
+/// This macro evolves with the crate, the first version of its DSL stays available as
+/// [`pipeline_v1!`].
+///
+/// The workgroup count and the number of times the execution command dispatches the shader can
+/// be overridden at runtime, see the [`overrides`] module. Every call of the execution command
+/// can also be captured with RenderDoc, see the [`renderdoc`] module. The panics of the test are
+/// followed by a note which identifies the device, see the [`context`] module.
+///
 /// # Panics
 ///
 /// * If the `instance`, `physical_device`, `device` or `queue` cannot be selected/initialized.
-/// * If the buffers cannot be initialized.
+/// * If the buffers or images cannot be initialized, or are too large to be bound.
+/// * If the bound range of a buffer exceeds it or is misaligned, see the [`checked`] module.
+/// * If the workgroup count does not fit into a `[u32; 3]`, see the [`checked`] module.
+/// * If the element type of a buffer does not match the reflected layout of the shader, or if
+///   the type of a uniform buffer is smaller than its block, see the [`reflection`] module.
+/// * If two specialization constants share an id, or a constant has an invalid size.
 /// * If the shader cannot be loaded.
 /// * If the pipeline cannot be created.
 ///
 /// # Example
 ///
 /// ```
-/// # #[macro_use]
 /// # extern crate vulkano;
 /// # #[macro_use]
 /// # extern crate vulkanology;
@@ -265,16 +1121,170 @@ macro_rules! cpu_array_buffer {
 ///
 /// // I. Invoke the `pipeline!` macro.
 /// // The macro parameters are:
+/// //    0. (Optional) The index of the physical device to run on, in the order of enumeration.
+/// //        `device: 1,`
+/// //       The default is the first device, unless it is overridden from the environment,
+/// //       see the [`overrides`] module. To compare the results of two devices, invoke
+/// //       `pipeline!` in a function taking the index, see `tests/cross_device.rs`.
+/// //       It can be followed by the device features which the shader requires, named like
+/// //       the fields of `vulkano::instance::Features`, e.g. `shader_f3264` for `shaderFloat64`:
+/// //        `features: [shader_f3264],`
+/// //       If the device does not support one of them, `pipeline!` notes it on stderr and
+/// //       returns from the enclosing function, so the test is skipped instead of failing.
+/// //       The enclosing function must therefore return `()`. All supported features are
+/// //       enabled, and if one which the reflected capabilities of the shaders require, e.g.
+/// //       `shader_f3264` for `Float64`, is not supported, `pipeline!` panics naming it.
+/// //        For reference see `tests/double_precision.rs`.
+/// //       Then validation can be made strict, which enables the Khronos validation layer and
+/// //       panics at the end of the test if it reported an error or a warning:
+/// //        `validation: strict,`
+/// //       The layer must be installed. For reference see `tests/validation.rs` and the
+/// //       [`validation`] module.
+/// //       All of them can be preceded by `unavailable: skip,` which skips the test on machines
+/// //       without a Vulkan implementation, like the same parameter of `instance!`:
+/// //        `unavailable: skip,`
+/// //       and by `context: shared,` which reuses the instance, device and queue of the other
+/// //       tests of the binary with the same device and validation, instead of creating them
+/// //       for every test. See the [`shared`] module.
+/// //        `context: shared,`
+/// //       and by `lock_timeout: $duration,` which sets the initial timeout of `read_buffer!`
+/// //       and `write_buffer!` for the rest of the test, see the [`locks`] module.
+/// //        `lock_timeout: Duration::from_secs(5),`
 /// //    1. The path to the shader program, relative to the crate root.
 /// //        `shader_path: "path/to/shader/program.comp"`
 /// //    2. A three-dimensional array defining the workgroup count:
 /// //        `workgroup_count: [1, 2, 3],`
+/// //       The dimensions can be of any integer type, they are checked to fit into a `u32`.
 /// //    3. (Optional) The push constants for your shader.
-/// //        For reference see `tests/push_constants.rs`.
-/// //    4. The buffers that your test shader uses:
+/// //        `push_constants: { a: f32 = 4.0, b: f32 = 10.0 },`
+/// //       The push constants can also be split into ranges with explicit offsets and
+/// //       optional sizes, matching `layout(offset = …)` in the shader:
+/// //        `push_constants: { offset 0: { a: f32 = 4.0 }, offset 16, size 4: { b: f32 = 1.0 } },`
+/// //       A range can restrict the stages which access it, e.g. `offset 0, stages [compute]`.
+/// //       The values are laid out by the `std430` rules. Besides scalars, they can be arrays,
+/// //       vectors and matrices like `Vec3<f32>` and `Mat4`, and structs declared with
+/// //       `std430_struct!`:
+/// //        `push_constants: { transform: Mat4 = transform, lights: [Light; 2] = lights },`
+/// //       The pushed block ends with the last range and may take up to 256 bytes, if the
+/// //       `maxPushConstantsSize` of the device allows it.
+/// //        For reference see `tests/push_constants.rs` and the [`push_constants`] module.
+/// //    4. (Optional) The specialization constants for your shader, which are set when the
+/// //       pipeline is created. The ids follow the order of declaration and can be given
+/// //       explicitly to match `layout(constant_id = …)` in the shader:
+/// //        `spec_constants: { block_size: u32 = 64, scale @ constant_id 3: f32 = 0.5 },`
+/// //       A constant is 4 or 8 bytes large, a GLSL `bool` is given as a `u32`. The workgroup
+/// //       size can be specialized with `layout(local_size_x_id = …) in;`.
+/// //        For reference see `tests/spec_constants.rs`.
+/// //    5. The buffers that your test shader uses:
 /// //        `buffers: { input_data: [u32;4], some_buffer: [Dennis;42] },`
-/// //    5. The name of the shader execution:
+/// //       A buffer can also take its length and contents from a `Vec` or slice:
+/// //        `buffers: { input_data: [u32] from input_vec },`
+/// //       or from a binary file, e.g. a NumPy `.npy` file, see the [`import`] module:
+/// //        `buffers: { input_data: [u32] from_file "tests/data/input.npy" },`
+/// //       or be initialized when it is created, either with the element at every index or
+/// //       with a `Vec` or slice of the given length:
+/// //        `buffers: { input_data: [u32; 4096] = |i| i as u32 * 3, weights: [f32; 16] = w },`
+/// //       or encode a constant length in the buffer type, given as a literal or as a
+/// //       module-level `const` item, see [`cpu_array_buffer!`]:
+/// //        `buffers: { input_data: [u32; const 4096] },`
+/// //        For reference see `tests/const_buffers.rs`.
+/// //       By default all buffers are in set 0 and the bindings follow the order of
+/// //       declaration. Both can be given explicitly to match an existing shader:
+/// //        `buffers: { input_data @ set 0, binding 3: [u32; 4096] },`
+/// //       The shader stages which access a buffer default to all stages and can be
+/// //       restricted in the same way:
+/// //        `buffers: { input_data @ stages [compute]: [u32; 4096] },`
+/// //       A storage buffer can be bound partially, as the `len` elements starting at the
+/// //       element `offset`, which must be aligned to the `minStorageBufferOffsetAlignment` of
+/// //       the device. Without `len`, the range extends to the end of the buffer:
+/// //        `buffers: { view @ offset 256, len 1024: [f32; 4096] },`
+/// //       Bindings which the shader declares, but which the tested code path does not use,
+/// //       can be marked as optional. A dummy buffer with a single element is bound to them,
+/// //       unless the `Option` given with `from` contains data:
+/// //        `buffers: { unused: optional [u32], maybe_data: optional [u32] from maybe_vec },`
+/// //       A buffer can be allocated in device local memory, which is faster to access from
+/// //       the shader than the host visible memory of the other buffers. Its content is read
+/// //       and written through a staging buffer, which every execution copies into the buffer
+/// //       before the dispatch and back after it:
+/// //        `buffers: { input_data: device_local [u32] from input_vec },`
+/// //       The element type of a buffer of GLSL structs can be declared with
+/// //       `std430_buffer_struct!`, which checks its `std430` size at compile time and the
+/// //       offsets of its members in a generated test, see the [`push_constants`] module. The
+/// //       GLSL vectors and matrices of the [`glsl`] module are padded like the elements of
+/// //       a `std430` array:
+/// //        `buffers: { positions: [ArrayVec3<f32>; 1024], transforms: [ArrayMat4; 16] },`
+/// //       A buffer bound to a block of scalars, e.g. of counters, can hold a single value instead
+/// //       of an array, which is either uninitialized or given with `=`:
+/// //        `buffers: { counters: Counters = Counters { hits: 0, misses: 0 } },`
+/// //       Texel buffers are given by their format and length, or take their contents from
+/// //       a `Vec` or slice of pixels, and are bound to `samplerBuffer` and `imageBuffer`
+/// //       uniforms respectively:
+/// //        `buffers: { lut: uniform_texel [R32Sfloat] from lut_vec },`
+/// //        `buffers: { result: storage_texel [R32Uint; 64] },`
+/// //       Instead of `buffers`, the buffers can be grouped by their descriptor set, in which
+/// //       the bindings follow the order of declaration:
+/// //        `sets: { 0: { input_data: [u32; 4096] }, 1: { result: [u32; 4096] } },`
+/// //       The sets are rewritten token by token, so a crate with several sets of buffers may
+/// //       need `#![recursion_limit = "128"]`.
+/// //        For reference see `tests/explicit_bindings.rs`.
+/// //    6. (Optional) The uniform buffers that your test shader uses. Each uniform buffer
+/// //       holds a single value, which is either uninitialized or given with `=`:
+/// //        `uniforms: { parameters: Parameters, scale: [f32; 4] = [2.0; 4] },`
+/// //       The bindings continue after the buffers and can be given explicitly like theirs:
+/// //        `uniforms: { parameters @ set 1, binding 0: Parameters },`
+/// //        For reference see `tests/uniforms.rs`.
+/// //    7. (Optional) The two-dimensional images that your test shader uses, given by their
+/// //       format and size. Storage images are bound to `image2D` uniforms:
+/// //        `images: { heightmap: [R32Sfloat; 256, 256] },`
+/// //       Sampled images are bound to `sampler2D` uniforms together with a sampler, whose
+/// //       filter and address mode default to `Nearest` and `ClampToEdge`:
+/// //        `images: { texture: sampled (Linear, Repeat) [R8G8B8A8Unorm; 16, 16] },`
+/// //       The bindings continue after the uniforms and can be given explicitly like theirs.
+/// //       The pixels of an image are read and written like the content of a buffer: every
+/// //       execution copies them from a staging buffer into the image before the dispatch,
+/// //       and back after it for storage images.
+/// //        For reference see `tests/images.rs`.
+/// //    8. (Optional) Further passes, which are dispatched after the shader in the order of
+/// //       declaration. Each pass has its own shader and workgroup count, and shares the
+/// //       buffers, images and constants of the pipeline:
+/// //        `passes: { scatter: { shader_path: "scatter.comp", workgroup_count: [64, 1, 1] } },`
+/// //       All dispatches are recorded into one command buffer, which inserts the barriers
+/// //       between them. The buffer layouts are only checked against the first shader.
+/// //        For reference see `tests/multipass.rs`.
+/// //    9. (Optional) Two buffers of the same type, which hold the current and the next state
+/// //       of an iterative shader:
+/// //        `ping_pong: { state, next_state },`
+/// //       The shader reads the first and writes the second buffer. The buffers swap their
+/// //       bindings after every dispatch, and the newest state always ends up in the first
+/// //       buffer.
+/// //        For reference see `tests/ping_pong.rs`.
+/// //   10. The name of the shader execution:
 /// //        `execution_command: run_example_shader_function_name`
+/// //       It waits for the shader at most for a timeout, after which it panics with a report
+/// //       of the probable hang, see the [`hang`] module.
+/// //       To dispatch the same pipeline with different workgroup counts, name an argument,
+/// //       which makes the shader execution take the workgroup count as a `[u32; 3]`:
+/// //        `execution_command: run_example_shader_function_name(workgroup_count)`
+/// //       The execution then builds a new command buffer every time, and the workgroup count
+/// //       passed to it is not overridden from the environment.
+/// //        For reference see `tests/workgroup_count.rs`.
+/// //       With `ping_pong` buffers, the argument is instead the number of dispatches as a
+/// //       `u32`, which are recorded into one command buffer:
+/// //        `execution_command: run_example_shader_function_name(dispatches)`
+/// //       A second command, which submits the command buffer of the execution without an
+/// //       argument a given number of times, can be named after it. The submissions are
+/// //       queued back to back and waited for once at the end, instead of after every one:
+/// //        `execution_command: run_example_shader_function_name, repeat_command: run_n`
+/// //       It takes the number of submissions as a `u32`, which is not overridden from the
+/// //       environment. `vulkano` still creates a fence and a semaphore for every
+/// //       submission, which cannot be reused.
+/// //       Another command, which submits the command buffer once and returns without
+/// //       waiting, can be named last. It returns the `Arc<Submission>` of `vulkano`, whose
+/// //       `wait(timeout)` waits for the execution and whose `finished()` polls it, so the CPU
+/// //       can prepare the expected results in the meantime. Dropping the submission also waits
+/// //       for it, without a timeout. The buffers must only be read after the wait:
+/// //        `execution_command: run_example_shader_function_name, async_command: run_async`
+/// //        For reference see `tests/repeat_command.rs` and `tests/async_command.rs`.
 /// pipeline!{
 ///     shader_path: "tests/shaders/example.comp",
 ///     workgroup_count: [100, 100, 1],
@@ -286,13 +1296,13 @@ macro_rules! cpu_array_buffer {
 /// }
 ///
 /// // II. Fill your buffers with input data. The buffers are bound to the
-/// //      names given in the `pipeline!` macro.
+/// //      names given in the `pipeline!` macro. `write_buffer!` and `read_buffer!` lock them,
+/// //      and retry if the device takes longer than the timeout of the lock.
 /// {
-///     use std::time::Duration;
 ///     use rand::random;
 ///
 ///     use vulkano::buffer::cpu_access::WriteLock;
-///     let mut mapping: WriteLock<[u32]> = data.write(Duration::new(1, 0)).unwrap();
+///     let mut mapping: WriteLock<[u32]> = write_buffer!(data);
 ///
 ///     for item in mapping.iter_mut() {
 ///         *item = random::<u32>();
@@ -306,10 +1316,9 @@ macro_rules! cpu_array_buffer {
 /// // IV. Assert validity of the results.
 /// //    `assert!(datainbuffersisvalid())`
 /// {
-///     use std::time::Duration;
 ///     use vulkano::buffer::cpu_access::ReadLock;
-///     let input: ReadLock<[u32]> = data.read(Duration::new(1, 0)).unwrap();
-///     let output: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+///     let input: ReadLock<[u32]> = read_buffer!(data);
+///     let output: ReadLock<[u32]> = read_buffer!(result);
 ///     let zipped = input.iter().zip(output.iter());
 ///
 ///     for (invocation_uid, (item_in, item_out)) in zipped.enumerate() {
@@ -322,44 +1331,2216 @@ macro_rules! cpu_array_buffer {
 /// [`CpuAccessibleBuffer`]:
 ///     https://docs.rs/vulkano/0.3.1/vulkano/buffer/cpu_access/struct.CpuAccessibleBuffer.html
 /// [`ComputePipeline`]: https://docs.rs/vulkano/0.3.1/vulkano/pipeline/struct.ComputePipeline.html
+/// [`cpu_array_buffer!`]: macro.cpu_array_buffer.html
+/// [`pipeline_v1!`]: macro.pipeline_v1.html
+/// [`overrides`]: overrides/index.html
+/// [`glsl`]: glsl/index.html
+/// [`hang`]: hang/index.html
+/// [`import`]: import/index.html
+/// [`locks`]: locks/index.html
+/// [`push_constants`]: push_constants/index.html
+/// [`checked`]: checked/index.html
+/// [`context`]: context/index.html
+/// [`reflection`]: reflection/index.html
+/// [`renderdoc`]: renderdoc/index.html
+/// [`shared`]: shared/index.html
+/// [`validation`]: validation/index.html
 ///
 #[macro_export]
 macro_rules! pipeline {
+    // Skip the test if Vulkan is not available at all.
     {
-        shader_path: $shader_path:expr,
-        workgroup_count: $workgroup_count:expr,
-        buffers: { $( $buf_ident:ident : [$buf_type:ty;$buf_len:expr] ),* },
-        execution_command: $exec_cmd:ident
+        unavailable: skip,
+        $( $rest:tt )*
     } => {
+        if let Some(reason) = instance!(__unavailable__) {
+            ::vulkanology::harness::note_unavailable(concat!(file!(), ":", line!()), &reason);
+            return;
+        }
         pipeline! {
-            shader_path: $shader_path,
-            workgroup_count: $workgroup_count,
-            push_constants: {},
-            buffers: { $( $buf_ident : [$buf_type;$buf_len] ),* },
-            execution_command: $exec_cmd
+            $( $rest )*
         }
     };
+    // Set the initial timeout of `read_buffer!` and `write_buffer!` for the rest of the test.
     {
-        shader_path: $shader_path:expr,
-        workgroup_count: $workgroup_count:expr,
-        push_constants: {
-            $( $push_constant_name:ident : $push_constant_type:ty = $push_constant_value:expr ),*
-        },
-        buffers: { $( $buf_ident:ident : [$buf_type:ty;$buf_len:expr] ),* },
-        execution_command: $exec_cmd:ident
+        lock_timeout: $lock_timeout:expr,
+        $( $rest:tt )*
     } => {
-        use vulkano::command_buffer::PrimaryCommandBufferBuilder;
-        use vulkano::command_buffer::submit as submit_command;
-        use vulkano::descriptor::descriptor_set::DescriptorPool;
-        use vulkano::pipeline::ComputePipeline;
-
-        // Include the shader wrapper.
-        mod shader {
-            #![allow(dead_code)]
-            include!{concat!(env!("OUT_DIR"), concat!("/shaders/", $shader_path))}
+        ::vulkanology::locks::set_timeout($lock_timeout);
+        pipeline! {
+            $( $rest )*
         }
-
-        // Create the pipeline layout wrapper.
+    };
+    // Reuse the instance, device and queue of the other tests of the binary.
+    {
+        context: shared,
+        $( $rest:tt )*
+    } => {
+        let _shared_context = ::vulkanology::shared::request();
+        pipeline! {
+            $( $rest )*
+        }
+    };
+    {
+        shader_path: $( $rest:tt )*
+    } => {
+        pipeline! {
+            device: ::vulkanology::overrides::DefaultDevice,
+            shader_path: $( $rest )*
+        }
+    };
+    {
+        features: $( $rest:tt )*
+    } => {
+        pipeline! {
+            device: ::vulkanology::overrides::DefaultDevice,
+            features: $( $rest )*
+        }
+    };
+    {
+        validation: $( $rest:tt )*
+    } => {
+        pipeline! {
+            device: ::vulkanology::overrides::DefaultDevice,
+            validation: $( $rest )*
+        }
+    };
+    {
+        device: $device:expr,
+        shader_path: $( $rest:tt )*
+    } => {
+        pipeline! {
+            device: $device,
+            validation: none,
+            shader_path: $( $rest )*
+        }
+    };
+    {
+        device: $device:expr,
+        validation: $validation:ident,
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        $( $sections:tt )*
+    } => {
+        pipeline! {
+            __sets__ {
+                device: $device,
+                validation: $validation,
+                shader_path: $shader_path,
+                workgroup_count: $workgroup_count,
+                passes: {},
+                ping_pong: {},
+            }
+            [] $( $sections )*
+        }
+    };
+    // Skip the test on devices which do not support one of the required features, before
+    // anything is allocated on them.
+    {
+        device: $device:expr,
+        features: [ $( $feature:ident ),* $(,)* ],
+        $( validation: $validation:ident, )*
+        shader_path: $shader_path:expr,
+        $( $rest:tt )*
+    } => {
+        {
+            use vulkanology::harness;
+            let instance = instance!();
+            let physical_device = physical_device!(instance, device: $device);
+            let supported = physical_device.supported_features();
+            let missing = [ $( (stringify!($feature), supported.$feature) ),* ].iter()
+                .filter(|&&(_, supported)| !supported)
+                .map(|&(feature, _)| feature)
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                harness::note_skipped($shader_path, &physical_device.name(), &missing);
+                return;
+            }
+        }
+        pipeline! {
+            device: $device,
+            $( validation: $validation, )*
+            shader_path: $shader_path,
+            $( $rest )*
+        }
+    };
+
+    // Replace the `sets` section by a `buffers` section, in which every buffer is placed in its
+    // set explicitly. The bindings of a set follow the order of declaration, starting at 0.
+    (__sets__ $head:tt [ $( $done:tt )* ] sets: { $( $sets:tt )* } $( $rest:tt )*) => {
+        pipeline! { __set__ $head [ $( $done )* ] [] ( $( $sets )* ) $( $rest )* }
+    };
+    // Move the `passes` and `ping_pong` sections into the head, as they do not take part in the
+    // other sections.
+    (__sets__ {
+        device: $device:expr,
+        validation: $validation:ident,
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        passes: $old_passes:tt,
+        ping_pong: $ping_pong:tt,
+    } $done:tt passes: $passes:tt, $( $rest:tt )*) => {
+        pipeline! {
+            __sets__ {
+                device: $device,
+                validation: $validation,
+                shader_path: $shader_path,
+                workgroup_count: $workgroup_count,
+                passes: $passes,
+                ping_pong: $ping_pong,
+            }
+            $done $( $rest )*
+        }
+    };
+    (__sets__ {
+        device: $device:expr,
+        validation: $validation:ident,
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        passes: $passes:tt,
+        ping_pong: $old_ping_pong:tt,
+    } $done:tt ping_pong: $ping_pong:tt, $( $rest:tt )*) => {
+        pipeline! {
+            __sets__ {
+                device: $device,
+                validation: $validation,
+                shader_path: $shader_path,
+                workgroup_count: $workgroup_count,
+                passes: $passes,
+                ping_pong: $ping_pong,
+            }
+            $done $( $rest )*
+        }
+    };
+    (__sets__ $head:tt [ $( $done:tt )* ] $token:tt $( $rest:tt )*) => {
+        pipeline! { __sets__ $head [ $( $done )* $token ] $( $rest )* }
+    };
+    (__sets__ $head:tt [ $( $done:tt )* ]) => {
+        pipeline! { __sections__ $head $( $done )* }
+    };
+    (__set__ $head:tt $done:tt $buffers:tt ( , $( $sets:tt )* ) $( $rest:tt )*) => {
+        pipeline! { __set__ $head $done $buffers ( $( $sets )* ) $( $rest )* }
+    };
+    (__set__ $head:tt $done:tt $buffers:tt
+     ( $set:tt : { $( $decls:tt )* } $( $sets:tt )* ) $( $rest:tt )*) => {
+        pipeline! {
+            __set_decl__ $head $done $buffers $set (0) ( $( $decls )* ) ( $( $sets )* ) $( $rest )*
+        }
+    };
+    (__set__ $head:tt [ $( $done:tt )* ] [ $( $buffers:tt )* ] () $( $rest:tt )*) => {
+        pipeline! { __sets__ $head [ $( $done )* buffers: { $( $buffers )* } ] $( $rest )* }
+    };
+    (__set_decl__ $head:tt $done:tt $buffers:tt $set:tt ($binding:expr)
+     ( , $( $decls:tt )* ) $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_decl__ $head $done $buffers $set ($binding) ( $( $decls )* ) $sets $( $rest )*
+        }
+    };
+    (__set_decl__ $head:tt $done:tt [ $( $buffers:tt )* ] $set:tt ($binding:expr)
+     ( $buf_ident:ident @ $( $decls:tt )* ) $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_type__ $head $done [ $( $buffers )* $buf_ident @ set $set, binding ($binding), ]
+            $set ($binding + 1) ( $( $decls )* ) $sets $( $rest )*
+        }
+    };
+    (__set_decl__ $head:tt $done:tt [ $( $buffers:tt )* ] $set:tt ($binding:expr)
+     ( $buf_ident:ident : $( $decls:tt )* ) $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_type__ $head $done [ $( $buffers )* $buf_ident @ set $set, binding ($binding) : ]
+            $set ($binding + 1) ( $( $decls )* ) $sets $( $rest )*
+        }
+    };
+    (__set_decl__ $head:tt $done:tt $buffers:tt $set:tt ($binding:expr)
+     () ( $( $sets:tt )* ) $( $rest:tt )*) => {
+        pipeline! { __set__ $head $done $buffers ( $( $sets )* ) $( $rest )* }
+    };
+    (__set_type__ $head:tt $done:tt [ $( $buffers:tt )* ] $set:tt ($binding:expr)
+     ( , $( $decls:tt )* ) $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_decl__ $head $done [ $( $buffers )* , ] $set ($binding) ( $( $decls )* ) $sets
+            $( $rest )*
+        }
+    };
+    (__set_type__ $head:tt $done:tt [ $( $buffers:tt )* ] $set:tt ($binding:expr)
+     ( $token:tt $( $decls:tt )* ) $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_type__ $head $done [ $( $buffers )* $token ] $set ($binding) ( $( $decls )* )
+            $sets $( $rest )*
+        }
+    };
+    (__set_type__ $head:tt $done:tt [ $( $buffers:tt )* ] $set:tt ($binding:expr)
+     () $sets:tt $( $rest:tt )*) => {
+        pipeline! {
+            __set_decl__ $head $done [ $( $buffers )* , ] $set ($binding) () $sets $( $rest )*
+        }
+    };
+
+    // Fill in the optional sections, in the order `push_constants`, `spec_constants`, `buffers`,
+    // `uniforms`, `images`, until all of them are present.
+    (__sections__ { $( $head:tt )* }
+     push_constants: { $( $push_constants:tt )* },
+     spec_constants: { $( $spec_constants:tt )* },
+     buffers: { $( $buffers:tt )* },
+     uniforms: { $( $uniforms:tt )* },
+     images: { $( $images:tt )* },
+     execution_command: $exec_cmd:ident $( ( $exec_count:ident ) )*
+     $( , repeat_command: $repeat_cmd:ident )*
+     $( , async_command: $async_cmd:ident )*) => {
+        pipeline! {
+            __spec_constants__ {
+                $( $head )*
+                push_constants: { $( $push_constants )* },
+                buffers: { $( $buffers )* },
+                uniforms: { $( $uniforms )* },
+                images: { $( $images )* },
+                execution_command: (
+                    $exec_cmd $( ($exec_count) )* [ $( $repeat_cmd )* ] [ $( $async_cmd )* ]
+                )
+            }
+            [] (0) $( $spec_constants )*
+        }
+    };
+    (__sections__ $head:tt
+     push_constants: $push_constants:tt,
+     spec_constants: $spec_constants:tt,
+     buffers: $buffers:tt,
+     uniforms: $uniforms:tt,
+     execution_command: $( $exec_cmd:tt )+) => {
+        pipeline! {
+            __sections__ $head
+            push_constants: $push_constants,
+            spec_constants: $spec_constants,
+            buffers: $buffers,
+            uniforms: $uniforms,
+            images: {},
+            execution_command: $( $exec_cmd )+
+        }
+    };
+    (__sections__ $head:tt
+     push_constants: $push_constants:tt,
+     spec_constants: $spec_constants:tt,
+     buffers: $buffers:tt,
+     $( images: { $( $images:tt )* }, )*
+     execution_command: $( $exec_cmd:tt )+) => {
+        pipeline! {
+            __sections__ $head
+            push_constants: $push_constants,
+            spec_constants: $spec_constants,
+            buffers: $buffers,
+            uniforms: {},
+            images: { $( $( $images )* )* },
+            execution_command: $( $exec_cmd )+
+        }
+    };
+    (__sections__ $head:tt push_constants: $push_constants:tt, buffers: $( $rest:tt )*) => {
+        pipeline! {
+            __sections__ $head
+            push_constants: $push_constants,
+            spec_constants: {},
+            buffers: $( $rest )*
+        }
+    };
+    (__sections__ $head:tt spec_constants: $( $rest:tt )*) => {
+        pipeline! { __sections__ $head push_constants: {}, spec_constants: $( $rest )* }
+    };
+    (__sections__ $head:tt buffers: $( $rest:tt )*) => {
+        pipeline! { __sections__ $head push_constants: {}, buffers: $( $rest )* }
+    };
+
+    // Normalize the specialization constants into a list of `($id, $name, $type, $value)`.
+    // `$index` is the position of the next constant, which is used as its id unless the id is
+    // given explicitly.
+    (__spec_constants__ $context:tt [ $( $done:tt )* ] ($index:expr)
+     $spec_name:ident @ constant_id $spec_id:tt : $spec_type:ty = $spec_value:expr
+     , $( $rest:tt )*) => {
+        pipeline! {
+            __spec_constants__ $context
+            [ $( $done )* ($spec_id, $spec_name, $spec_type, $spec_value) ]
+            ($index + 1) $( $rest )*
+        }
+    };
+    (__spec_constants__ $context:tt $done:tt ($index:expr)
+     $spec_name:ident @ constant_id $spec_id:tt : $spec_type:ty = $spec_value:expr) => {
+        pipeline! {
+            __spec_constants__ $context $done ($index)
+            $spec_name @ constant_id $spec_id : $spec_type = $spec_value,
+        }
+    };
+    (__spec_constants__ $context:tt [ $( $done:tt )* ] ($index:expr)
+     $spec_name:ident : $spec_type:ty = $spec_value:expr , $( $rest:tt )*) => {
+        pipeline! {
+            __spec_constants__ $context
+            [ $( $done )* (($index), $spec_name, $spec_type, $spec_value) ]
+            ($index + 1) $( $rest )*
+        }
+    };
+    (__spec_constants__ $context:tt $done:tt ($index:expr)
+     $spec_name:ident : $spec_type:ty = $spec_value:expr) => {
+        pipeline! {
+            __spec_constants__ $context $done ($index) $spec_name : $spec_type = $spec_value,
+        }
+    };
+    (__spec_constants__ {
+        device: $device:expr,
+        validation: $validation:ident,
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        passes: $passes:tt,
+        ping_pong: $ping_pong:tt,
+        push_constants: { $( $push_constants:tt )* },
+        buffers: $buffers:tt,
+        uniforms: $uniforms:tt,
+        images: $images:tt,
+        execution_command: $exec_cmd:tt
+    } $done:tt ($index:expr)) => {
+        pipeline! {
+            __push_constants__ {
+                device: $device,
+                validation: $validation,
+                shader_path: $shader_path,
+                workgroup_count: $workgroup_count,
+                passes: $passes,
+                ping_pong: $ping_pong,
+                spec_constants: $done,
+                buffers: $buffers,
+                uniforms: $uniforms,
+                images: $images,
+                execution_command: $exec_cmd
+            }
+            $( $push_constants )*
+        }
+    };
+
+    // Normalize the push constants into a list of ranges `($offset, $size, $stages) { $fields }`,
+    // where `$size` is an `Option` and `$stages` is either `default` or a list of shader stages.
+    // A flat list of push constants is a single range at offset 0.
+    (__push_constants__ $context:tt) => {
+        pipeline! { __push_constant_ranges__ $context [] }
+    };
+    (__push_constants__ $context:tt
+     $( $push_constant_name:ident : $push_constant_type:ty = $push_constant_value:expr ),+) => {
+        pipeline! {
+            __push_constant_ranges__ $context
+            [
+                (0, None, default) {
+                    $( $push_constant_name: $push_constant_type = $push_constant_value ),+
+                }
+            ]
+        }
+    };
+    (__push_constants__ $context:tt $( $push_constant_ranges:tt )*) => {
+        pipeline! { __push_constant_ranges__ $context [] $( $push_constant_ranges )* }
+    };
+    (__push_constant_ranges__ $context:tt $done:tt offset $offset:tt $( $rest:tt )*) => {
+        pipeline! {
+            __push_constant_range__ $context $done
+            { offset: $offset, size: None, stages: default } $( $rest )*
+        }
+    };
+    (__push_constant_range__ $context:tt $done:tt
+     { offset: $offset:tt, size: $size:tt, stages: $stages:tt }
+     , size $new_size:tt $( $rest:tt )*) => {
+        pipeline! {
+            __push_constant_range__ $context $done
+            { offset: $offset, size: (Some($new_size)), stages: $stages } $( $rest )*
+        }
+    };
+    (__push_constant_range__ $context:tt $done:tt
+     { offset: $offset:tt, size: $size:tt, stages: $stages:tt }
+     , stages [ $( $stage:ident ),* ] $( $rest:tt )*) => {
+        pipeline! {
+            __push_constant_range__ $context $done
+            { offset: $offset, size: $size, stages: [ $( $stage ),* ] } $( $rest )*
+        }
+    };
+    (__push_constant_range__ $context:tt [ $( $done:tt )* ]
+     { offset: $offset:tt, size: $size:tt, stages: $stages:tt }
+     : { $( $fields:tt )* } $( $rest:tt )*) => {
+        pipeline! {
+            __push_constant_ranges__ $context
+            [ $( $done )* ($offset, $size, $stages) { $( $fields )* } ]
+            $( $rest )*
+        }
+    };
+    (__push_constant_ranges__ $context:tt [ $( $done:tt )* ] , $( $rest:tt )*) => {
+        pipeline! { __push_constant_ranges__ $context [ $( $done )* ] $( $rest )* }
+    };
+    (__push_constant_ranges__ {
+        device: $device:expr,
+        validation: $validation:ident,
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        passes: $passes:tt,
+        ping_pong: $ping_pong:tt,
+        spec_constants: $spec_constants:tt,
+        buffers: { $( $buffers:tt )* },
+        uniforms: { $( $uniforms:tt )* },
+        images: { $( $images:tt )* },
+        execution_command: $exec_cmd:tt
+    } [ $( $done:tt )* ]) => {
+        pipeline! {
+            __append__ {
+                device: $device,
+                validation: $validation,
+                shader_path: $shader_path,
+                workgroup_count: $workgroup_count,
+                passes: $passes,
+                ping_pong: $ping_pong,
+                push_constants: [ $( $done )* ],
+                spec_constants: $spec_constants,
+                execution_command: $exec_cmd
+            }
+            [ $( $buffers )* , ] uniform ( $( $uniforms )* ) image ( $( $images )* )
+        }
+    };
+
+    // Append the uniforms and images to the buffers as `$name $( @ $location )*: $kind $type`,
+    // where `$kind` is `uniform` or `image`, by copying the tokens of every declaration up to
+    // its colon and comma.
+    (__append__ $context:tt [ $( $buffers:tt )* ] $kind:ident
+     ( $decl_ident:ident $( $decl:tt )* ) $( $sections:tt )*) => {
+        pipeline! {
+            __append_location__ $context [ $( $buffers )* $decl_ident ] $kind
+            ( $( $decl )* ) $( $sections )*
+        }
+    };
+    (__append__ $context:tt $buffers:tt $kind:ident () $next_kind:ident $next:tt
+     $( $sections:tt )*) => {
+        pipeline! { __append__ $context $buffers $next_kind $next $( $sections )* }
+    };
+    (__append__ $context:tt [ $( $buffers:tt )* ] $kind:ident ()) => {
+        pipeline! { __buffers__ $context [] (0) $( $buffers )* }
+    };
+    (__append_location__ $context:tt [ $( $buffers:tt )* ] $kind:ident
+     ( : $( $decl:tt )* ) $( $sections:tt )*) => {
+        pipeline! {
+            __append_type__ $context [ $( $buffers )* : $kind ] $kind ( $( $decl )* )
+            $( $sections )*
+        }
+    };
+    (__append_location__ $context:tt [ $( $buffers:tt )* ] $kind:ident
+     ( $token:tt $( $decl:tt )* ) $( $sections:tt )*) => {
+        pipeline! {
+            __append_location__ $context [ $( $buffers )* $token ] $kind ( $( $decl )* )
+            $( $sections )*
+        }
+    };
+    (__append_type__ $context:tt [ $( $buffers:tt )* ] $kind:ident
+     ( , $( $decl:tt )* ) $( $sections:tt )*) => {
+        pipeline! {
+            __append__ $context [ $( $buffers )* , ] $kind ( $( $decl )* ) $( $sections )*
+        }
+    };
+    (__append_type__ $context:tt [ $( $buffers:tt )* ] $kind:ident
+     ( $token:tt $( $decl:tt )* ) $( $sections:tt )*) => {
+        pipeline! {
+            __append_type__ $context [ $( $buffers )* $token ] $kind ( $( $decl )* )
+            $( $sections )*
+        }
+    };
+    (__append_type__ $context:tt [ $( $buffers:tt )* ] $kind:ident () $( $sections:tt )*) => {
+        pipeline! { __append__ $context [ $( $buffers )* , ] $kind () $( $sections )* }
+    };
+    // The push constant block ends with the last range, whose end is computed from the types of
+    // its push constants, so the pipeline layout declares and every dispatch pushes the bytes of
+    // the ranges and not more.
+    (__push_constants_type__) => {
+        type PushConstants = ();
+    };
+    (__push_constants_type__ $( $range:tt )+) => {
+        use vulkanology::push_constants::{PushConstantBlock, Std430Extent};
+        type PushConstants = PushConstantBlock<
+            [u32; (<pipeline!(__push_constants_extent__ (); $( $range )+) as
+                    Std430Extent>::END + 3) / 4]
+        >;
+    };
+    (__push_constants_extent__ $extent:ty;) => { $extent };
+    (__push_constants_extent__ $extent:ty; ($offset:tt, None) { $( $field_type:ty ),* }
+     $( $rest:tt )*) => {
+        pipeline!(__push_constants_extent__
+                     ::vulkanology::push_constants::Union<
+                         $extent,
+                         pipeline!(__push_constant_range_extent__
+                                      ::vulkanology::push_constants::Words<[u32; $offset / 4]>;
+                                      $( $field_type ),*)
+                     >;
+                     $( $rest )*)
+    };
+    (__push_constants_extent__ $extent:ty; ($offset:tt, (Some($size:expr))) $fields:tt
+     $( $rest:tt )*) => {
+        pipeline!(__push_constants_extent__
+                     ::vulkanology::push_constants::Union<
+                         $extent,
+                         ::vulkanology::push_constants::Words<
+                             [u32; ($offset + $size + 3) / 4]
+                         >
+                     >;
+                     $( $rest )*)
+    };
+    (__push_constant_range_extent__ $extent:ty;) => { $extent };
+    (__push_constant_range_extent__ $extent:ty; $field_type:ty $(, $rest:ty )*) => {
+        pipeline!(__push_constant_range_extent__
+                     ::vulkanology::push_constants::Then<$extent, $field_type>;
+                     $( $rest ),*)
+    };
+    (__stages__ default) => {
+        ShaderStages::all()
+    };
+    (__stages__ [ $( $stage:ident ),* ]) => ({
+        let mut stages = ShaderStages::none();
+        $( stages.$stage = true; )*
+        stages
+    });
+
+    // Normalize the buffer declarations one at a time. Every buffer is turned into
+    // `$buf_ident: $buf_kind $buf_content = ($buf_alloc)
+    //  @ ($buf_set, $buf_binding, $buf_stages, $buf_memory, $buf_range),`
+    // where `$buf_kind` is the descriptor marker, `$buf_content` is the buffer content type,
+    // `$buf_alloc` are the trailing arguments of `__allocate__`, `$buf_memory` is `host` or
+    // `device_local` and `$buf_range` is the bound `($offset, $len)` in elements, `(0, all)` for
+    // the whole buffer. `$index` is the position of the next buffer, which is used as its binding
+    // unless the binding is given explicitly.
+    (__buffers__ $context:tt $done:tt ($index:expr) $buf_ident:ident @ $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: 0, binding: ($index), stages: default, offset: 0, len: all } $( $rest )*
+        }
+    };
+    (__buffers__ $context:tt $done:tt ($index:expr) $buf_ident:ident : $( $rest:tt )*) => {
+        pipeline! {
+            __buffer__ $context $done ($index + 1) $buf_ident (0, $index, default, host, (0, all))
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     set $new_set:tt $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: $new_set, binding: $binding, stages: $stages, offset: $offset, len: $len }
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     binding $new_binding:tt $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: $set, binding: $new_binding, stages: $stages, offset: $offset, len: $len }
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     stages [ $( $stage:ident ),* ] $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: $set, binding: $binding, stages: [ $( $stage ),* ], offset: $offset, len: $len }
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     offset $new_offset:tt $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: $set, binding: $binding, stages: $stages, offset: $new_offset, len: $len }
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     len $new_len:tt $( $rest:tt )*) => {
+        pipeline! {
+            __location__ $context $done ($index) $buf_ident
+            { set: $set, binding: $binding, stages: $stages, offset: $offset, len: $new_len }
+            $( $rest )*
+        }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident $location:tt
+     , $( $rest:tt )*) => {
+        pipeline! { __location__ $context $done ($index) $buf_ident $location $( $rest )* }
+    };
+    (__location__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     { set: $set:tt, binding: $binding:tt, stages: $stages:tt, offset: $offset:tt, len: $len:tt }
+     : $( $rest:tt )*) => {
+        pipeline! {
+            __buffer__ $context $done ($index + 1) $buf_ident
+            ($set, $binding, $stages, host, ($offset, $len)) $( $rest )*
+        }
+    };
+    (__buffers__ $context:tt [ $( $done:tt )* ] ($index:expr) , $( $rest:tt )*) => {
+        pipeline! { __buffers__ $context [ $( $done )* ] ($index) $( $rest )* }
+    };
+    (__buffers__ $context:tt [ $( $done:tt )* ] ($index:expr)) => {
+        pipeline! { __inner__ $context buffers: { $( $done )* } }
+    };
+    (__buffer__ $context:tt $done:tt ($index:expr) $buf_ident:ident
+     ($set:tt, $binding:tt, $stages:tt, $memory:ident, $range:tt) device_local $( $rest:tt )*) => {
+        pipeline! {
+            __buffer__ $context $done ($index) $buf_ident
+            ($set, $binding, $stages, device_local, $range) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     optional [$buf_type:ty] from $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, optional from $buf_data)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     optional [$buf_type:ty] from $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, optional from $buf_data)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     optional [$buf_type:ty] $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: StorageBuffer [$buf_type] = ($buf_type, 1) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty] from_file $buf_path:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, from_file $buf_path)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty] from_file $buf_path:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, from_file $buf_path)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty] from $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, from $buf_data) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty] from $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, from $buf_data) @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty; const $buf_len:expr] $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type; $buf_len] = ($buf_type, const $buf_len)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty; $buf_len:expr] = | $init_index:ident | $init:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, $buf_len, |$init_index| $init)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty; $buf_len:expr] = | $init_index:ident | $init:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, $buf_len, |$init_index| $init)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty; $buf_len:expr] = $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, $buf_len, from $buf_data)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty; $buf_len:expr] = $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, $buf_len, from $buf_data)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     [$buf_type:ty;$buf_len:expr] $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageBuffer [$buf_type] = ($buf_type, $buf_len) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt $done:tt ($index:expr) $buf_ident:ident $location:tt
+     uniform_texel $( $rest:tt )*) => {
+        pipeline! {
+            __texel__ $context $done ($index) $buf_ident $location
+            UniformTexelBuffer uniform_texel_buffer $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt $done:tt ($index:expr) $buf_ident:ident $location:tt
+     storage_texel $( $rest:tt )*) => {
+        pipeline! {
+            __texel__ $context $done ($index) $buf_ident $location
+            StorageTexelBuffer storage_texel_buffer $( $rest )*
+        }
+    };
+    (__texel__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $marker:ident $usage:ident [$format:ident] from $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: $marker
+              <::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel =
+              (<::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel,
+               texel $usage $format, from $buf_data) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__texel__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $marker:ident $usage:ident [$format:ident] from $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: $marker
+              <::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel =
+              (<::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel,
+               texel $usage $format, from $buf_data) @ $location, ]
+            ($index)
+        }
+    };
+    (__texel__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $marker:ident $usage:ident [$format:ident; $buf_len:expr] $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: $marker
+              <::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel =
+              (<::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel,
+               texel $usage $format, $buf_len) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     image [$format:ident; $width:expr, $height:expr] $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )*
+              $buf_ident: StorageImage
+              <::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel =
+              (<::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel,
+               image $format, $width, $height) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     image sampled [$format:ident; $width:expr, $height:expr] $( $rest:tt )*) => {
+        pipeline! {
+            __buffer__ $context [ $( $done )* ] ($index) $buf_ident $location
+            image sampled (Nearest, ClampToEdge) [$format; $width, $height] $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     image sampled ($filter:ident, $address_mode:ident) [$format:ident; $width:expr, $height:expr]
+     $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: CombinedImageSampler
+              <::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel =
+              (<::vulkano::format::$format as ::vulkano::format::StrongStorage>::Pixel,
+               sampled ($filter, $address_mode) $format, $width, $height) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     uniform $buf_type:ty = $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: UniformBuffer $buf_type = ($buf_type, single from $buf_data)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     uniform $buf_type:ty = $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: UniformBuffer $buf_type = ($buf_type, single from $buf_data)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     uniform $buf_type:ty, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: UniformBuffer $buf_type = ($buf_type, single) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     uniform $buf_type:ty) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: UniformBuffer $buf_type = ($buf_type, single) @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $buf_type:ty = $buf_data:expr, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: StorageBlock $buf_type = ($buf_type, single from $buf_data)
+              @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $buf_type:ty = $buf_data:expr) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: StorageBlock $buf_type = ($buf_type, single from $buf_data)
+              @ $location, ]
+            ($index)
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $buf_type:ty, $( $rest:tt )*) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: StorageBlock $buf_type = ($buf_type, single) @ $location, ]
+            ($index) $( $rest )*
+        }
+    };
+    (__buffer__ $context:tt [ $( $done:tt )* ] ($index:expr) $buf_ident:ident $location:tt
+     $buf_type:ty) => {
+        pipeline! {
+            __buffers__ $context
+            [ $( $done )* $buf_ident: StorageBlock $buf_type = ($buf_type, single) @ $location, ]
+            ($index)
+        }
+    };
+    (__allocate__ $device:ident, $queue:ident, $buf_type:ty,
+     image $format:ident, $width:expr, $height:expr) => ({
+        use vulkano::format;
+        use vulkano::image::{Dimensions, StorageImage};
+        use vulkanology::checked;
+        let width = checked::to_u32(stringify!($width), $width);
+        let height = checked::to_u32(stringify!($height), $height);
+        let image = StorageImage::new($device,
+                                      Dimensions::Dim2d { width: width, height: height },
+                                      format::$format,
+                                      Some($queue.family()))
+            .expect("Failed to create a storage image.");
+        StagedImage {
+            image: image,
+            staging: cpu_array_buffer!($device,
+                                       $queue,
+                                       $buf_type,
+                                       width as usize * height as usize),
+            extent: [width, height, 1],
+            sampler: (),
+        }
+    });
+    (__allocate__ $device:ident, $queue:ident, $buf_type:ty,
+     sampled ($filter:ident, $address_mode:ident) $format:ident, $width:expr, $height:expr) => ({
+        use vulkano::format;
+        use vulkano::image::{Dimensions, ImmutableImage};
+        use vulkano::sampler::{Filter, MipmapMode, Sampler, SamplerAddressMode};
+        use vulkanology::checked;
+        let width = checked::to_u32(stringify!($width), $width);
+        let height = checked::to_u32(stringify!($height), $height);
+        let image = ImmutableImage::new($device,
+                                        Dimensions::Dim2d { width: width, height: height },
+                                        format::$format,
+                                        Some($queue.family()))
+            .expect("Failed to create a sampled image.");
+        // The image has a single mipmap level, so the level of detail is clamped to 0.
+        let sampler = Sampler::new($device,
+                                   Filter::$filter,
+                                   Filter::$filter,
+                                   MipmapMode::Nearest,
+                                   SamplerAddressMode::$address_mode,
+                                   SamplerAddressMode::$address_mode,
+                                   SamplerAddressMode::$address_mode,
+                                   0.0,
+                                   1.0,
+                                   0.0,
+                                   0.0)
+            .expect("Failed to create a sampler.");
+        StagedImage {
+            image: image,
+            staging: cpu_array_buffer!($device,
+                                       $queue,
+                                       $buf_type,
+                                       width as usize * height as usize),
+            extent: [width, height, 1],
+            sampler: sampler,
+        }
+    });
+    (__allocate__ $device:ident, $queue:ident, $buf_type:ty,
+     texel $usage:ident $format:ident, $( $buf_alloc:tt )*) => ({
+        use vulkano::buffer::{BufferUsage, BufferView};
+        use vulkano::format;
+        // The format only needs to support the kind of texel buffer which the buffer is used as.
+        let usage = BufferUsage {
+            $usage: true,
+            transfer_source: true,
+            transfer_dest: true,
+            ..BufferUsage::none()
+        };
+        let buffer = cpu_array_buffer!($device, $queue, usage: usage, $buf_type, $( $buf_alloc )*);
+        let view = BufferView::new(&buffer, format::$format)
+            .expect("Failed to create a buffer view.");
+        TexelBuffer {
+            buffer: buffer,
+            view: view,
+        }
+    });
+    (__allocate__ $device:ident, $queue:ident, $buf_type:ty, $( $buf_alloc:tt )*) => {
+        cpu_array_buffer!($device, $queue, $buf_type, $( $buf_alloc )*)
+    };
+    (__memory__ host $device:ident, $queue:ident, $buffer:expr) => { $buffer };
+    (__memory__ device_local $device:ident, $queue:ident, $buffer:expr) => ({
+        use vulkano::buffer::{Buffer, BufferUsage, DeviceLocalBuffer};
+        let staging = $buffer;
+        let buffer = unsafe {
+            DeviceLocalBuffer::raw($device,
+                                   staging.size(),
+                                   &BufferUsage::all(),
+                                   Some($queue.family()))
+                .expect("Failed to create a device local buffer.")
+        };
+        StagedBuffer {
+            buffer: buffer,
+            staging: staging,
+        }
+    });
+    (__check_layout__ StorageBuffer $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {
+        reflection::check_element_type(shader::BUFFER_LAYOUTS,
+                                       stringify!($buf_ident),
+                                       $buf_set,
+                                       $buf_binding,
+                                       stringify!($buf_type),
+                                       mem::size_of::<$buf_type>(),
+                                       mem::align_of::<$buf_type>());
+    };
+    (__check_layout__ StorageBlock $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {
+        pipeline!{ __check_layout__ UniformBuffer $buf_ident ($buf_set, $buf_binding) $buf_type }
+    };
+    (__check_layout__ UniformBuffer $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {
+        reflection::check_block_type(shader::BUFFER_LAYOUTS,
+                                     stringify!($buf_ident),
+                                     $buf_set,
+                                     $buf_binding,
+                                     stringify!($buf_type),
+                                     mem::size_of::<$buf_type>());
+    };
+
+    (__check_layout__ StorageImage $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {};
+    (__check_layout__ CombinedImageSampler $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {};
+    (__check_layout__ UniformTexelBuffer $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {};
+    (__check_layout__ StorageTexelBuffer $buf_ident:ident ($buf_set:expr, $buf_binding:expr)
+     $buf_type:ty) => {};
+
+    // The descriptor marker of a buffer, and the descriptor write of its resource. Images are
+    // bound as the image, not as its staging buffer, and sampled images together with their
+    // sampler. Texel buffers are bound as their buffer view. Storage buffers are bound as the
+    // buffer on the device, either as a whole or as the range given by `offset` and `len`.
+    (__marker__ StorageImage $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::StorageImage
+    };
+    (__marker__ CombinedImageSampler $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::CombinedImageSampler
+    };
+    (__marker__ UniformTexelBuffer $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::UniformTexelBuffer
+    };
+    (__marker__ StorageTexelBuffer $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::StorageTexelBuffer
+    };
+    (__marker__ StorageBlock $buf_content:ty) => {
+        pipeline!(__marker__ StorageBuffer $buf_content)
+    };
+    (__marker__ StorageBuffer $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::StorageBuffer<$buf_content>
+    };
+    (__marker__ UniformBuffer $buf_content:ty) => {
+        ::vulkano::descriptor::pipeline_layout::custom_pipeline_macro::UniformBuffer<$buf_content>
+    };
+    (__write__ StorageImage $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        ValidParameter::<pipeline!(__marker__ StorageImage $buf_content)>::write(
+            &&$buf_ident.image, $buf_binding)
+    };
+    (__write__ CombinedImageSampler $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        ValidParameter::<pipeline!(__marker__ CombinedImageSampler $buf_content)>::write(
+            &(&$buf_ident.sampler, &$buf_ident.image), $buf_binding)
+    };
+    (__write__ UniformTexelBuffer $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        ValidParameter::<pipeline!(__marker__ UniformTexelBuffer $buf_content)>::write(
+            &&$buf_ident.view, $buf_binding)
+    };
+    (__write__ StorageTexelBuffer $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        ValidParameter::<pipeline!(__marker__ StorageTexelBuffer $buf_content)>::write(
+            &&$buf_ident.view, $buf_binding)
+    };
+    (__write__ StorageBlock $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        pipeline!{
+            __write__ StorageBuffer $buf_memory $buf_content, $buf_ident, $buf_binding, (0, all),
+            $device
+        }
+    };
+    (__write__ StorageBuffer $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, (0, all), $device:ident) => ({
+        let buffer = DeviceBuffer::device_buffer(&$buf_ident);
+        ValidParameter::<pipeline!(__marker__ StorageBuffer $buf_content)>::write(
+            &buffer, $buf_binding)
+    });
+    (__write__ StorageBuffer $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, ($buf_offset:expr, $buf_len:tt), $device:ident) => ({
+        use vulkano::buffer::BufferSlice;
+        fn element_size<T, B>(_: &BufferSlice<[T], B>) -> usize {
+            mem::size_of::<T>()
+        }
+        let buffer = BufferSlice::from(DeviceBuffer::device_buffer(&$buf_ident));
+        let range = checked::buffer_range(stringify!($buf_ident),
+                                          $buf_offset,
+                                          pipeline!(__range_len__ $buf_len),
+                                          buffer.len(),
+                                          element_size(&buffer),
+                                          $device.physical_device()
+                                              .limits()
+                                              .min_storage_buffer_offset_alignment());
+        DescriptorWrite::storage_buffer($buf_binding, buffer.slice(range).unwrap())
+    });
+    (__write__ $buf_kind:ident $buf_memory:ident $buf_content:ty, $buf_ident:ident,
+     $buf_binding:expr, $buf_range:tt, $device:ident) => {
+        ValidParameter::<pipeline!(__marker__ $buf_kind $buf_content)>::write(&&$buf_ident,
+                                                                              $buf_binding)
+    };
+    (__range_len__ all) => { None };
+    (__range_len__ $buf_len:expr) => { Some($buf_len) };
+
+    // Images and device local buffers are copied from their staging buffer before the dispatch.
+    // Storage images and buffers are copied back into it after the dispatch, sampled images are
+    // only read by the shader.
+    (__upload__ CombinedImageSampler $buf_memory:ident $builder:ident $buf_ident:ident) => {
+        pipeline!{ __upload__ StorageImage $buf_memory $builder $buf_ident }
+    };
+    (__upload__ StorageBlock device_local $builder:ident $buf_ident:ident) => {
+        pipeline!{ __upload__ StorageBuffer device_local $builder $buf_ident }
+    };
+    (__upload__ StorageBuffer device_local $builder:ident $buf_ident:ident) => {
+        $builder.copy_buffer(&$buf_ident.staging, &$buf_ident.buffer)
+    };
+    (__upload__ StorageImage $buf_memory:ident $builder:ident $buf_ident:ident) => {
+        $builder.copy_buffer_to_color_image(&$buf_ident.staging,
+                                            &$buf_ident.image,
+                                            0,
+                                            0..1,
+                                            [0, 0, 0],
+                                            $buf_ident.extent)
+    };
+    (__upload__ $buf_kind:ident $buf_memory:ident $builder:ident $buf_ident:ident) => {
+        $builder
+    };
+    (__download__ StorageBlock device_local $builder:ident $buf_ident:ident) => {
+        pipeline!{ __download__ StorageBuffer device_local $builder $buf_ident }
+    };
+    (__download__ StorageBuffer device_local $builder:ident $buf_ident:ident) => {
+        $builder.copy_buffer(&$buf_ident.buffer, &$buf_ident.staging)
+    };
+    (__download__ StorageImage $buf_memory:ident $builder:ident $buf_ident:ident) => {
+        $builder.copy_color_image_to_buffer(&$buf_ident.staging,
+                                            &$buf_ident.image,
+                                            0,
+                                            0..1,
+                                            [0, 0, 0],
+                                            $buf_ident.extent)
+    };
+    (__download__ $buf_kind:ident $buf_memory:ident $builder:ident $buf_ident:ident) => {
+        $builder
+    };
+
+    // The argument of the execution command is the number of dispatches for ping-pong buffers,
+    // and the workgroup count otherwise.
+    (__exec_arg__ {}) => { [u32; 3] };
+    (__exec_arg__ $ping_pong:tt) => { u32 };
+    (__exec_workgroup_count__ $workgroup_count:ident $exec_count:ident {}) => { $exec_count };
+    (__exec_workgroup_count__ $workgroup_count:ident $exec_count:ident $ping_pong:tt) => {
+        $workgroup_count
+    };
+    (__exec_command__ $build_command:ident $workgroup_count:ident $exec_count:ident {}) => {
+        $build_command($exec_count, 1)
+    };
+    (__exec_command__ $build_command:ident $workgroup_count:ident $exec_count:ident
+     $ping_pong:tt) => {
+        $build_command($workgroup_count, $exec_count)
+    };
+    (__ping_pong_names__ {}) => { None };
+    (__ping_pong_names__ { $front:ident, $back:ident $(,)* }) => {
+        Some((stringify!($front), stringify!($back)))
+    };
+    // After an odd number of dispatches the newest state is in the second buffer, from which it
+    // is copied into the first one.
+    (__ping_pong_copy__ $builder:ident $dispatches:ident {}) => { $builder };
+    (__ping_pong_copy__ $builder:ident $dispatches:ident { $front:ident, $back:ident $(,)* }) => {
+        if $dispatches % 2 == 1 {
+            $builder.copy_buffer(DeviceBuffer::device_buffer(&$back),
+                                 DeviceBuffer::device_buffer(&$front))
+        } else {
+            $builder
+        }
+    };
+
+    // Create the instance, with the validation layer and a debug callback for `strict`.
+    (__instance__ none) => { instance!() };
+    (__instance__ strict) => ({
+        use vulkanology::validation::VALIDATION_LAYER;
+        instance!(extensions: [ext_debug_report], layers: [VALIDATION_LAYER])
+    });
+    (__validation_log__ none $instance:ident, $shader_path:expr) => { () };
+    (__validation_log__ strict $instance:ident, $shader_path:expr) => ({
+        use vulkano::instance::debug::DebugCallback;
+        use vulkanology::validation::ValidationLog;
+        let log = ValidationLog::new($shader_path);
+        let sink = log.sink();
+        let callback = DebugCallback::errors_and_warnings(&$instance, move |message| {
+            sink.record(message.layer_prefix, message.description);
+        });
+        // The log is dropped before the callback, so it is checked while the callback exists.
+        (log, callback.expect("Failed to install the debug callback."))
+    });
+
+    // Name the objects of the pipeline in the messages of the validation layers.
+    (__name_objects__ none $log:ident, $objects:tt, $pass_paths:tt, $buffers:tt) => {};
+    (__name_objects__ strict $log:ident,
+     ($shader_path:expr, $instance:ident, $device:ident, $pipeline:ident, $passes:ident),
+     [ $( $pass_shader_path:expr ),* ],
+     [ $( $buf_ident:ident ),* ]) => {{
+        use vulkano::VulkanObject;
+        let log = &mut $log.0;
+        log.name($instance.internal_object() as u64, "instance");
+        log.name($device.internal_object() as u64, "device");
+        log.name($pipeline.internal_object(), $shader_path);
+        let pass_paths: Vec<&str> = vec![ $( $pass_shader_path ),* ];
+        for (&(ref pass_pipeline, _), pass_path) in $passes.iter().zip(pass_paths) {
+            log.name(pass_pipeline.internal_object(), pass_path);
+        }
+        $(
+            for (handle, name) in ObjectHandles::object_handles(&$buf_ident,
+                                                                stringify!($buf_ident)) {
+                log.name(handle, &name);
+            }
+        )*
+    }};
+
+    (__shader_module__ $shader_path:expr) => {
+        pub use vulkanology::reflection::defaults::*;
+        include!{concat!(env!("OUT_DIR"), concat!("/shaders/", $shader_path))}
+
+        /// Returns the entry point `main` with the specialization constants `S`. The entry
+        /// point generated by vulkano-shaders takes no specialization constants.
+        pub fn specialized_entry_point<S>(shader: &Shader)
+            -> ::vulkano::pipeline::shader::ComputeShaderEntryPoint<S, Layout> {
+            unsafe {
+                static NAME: [u8; 5] = [109, 97, 105, 110, 0]; // "main"
+                shader.module().compute_shader_entry_point(
+                    ::std::ffi::CStr::from_ptr(NAME.as_ptr() as *const _),
+                    Layout(ShaderStages { compute: true, ..ShaderStages::none() }))
+            }
+        }
+    };
+
+    {
+        __inner__ {
+            device: $device:expr,
+            validation: $validation:ident,
+            shader_path: $shader_path:expr,
+            workgroup_count: $workgroup_count:expr,
+            passes: {
+                $(
+                    $pass_name:ident: {
+                        shader_path: $pass_shader_path:expr,
+                        workgroup_count: $pass_workgroup_count:expr $(,)*
+                    }
+                ),*
+                $(,)*
+            },
+            ping_pong: $ping_pong:tt,
+            push_constants: [
+                $(
+                    ($push_constant_offset:tt, $push_constant_size:tt, $push_constant_stages:tt) {
+                        $(
+                            $push_constant_name:ident : $push_constant_type:ty =
+                            $push_constant_value:expr
+                        ),*
+                    }
+                )*
+            ],
+            spec_constants: [
+                $( ($spec_id:expr, $spec_name:ident, $spec_type:ty, $spec_value:expr) )*
+            ],
+            execution_command: (
+                $exec_cmd:ident $( ( $exec_count:ident ) )*
+                [ $( $repeat_cmd:ident )* ] [ $( $async_cmd:ident )* ]
+            )
+        }
+        buffers: {
+            $(
+                $buf_ident:ident : $buf_kind:ident $buf_content:ty =
+                ( $buf_type:ty, $( $buf_alloc:tt )* )
+                @ ( $buf_set:expr, $buf_binding:expr, $buf_stages:tt, $buf_memory:ident,
+                    $buf_range:tt ),
+            )*
+        }
+    } => {
+        use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+        use vulkano::command_buffer::submit as submit_command;
+        use vulkano::descriptor::descriptor::{DescriptorDesc, ShaderStages};
+        use vulkano::descriptor::descriptor_set::{DescriptorPool, DescriptorWrite};
+        use vulkano::descriptor::pipeline_layout::custom_pipeline_macro::DescriptorMarker;
+        // Pipelines which bind all of their buffers as ranges write no descriptor through it.
+        #[allow(unused_imports)]
+        use vulkano::descriptor::pipeline_layout::custom_pipeline_macro::ValidParameter;
+        use vulkano::pipeline::ComputePipeline;
+        use vulkano::pipeline::shader::{SpecializationConstants, SpecializationMapEntry};
+        use std::mem;
+        use vulkano::command_buffer::Submission;
+        use vulkano::sync::FenceWaitError;
+        use vulkanology::{checked, context, hang, harness, overrides, ping_pong, quirks,
+                          reflection, renderdoc, shared};
+
+        // Include the shader wrapper. The reflected buffer layouts, if they were appended to the
+        // shader module by the build script, shadow the empty defaults.
+        mod shader {
+            #![allow(dead_code, unused_imports)]
+            pipeline!{ __shader_module__ $shader_path }
+        }
+        $(
+            mod $pass_name {
+                #![allow(dead_code, unused_imports)]
+                pipeline!{ __shader_module__ $pass_shader_path }
+            }
+        )*
+
+        // Create the specialization constants. The map entries are computed from the field
+        // offsets once, as vulkano expects them to be static.
+        #[repr(C)]
+        #[allow(non_snake_case)]
+        struct SpecConstants {
+            $( $spec_name: $spec_type, )*
+        }
+
+        unsafe impl SpecializationConstants for SpecConstants {
+            fn descriptors() -> &'static [SpecializationMapEntry] {
+                use std::sync::Once;
+                static INIT: Once = Once::new();
+                static mut DESCRIPTORS: *const Vec<SpecializationMapEntry> = 0 as *const _;
+                unsafe {
+                    INIT.call_once(|| {
+                        #[allow(unused_variables)]
+                        let constants: SpecConstants = mem::zeroed();
+                        #[allow(unused_variables)]
+                        let base = &constants as *const SpecConstants as usize;
+                        let descriptors: Vec<SpecializationMapEntry> = vec![
+                            $(
+                                SpecializationMapEntry {
+                                    constant_id: $spec_id,
+                                    offset: (&constants.$spec_name as *const $spec_type as usize -
+                                             base) as u32,
+                                    size: mem::size_of::<$spec_type>(),
+                                },
+                            )*
+                        ];
+                        DESCRIPTORS = Box::into_raw(Box::new(descriptors));
+                    });
+                    &*DESCRIPTORS
+                }
+            }
+        }
+
+        pipeline!{
+            __push_constants_type__
+            $( ($push_constant_offset, $push_constant_size) { $( $push_constant_type ),* } )*
+        }
+
+        // Create the pipeline layout wrapper. Unlike the layouts generated by vulkano's
+        // `pipeline_layout!`, the set and binding of every descriptor are chosen freely.
+        mod layout_definition {
+            #![allow(dead_code)]
+
+            use std::sync::Arc;
+            use std::vec::IntoIter as VecIntoIter;
+            use vulkano::device::Device;
+            use vulkano::descriptor::descriptor::{DescriptorDesc, ShaderStages};
+            use vulkano::descriptor::descriptor_set::{DescriptorPool, DescriptorSet,
+                                                      DescriptorSetDesc, DescriptorSetsCollection,
+                                                      DescriptorWrite, UnsafeDescriptorSet,
+                                                      UnsafeDescriptorSetLayout};
+            use vulkano::descriptor::pipeline_layout::{PipelineLayout, PipelineLayoutDesc,
+                                                       UnsafePipelineLayout,
+                                                       UnsafePipelineLayoutCreationError};
+            use vulkanology::checked;
+
+            pub struct CustomPipeline {
+                inner: UnsafePipelineLayout,
+                num_sets: usize,
+            }
+
+            impl CustomPipeline {
+                /// Creates a layout from the `(name, set, descriptor)` triples of all
+                /// descriptors, and the size of the push constants and the stages which access
+                /// them. Sets which contain no descriptor are left empty.
+                ///
+                /// Panics if two descriptors share a set and a binding, or if the push constants
+                /// do not fit into the `maxPushConstantsSize` of the device.
+                pub fn new(device: &Arc<Device>,
+                           descriptors: &[(&str, u32, DescriptorDesc)],
+                           push_constants_size: usize,
+                           push_constant_stages: ShaderStages)
+                           -> Result<Arc<CustomPipeline>, UnsafePipelineLayoutCreationError> {
+                    for (index, &(name, set, desc)) in descriptors.iter().enumerate() {
+                        let duplicate = descriptors[..index]
+                            .iter()
+                            .find(|&&(_, other_set, other_desc)| {
+                                other_set == set && other_desc.binding == desc.binding
+                            });
+                        if let Some(&(other_name, _, _)) = duplicate {
+                            panic!("The buffers `{}` and `{}` are both bound to set {}, \
+                                    binding {}.",
+                                   other_name,
+                                   name,
+                                   set,
+                                   desc.binding);
+                        }
+                    }
+
+                    let num_sets = descriptors.iter()
+                        .map(|&(_, set, _)| set as usize + 1)
+                        .max()
+                        .unwrap_or(0);
+                    let mut layouts = Vec::with_capacity(num_sets);
+                    for set_num in 0..num_sets {
+                        let set_descriptors = descriptors.iter()
+                            .filter(|&&(_, set, _)| set as usize == set_num)
+                            .map(|&(_, _, desc)| desc);
+                        layouts.push(Arc::new(
+                            try!(UnsafeDescriptorSetLayout::raw(device.clone(), set_descriptors))));
+                    }
+
+                    checked::push_constants_size(push_constants_size,
+                                                 device.physical_device()
+                                                     .limits()
+                                                     .max_push_constants_size());
+                    let push_constants = if push_constants_size >= 1 {
+                        Some((0, push_constants_size, push_constant_stages))
+                    } else {
+                        None
+                    };
+
+                    let inner = try!(UnsafePipelineLayout::new(device,
+                                                               layouts.iter(),
+                                                               push_constants));
+
+                    Ok(Arc::new(CustomPipeline {
+                        inner: inner,
+                        num_sets: num_sets,
+                    }))
+                }
+            }
+
+            /// Returns the stages which are contained in any of `stages`.
+            pub fn union_stages(stages: &[ShaderStages]) -> ShaderStages {
+                stages.iter().fold(ShaderStages::none(), |union, other| {
+                    ShaderStages {
+                        vertex: union.vertex || other.vertex,
+                        tessellation_control: union.tessellation_control ||
+                                              other.tessellation_control,
+                        tessellation_evaluation: union.tessellation_evaluation ||
+                                                 other.tessellation_evaluation,
+                        geometry: union.geometry || other.geometry,
+                        fragment: union.fragment || other.fragment,
+                        compute: union.compute || other.compute,
+                    }
+                })
+            }
+
+            unsafe impl PipelineLayout for CustomPipeline {
+                #[inline]
+                fn inner(&self) -> &UnsafePipelineLayout {
+                    &self.inner
+                }
+            }
+
+            unsafe impl PipelineLayoutDesc for CustomPipeline {
+                type SetsIter = VecIntoIter<Self::DescIter>;
+                type DescIter = VecIntoIter<DescriptorDesc>;
+
+                fn descriptors_desc(&self) -> Self::SetsIter {
+                    // Like vulkano's `pipeline_layout!`, the layout does not describe itself.
+                    vec![].into_iter()
+                }
+            }
+
+            pub struct Set {
+                inner: UnsafeDescriptorSet,
+            }
+
+            unsafe impl DescriptorSet for Set {
+                #[inline]
+                fn inner(&self) -> &UnsafeDescriptorSet {
+                    &self.inner
+                }
+            }
+
+            unsafe impl DescriptorSetDesc for Set {
+                type Iter = VecIntoIter<DescriptorDesc>;
+
+                #[inline]
+                fn desc(&self) -> Self::Iter {
+                    vec![].into_iter()
+                }
+            }
+
+            /// One descriptor set for every set of the layout, starting with set 0.
+            #[derive(Clone)]
+            pub struct Sets(Vec<Arc<Set>>);
+
+            impl Sets {
+                /// Allocates the sets and writes the `(set, write)` pairs into them.
+                pub fn new(pool: &Arc<DescriptorPool>,
+                           layout: &Arc<CustomPipeline>,
+                           writes: Vec<(u32, DescriptorWrite)>)
+                           -> Sets {
+                    let mut set_writes = (0..layout.num_sets)
+                        .map(|_| Vec::new())
+                        .collect::<Vec<_>>();
+                    for (set, write) in writes {
+                        set_writes[set as usize].push(write);
+                    }
+
+                    let sets = set_writes.into_iter()
+                        .enumerate()
+                        .map(|(set_num, writes)| unsafe {
+                            let set_layout = layout.inner.descriptor_set_layout(set_num).unwrap();
+                            let mut set = UnsafeDescriptorSet::uninitialized_raw(pool, set_layout)
+                                .expect("Failed to allocate a descriptor set.");
+                            set.write(writes);
+                            Arc::new(Set { inner: set })
+                        })
+                        .collect();
+                    Sets(sets)
+                }
+            }
+
+            unsafe impl DescriptorSetsCollection for Sets {
+                type ListIter = VecIntoIter<Arc<DescriptorSet>>;
+                type SetsIter = VecIntoIter<Self::DescIter>;
+                type DescIter = VecIntoIter<DescriptorDesc>;
+
+                fn list(&self) -> Self::ListIter {
+                    self.0
+                        .iter()
+                        .map(|set| set.clone() as Arc<DescriptorSet>)
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                }
+
+                fn description(&self) -> Self::SetsIter {
+                    self.0.iter().map(|set| set.desc()).collect::<Vec<_>>().into_iter()
+                }
+            }
+        }
+
+        // RenderDoc must be loaded before the instance is created to capture its commands.
+        let renderdoc = renderdoc::Capture::from_env($shader_path);
+
+        // Init `vulkano`, or reuse the objects of the shared context. The validation log panics
+        // with the messages of the validation layers when it goes out of scope at the end of the
+        // test.
+        let shared_context = shared::take_request();
+        let instance = if shared_context {
+            shared::get_or_init(concat!("instance ", stringify!($validation)),
+                                || pipeline!{ __instance__ $validation })
+        } else {
+            pipeline!{ __instance__ $validation }
+        };
+        #[allow(unused_mut, unused_variables)]
+        let mut validation_log =
+            pipeline!{ __validation_log__ $validation instance, $shader_path };
+        let physical_device = physical_device!(instance, device: $device);
+        harness::record_device(&physical_device.name());
+        context::set(context::DeviceContext {
+            name: physical_device.name(),
+            vendor_id: physical_device.pci_vendor_id(),
+            device_id: physical_device.pci_device_id(),
+            driver_version: physical_device.driver_version(),
+            api_version: {
+                let version = physical_device.api_version();
+                [version.major, version.minor, version.patch]
+            },
+        });
+        quirks::set_device(physical_device.pci_vendor_id(), physical_device.driver_version());
+        {
+            // Results of CPU implementations of Vulkan only confirm the correctness of a shader.
+            use std::io::{self, Write};
+            use vulkano::instance::PhysicalDeviceType;
+            if physical_device.ty() == PhysicalDeviceType::Cpu {
+                let _ = writeln!(io::stderr(),
+                                 "note: `{}` runs on the emulated device `{}`.",
+                                 $shader_path,
+                                 physical_device.name());
+            }
+        }
+        {
+            // The capabilities of the shaders can require features which were not given
+            // explicitly. The names are those of `reflection::required_features`.
+            let supported = physical_device.supported_features();
+            let required = reflection::required_features(shader::CAPABILITIES).into_iter()
+                $( .chain(reflection::required_features($pass_name::CAPABILITIES)) )*;
+            let missing = required
+                .filter(|&feature| {
+                    !match feature {
+                        "shader_f3264" => supported.shader_f3264,
+                        "shader_int64" => supported.shader_int64,
+                        "shader_int16" => supported.shader_int16,
+                        _ => true,
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                panic!("The device `{}` does not support the features {:?}, which the shaders \
+                        require. Give them as `features` to skip the test instead.",
+                       physical_device.name(),
+                       missing);
+            }
+        }
+        let (ref device, ref queue) = if shared_context {
+            let key = format!("device {} {}", stringify!($validation), physical_device.index());
+            shared::get_or_init(&key, || device_and_queue!(physical_device))
+        } else {
+            device_and_queue!(physical_device)
+        };
+
+        // Allocate buffers. Images are allocated together with a staging buffer, which gives
+        // access to their pixels like to the content of a buffer, and sampled images with their
+        // sampler.
+        #[allow(dead_code)]
+        struct StagedImage<I, P: 'static, S = ()> {
+            image: ::std::sync::Arc<I>,
+            staging: ::std::sync::Arc<::vulkano::buffer::CpuAccessibleBuffer<[P]>>,
+            extent: [u32; 3],
+            sampler: S,
+        }
+
+        impl<I, P, S> ::std::ops::Deref for StagedImage<I, P, S> {
+            type Target = ::vulkano::buffer::CpuAccessibleBuffer<[P]>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.staging
+            }
+        }
+
+        // Texel buffers are allocated together with their buffer view.
+        #[allow(dead_code)]
+        struct TexelBuffer<F, P: Send + Sync + 'static> {
+            buffer: ::std::sync::Arc<::vulkano::buffer::CpuAccessibleBuffer<[P]>>,
+            view: ::std::sync::Arc<::vulkano::buffer::BufferView<F,
+                                   ::vulkano::buffer::CpuAccessibleBuffer<[P]>>>,
+        }
+
+        impl<F, P: Send + Sync> ::std::ops::Deref for TexelBuffer<F, P> {
+            type Target = ::vulkano::buffer::CpuAccessibleBuffer<[P]>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.buffer
+            }
+        }
+
+        // Device local buffers are allocated together with a staging buffer of the same content.
+        #[allow(dead_code)]
+        struct StagedBuffer<C: ?Sized + 'static> {
+            buffer: ::std::sync::Arc<::vulkano::buffer::DeviceLocalBuffer<C>>,
+            staging: ::std::sync::Arc<::vulkano::buffer::CpuAccessibleBuffer<C>>,
+        }
+
+        impl<C: ?Sized> ::std::ops::Deref for StagedBuffer<C> {
+            type Target = ::vulkano::buffer::CpuAccessibleBuffer<C>;
+
+            fn deref(&self) -> &Self::Target {
+                &self.staging
+            }
+        }
+
+        // The buffer which the shader accesses, for copies between buffers on the device.
+        #[allow(dead_code)]
+        trait DeviceBuffer {
+            type Buffer;
+            fn device_buffer(&self) -> &Self::Buffer;
+        }
+
+        impl<C> DeviceBuffer for ::std::sync::Arc<::vulkano::buffer::CpuAccessibleBuffer<C>>
+            where C: ?Sized
+        {
+            type Buffer = Self;
+            fn device_buffer(&self) -> &Self {
+                self
+            }
+        }
+
+        impl<C: ?Sized> DeviceBuffer for StagedBuffer<C> {
+            type Buffer = ::std::sync::Arc<::vulkano::buffer::DeviceLocalBuffer<C>>;
+            fn device_buffer(&self) -> &Self::Buffer {
+                &self.buffer
+            }
+        }
+
+        // The handles of the objects of a buffer, which are named after it in the messages of the
+        // validation layers.
+        #[allow(dead_code)]
+        trait ObjectHandles {
+            fn object_handles(&self, name: &str) -> Vec<(u64, String)>;
+        }
+
+        impl<C> ObjectHandles for ::std::sync::Arc<::vulkano::buffer::CpuAccessibleBuffer<C>>
+            where C: ?Sized + Send + Sync + 'static
+        {
+            fn object_handles(&self, name: &str) -> Vec<(u64, String)> {
+                use vulkano::VulkanObject;
+                use vulkano::buffer::Buffer;
+                vec![(Buffer::inner(&**self).internal_object(), name.to_owned())]
+            }
+        }
+
+        impl<C: ?Sized + Send + Sync> ObjectHandles for StagedBuffer<C> {
+            fn object_handles(&self, name: &str) -> Vec<(u64, String)> {
+                use vulkano::VulkanObject;
+                use vulkano::buffer::Buffer;
+                let staging = Buffer::inner(&*self.staging).internal_object();
+                vec![(Buffer::inner(&*self.buffer).internal_object(), name.to_owned()),
+                     (staging, format!("{} (staging)", name))]
+            }
+        }
+
+        impl<F, P: Send + Sync> ObjectHandles for TexelBuffer<F, P> {
+            fn object_handles(&self, name: &str) -> Vec<(u64, String)> {
+                use vulkano::VulkanObject;
+                use vulkano::buffer::Buffer;
+                vec![(Buffer::inner(&*self.buffer).internal_object(), name.to_owned())]
+            }
+        }
+
+        impl<I: ::vulkano::image::Image, P: Send + Sync, S> ObjectHandles for StagedImage<I, P, S> {
+            fn object_handles(&self, name: &str) -> Vec<(u64, String)> {
+                use vulkano::VulkanObject;
+                use vulkano::buffer::Buffer;
+                use vulkano::image::Image;
+                let staging = Buffer::inner(&*self.staging).internal_object();
+                vec![(Image::inner(&*self.image).internal_object(), name.to_owned()),
+                     (staging, format!("{} (staging)", name))]
+            }
+        }
+
+        $(
+            let $buf_ident = pipeline!{
+                __memory__ $buf_memory device, queue,
+                pipeline!{ __allocate__ device, queue, $buf_type, $( $buf_alloc )* }
+            };
+        )*
+
+        // Check the element types against the buffer layouts of the shader.
+        $(
+            pipeline!{
+                __check_layout__ $buf_kind $buf_ident ($buf_set, $buf_binding) $buf_type
+            }
+        )*
+
+        // Create descriptor pool.
+        let descriptor_pool = DescriptorPool::new(device);
+
+        // Create pipeline layout.
+        let buffer_descriptors: Vec<(&str, u32, DescriptorDesc)> = vec![
+            $(
+                (stringify!($buf_ident), $buf_set, DescriptorDesc {
+                    binding: $buf_binding,
+                    ty: <pipeline!(__marker__ $buf_kind $buf_content) as DescriptorMarker>
+                        ::descriptor_type(),
+                    array_count: 1,
+                    stages: pipeline!{ __stages__ $buf_stages },
+                    readonly: false,
+                }),
+            )*
+        ];
+        // All push constant ranges share a single range of the layout, which is accessible
+        // from every stage of any of them.
+        let push_constant_stages = layout_definition::union_stages(&[
+            $( pipeline!{ __stages__ $push_constant_stages } ),*
+        ]);
+        let push_constants_size = mem::size_of::<PushConstants>();
+        let pipeline_layout = layout_definition::CustomPipeline::new(device,
+                                                                     &buffer_descriptors,
+                                                                     push_constants_size,
+                                                                     push_constant_stages)
+            .expect("Failed to create pipeline layout.");
+        let buffer_writes: Vec<(u32, DescriptorWrite)> = vec![
+            $(
+                ($buf_set,
+                 pipeline!{
+                     __write__ $buf_kind $buf_memory $buf_content, $buf_ident, $buf_binding,
+                     $buf_range, device
+                 }),
+            )*
+        ];
+        let buffer_set = layout_definition::Sets::new(&descriptor_pool,
+                                                      &pipeline_layout,
+                                                      buffer_writes);
+        // Ping-pong buffers swap their locations in the descriptor sets of every other dispatch.
+        let swapped_set = pipeline!(__ping_pong_names__ $ping_pong).map(|pair| {
+            let locations = [ $( (stringify!($buf_ident), $buf_set, $buf_binding) ),* ];
+            let swapped_writes: Vec<(u32, DescriptorWrite)> = vec![
+                $(
+                    {
+                        let (set, binding) =
+                            ping_pong::swapped_location(pair, &locations, stringify!($buf_ident));
+                        (set, pipeline!{
+                            __write__ $buf_kind $buf_memory $buf_content, $buf_ident, binding,
+                            $buf_range, device
+                        })
+                    },
+                )*
+            ];
+            layout_definition::Sets::new(&descriptor_pool, &pipeline_layout, swapped_writes)
+        });
+
+        // Load the shader and assemble the pipeline.
+        reflection::check_capabilities($shader_path, shader::CAPABILITIES);
+        let compute_shader = shader::Shader::load(device).expect("Failed to create shader module.");
+        checked::spec_constants(&[
+            $( (stringify!($spec_name), $spec_id, mem::size_of::<$spec_type>()), )*
+        ]);
+        let spec_constants = SpecConstants { $( $spec_name: $spec_value, )* };
+        let pipeline = ComputePipeline::new(device,
+                                            &pipeline_layout,
+                                            &shader::specialized_entry_point(&compute_shader),
+                                            &spec_constants)
+            .expect("Failed to create compute pipeline.");
+
+        // The passes share the layout, the descriptor sets and the constants of the pipeline.
+        type PassPipeline = ::std::sync::Arc<ComputePipeline<layout_definition::CustomPipeline>>;
+        let passes: Vec<(PassPipeline, [u32; 3])> = vec![
+            $(
+                {
+                    reflection::check_capabilities($pass_shader_path, $pass_name::CAPABILITIES);
+                    let pass_shader = $pass_name::Shader::load(device)
+                        .expect("Failed to create shader module.");
+                    let pass_pipeline =
+                        ComputePipeline::new(device,
+                                             &pipeline_layout,
+                                             &$pass_name::specialized_entry_point(&pass_shader),
+                                             &spec_constants)
+                            .expect("Failed to create compute pipeline.");
+                    (pass_pipeline, checked::workgroup_count($pass_workgroup_count))
+                },
+            )*
+        ];
+
+        pipeline!{
+            __name_objects__ $validation validation_log,
+            ($shader_path, instance, device, pipeline, passes),
+            [ $( $pass_shader_path ),* ],
+            [ $( $buf_ident ),* ]
+        }
+
+        // Assemble and return the execution command.
+        #[allow(unused_mut)]
+        let mut push_constants: PushConstants = Default::default();
+        $(
+            {
+                let mut range = push_constants.range($push_constant_offset, $push_constant_size);
+                $( range.push::<$push_constant_type>($push_constant_value); )*
+            }
+        )*
+        // The workgroup count and the number of iterations can be overridden from the environment.
+        let workgroup_count = checked::workgroup_count($workgroup_count);
+        let workgroup_count = overrides::workgroup_count(workgroup_count);
+        let iterations = overrides::iterations(1);
+        let default_timeout = ::std::time::Duration::from_secs(hang::DEFAULT_TIMEOUT_SECS);
+        let timeout = overrides::timeout(default_timeout);
+        // Waits for the submissions, which the queue executes in order. A shader which does not
+        // finish in time probably hangs, and its submissions are leaked, as dropping them would
+        // wait for it forever.
+        let wait = |submissions: Vec<::std::sync::Arc<Submission>>, workgroup_count: [u32; 3]| {
+            for index in 0..submissions.len() {
+                let result = submissions[index].wait(timeout);
+                match result {
+                    Ok(()) => {}
+                    Err(FenceWaitError::Timeout) => {
+                        mem::forget(submissions);
+                        hang::probable_hang($shader_path, workgroup_count, timeout);
+                    }
+                    Err(err) => {
+                        panic!("Failed to wait for the execution of `{}`: {:?}", $shader_path, err)
+                    }
+                }
+            }
+        };
+        let build_command = |workgroup_count: [u32; 3], dispatches: u32| {
+            let builder = PrimaryCommandBufferBuilder::new(device, queue.family());
+            $( let builder = pipeline!{ __upload__ $buf_kind $buf_memory builder $buf_ident }; )*
+            // The command buffer inserts the barriers between the dispatches.
+            let mut builder = builder;
+            for dispatch in 0..dispatches {
+                let sets = match swapped_set {
+                    Some(ref swapped_set) if dispatch % 2 == 1 => swapped_set.clone(),
+                    _ => buffer_set.clone(),
+                };
+                builder = builder.dispatch(&pipeline,
+                                           sets.clone(),
+                                           workgroup_count,
+                                           &push_constants);
+                for &(ref pass_pipeline, pass_workgroup_count) in &passes {
+                    builder = builder.dispatch(pass_pipeline,
+                                               sets.clone(),
+                                               pass_workgroup_count,
+                                               &push_constants);
+                }
+            }
+            let builder = pipeline!{ __ping_pong_copy__ builder dispatches $ping_pong };
+            $(
+                let builder = pipeline!{ __download__ $buf_kind $buf_memory builder $buf_ident };
+            )*
+            builder.build()
+        };
+        // If the execution command takes an argument, it builds a new command buffer for every
+        // execution, otherwise it reuses the command buffer of a single dispatch.
+        #[allow(unused_variables)]
+        let execution_command = build_command(workgroup_count, 1);
+        // Tests which only submit through the repeat or async command leave it unused.
+        #[allow(unused_variables)]
+        let $exec_cmd = |$( $exec_count: pipeline!(__exec_arg__ $ping_pong) )*| {
+            $(
+                let execution_command = pipeline!{
+                    __exec_command__ build_command workgroup_count $exec_count $ping_pong
+                };
+            )*
+            #[allow(unused_variables)]
+            let reported_workgroup_count = workgroup_count;
+            $(
+                let reported_workgroup_count = pipeline!{
+                    __exec_workgroup_count__ workgroup_count $exec_count $ping_pong
+                };
+            )*
+            let execute = || {
+                let submissions = (0..iterations)
+                    .map(|_| submit_command(&execution_command, queue).unwrap())
+                    .collect::<Vec<_>>();
+                wait(submissions, reported_workgroup_count);
+            };
+            match renderdoc {
+                Some(ref capture) => capture.record(execute),
+                None => execute(),
+            }
+        };
+        $(
+            let $repeat_cmd = |count: u32| {
+                let execute = || {
+                    let submissions = (0..count)
+                        .map(|_| submit_command(&execution_command, queue).unwrap())
+                        .collect::<Vec<_>>();
+                    wait(submissions, workgroup_count);
+                };
+                match renderdoc {
+                    Some(ref capture) => capture.record(execute),
+                    None => execute(),
+                }
+            };
+        )*
+        $(
+            let $async_cmd = || {
+                let submit = || submit_command(&execution_command, queue).unwrap();
+                let mut submission = None;
+                match renderdoc {
+                    Some(ref capture) => capture.record(|| submission = Some(submit())),
+                    None => submission = Some(submit()),
+                }
+                submission.unwrap()
+            };
+        )*
+    };
+}
+
+/// The first version of [`pipeline!`].
+///
+/// `pipeline_v1!` accepts the shader path, the workgroup count, the optional push constants, the
+/// buffers given as `[T; N]` and the name of the execution command, and nothing else. Every
+/// parameter and buffer declaration that [`pipeline!`] gained later is rejected.
+///
+/// The expansion is frozen as well: `pipeline_v1!` does not expand to [`pipeline!`], but to the
+/// code of its first version. It creates the instance and selects the first physical device
+/// exactly as that version did, and none of the checks which were added to [`pipeline!`] later
+/// apply, e.g. the checks of the buffer element types against the layouts of the shader, of the
+/// required features or of the hang timeout. The buffers are allocated with
+/// [`cpu_array_buffer!`], like in the first version, so only its checks of the buffer lengths
+/// apply. A test pinned to this macro therefore keeps the behavior it was written against.
+///
+/// As in the first version, the expansion refers to the `pipeline_layout!` macro of [`vulkano`],
+/// so the test module must import `vulkano` with `#[macro_use]`.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/push_constants.comp",
+///     workgroup_count: [100, 100, 1],
+///     push_constants: {
+///         a: f32 = 4.0,
+///         b: f32 = 10.0
+///     },
+///     buffers: {
+///         result: [f32; 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+///
+/// execute_shader();
+/// # }
+/// ```
+///
+/// Parameters which were added to [`pipeline!`] later are compile errors:
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// pipeline_v1!{
+///     context: shared,
+///     shader_path: "tests/shaders/push_constants.comp",
+///     workgroup_count: [100, 100, 1],
+///     buffers: {
+///         result: [f32; 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let input = (0..1024).collect::<Vec<u32>>();
+///
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/spec_constants.comp",
+///     workgroup_count: [32, 1, 1],
+///     spec_constants: {
+///         local_size: u32 = 32,
+///         multiplier: u32 = 3,
+///         offset: f32 = 0.5,
+///         negate: u32 = 1
+///     },
+///     buffers: {
+///         data: [u32] from input,
+///         result: [f32; 1024]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// and so are buffer declarations which were added later:
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/push_constants.comp",
+///     workgroup_count: [100, 100, 1],
+///     push_constants: {
+///         a: f32 = 4.0,
+///         b: f32 = 10.0
+///     },
+///     buffers: {
+///         result: device_local [f32; 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/push_constants.comp",
+///     workgroup_count: [100, 100, 1],
+///     push_constants: {
+///         a: f32 = 4.0,
+///         b: f32 = 10.0
+///     },
+///     buffers: {
+///         result @ binding 0: [f32; 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let input = (0..640000).collect::<Vec<u32>>();
+///
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/example.comp",
+///     workgroup_count: [100, 100, 1],
+///     buffers: {
+///         data: [u32] from input,
+///         result: [u32; 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkano;
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// pipeline_v1!{
+///     shader_path: "tests/shaders/push_constants.comp",
+///     workgroup_count: [100, 100, 1],
+///     push_constants: {
+///         a: f32 = 4.0,
+///         b: f32 = 10.0
+///     },
+///     buffers: {
+///         result: [f32; const 640000]
+///     },
+///     execution_command: execute_shader
+/// }
+/// # }
+/// ```
+///
+/// [`cpu_array_buffer!`]: macro.cpu_array_buffer.html
+/// [`pipeline!`]: macro.pipeline.html
+/// [`vulkano`]: https://github.com/tomaka/vulkano
+///
+#[macro_export]
+macro_rules! pipeline_v1 {
+    {
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        buffers: { $( $buf_ident:ident : [$buf_type:ty;$buf_len:expr] ),* },
+        execution_command: $exec_cmd:ident
+    } => {
+        pipeline_v1! {
+            shader_path: $shader_path,
+            workgroup_count: $workgroup_count,
+            push_constants: {},
+            buffers: { $( $buf_ident : [$buf_type;$buf_len] ),* },
+            execution_command: $exec_cmd
+        }
+    };
+    // The expansion of the first version of `pipeline!`, which must not change.
+    {
+        shader_path: $shader_path:expr,
+        workgroup_count: $workgroup_count:expr,
+        push_constants: {
+            $( $push_constant_name:ident : $push_constant_type:ty = $push_constant_value:expr ),*
+        },
+        buffers: { $( $buf_ident:ident : [$buf_type:ty;$buf_len:expr] ),* },
+        execution_command: $exec_cmd:ident
+    } => {
+        use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+        use vulkano::command_buffer::submit as submit_command;
+        use vulkano::descriptor::descriptor_set::DescriptorPool;
+        use vulkano::device::{Device, DeviceExtensions};
+        use vulkano::instance::{Instance, InstanceExtensions, PhysicalDevice};
+        use vulkano::pipeline::ComputePipeline;
+
+        // Include the shader wrapper.
+        mod shader {
+            #![allow(dead_code)]
+            include!{concat!(env!("OUT_DIR"), concat!("/shaders/", $shader_path))}
+        }
+
+        // Create the pipeline layout wrapper.
         mod layout_definition {
             pipeline_layout!{
                 push_constants: {
@@ -372,9 +3553,20 @@ macro_rules! pipeline {
         }
 
         // Init `vulkano`.
-        let instance = instance!();
-        let physical_device = physical_device!(instance);
-        let (ref device, ref queue) = device_and_queue!(physical_device);
+        let instance = Instance::new(None, &InstanceExtensions::none(), None)
+            .expect("Failed to initialize vulkano.");
+        let physical_device = PhysicalDevice::enumerate(&instance).next()
+            .expect("No physical devices are available.");
+        let queue_family = physical_device.queue_families().find(|q| q.supports_compute())
+            .expect("Couldn't find a compute queue family.");
+        let (ref device, ref queue) = {
+            let (device, mut queues) = Device::new(&physical_device,
+                                                   &physical_device.supported_features(),
+                                                   &DeviceExtensions::none(),
+                                                   [(queue_family, 0.5)].iter().cloned())
+                .expect("Failed to create device.");
+            (device, queues.next().unwrap())
+        };
 
         // Allocate buffers.
         $( let $buf_ident = cpu_array_buffer!(device, queue, $buf_type, $buf_len); )*
@@ -410,4 +3602,81 @@ macro_rules! pipeline {
             submit_command(&execution_command, queue).unwrap();
         };
     };
+
+    ( $( $args:tt )* ) => {
+        compile_error!("`pipeline_v1!` only accepts the parameters of the first `pipeline!` DSL");
+    };
+}
+
+/// Generates a correctness test and a benchmark from a single shader test definition, so that
+/// the two never drift apart.
+///
+/// The `pipeline` parameters are passed to [`pipeline!`], together with the
+/// `execution_command`. Both functions create the pipeline and run the `setup` block. The test
+/// then executes the shader once and runs the `verify` block, while the benchmark measures the
+/// execution command.
+///
+/// Benchmarks require the unstable `test` crate, so the test module must declare
+/// `#![feature(test)]` and `extern crate test;`.
+///
+/// # Example
+///
+/// ```ignore
+/// #![feature(test)]
+///
+/// extern crate vulkano;
+/// #[macro_use]
+/// extern crate vulkanology;
+/// extern crate test;
+///
+/// shader_test!{
+///     test: test_example,
+///     bench: bench_example,
+///     execution_command: execute_shader,
+///     pipeline: {
+///         shader_path: "tests/shaders/example.comp",
+///         workgroup_count: [100, 100, 1],
+///         buffers: {
+///             data: [u32; 640000],
+///             result: [u32; 640000]
+///         }
+///     },
+///     setup: {
+///         // Fill `data`.
+///     },
+///     verify: {
+///         // Compare `result` against a reference.
+///     }
+/// }
+/// ```
+///
+/// For a complete example see `tests/shader_test.rs`.
+///
+/// [`pipeline!`]: macro.pipeline.html
+///
+#[macro_export]
+macro_rules! shader_test {
+    {
+        test: $test_name:ident,
+        bench: $bench_name:ident,
+        execution_command: $exec_cmd:ident,
+        pipeline: { $( $pipeline_args:tt )* },
+        setup: $setup:block,
+        verify: $verify:block
+    } => {
+        #[test]
+        fn $test_name() {
+            pipeline!{ $( $pipeline_args )*, execution_command: $exec_cmd }
+            $setup
+            $exec_cmd();
+            $verify
+        }
+
+        #[bench]
+        fn $bench_name(bencher: &mut ::test::Bencher) {
+            pipeline!{ $( $pipeline_args )*, execution_command: $exec_cmd }
+            $setup
+            bencher.iter(|| $exec_cmd());
+        }
+    };
 }
@@ -0,0 +1,79 @@
+//! This module compares the limits of physical devices against the requirements given to
+//! `physical_device!`.
+//!
+//! The limits are the methods of `vulkano::instance::Limits`, e.g.
+//! `max_compute_work_group_invocations`. A limit is met if it is at least the required value,
+//! limits with several components, like `max_compute_work_group_size`, are compared
+//! componentwise.
+
+use std::fmt::{Debug, Write};
+
+/// The value of a device limit.
+pub trait Limit: Debug {
+    /// Returns whether the limit is at least `min`, in every component.
+    fn at_least(&self, min: &Self) -> bool;
+}
+
+macro_rules! impl_limit {
+    ($( $ty:ty ),*) => {
+        $(
+            impl Limit for $ty {
+                fn at_least(&self, min: &Self) -> bool {
+                    self >= min
+                }
+            }
+
+            impl Limit for [$ty; 2] {
+                fn at_least(&self, min: &Self) -> bool {
+                    self.iter().zip(min).all(|(value, min)| value >= min)
+                }
+            }
+
+            impl Limit for [$ty; 3] {
+                fn at_least(&self, min: &Self) -> bool {
+                    self.iter().zip(min).all(|(value, min)| value >= min)
+                }
+            }
+        )*
+    }
+}
+
+impl_limit!(u32, u64, usize, f32);
+
+/// Returns a description of the limit `name`, if its value `value` is less than `min`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::limits::check;
+///
+/// assert_eq!(check("max_compute_work_group_invocations", 1024u32, 256), None);
+/// assert_eq!(check("max_compute_work_group_size", [1024u32, 1024, 64], [256, 256, 256]),
+///            Some("max_compute_work_group_size is [1024, 1024, 64], expected at least \
+///                  [256, 256, 256]".to_owned()));
+/// # }
+/// ```
+///
+pub fn check<T: Limit>(name: &str, value: T, min: T) -> Option<String> {
+    if value.at_least(&min) {
+        None
+    } else {
+        Some(format!("{} is {:?}, expected at least {:?}", name, value, min))
+    }
+}
+
+/// Panics with the limits which every device failed, given as the name of the device and the
+/// descriptions returned by `check`.
+pub fn unmet(failures: &[(String, Vec<String>)]) -> ! {
+    if failures.is_empty() {
+        panic!("No physical devices are available.");
+    }
+    let mut report = String::from("No physical device meets the required limits:");
+    for &(ref device, ref limits) in failures {
+        let _ = write!(report, "\n    `{}`: {}", device, limits.join(", "));
+    }
+    panic!("{}", report);
+}
@@ -0,0 +1,132 @@
+//! This module locks the buffers of `pipeline!` for reading and writing on the host.
+//!
+//! Locking a buffer waits until the device has finished the submissions which use it, but at
+//! most for a timeout. On slow devices, a fixed timeout like `Duration::new(1, 0)` expires
+//! spuriously, so `read_buffer!` and `write_buffer!` retry the lock with a doubled timeout, up to
+//! `RETRIES` times, before they panic. The initial timeout is one second, unless `pipeline!` was
+//! given another one with `lock_timeout: $duration`, which applies to the rest of the test.
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// The initial timeout of a lock, unless `pipeline!` was given another one.
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The number of times a lock is retried after its timeout expired.
+pub const RETRIES: u32 = 4;
+
+thread_local! {
+    static TIMEOUT: Cell<Option<Duration>> = Cell::new(None);
+}
+
+/// Sets the initial timeout of the locks of the current thread. `pipeline!` calls this function
+/// with `lock_timeout`.
+pub fn set_timeout(timeout: Duration) {
+    TIMEOUT.with(|current| current.set(Some(timeout)));
+}
+
+/// Returns the initial timeout of the locks of the current thread.
+pub fn timeout() -> Duration {
+    TIMEOUT.with(|current| current.get()).unwrap_or(DEFAULT_LOCK_TIMEOUT)
+}
+
+/// Calls `lock` with the timeout `timeout`, and again with twice the timeout of the previous
+/// call whenever it fails, up to `RETRIES` times. Returns the first lock which succeeded.
+///
+/// # Panics
+///
+/// If every attempt failed, naming the buffer `name`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::time::Duration;
+/// use vulkanology::locks::retry;
+///
+/// let mut timeouts = Vec::new();
+/// let lock = retry("data", Duration::from_millis(10), |timeout| {
+///     timeouts.push(timeout);
+///     if timeouts.len() < 3 { Err("Timeout") } else { Ok(42) }
+/// });
+/// assert_eq!(lock, 42);
+/// assert_eq!(timeouts,
+///            [Duration::from_millis(10), Duration::from_millis(20), Duration::from_millis(40)]);
+/// # }
+/// ```
+///
+pub fn retry<T, E, F>(name: &str, timeout: Duration, mut lock: F) -> T
+    where E: Debug,
+          F: FnMut(Duration) -> Result<T, E>
+{
+    let mut timeout = timeout;
+    let mut waited = Duration::from_secs(0);
+    for attempt in 0..RETRIES + 1 {
+        match lock(timeout) {
+            Ok(lock) => return lock,
+            Err(err) => {
+                waited += timeout;
+                if attempt == RETRIES {
+                    panic!("Failed to lock the buffer `{}` within {:?} in {} attempts: {:?}",
+                           name,
+                           waited,
+                           RETRIES + 1,
+                           err);
+                }
+            }
+        }
+        timeout *= 2;
+    }
+    unreachable!()
+}
+
+/// Locks the buffer `$buffer` of `pipeline!` for reading, see the [`locks`] module. Evaluates to
+/// its `ReadLock`.
+///
+/// # Panics
+///
+/// If the buffer could not be locked within the retries.
+///
+/// # Example
+///
+/// ```ignore
+/// let output: ReadLock<[u32]> = read_buffer!(result);
+/// ```
+///
+/// [`locks`]: locks/index.html
+///
+#[macro_export]
+macro_rules! read_buffer {
+    ($buffer:expr) => {
+        ::vulkanology::locks::retry(stringify!($buffer),
+                                    ::vulkanology::locks::timeout(),
+                                    |timeout| $buffer.read(timeout))
+    };
+}
+
+/// Locks the buffer `$buffer` of `pipeline!` for writing, see the [`locks`] module. Evaluates to
+/// its `WriteLock`.
+///
+/// # Panics
+///
+/// If the buffer could not be locked within the retries.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut mapping: WriteLock<[u32]> = write_buffer!(data);
+/// ```
+///
+/// [`locks`]: locks/index.html
+///
+#[macro_export]
+macro_rules! write_buffer {
+    ($buffer:expr) => {
+        ::vulkanology::locks::retry(stringify!($buffer),
+                                    ::vulkanology::locks::timeout(),
+                                    |timeout| $buffer.write(timeout))
+    };
+}
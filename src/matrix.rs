@@ -0,0 +1,118 @@
+//! This module contains a CPU reference for verifying matrix multiplication kernels.
+//!
+//! All matrices are stored densely in row-major order, i.e. the element at row `i` and column `j`
+//! of an `m`×`n` matrix is at index `i * n + j`.
+
+use quirks;
+
+/// Computes `A * B + C` for an `m`×`k` matrix `A`, a `k`×`n` matrix `B` and an `m`×`n` matrix `C`,
+/// where `dimensions` is `(m, n, k)`.
+///
+/// The products are accumulated in `f64`, so the result can serve as a reference for kernels
+/// with less precise accumulators.
+///
+/// # Panics
+///
+/// If the length of one of the matrices does not match the dimensions.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::matrix::gemm;
+///
+/// let a = [1.0, 2.0, 3.0, 4.0];
+/// let b = [5.0, 6.0, 7.0, 8.0];
+/// let c = [0.0, 0.0, 0.0, 1.0];
+/// assert_eq!(gemm((2, 2, 2), &a, &b, &c), vec![19.0, 22.0, 43.0, 51.0]);
+/// # }
+/// ```
+///
+pub fn gemm(dimensions: (usize, usize, usize), a: &[f32], b: &[f32], c: &[f32]) -> Vec<f32> {
+    let (m, n, k) = dimensions;
+    check_len("A", a, m, k);
+    check_len("B", b, k, n);
+    check_len("C", c, m, n);
+
+    let mut result = Vec::with_capacity(m * n);
+    for i in 0..m {
+        for j in 0..n {
+            let mut sum = c[i * n + j] as f64;
+            for l in 0..k {
+                sum += a[i * k + l] as f64 * b[l * n + j] as f64;
+            }
+            result.push(sum as f32);
+        }
+    }
+    result
+}
+
+/// Checks the `m`×`n` matrix `result` of a kernel against `gemm(dimensions, a, b, c)`.
+///
+/// An element matches if its absolute difference to the reference is at most `tolerance` times
+/// the sum of the absolute values of the summands, which accounts for the rounding of the
+/// kernel's accumulator.
+///
+/// # Panics
+///
+/// If an element does not match, naming its row and column.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::matrix::assert_gemm_eq;
+///
+/// let a = [1.0, 2.0, 3.0, 4.0];
+/// let b = [5.0, 6.0, 7.0, 8.0];
+/// let c = [0.0; 4];
+/// assert_gemm_eq((2, 2, 2), &a, &b, &c, &[19.0, 22.0, 43.0, 51.0], 1e-3);
+/// # }
+/// ```
+///
+pub fn assert_gemm_eq(dimensions: (usize, usize, usize),
+                      a: &[f32],
+                      b: &[f32],
+                      c: &[f32],
+                      result: &[f32],
+                      tolerance: f32) {
+    let (m, n, k) = dimensions;
+    let expected = gemm(dimensions, a, b, c);
+    check_len("result", result, m, n);
+    let tolerance = match quirks::tolerance("assert_gemm_eq", tolerance) {
+        Some(tolerance) => tolerance,
+        None => return,
+    };
+
+    for i in 0..m {
+        for j in 0..n {
+            let mut magnitude = c[i * n + j].abs() as f64;
+            for l in 0..k {
+                magnitude += (a[i * k + l] as f64 * b[l * n + j] as f64).abs();
+            }
+            let index = i * n + j;
+            let error = (result[index] as f64 - expected[index] as f64).abs();
+            if error.is_nan() || error > tolerance as f64 * magnitude {
+                panic!("The result differs from the reference at row {}, column {}: {} != {}.",
+                       i,
+                       j,
+                       result[index],
+                       expected[index]);
+            }
+        }
+    }
+}
+
+fn check_len(name: &str, matrix: &[f32], rows: usize, columns: usize) {
+    if matrix.len() != rows * columns {
+        panic!("The matrix {} has {} elements, expected {}x{}.",
+               name,
+               matrix.len(),
+               rows,
+               columns);
+    }
+}
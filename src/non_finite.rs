@@ -0,0 +1,213 @@
+//! This module finds the NaNs and infinities in float buffers.
+//!
+//! A single NaN spreads through every result computed from it, so the elements which are NaN
+//! after an execution are rarely the ones which produced it. `scan_non_finite!` lists every
+//! non-finite element of a buffer, and given the dispatch, the global invocation id which wrote
+//! it, assuming that the invocations write their elements in the order of their flattened
+//! global id:
+//!
+//! ```ignore
+//! let non_finite = scan_non_finite!(result,
+//!                                   workgroup_count: [100, 100, 1],
+//!                                   local_size: [8, 8, 1]);
+//! for element in &non_finite {
+//!     println!("{}", element);
+//! }
+//! assert!(non_finite.is_empty());
+//! ```
+//!
+//! The elements are listed by their index, which is not necessarily the order in which the
+//! invocations computed them. The invocation with the lowest id among them is a good start for
+//! debugging it, e.g. with the `debug_log` segment.
+
+use std::fmt;
+
+/// The float element types which are scanned by `scan_non_finite!`.
+pub trait Float: Copy {
+    /// Returns whether the value is neither NaN nor infinite.
+    fn is_finite(self) -> bool;
+
+    /// Returns the value as a `f64`.
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// The layout in which the invocations of a dispatch write a buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The number of invocations in every dimension, i.e. the workgroup count times the local
+    /// size.
+    pub extent: [u32; 3],
+    /// The number of consecutive elements which every invocation writes.
+    pub elements_per_invocation: usize,
+}
+
+impl Layout {
+    /// Returns the layout of a dispatch of `workgroup_count` workgroups of the size
+    /// `local_size`, in which every invocation writes `elements_per_invocation` elements.
+    pub fn new(workgroup_count: [u32; 3],
+               local_size: [u32; 3],
+               elements_per_invocation: usize)
+               -> Layout {
+        Layout {
+            extent: [workgroup_count[0] * local_size[0],
+                     workgroup_count[1] * local_size[1],
+                     workgroup_count[2] * local_size[2]],
+            elements_per_invocation: elements_per_invocation,
+        }
+    }
+
+    /// Returns the global invocation id which writes the element `index`, or `None` if the
+    /// index is beyond the dispatch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use vulkanology::non_finite::Layout;
+    ///
+    /// let layout = Layout::new([100, 100, 1], [8, 8, 1], 1);
+    /// assert_eq!(layout.invocation(0), Some([0, 0, 0]));
+    /// assert_eq!(layout.invocation(801), Some([1, 1, 0]));
+    /// assert_eq!(layout.invocation(640000), None);
+    /// # }
+    /// ```
+    ///
+    pub fn invocation(&self, index: usize) -> Option<[u32; 3]> {
+        if self.elements_per_invocation == 0 {
+            return None;
+        }
+        let invocation = (index / self.elements_per_invocation) as u64;
+        let x = self.extent[0] as u64;
+        let y = self.extent[1] as u64;
+        if invocation >= x * y * self.extent[2] as u64 {
+            return None;
+        }
+        Some([(invocation % x) as u32, (invocation / x % y) as u32, (invocation / (x * y)) as u32])
+    }
+}
+
+/// A NaN or an infinity in a buffer.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NonFinite {
+    /// The index of the element.
+    pub index: usize,
+    /// The element.
+    pub value: f64,
+    /// The global invocation id which wrote the element, if the layout of the buffer is known.
+    pub invocation: Option<[u32; 3]>,
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {}", self.index)?;
+        if let Some(invocation) = self.invocation {
+            write!(f, " (invocation {:?})", invocation)?;
+        }
+        write!(f, ": {}", self.value)
+    }
+}
+
+/// Returns the NaNs and infinities in `buffer`, with the invocations which wrote them given the
+/// `layout` of the buffer.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::f32;
+/// use vulkanology::non_finite::{scan, Layout};
+///
+/// let buffer = [1.0, f32::NAN, 2.0, 3.0, f32::INFINITY, 4.0];
+/// let non_finite = scan(&buffer, Some(Layout::new([1, 1, 1], [3, 1, 1], 2)));
+/// assert_eq!(non_finite.len(), 2);
+/// assert_eq!(non_finite[0].to_string(), "index 1 (invocation [0, 0, 0]): NaN");
+/// assert_eq!(non_finite[1].to_string(), "index 4 (invocation [2, 0, 0]): inf");
+/// # }
+/// ```
+///
+pub fn scan<T: Float>(buffer: &[T], layout: Option<Layout>) -> Vec<NonFinite> {
+    buffer.iter()
+        .enumerate()
+        .filter(|&(_, value)| !value.is_finite())
+        .map(|(index, value)| {
+            NonFinite {
+                index: index,
+                value: value.to_f64(),
+                invocation: layout.and_then(|layout| layout.invocation(index)),
+            }
+        })
+        .collect()
+}
+
+/// Returns the NaNs and infinities in the float buffer `$buffer`, see the [`non_finite`] module,
+/// as a `Vec<non_finite::NonFinite>`. The buffer can be anything which can be sliced, e.g. the
+/// `ReadLock` of a buffer, and its elements are `f32` or `f64`.
+///
+/// Given the `workgroup_count` and the `local_size` of the dispatch, every element is reported
+/// with the invocation which wrote it, optionally followed by the number of consecutive elements
+/// which every invocation writes, `elements_per_invocation`, which defaults to one.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// let result = vec![0.0f32, 1.0 / 0.0, 2.0, 0.0 / 0.0];
+/// assert_eq!(scan_non_finite!(result).len(), 2);
+///
+/// let non_finite = scan_non_finite!(result, workgroup_count: [2, 1, 1], local_size: [2, 1, 1]);
+/// assert_eq!(non_finite[1].invocation, Some([3, 0, 0]));
+/// # }
+/// ```
+///
+/// [`non_finite`]: non_finite/index.html
+///
+#[macro_export]
+macro_rules! scan_non_finite {
+    ($buffer:expr) => {
+        ::vulkanology::non_finite::scan(&$buffer[..], None)
+    };
+    ($buffer:expr, workgroup_count: $workgroup_count:expr, local_size: $local_size:expr) => {
+        scan_non_finite!($buffer,
+                         workgroup_count: $workgroup_count,
+                         local_size: $local_size,
+                         elements_per_invocation: 1)
+    };
+    ($buffer:expr,
+     workgroup_count: $workgroup_count:expr,
+     local_size: $local_size:expr,
+     elements_per_invocation: $elements_per_invocation:expr) => {{
+        use vulkanology::checked;
+        use vulkanology::non_finite::{self, Layout};
+        let layout = Layout::new(checked::workgroup_count($workgroup_count),
+                                 checked::workgroup_count($local_size),
+                                 $elements_per_invocation);
+        non_finite::scan(&$buffer[..], Some(layout))
+    }};
+}
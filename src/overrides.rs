@@ -0,0 +1,244 @@
+//! This module reads environment variables which override parameters of the generated test code.
+//!
+//! The overrides are evaluated at runtime, so a failing test can, for example, be run with a
+//! smaller workload without editing and recompiling it:
+//!
+//! `VULKANOLOGY_WORKGROUPS=10x10x1 VULKANOLOGY_ITERATIONS=1 cargo test test_random_next_u64`
+//!
+//! The physical device can be forced in the same way, by its position in the enumeration or by
+//! a part of its name, e.g. on CI machines or workstations with several GPUs:
+//!
+//! `VULKANOLOGY_DEVICE_NAME=llvmpipe cargo test`
+//!
+//! The override applies to `physical_device!` and to `pipeline!` without a `device` parameter.
+//! Devices selected explicitly by `index`, like those of `tests/cross_device.rs`, are kept.
+//!
+//! The time which the execution command waits for the shader before it reports a probable hang
+//! can be raised for slow devices, e.g. CPU implementations of Vulkan:
+//!
+//! `VULKANOLOGY_TIMEOUT=600 cargo test`
+
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The environment variable which overrides the workgroup count, e.g. `10x10x1`.
+pub const WORKGROUPS_VAR: &'static str = "VULKANOLOGY_WORKGROUPS";
+
+/// The environment variable which overrides the number of iterations of the execution command.
+pub const ITERATIONS_VAR: &'static str = "VULKANOLOGY_ITERATIONS";
+
+/// The environment variable which overrides the timeout of the execution command in seconds.
+pub const TIMEOUT_VAR: &'static str = "VULKANOLOGY_TIMEOUT";
+
+/// The environment variable which selects the physical device by its index, e.g. `1`.
+pub const DEVICE_VAR: &'static str = "VULKANOLOGY_DEVICE";
+
+/// The environment variable which selects the first physical device whose name contains its
+/// value, ignoring case, e.g. `llvmpipe`.
+pub const DEVICE_NAME_VAR: &'static str = "VULKANOLOGY_DEVICE_NAME";
+
+/// Returns the workgroup count, which is either `default` or the value of `VULKANOLOGY_WORKGROUPS`.
+///
+/// # Panics
+///
+/// If the variable is set, but is not of the form `<x>x<y>x<z>`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use vulkanology::overrides::{workgroup_count, WORKGROUPS_VAR};
+///
+/// env::remove_var(WORKGROUPS_VAR);
+/// assert_eq!(workgroup_count([100, 100, 1]), [100, 100, 1]);
+///
+/// env::set_var(WORKGROUPS_VAR, "10x10x1");
+/// assert_eq!(workgroup_count([100, 100, 1]), [10, 10, 1]);
+/// # }
+/// ```
+///
+pub fn workgroup_count(default: [u32; 3]) -> [u32; 3] {
+    match env::var(WORKGROUPS_VAR) {
+        Ok(value) => {
+            let dimensions = value.split('x')
+                .map(|dimension| u32::from_str(dimension.trim()))
+                .collect::<Result<Vec<u32>, _>>();
+            match dimensions {
+                Ok(ref dimensions) if dimensions.len() == 3 => {
+                    [dimensions[0], dimensions[1], dimensions[2]]
+                }
+                _ => {
+                    panic!("Invalid value of {}: `{}`, expected `<x>x<y>x<z>`.",
+                           WORKGROUPS_VAR,
+                           value)
+                }
+            }
+        }
+        Err(_) => default,
+    }
+}
+
+/// Returns the number of iterations, which is either `default` or the value of
+/// `VULKANOLOGY_ITERATIONS`.
+///
+/// # Panics
+///
+/// If the variable is set, but is not a positive integer.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use vulkanology::overrides::{iterations, ITERATIONS_VAR};
+///
+/// env::remove_var(ITERATIONS_VAR);
+/// assert_eq!(iterations(1), 1);
+///
+/// env::set_var(ITERATIONS_VAR, "5");
+/// assert_eq!(iterations(1), 5);
+/// # }
+/// ```
+///
+pub fn iterations(default: usize) -> usize {
+    match env::var(ITERATIONS_VAR) {
+        Ok(value) => {
+            match usize::from_str(value.trim()) {
+                Ok(iterations) if iterations > 0 => iterations,
+                _ => {
+                    panic!("Invalid value of {}: `{}`, expected a positive integer.",
+                           ITERATIONS_VAR,
+                           value)
+                }
+            }
+        }
+        Err(_) => default,
+    }
+}
+
+/// Returns the timeout of the execution command, which is either `default` or the value of
+/// `VULKANOLOGY_TIMEOUT` in seconds.
+///
+/// # Panics
+///
+/// If the variable is set, but is not a positive integer.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use std::time::Duration;
+/// use vulkanology::overrides::{timeout, TIMEOUT_VAR};
+///
+/// env::remove_var(TIMEOUT_VAR);
+/// assert_eq!(timeout(Duration::from_secs(60)), Duration::from_secs(60));
+///
+/// env::set_var(TIMEOUT_VAR, "600");
+/// assert_eq!(timeout(Duration::from_secs(60)), Duration::from_secs(600));
+/// # }
+/// ```
+///
+pub fn timeout(default: Duration) -> Duration {
+    match env::var(TIMEOUT_VAR) {
+        Ok(value) => {
+            match u64::from_str(value.trim()) {
+                Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+                _ => {
+                    panic!("Invalid value of {}: `{}`, expected a positive integer.",
+                           TIMEOUT_VAR,
+                           value)
+                }
+            }
+        }
+        Err(_) => default,
+    }
+}
+
+/// Returns the index of the physical device selected by `VULKANOLOGY_DEVICE` or
+/// `VULKANOLOGY_DEVICE_NAME` among the devices named `names`, or `None` if neither is set. The
+/// index takes precedence over the name.
+///
+/// # Panics
+///
+/// If the index is not a valid index of `names`, or if no name contains the value of
+/// `VULKANOLOGY_DEVICE_NAME`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use vulkanology::overrides::{device, DEVICE_NAME_VAR, DEVICE_VAR};
+///
+/// let names = vec!["Intel(R) UHD Graphics".to_owned(), "NVIDIA GeForce RTX 3060".to_owned()];
+/// env::remove_var(DEVICE_VAR);
+/// env::remove_var(DEVICE_NAME_VAR);
+/// assert_eq!(device(&names), None);
+///
+/// env::set_var(DEVICE_NAME_VAR, "geforce");
+/// assert_eq!(device(&names), Some(1));
+///
+/// env::set_var(DEVICE_VAR, "0");
+/// assert_eq!(device(&names), Some(0));
+/// # }
+/// ```
+///
+pub fn device(names: &[String]) -> Option<usize> {
+    if let Ok(value) = env::var(DEVICE_VAR) {
+        return match usize::from_str(value.trim()) {
+            Ok(index) if index < names.len() => Some(index),
+            _ => {
+                panic!("Invalid value of {}: `{}`, expected the index of one of the devices {:?}.",
+                       DEVICE_VAR,
+                       value,
+                       names)
+            }
+        };
+    }
+    env::var(DEVICE_NAME_VAR).ok().map(|value| {
+        let pattern = value.to_lowercase();
+        match names.iter().position(|name| name.to_lowercase().contains(&pattern)) {
+            Some(index) => index,
+            None => {
+                panic!("Invalid value of {}: `{}`, no device of {:?} has a matching name.",
+                       DEVICE_NAME_VAR,
+                       value,
+                       names)
+            }
+        }
+    })
+}
+
+/// The physical device of a pipeline, by its index or by default.
+pub trait DeviceIndex {
+    /// Returns the index of the device among the devices named `names`.
+    fn device_index(self, names: &[String]) -> usize;
+}
+
+impl DeviceIndex for usize {
+    fn device_index(self, _: &[String]) -> usize {
+        self
+    }
+}
+
+/// The device of a pipeline without a `device` parameter, which is the first device unless it
+/// is overridden by `device`.
+#[derive(Debug, Copy, Clone)]
+pub struct DefaultDevice;
+
+impl DeviceIndex for DefaultDevice {
+    fn device_index(self, names: &[String]) -> usize {
+        device(names).unwrap_or(0)
+    }
+}
@@ -0,0 +1,52 @@
+//! This module assigns the descriptor locations of ping-pong buffers.
+//!
+//! Iterative shaders, e.g. simulations and blurs, read the current state from one buffer and
+//! write the next state into another. Instead of copying the next state back after every
+//! dispatch, `pipeline!` swaps the locations of the two buffers in the descriptor sets of every
+//! other dispatch, so the shader always reads the first binding and writes the second.
+
+/// Returns the `(set, binding)` of the buffer `name` in the swapped descriptor sets. `pair` names
+/// the two ping-pong buffers, which take each other's location, and `locations` lists the
+/// `(name, set, binding)` of all buffers of the pipeline, which keep their own location.
+///
+/// # Panics
+///
+/// If `name` or one of the buffers of `pair` is not in `locations`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::ping_pong::swapped_location;
+///
+/// let locations = [("state", 0, 0), ("next_state", 0, 1), ("weights", 1, 0)];
+/// let pair = ("state", "next_state");
+/// assert_eq!(swapped_location(pair, &locations, "state"), (0, 1));
+/// assert_eq!(swapped_location(pair, &locations, "next_state"), (0, 0));
+/// assert_eq!(swapped_location(pair, &locations, "weights"), (1, 0));
+/// # }
+/// ```
+///
+pub fn swapped_location(pair: (&str, &str),
+                        locations: &[(&str, u32, u32)],
+                        name: &str)
+                        -> (u32, u32) {
+    let location = |name: &str| {
+        locations.iter()
+            .find(|&&(buffer, _, _)| buffer == name)
+            .map(|&(_, set, binding)| (set, binding))
+            .expect(format!("Failed to find the buffer `{}`.", name).as_ref())
+    };
+    let (front, back) = pair;
+    // Both buffers of the pair are looked up, so a misspelled pair panics for every buffer.
+    let (front_location, back_location) = (location(front), location(back));
+    if name == front {
+        back_location
+    } else if name == back {
+        front_location
+    } else {
+        location(name)
+    }
+}
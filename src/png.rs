@@ -0,0 +1,281 @@
+//! This module writes buffers which hold two-dimensional data as PNG images, so the result of a
+//! kernel can be inspected, or compared against a reference image by eye:
+//!
+//! ```ignore
+//! let output: ReadLock<[f32]> = read_buffer!(result);
+//! save_png!(output, WIDTH, HEIGHT);
+//! ```
+//!
+//! The element at the index `y * width + x` is the pixel in the row `y` from the top and the
+//! column `x` from the left. Elements of one scalar become grayscale pixels, elements of two
+//! scalars grayscale pixels with alpha, and elements of three or four scalars RGB or RGBA
+//! pixels, e.g. `ArrayVec4<f32>`, see the [`export`] module. The scalars are mapped linearly onto
+//! the 8-bit channels, from the range given by a `Normalize` to 0 to 255, and values outside of
+//! the range are clamped. NaNs become 0, see `scan_non_finite!` to find them.
+//!
+//! `save_png!` saves the image as `target/test_images/<test>/<buffer>.png`, named after the
+//! thread of the test, which the test harness names after the test, and notes the path on
+//! stderr. The image is written without compression, so no further crates are needed.
+//!
+//! [`export`]: ../export/index.html
+
+use std::f64;
+use std::fs::{create_dir_all, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use export::{Element, Scalar};
+
+/// The directory of the images saved by `save_png!`, relative to the working directory.
+pub const IMAGE_DIR: &'static str = "target/test_images";
+
+/// The mapping of the scalars onto the channels of the image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Normalize {
+    /// Maps the range from the smallest to the largest finite scalar of the buffer onto the
+    /// channels.
+    MinMax,
+    /// Maps the range from `min` to `max` onto the channels, e.g. `Range(0.0, 1.0)` for colors.
+    Range(f64, f64),
+}
+
+/// Returns the scalars of `data` as `f64`, and their number per element.
+fn scalars<T: Element>(data: &[T]) -> (Vec<f64>, usize) {
+    let mut scalars = Vec::new();
+    for element in data {
+        element.scalars(&mut scalars);
+    }
+    let channels = if data.is_empty() { 1 } else { scalars.len() / data.len() };
+    (scalars.into_iter().map(Scalar::to_f64).collect(), channels)
+}
+
+/// Maps the scalars onto 8-bit channels with `normalize`.
+fn quantize(scalars: &[f64], normalize: Normalize) -> Vec<u8> {
+    let (min, max) = match normalize {
+        Normalize::Range(min, max) => (min, max),
+        Normalize::MinMax => {
+            scalars.iter()
+                .filter(|scalar| scalar.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY),
+                      |(min, max), &scalar| (min.min(scalar), max.max(scalar)))
+        }
+    };
+    let scale = if max > min { 255.0 / (max - min) } else { 0.0 };
+    scalars.iter()
+        .map(|&scalar| {
+            let channel = ((scalar - min) * scale).round();
+            if channel.is_nan() { 0 } else { channel.max(0.0).min(255.0) as u8 }
+        })
+        .collect()
+}
+
+/// Returns the CRC-32 of `bytes`, which checks every chunk of a PNG file.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 { crc >> 1 ^ 0xedb8_8320 } else { crc >> 1 }
+        })
+    })
+}
+
+/// Returns the Adler-32 checksum of `bytes`, which checks the zlib stream of the image data.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+/// Returns the big endian bytes of `value`, as PNG stores its integers.
+fn be_bytes(value: u32) -> [u8; 4] {
+    [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+/// Returns the little endian bytes of `value`, as deflate stores the lengths of its blocks.
+fn le_bytes(value: u16) -> [u8; 2] {
+    [value as u8, (value >> 8) as u8]
+}
+
+/// Writes the chunk `kind` with the content `data`.
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut checked = kind.to_vec();
+    checked.extend_from_slice(data);
+    out.write_all(&be_bytes(data.len() as u32))?;
+    out.write_all(&checked)?;
+    out.write_all(&be_bytes(crc32(&checked)))
+}
+
+/// Writes `data` as a PNG image of `width` times `height` pixels to `out`, with the channels
+/// mapped by `normalize`.
+///
+/// # Panics
+///
+/// If `data` does not have `width * height` elements, or if its elements have more than four
+/// scalars.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::png::{write_png, Normalize};
+///
+/// let mut png = Vec::new();
+/// write_png(&mut png, &[0.0f32, 0.5, 1.0, 0.25], 2, 2, Normalize::Range(0.0, 1.0)).unwrap();
+/// assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+/// assert_eq!(&png[12..16], b"IHDR");
+/// assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+/// # }
+/// ```
+///
+pub fn write_png<W, T>(out: &mut W,
+                       data: &[T],
+                       width: usize,
+                       height: usize,
+                       normalize: Normalize)
+                       -> io::Result<()>
+    where W: Write,
+          T: Element
+{
+    if data.len() != width * height {
+        panic!("The buffer has {} elements, but an image of {}x{} pixels has {}.",
+               data.len(),
+               width,
+               height,
+               width * height);
+    }
+    let (scalars, channels) = scalars(data);
+    let color_type = match channels {
+        1 => 0,
+        2 => 4,
+        3 => 2,
+        4 => 6,
+        _ => panic!("The elements of an image have {} scalars instead of 1 to 4.", channels),
+    };
+    let pixels = quantize(&scalars, normalize);
+
+    // Every row starts with the filter type 0, which stores the pixels unchanged.
+    let mut rows = Vec::with_capacity(height * (width * channels + 1));
+    for row in pixels.chunks(width * channels).take(height) {
+        rows.push(0);
+        rows.extend_from_slice(row);
+    }
+    // A zlib stream of uncompressed deflate blocks, of at most 65535 bytes each.
+    let mut zlib = vec![0x78, 0x01];
+    let blocks = rows.chunks(65535).collect::<Vec<_>>();
+    for (index, block) in blocks.iter().enumerate() {
+        let last = index + 1 == blocks.len();
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&le_bytes(block.len() as u16));
+        zlib.extend_from_slice(&le_bytes(!(block.len() as u16)));
+        zlib.extend_from_slice(block);
+    }
+    if blocks.is_empty() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    zlib.extend_from_slice(&be_bytes(adler32(&rows)));
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&be_bytes(width as u32));
+    header.extend_from_slice(&be_bytes(height as u32));
+    header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_chunk(out, b"IHDR", &header)?;
+    write_chunk(out, b"IDAT", &zlib)?;
+    write_chunk(out, b"IEND", &[])
+}
+
+/// Saves `data` as a PNG image at `path` like `write_png`, creating the parent directories.
+///
+/// # Panics
+///
+/// If the image cannot be written, or like `write_png`.
+pub fn save<T: Element>(path: &Path,
+                        data: &[T],
+                        width: usize,
+                        height: usize,
+                        normalize: Normalize) {
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).expect("Failed to create the image directory.");
+    }
+    File::create(path)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write_png(&mut out, data, width, height, normalize)?;
+            out.flush()
+        })
+        .expect(format!("Failed to write the image: {}", path.display()).as_ref());
+}
+
+/// Returns the path of the image of the buffer `name` of the current test, in `IMAGE_DIR`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::path::Path;
+/// use std::thread;
+/// use vulkanology::png::image_path;
+///
+/// let path = thread::Builder::new()
+///     .name("blur::test_blur".to_owned())
+///     .spawn(|| image_path("result"))
+///     .unwrap()
+///     .join()
+///     .unwrap();
+/// assert_eq!(path, Path::new("target/test_images/blur-test_blur/result.png"));
+/// # }
+/// ```
+///
+pub fn image_path(name: &str) -> PathBuf {
+    let test = thread::current().name().unwrap_or("unnamed").replace("::", "-");
+    Path::new(IMAGE_DIR).join(test).join(name).with_extension("png")
+}
+
+/// Saves the buffer `$buffer` of `$width` times `$height` elements as a PNG image, see the
+/// [`png`] module, and evaluates to its path. The buffer can be anything which can be sliced,
+/// e.g. the `ReadLock` of a buffer. The scalars are mapped onto the channels from their
+/// smallest to their largest value, or from the range given with `normalize: $normalize` as a
+/// `png::Normalize`.
+///
+/// # Panics
+///
+/// If the buffer does not have `$width * $height` elements, or if the image cannot be written.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::png::Normalize;
+///
+/// let gradient = (0..64 * 32).map(|i| (i % 64) as f32 / 63.0).collect::<Vec<_>>();
+/// let path = save_png!(gradient, 64, 32, normalize: Normalize::Range(0.0, 1.0));
+/// assert!(path.ends_with("gradient.png"));
+/// # }
+/// ```
+///
+/// [`png`]: png/index.html
+///
+#[macro_export]
+macro_rules! save_png {
+    ($buffer:expr, $width:expr, $height:expr) => {
+        save_png!($buffer, $width, $height, normalize: ::vulkanology::png::Normalize::MinMax)
+    };
+    ($buffer:expr, $width:expr, $height:expr, normalize: $normalize:expr) => {{
+        use std::io::{self, Write};
+        use vulkanology::png;
+        let path = png::image_path(stringify!($buffer));
+        png::save(&path, &$buffer[..], $width as usize, $height as usize, $normalize);
+        let _ = writeln!(io::stderr(), "note: Saved the image {}.", path.display());
+        path
+    }};
+}
@@ -0,0 +1,357 @@
+//! This module measures the precision of a float shader against a double-precision reference,
+//! instead of asserting a tolerance, e.g. to compare the accuracy of fast-math variants of a
+//! kernel.
+//!
+//! `precision_report!` creates the pipeline, executes the shader once, and evaluates the
+//! reference closure in `f64` for the input element of every output element. The errors are
+//! measured in units in the last place of `f32` at the reference value, like the precision
+//! requirements of the Vulkan specification. The resulting `Report` holds the largest and the
+//! mean error and the input with the largest error, and is noted on stderr and in the report of
+//! the [`harness`]:
+//!
+//! ```ignore
+//! let report = precision_report!{
+//!     pipeline: {
+//!         shader_path: "tests/shaders/precision.comp",
+//!         workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+//!         buffers: {
+//!             data: [f32] from input_vec,
+//!             result: [f32; NUM_ELEMENTS]
+//!         }
+//!     },
+//!     input: data,
+//!     output: result,
+//!     reference: |&x| (x as f64).exp()
+//! };
+//! assert!(report.max_ulps < 16.0, "{}", report);
+//! ```
+//!
+//! The element `i` of the output is compared with the reference of the element `i` of the input.
+//! Kernels with several inputs can zip them into one buffer of vectors, e.g. of `glsl::ArrayVec2`.
+//! Outputs which are NaN or infinite where the reference is not, or the other way around, are
+//! counted separately and do not count towards the largest or the mean error.
+//!
+//! [`harness`]: ../harness/index.html
+
+use std::f64;
+use std::fmt::{self, Debug};
+use std::io::{self, Write};
+
+use harness;
+use non_finite::Layout;
+
+/// Returns the unit in the last place of `f32` at the value `x`, i.e. the distance between the
+/// two `f32` around `x`, or the distance between the denormals if `x` is smaller than the
+/// smallest normal `f32`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::precision::ulp;
+///
+/// assert_eq!(ulp(1.0), 2f64.powi(-23));
+/// assert_eq!(ulp(-3.0), 2f64.powi(-22));
+/// assert_eq!(ulp(0.0), 2f64.powi(-149));
+/// # }
+/// ```
+///
+pub fn ulp(x: f64) -> f64 {
+    let exponent = (((x.abs().to_bits() >> 52) & 0x7ff) as i32 - 1023).max(-126);
+    2f64.powi(exponent - 23)
+}
+
+/// Returns the error of `actual` in units in the last place at the value `reference`. The error
+/// is 0 if both are NaN, or if `reference` rounds to the same infinity, and infinite if only one
+/// of them is NaN or infinite.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::f32;
+/// use vulkanology::precision::ulp_error;
+///
+/// assert_eq!(ulp_error(1.0 + f32::EPSILON, 1.0), 1.0);
+/// assert_eq!(ulp_error(1.0, 1.0 + 2f64.powi(-24)), 0.5);
+/// assert_eq!(ulp_error(f32::INFINITY, 1e300), 0.0);
+/// assert_eq!(ulp_error(f32::NAN, 1.0), ::std::f64::INFINITY);
+/// # }
+/// ```
+///
+pub fn ulp_error(actual: f32, reference: f64) -> f64 {
+    if actual.is_nan() || reference.is_nan() {
+        return if actual.is_nan() && reference.is_nan() { 0.0 } else { f64::INFINITY };
+    }
+    let rounded = reference as f32;
+    if !actual.is_finite() || !rounded.is_finite() {
+        return if actual == rounded { 0.0 } else { f64::INFINITY };
+    }
+    (actual as f64 - reference).abs() / ulp(reference)
+}
+
+/// An output element compared with its reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// The index of the element.
+    pub index: usize,
+    /// The global invocation id which wrote the element, if the layout of the buffer is known.
+    pub invocation: Option<[u32; 3]>,
+    /// The input element of the reference.
+    pub input: String,
+    /// The element of the output.
+    pub actual: f32,
+    /// The value of the reference.
+    pub reference: f64,
+    /// The error in units in the last place, see `ulp_error`.
+    pub ulps: f64,
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {}", self.index)?;
+        if let Some(invocation) = self.invocation {
+            write!(f, " (invocation {:?})", invocation)?;
+        }
+        write!(f,
+               ": {:?} for the input {}, the reference is {:?}",
+               self.actual,
+               self.input,
+               self.reference)
+    }
+}
+
+/// The precision of an output buffer against its reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// The name of the output buffer.
+    pub name: String,
+    /// The number of compared elements.
+    pub elements: usize,
+    /// The largest error of the finite outputs in units in the last place.
+    pub max_ulps: f64,
+    /// The mean error of the finite outputs in units in the last place.
+    pub mean_ulps: f64,
+    /// The element with the largest error, unless no element is finite.
+    pub worst: Option<Sample>,
+    /// The number of elements which are NaN or infinite where the reference is not, or the
+    /// other way around.
+    pub non_finite: usize,
+    /// The first of the `non_finite` elements.
+    pub first_non_finite: Option<Sample>,
+}
+
+impl Report {
+    /// Asserts that the largest error is at most `max_ulps`, and that no output is NaN or
+    /// infinite where the reference is not.
+    ///
+    /// # Panics
+    ///
+    /// If the precision is worse, with the report.
+    pub fn assert_within(&self, max_ulps: f64) {
+        if self.max_ulps > max_ulps || self.non_finite > 0 {
+            panic!("The precision of `{}` is worse than {} ULP:\n{}", self.name, max_ulps, self);
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "`{}`: {} elements, max error {:.3} ULP, mean error {:.3} ULP",
+               self.name,
+               self.elements,
+               self.max_ulps,
+               self.mean_ulps)?;
+        if let Some(ref worst) = self.worst {
+            write!(f, "\n  worst: {}", worst)?;
+        }
+        if let Some(ref first) = self.first_non_finite {
+            write!(f,
+                   "\n  {} elements differ from the reference in being NaN or infinite, first: {}",
+                   self.non_finite,
+                   first)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the precision of the output buffer `name`, whose element `i` is compared with
+/// `reference(&inputs[i])`, with the invocations which wrote the elements given the `layout` of
+/// the buffer.
+///
+/// # Panics
+///
+/// If there are fewer inputs than output elements.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::precision::report;
+///
+/// let input = [0.0f32, 0.5, 1.0, 2.0];
+/// let output = [1.0f32, 1.6487212, 2.7182817, 7.389056];
+/// let report = report("result", &output, &input, |&x| (x as f64).exp(), None);
+/// assert_eq!(report.elements, 4);
+/// assert!(report.max_ulps < 1.0);
+/// assert_eq!(report.worst.as_ref().unwrap().index, 1);
+/// assert_eq!(report.non_finite, 0);
+/// report.assert_within(1.0);
+/// # }
+/// ```
+///
+pub fn report<I, F>(name: &str,
+                    output: &[f32],
+                    inputs: &[I],
+                    reference: F,
+                    layout: Option<Layout>)
+                    -> Report
+    where I: Debug,
+          F: Fn(&I) -> f64
+{
+    if inputs.len() < output.len() {
+        panic!("The output `{}` has {} elements, but only {} inputs were given.",
+               name,
+               output.len(),
+               inputs.len());
+    }
+
+    let mut report = Report {
+        name: name.to_owned(),
+        elements: output.len(),
+        max_ulps: 0.0,
+        mean_ulps: 0.0,
+        worst: None,
+        non_finite: 0,
+        first_non_finite: None,
+    };
+    let mut sum = 0.0;
+    for (index, (&actual, input)) in output.iter().zip(inputs).enumerate() {
+        let expected = reference(input);
+        let ulps = ulp_error(actual, expected);
+        let sample = || {
+            Sample {
+                index: index,
+                invocation: layout.and_then(|layout| layout.invocation(index)),
+                input: format!("{:?}", input),
+                actual: actual,
+                reference: expected,
+                ulps: ulps,
+            }
+        };
+        if ulps.is_infinite() {
+            report.non_finite += 1;
+            if report.first_non_finite.is_none() {
+                report.first_non_finite = Some(sample());
+            }
+            continue;
+        }
+        sum += ulps;
+        if report.worst.is_none() || ulps > report.max_ulps {
+            report.max_ulps = ulps;
+            report.worst = Some(sample());
+        }
+    }
+    let finite = report.elements - report.non_finite;
+    if finite > 0 {
+        report.mean_ulps = sum / finite as f64;
+    }
+    report
+}
+
+/// Notes the report `report` on stderr and in the report of the [`harness`].
+///
+/// [`harness`]: ../harness/index.html
+pub fn note(report: &Report) {
+    let note = format!("The precision of {}", report);
+    let _ = writeln!(io::stderr(), "note: {}", note);
+    harness::record_note(note);
+}
+
+/// Executes a shader once and returns the `precision::Report` of its output against a
+/// double-precision reference closure, see the [`precision`] module. The report is also noted
+/// on stderr and in the report of the harness.
+///
+/// The `pipeline` takes the parameters of `pipeline!` without the execution command. The closure
+/// `reference` is called with a reference to every element of the `input` buffer, and returns
+/// the exact result of the element of the `output` buffer at the same index as `f64`. Given the
+/// `workgroup_count` and the `local_size` of the dispatch, the worst elements are reported with
+/// the invocations which wrote them.
+///
+/// # Panics
+///
+/// Like `pipeline!`, or if the input buffer has fewer elements than the output buffer.
+///
+/// # Example
+///
+/// ```ignore
+/// let report = precision_report!{
+///     pipeline: {
+///         shader_path: "tests/shaders/precision.comp",
+///         workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+///         buffers: {
+///             data: [f32] from input_vec,
+///             result: [f32; NUM_ELEMENTS]
+///         }
+///     },
+///     input: data,
+///     output: result,
+///     reference: |&x| (x as f64).exp(),
+///     workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+///     local_size: [64, 1, 1]
+/// };
+/// ```
+///
+/// [`precision`]: precision/index.html
+///
+#[macro_export]
+macro_rules! precision_report {
+    {
+        pipeline: { $( $pipeline_args:tt )* },
+        input: $input:ident,
+        output: $output:ident,
+        reference: $reference:expr,
+        workgroup_count: $workgroup_count:expr,
+        local_size: $local_size:expr $(,)*
+    } => {
+        precision_report!{
+            __report__ { $( $pipeline_args )* } $input, $output, $reference,
+            Some(::vulkanology::non_finite::Layout::new(
+                ::vulkanology::checked::workgroup_count($workgroup_count),
+                ::vulkanology::checked::workgroup_count($local_size),
+                1))
+        }
+    };
+    {
+        pipeline: { $( $pipeline_args:tt )* },
+        input: $input:ident,
+        output: $output:ident,
+        reference: $reference:expr $(,)*
+    } => {
+        precision_report!{
+            __report__ { $( $pipeline_args )* } $input, $output, $reference, None
+        }
+    };
+    (__report__ { $( $pipeline_args:tt )* } $input:ident, $output:ident, $reference:expr,
+     $layout:expr) => ({
+        use vulkanology::precision;
+
+        pipeline!{ $( $pipeline_args )*, execution_command: execute }
+        execute();
+
+        let report = precision::report(stringify!($output),
+                                       &read_buffer!($output)[..],
+                                       &read_buffer!($input)[..],
+                                       $reference,
+                                       $layout);
+        precision::note(&report);
+        report
+    });
+}
@@ -0,0 +1,671 @@
+//! This module contains the push constant storage used by the `pipeline!` macro.
+//!
+//! The push constants are declared as ranges with explicit offsets, which mirrors
+//! `layout(offset = …)` declarations in the GLSL push constant block. Vulkan does not allow two
+//! push constant ranges of a pipeline layout to share a shader stage, so all ranges of a compute
+//! pipeline are stored in a single `PushConstantBlock` and pushed in one piece. The block ends
+//! with the last range, whose end `pipeline!` computes from the types of the push constants with
+//! `Std430Extent`.
+//!
+//! The values are laid out by the `std430` rules, which GLSL uses for push constant blocks. The
+//! `Std430` trait describes the layout of a type: it is implemented for the scalar types, for
+//! arrays, for the vector and matrix types of this module, and for structs declared with
+//! `std430_struct!` or `std430_buffer_struct!`. The latter also checks that the Rust layout of the
+//! struct matches, its size at compile time and the offsets of its members in a generated test,
+//! so it can be the element type of a storage buffer.
+
+use std::cmp;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+/// The size of a `PushConstantBlock` without an explicit storage in bytes. This is the minimum
+/// of `maxPushConstantsSize` guaranteed by the Vulkan specification.
+pub const PUSH_CONSTANTS_SIZE: usize = 128;
+
+/// An array of `u32` which stores a `PushConstantBlock`. It is implemented for the arrays of up
+/// to 64 elements, so a block takes at most 256 bytes, which is the `maxPushConstantsSize` of
+/// most desktop GPUs.
+pub unsafe trait PushConstantStorage: Copy + Send + Sync + 'static {
+    /// The number of elements of the array.
+    const LEN: usize;
+}
+
+macro_rules! impl_push_constant_storage {
+    ( $( $len:expr ),* ) => {
+        $(
+            unsafe impl PushConstantStorage for [u32; $len] {
+                const LEN: usize = $len;
+            }
+        )*
+    };
+}
+
+impl_push_constant_storage!{
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+    17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,
+    33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48,
+    49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64
+}
+
+/// The storage for all push constants of a pipeline, which is as large as the array `S`.
+/// `pipeline!` sizes the array to the end of its last push constant range, so exactly the
+/// declared bytes are pushed.
+pub struct PushConstantBlock<S = [u32; PUSH_CONSTANTS_SIZE / 4]> {
+    data: S,
+}
+
+impl PushConstantBlock {
+    /// Returns a block of `PUSH_CONSTANTS_SIZE` bytes, which are all zero.
+    pub fn new() -> PushConstantBlock {
+        PushConstantBlock::default()
+    }
+}
+
+impl<S: PushConstantStorage> PushConstantBlock<S> {
+    /// Returns the size of the block in bytes.
+    pub fn size() -> usize {
+        mem::size_of::<S>()
+    }
+
+    /// Returns a writer for the range starting at `offset`. If `size` is given, the values
+    /// written to the range must fit into `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` is not a multiple of 4.
+    pub fn range(&mut self, offset: usize, size: Option<usize>) -> PushConstantRange {
+        if offset % 4 != 0 {
+            panic!("The push constant offset {} is not a multiple of 4.", offset);
+        }
+        PushConstantRange {
+            bytes: self.as_bytes_mut(),
+            offset: offset,
+            end: size.map_or(Self::size(), |size| offset + size),
+            cursor: offset,
+        }
+    }
+
+    /// Returns the contents of the block as bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(&self.data as *const S as *const u8, Self::size()) }
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(&mut self.data as *mut S as *mut u8, Self::size()) }
+    }
+}
+
+// The arrays of more than 32 elements do not implement the traits, so they are implemented with
+// the bytes of the block.
+impl<S: PushConstantStorage> Default for PushConstantBlock<S> {
+    fn default() -> PushConstantBlock<S> {
+        PushConstantBlock { data: unsafe { mem::zeroed() } }
+    }
+}
+
+impl<S: PushConstantStorage> Clone for PushConstantBlock<S> {
+    fn clone(&self) -> PushConstantBlock<S> {
+        *self
+    }
+}
+
+impl<S: PushConstantStorage> Copy for PushConstantBlock<S> {}
+
+impl<S: PushConstantStorage> fmt::Debug for PushConstantBlock<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PushConstantBlock").field("bytes", &self.as_bytes()).finish()
+    }
+}
+
+/// Writes consecutive values into a range of a `PushConstantBlock`.
+///
+/// Each value is aligned to its `std430` alignment, like the members of a GLSL push constant
+/// block.
+pub struct PushConstantRange<'a> {
+    bytes: &'a mut [u8],
+    offset: usize,
+    end: usize,
+    cursor: usize,
+}
+
+impl<'a> PushConstantRange<'a> {
+    /// Writes `value` behind the previously written values, at the next offset which is a multiple
+    /// of its alignment.
+    ///
+    /// # Panics
+    ///
+    /// If the value does not fit into the range or into the block.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use vulkanology::push_constants::PushConstantBlock;
+    ///
+    /// let mut block = PushConstantBlock::new();
+    /// {
+    ///     let mut range = block.range(0, Some(8));
+    ///     range.push(4.0f32);
+    ///     range.push(10.0f32);
+    /// }
+    /// block.range(16, None).push(7u32);
+    ///
+    /// assert_eq!(&block.as_bytes()[16..20], &[7, 0, 0, 0]);
+    /// # }
+    /// ```
+    ///
+    pub fn push<T: Std430>(&mut self, value: T) {
+        let size = T::SIZE;
+        let start = align_up(self.cursor, T::ALIGNMENT);
+        if start + size > self.end || start + size > self.bytes.len() {
+            panic!("The push constant range at offset {} overflows: {} bytes at offset {} do \
+                    not fit.",
+                   self.offset,
+                   size,
+                   start);
+        }
+        value.write(&mut self.bytes[start..start + size]);
+        self.cursor = start + size;
+    }
+}
+
+/// Rounds `offset` up to a multiple of `alignment`.
+fn align_up(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+/// A type which can be written to a push constant block, or another block with the `std430`
+/// layout.
+pub trait Std430 {
+    /// The base alignment of the type in bytes, which is a power of two.
+    const ALIGNMENT: usize;
+
+    /// The size of the type in bytes. The size of a struct or an array includes its padding at
+    /// the end, while the size of a `vec3` does not, so a following scalar is packed behind it.
+    const SIZE: usize;
+
+    /// Writes the value into `out`, whose length is `SIZE`.
+    fn write(&self, out: &mut [u8]);
+}
+
+macro_rules! impl_std430_scalar {
+    ( $( $scalar:ty: $alignment:expr ),* ) => {
+        $(
+            impl Std430 for $scalar {
+                const ALIGNMENT: usize = $alignment;
+                const SIZE: usize = mem::size_of::<$scalar>();
+
+                fn write(&self, out: &mut [u8]) {
+                    unsafe {
+                        ptr::copy_nonoverlapping(self as *const $scalar as *const u8,
+                                                 out.as_mut_ptr(),
+                                                 mem::size_of::<$scalar>());
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// A GLSL `bool` is 4 bytes large, so it is pushed as a `u32`.
+impl_std430_scalar!{ f32: 4, i32: 4, u32: 4, f64: 8, i64: 8, u64: 8 }
+
+// The stride of an array is the size of its element, rounded up to the alignment of the element.
+macro_rules! impl_std430_array {
+    ( $( $len:expr ),* ) => {
+        $(
+            impl<T: Std430> Std430 for [T; $len] {
+                const ALIGNMENT: usize = T::ALIGNMENT;
+                const SIZE: usize = $len * ((T::SIZE + T::ALIGNMENT - 1) / T::ALIGNMENT *
+                                            T::ALIGNMENT);
+
+                fn write(&self, out: &mut [u8]) {
+                    let stride = align_up(T::SIZE, T::ALIGNMENT);
+                    for (index, element) in self.iter().enumerate() {
+                        let start = index * stride;
+                        element.write(&mut out[start..start + T::SIZE]);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_std430_array!{
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+    17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32
+}
+
+macro_rules! std430_vectors {
+    ( $( $(#[$attr:meta])* $vector:ident: $len:expr, $alignment:expr; )* ) => {
+        $(
+            $(#[$attr])*
+            #[repr(C)]
+            #[derive(Debug, Copy, Clone, Default, PartialEq)]
+            pub struct $vector<T>(pub [T; $len]);
+
+            impl<T: Std430> Std430 for $vector<T> {
+                const ALIGNMENT: usize = $alignment * T::ALIGNMENT;
+                const SIZE: usize = $len * T::SIZE;
+
+                fn write(&self, out: &mut [u8]) {
+                    for (index, component) in self.0.iter().enumerate() {
+                        let start = index * T::SIZE;
+                        component.write(&mut out[start..start + T::SIZE]);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+std430_vectors!{
+    /// A GLSL `vec2`, `ivec2`, `uvec2` or `dvec2` with the components of type `T`.
+    Vec2: 2, 2;
+    /// A GLSL `vec3`, `ivec3`, `uvec3` or `dvec3`, which is aligned like a `Vec4`.
+    Vec3: 3, 4;
+    /// A GLSL `vec4`, `ivec4`, `uvec4` or `dvec4`.
+    Vec4: 4, 4;
+}
+
+macro_rules! std430_matrices {
+    ( $( $(#[$attr:meta])* $matrix:ident: [$column:ident; $len:expr]; )* ) => {
+        $(
+            $(#[$attr])*
+            #[repr(C)]
+            #[derive(Debug, Copy, Clone, Default, PartialEq)]
+            pub struct $matrix(pub [[f32; $len]; $len]);
+
+            impl Std430 for $matrix {
+                const ALIGNMENT: usize = <$column<f32> as Std430>::ALIGNMENT;
+                const SIZE: usize = <[$column<f32>; $len] as Std430>::SIZE;
+
+                fn write(&self, out: &mut [u8]) {
+                    let mut columns = [$column([0.0; $len]); $len];
+                    for (column, values) in columns.iter_mut().zip(self.0.iter()) {
+                        column.0 = *values;
+                    }
+                    columns.write(out);
+                }
+            }
+        )*
+    };
+}
+
+std430_matrices!{
+    /// A GLSL `mat2` given as its columns, which are laid out like an array of `Vec2<f32>`.
+    Mat2: [Vec2; 2];
+    /// A GLSL `mat3` given as its columns, which are laid out like an array of `Vec3<f32>`, so
+    /// every column is padded to 16 bytes.
+    Mat3: [Vec3; 3];
+    /// A GLSL `mat4` given as its columns, which are laid out like an array of `Vec4<f32>`.
+    Mat4: [Vec4; 4];
+}
+
+/// Computes the offsets of the members of a struct with the `std430` layout. `std430_struct!`
+/// implements `Std430` with it, and `std430_buffer_struct!` checks the Rust layout against it.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::push_constants::{Std430Layout, Vec2, Vec3};
+///
+/// // struct { vec3 position; float intensity; uvec2 range[2]; }
+/// let mut layout = Std430Layout::new();
+/// assert_eq!(layout.member::<Vec3<f32>>(), 0);
+/// assert_eq!(layout.member::<f32>(), 12);
+/// assert_eq!(layout.member::<[Vec2<u32>; 2]>(), 16);
+/// assert_eq!((layout.alignment(), layout.size()), (16, 32));
+/// # }
+/// ```
+///
+#[derive(Debug, Copy, Clone)]
+pub struct Std430Layout {
+    end: usize,
+    alignment: usize,
+}
+
+impl Std430Layout {
+    /// Returns the layout of a struct without members.
+    pub fn new() -> Std430Layout {
+        Std430Layout {
+            end: 0,
+            alignment: 1,
+        }
+    }
+
+    /// Appends a member of type `T` and returns its offset.
+    pub fn member<T: Std430>(&mut self) -> usize {
+        let offset = align_up(self.end, T::ALIGNMENT);
+        self.end = offset + T::SIZE;
+        self.alignment = cmp::max(self.alignment, T::ALIGNMENT);
+        offset
+    }
+
+    /// Returns the alignment of the struct, which is the largest alignment of its members.
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Returns the size of the struct, which is padded to a multiple of its alignment.
+    pub fn size(&self) -> usize {
+        align_up(self.end, self.alignment)
+    }
+}
+
+impl Default for Std430Layout {
+    fn default() -> Std430Layout {
+        Std430Layout::new()
+    }
+}
+
+/// The extent of consecutive values with the `std430` layout, which is computed from their types
+/// at compile time, unlike a `Std430Layout`. `std430_struct!` computes the size of a struct with
+/// it, and `pipeline!` the size of its push constant block.
+///
+/// The extents are built from `()`, which is empty, and the types `Words`, `Then` and `Union`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::push_constants::{Std430Extent, Then, Union, Vec2, Vec3, Words};
+///
+/// // struct { vec3 position; float intensity; uvec2 range[2]; }
+/// type Light = Then<Then<Then<(), Vec3<f32>>, f32>, [Vec2<u32>; 2]>;
+/// assert_eq!((Light::END, Light::ALIGNMENT, Light::SIZE), (32, 16, 32));
+///
+/// // layout(offset = 4) float scale; vec3 offset;
+/// type Range = Then<Then<Words<[u32; 1]>, f32>, Vec3<f32>>;
+/// assert_eq!(Range::END, 28);
+/// assert_eq!(<Union<Range, Words<[u32; 10]>>>::END, 40);
+/// # }
+/// ```
+///
+pub trait Std430Extent {
+    /// The end of the last value in bytes.
+    const END: usize;
+
+    /// The largest alignment of the values.
+    const ALIGNMENT: usize;
+
+    /// The size of a struct of the values, which is padded to a multiple of its alignment.
+    const SIZE: usize = (Self::END + Self::ALIGNMENT - 1) / Self::ALIGNMENT * Self::ALIGNMENT;
+}
+
+impl Std430Extent for () {
+    const END: usize = 0;
+    const ALIGNMENT: usize = 1;
+}
+
+/// The bytes of the array `S` of `u32`, e.g. `Words<[u32; 4]>` for the bytes before a push
+/// constant range at the offset 16.
+pub struct Words<S>(PhantomData<S>);
+
+impl<S: PushConstantStorage> Std430Extent for Words<S> {
+    const END: usize = 4 * S::LEN;
+    const ALIGNMENT: usize = 1;
+}
+
+/// The values of the extent `P`, followed by a value of type `T` at the next offset which is a
+/// multiple of its alignment.
+pub struct Then<P, T>(PhantomData<(P, T)>);
+
+// The constants cannot call `align_up` or `cmp::max`, so they are written out.
+impl<P: Std430Extent, T: Std430> Std430Extent for Then<P, T> {
+    const END: usize = (P::END + T::ALIGNMENT - 1) / T::ALIGNMENT * T::ALIGNMENT + T::SIZE;
+    const ALIGNMENT: usize = (P::ALIGNMENT > T::ALIGNMENT) as usize * P::ALIGNMENT +
+                             (P::ALIGNMENT <= T::ALIGNMENT) as usize * T::ALIGNMENT;
+}
+
+/// The values of both extents `P` and `Q`, e.g. of two push constant ranges, which end at the
+/// larger end of the two.
+pub struct Union<P, Q>(PhantomData<(P, Q)>);
+
+impl<P: Std430Extent, Q: Std430Extent> Std430Extent for Union<P, Q> {
+    const END: usize = (P::END > Q::END) as usize * P::END + (P::END <= Q::END) as usize * Q::END;
+    const ALIGNMENT: usize = (P::ALIGNMENT > Q::ALIGNMENT) as usize * P::ALIGNMENT +
+                             (P::ALIGNMENT <= Q::ALIGNMENT) as usize * Q::ALIGNMENT;
+}
+
+/// Declares a struct and implements `Std430` for it, so it can be used as a push constant like
+/// a GLSL struct with the same members. The members must implement `Std430` themselves.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::push_constants::{PushConstantBlock, Vec3};
+///
+/// std430_struct!{
+///     /// struct Light { vec3 position; float intensity; };
+///     #[derive(Debug, Copy, Clone)]
+///     struct Light {
+///         position: Vec3<f32>,
+///         intensity: f32,
+///     }
+/// }
+///
+/// let light = Light { position: Vec3([1.0, 2.0, 3.0]), intensity: 0.5 };
+/// let mut block = PushConstantBlock::new();
+/// {
+///     let mut range = block.range(0, None);
+///     range.push(7u32);
+///     range.push([light; 2]);
+/// }
+///
+/// // The lights are aligned to 16 bytes, the intensity is packed behind the position. The
+/// // little-endian bytes of 1.0 are `[0, 0, 0x80, 0x3f]`, those of 0.5 are `[0, 0, 0, 0x3f]`.
+/// assert_eq!(&block.as_bytes()[16..20], &[0, 0, 0x80, 0x3f]);
+/// assert_eq!(&block.as_bytes()[28..32], &[0, 0, 0, 0x3f]);
+/// assert_eq!(&block.as_bytes()[44..48], &[0, 0, 0, 0x3f]);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! std430_struct {
+    { $(#[$attr:meta])* pub struct $name:ident { $( $members:tt )* } } => {
+        std430_struct!{ __members__ plain [$(#[$attr])*] (pub) $name [] $( $members )* }
+    };
+    { $(#[$attr:meta])* struct $name:ident { $( $members:tt )* } } => {
+        std430_struct!{ __members__ plain [$(#[$attr])*] () $name [] $( $members )* }
+    };
+    // The members are normalized one by one, so each can be public or not, and then declared
+    // together. A `buffer` struct also gets the layout check of `std430_buffer_struct!`.
+    { __members__ $kind:ident [$( $attr:tt )*] ($( $vis:tt )*) $name:ident
+      [$( [$( $member_attr:tt )*] ($( $member_vis:tt )*) $member:ident : $member_type:ty, )*] } => {
+        $( $attr )*
+        $( $vis )* struct $name {
+            $( $( $member_attr )* $( $member_vis )* $member: $member_type, )*
+        }
+
+        std430_struct!{ __impl__ $name { $( $member: $member_type ),* } }
+        std430_struct!{ __check__ $kind $name { $( $member: $member_type ),* } }
+    };
+    { __members__ $kind:ident $attrs:tt $vis:tt $name:ident [$( $done:tt )*]
+      $(#[$member_attr:meta])* pub $member:ident : $member_type:ty, $( $rest:tt )* } => {
+        std430_struct!{
+            __members__ $kind $attrs $vis $name
+            [$( $done )* [$(#[$member_attr])*] (pub) $member: $member_type,] $( $rest )*
+        }
+    };
+    { __members__ $kind:ident $attrs:tt $vis:tt $name:ident [$( $done:tt )*]
+      $(#[$member_attr:meta])* pub $member:ident : $member_type:ty } => {
+        std430_struct!{
+            __members__ $kind $attrs $vis $name
+            [$( $done )* [$(#[$member_attr])*] (pub) $member: $member_type,]
+        }
+    };
+    { __members__ $kind:ident $attrs:tt $vis:tt $name:ident [$( $done:tt )*]
+      $(#[$member_attr:meta])* $member:ident : $member_type:ty, $( $rest:tt )* } => {
+        std430_struct!{
+            __members__ $kind $attrs $vis $name
+            [$( $done )* [$(#[$member_attr])*] () $member: $member_type,] $( $rest )*
+        }
+    };
+    { __members__ $kind:ident $attrs:tt $vis:tt $name:ident [$( $done:tt )*]
+      $(#[$member_attr:meta])* $member:ident : $member_type:ty } => {
+        std430_struct!{
+            __members__ $kind $attrs $vis $name
+            [$( $done )* [$(#[$member_attr])*] () $member: $member_type,]
+        }
+    };
+    { __check__ plain $name:ident { $( $members:tt )* } } => {};
+    { __check__ buffer $name:ident { $( $members:tt )* } } => {
+        std430_buffer_struct!{ __check__ $name { $( $members )* } }
+    };
+    { __impl__ $name:ident { $( $member:ident : $member_type:ty ),* } } => {
+        impl $crate::push_constants::Std430 for $name {
+            const ALIGNMENT: usize = <std430_struct!(__extent__ (); $( $member_type ),*) as
+                                      $crate::push_constants::Std430Extent>::ALIGNMENT;
+            const SIZE: usize = <std430_struct!(__extent__ (); $( $member_type ),*) as
+                                 $crate::push_constants::Std430Extent>::SIZE;
+
+            fn write(&self, out: &mut [u8]) {
+                use $crate::push_constants::Std430;
+                let mut layout = $crate::push_constants::Std430Layout::new();
+                $(
+                    let offset = layout.member::<$member_type>();
+                    let size = <$member_type as Std430>::SIZE;
+                    self.$member.write(&mut out[offset..offset + size]);
+                )*
+            }
+        }
+    };
+    // Builds the `Std430Extent` of the members, `Then<Then<(), A>, B>` for the members `A, B`.
+    (__extent__ $extent:ty;) => { $extent };
+    (__extent__ $extent:ty; $member_type:ty $(, $rest:ty )*) => {
+        std430_struct!(__extent__ $crate::push_constants::Then<$extent, $member_type>;
+                       $( $rest ),*)
+    };
+}
+
+/// Declares a `#[repr(C)]` struct like `std430_struct!`, checks at compile time that the struct
+/// has its `std430` size, and declares a test which checks that every member is at its `std430`
+/// offset. The struct can then be the element type of a storage buffer of a GLSL struct with the
+/// same members.
+///
+/// A struct of the wrong size fails to compile with a mismatch of the array sizes `0` and `1`.
+/// The test has the name of the struct and calls `check_std430_layout()`, which the macro also
+/// declares, so the offsets can be checked before the first buffer of the struct is created.
+/// Rust does not align the vector types of this module to 16 bytes like GLSL does, so a
+/// mismatch usually means that the struct needs explicit padding members. The padding which
+/// GLSL does not insert, e.g. behind a `vec3` which is followed by a scalar, is never needed.
+///
+/// # Example
+///
+/// ```
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::mem;
+/// use vulkanology::push_constants::Vec3;
+///
+/// std430_buffer_struct!{
+///     /// struct Particle { float mass; vec3 position; vec3 velocity; uint id; };
+///     #[derive(Debug, Copy, Clone)]
+///     struct Particle {
+///         mass: f32,
+///         _padding: [f32; 3],
+///         position: Vec3<f32>,
+///         _padding_1: f32,
+///         velocity: Vec3<f32>,
+///         id: u32,
+///     }
+/// }
+///
+/// Particle::check_std430_layout();
+/// assert_eq!(mem::size_of::<Particle>(), 48);
+/// # }
+/// ```
+///
+/// Without the first padding member, the position would be at the offset 4 instead of 16, and
+/// the struct would take 16 instead of 32 bytes:
+///
+/// ```compile_fail
+/// # #[macro_use]
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::push_constants::Vec3;
+///
+/// std430_buffer_struct!{
+///     struct Particle {
+///         mass: f32,
+///         position: Vec3<f32>,
+///     }
+/// }
+///
+/// Particle::check_std430_layout();
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! std430_buffer_struct {
+    { $(#[$attr:meta])* pub struct $name:ident { $( $members:tt )* } } => {
+        std430_struct!{ __members__ buffer [$(#[$attr])* #[repr(C)]] (pub) $name [] $( $members )* }
+    };
+    { $(#[$attr:meta])* struct $name:ident { $( $members:tt )* } } => {
+        std430_struct!{ __members__ buffer [$(#[$attr])* #[repr(C)]] () $name [] $( $members )* }
+    };
+    { __check__ $name:ident { $( $member:ident : $member_type:ty ),* } } => {
+        impl $name {
+            /// Checks that every member of the struct is at its `std430` offset, and that the
+            /// struct has its `std430` size.
+            ///
+            /// # Panics
+            ///
+            /// If a member needs padding before it, or the struct needs padding at the end.
+            #[allow(dead_code)]
+            pub fn check_std430_layout() {
+                // The offsets are only known at runtime, but the size is checked at compile
+                // time: the array has one element if the sizes differ.
+                let _: [(); 0] = [(); (::std::mem::size_of::<$name>() !=
+                                       <$name as $crate::push_constants::Std430>::SIZE) as usize];
+                let value: $name = unsafe { ::std::mem::zeroed() };
+                let start = &value as *const $name as usize;
+                let mut layout = $crate::push_constants::Std430Layout::new();
+                $(
+                    let offset = &value.$member as *const $member_type as usize - start;
+                    let expected = layout.member::<$member_type>();
+                    if offset != expected {
+                        panic!("The member `{}` of `{}` is at the offset {} instead of its std430 \
+                                offset {}, it needs padding before it.",
+                               stringify!($member),
+                               stringify!($name),
+                               offset,
+                               expected);
+                    }
+                )*
+                let size = ::std::mem::size_of::<$name>();
+                if size != layout.size() {
+                    panic!("The size of `{}` is {} instead of its std430 size {}, it needs \
+                            padding at the end.",
+                           stringify!($name),
+                           size,
+                           layout.size());
+                }
+            }
+        }
+
+        #[test]
+        #[allow(non_snake_case)]
+        fn $name() {
+            $name::check_std430_layout();
+        }
+    };
+}
@@ -0,0 +1,153 @@
+//! This module assigns the queues requested from `device_and_queue!` to queue families.
+//!
+//! Every queue is taken from the most specialized family which still has a queue left, so tests
+//! get queues of different families where the device has them, and otherwise several queues of a
+//! shared family:
+//!
+//! - `compute` takes a queue of any family which supports compute operations.
+//! - `compute_only` prefers the families which support compute, but not graphics operations,
+//!   which run asynchronously to the graphics queue on most GPUs.
+//! - `transfer` prefers the families which only support transfer operations, like the DMA
+//!   engines of discrete GPUs, followed by the compute-only families. Every other family
+//!   implicitly supports transfers as well.
+//!
+//! The family of a queue is `queue.family().id()`. Queues of different families require
+//! ownership transfers of exclusive resources, while queues of the same family are only
+//! separate submission targets.
+//!
+//! Ownership transfers cannot be recorded with `vulkano` 0.3, so resources used by queues of
+//! several families must be shared concurrently, by creating them with all of these families:
+//!
+//! ```ignore
+//! let (ref device, (compute, transfer)) =
+//!     device_and_queue!(physical_device, queues: [compute, transfer]);
+//! let families = [compute.family(), transfer.family()];
+//! let buffer = CpuAccessibleBuffer::<[u32]>::from_iter(device,
+//!                                                      &Usage::all(),
+//!                                                      families.iter().cloned(),
+//!                                                      data.iter().cloned())
+//!     .expect("Failed to create a shared buffer.");
+//! ```
+//!
+//! The families of concurrently shared resources must be distinct, so if both queues are of the
+//! same family, the family is given once and the buffer stays exclusive.
+
+/// The kind of a requested queue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QueueKind {
+    /// A queue which supports compute operations.
+    Compute,
+    /// A queue which supports compute operations, preferably without graphics operations.
+    ComputeOnly,
+    /// A queue for transfers, preferably of a dedicated family.
+    Transfer,
+}
+
+/// The capabilities of a queue family.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct QueueFamily {
+    /// Whether the family supports graphics operations.
+    pub graphics: bool,
+    /// Whether the family supports compute operations.
+    pub compute: bool,
+    /// Whether the family explicitly supports transfer operations.
+    pub transfer: bool,
+    /// The number of queues of the family.
+    pub queues: usize,
+}
+
+impl QueueFamily {
+    /// Returns how well the family fits the kind `kind`, lower is better, or `None` if it cannot
+    /// provide a queue of that kind.
+    fn rank(&self, kind: QueueKind) -> Option<u32> {
+        match kind {
+            QueueKind::Compute if self.compute => Some(0),
+            QueueKind::ComputeOnly if self.compute => Some(if self.graphics { 1 } else { 0 }),
+            QueueKind::Transfer => {
+                Some(match (self.graphics, self.compute) {
+                    (false, false) if self.transfer => 0,
+                    (false, true) => 1,
+                    _ => 2,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether the family `family` only approximates the kind `kind`, e.g. a `compute_only`
+/// queue of a family which also supports graphics operations.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::queues::{is_fallback, QueueFamily, QueueKind};
+///
+/// let family = QueueFamily { graphics: true, compute: true, transfer: true, queues: 1 };
+/// assert!(!is_fallback(&family, QueueKind::Compute));
+/// assert!(is_fallback(&family, QueueKind::ComputeOnly));
+/// # }
+/// ```
+///
+pub fn is_fallback(family: &QueueFamily, kind: QueueKind) -> bool {
+    family.rank(kind) != Some(0)
+}
+
+/// Returns the index of the family in `families` for every queue in `kinds`. Among families of
+/// the same rank, the first one with a queue left is used.
+///
+/// # Panics
+///
+/// If the families do not have enough queues for the requested kinds.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::queues::{assign, QueueFamily, QueueKind};
+///
+/// // The families of a typical discrete GPU.
+/// let families = [
+///     QueueFamily { graphics: true, compute: true, transfer: true, queues: 16 },
+///     QueueFamily { graphics: false, compute: false, transfer: true, queues: 2 },
+///     QueueFamily { graphics: false, compute: true, transfer: true, queues: 8 },
+/// ];
+/// let kinds = [QueueKind::Compute, QueueKind::ComputeOnly, QueueKind::Transfer];
+/// assert_eq!(assign(&families, &kinds), [0, 2, 1]);
+///
+/// // An integrated GPU with a single family.
+/// let families = [QueueFamily { graphics: true, compute: true, transfer: true, queues: 1 }];
+/// assert_eq!(assign(&families, &[QueueKind::Compute]), [0]);
+/// # }
+/// ```
+///
+pub fn assign(families: &[QueueFamily], kinds: &[QueueKind]) -> Vec<usize> {
+    let mut used = vec![0; families.len()];
+    kinds.iter()
+        .map(|&kind| {
+            let family = families.iter()
+                .enumerate()
+                .filter(|&(index, family)| used[index] < family.queues)
+                .filter_map(|(index, family)| family.rank(kind).map(|rank| (rank, index)))
+                .min()
+                .map(|(_, index)| index);
+            match family {
+                Some(index) => {
+                    used[index] += 1;
+                    index
+                }
+                None => {
+                    panic!("Couldn't find a queue family with a queue left for the `{:?}` queue \
+                            of the queues {:?}.",
+                           kind,
+                           kinds)
+                }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,258 @@
+//! This module generates the contents of buffers with [quickcheck], so the properties of a
+//! shader can be tested on random inputs, which quickcheck shrinks to a minimal failing input.
+//! It requires the optional `quickcheck` feature.
+//!
+//! A `Buffer<T, S>` holds the generated elements of the type `T`, whose number is bounded by
+//! the size hint `S`. The elements are scalars or the vectors and matrices of the [`glsl`]
+//! module. `check` runs a property on `tests` generated inputs, and every `pipeline!` of the
+//! property reuses the same instance, device and queue, as with `context: shared`, so the cases
+//! do not create a device each:
+//!
+//! ```ignore
+//! extern crate vulkano;
+//! #[macro_use]
+//! extern crate vulkanology;
+//!
+//! use vulkanology::quickcheck_adapter::{check, Buffer, SizeHint};
+//!
+//! /// Whole workgroups of 64 invocations.
+//! struct Workgroups;
+//!
+//! impl SizeHint for Workgroups {
+//!     const MIN: usize = 64;
+//!     const MAX: usize = 64 * 256;
+//!     const MULTIPLE: usize = 64;
+//! }
+//!
+//! fn doubles(input: Buffer<u32, Workgroups>) -> bool {
+//!     pipeline!{
+//!         // ...
+//!         workgroup_count: [(input.len() / 64) as u32, 1, 1],
+//!         buffers: {
+//!             data: [u32] from input,
+//!             result: [u32; input.len()]
+//!         },
+//!         execution_command: execute_shader
+//!     };
+//!     execute_shader();
+//!     let result = read_buffer!(result);
+//!     input.iter().zip(result.iter()).all(|(&x, &y)| y == x.wrapping_mul(2))
+//! }
+//!
+//! #[test]
+//! fn test_doubles() {
+//!     check(20, doubles as fn(Buffer<u32, Workgroups>) -> bool);
+//! }
+//! ```
+//!
+//! The generated lengths are multiples of `S::MULTIPLE` from `S::MIN` to `S::MAX`, and at most
+//! the size of the quickcheck generator times the multiple, which can be set with the
+//! `QUICKCHECK_GENERATOR_SIZE` environment variable. A failing buffer is shrunk to shorter
+//! lengths which obey the same bounds, and element by element.
+//!
+//! [quickcheck]: https://github.com/BurntSushi/quickcheck
+//! [`glsl`]: ../glsl/index.html
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use quickcheck::{Arbitrary, Gen, QuickCheck, Testable};
+
+use glsl::{ArrayDVec2, ArrayDVec3, ArrayDVec4, ArrayMat2, ArrayMat3, ArrayMat4, ArrayVec2,
+           ArrayVec3, ArrayVec4};
+use shared;
+
+/// The bounds of the number of elements of a generated `Buffer`.
+pub trait SizeHint {
+    /// The smallest number of elements, which must not be 0, as buffers cannot be empty.
+    const MIN: usize;
+    /// The largest number of elements.
+    const MAX: usize;
+    /// The number of elements is a multiple of this number, e.g. of the local size.
+    const MULTIPLE: usize = 1;
+}
+
+/// A size hint for buffers of at least one element, up to the size of the generator.
+#[derive(Debug, Copy, Clone)]
+pub struct AnySize;
+
+impl SizeHint for AnySize {
+    const MIN: usize = 1;
+    const MAX: usize = usize::max_value();
+}
+
+/// The generated contents of a buffer, whose length is bounded by `S`. Dereferences to the
+/// elements, so it can be the data of a buffer of `pipeline!` with `from`.
+pub struct Buffer<T, S = AnySize> {
+    /// The elements of the buffer.
+    pub data: Vec<T>,
+    size_hint: PhantomData<fn() -> S>,
+}
+
+impl<T, S: SizeHint> Buffer<T, S> {
+    /// Returns the buffer of the elements `data`.
+    ///
+    /// # Panics
+    ///
+    /// If the number of elements is not within the bounds of `S`.
+    pub fn new(data: Vec<T>) -> Buffer<T, S> {
+        if data.len() < S::MIN || data.len() > S::MAX || data.len() % S::MULTIPLE != 0 {
+            panic!("The buffer has {} elements, expected a multiple of {} from {} to {}.",
+                   data.len(),
+                   S::MULTIPLE,
+                   S::MIN,
+                   S::MAX);
+        }
+        Buffer {
+            data: data,
+            size_hint: PhantomData,
+        }
+    }
+
+    /// Returns the bounds of the length in multiples of `S::MULTIPLE`.
+    fn multiples() -> (usize, usize) {
+        let min = (S::MIN + S::MULTIPLE - 1) / S::MULTIPLE;
+        let max = S::MAX / S::MULTIPLE;
+        if min == 0 || min > max {
+            panic!("The size hint allows no buffer lengths: a multiple of {} from {} to {}.",
+                   S::MULTIPLE,
+                   S::MIN,
+                   S::MAX);
+        }
+        (min, max)
+    }
+}
+
+impl<T, S> Deref for Buffer<T, S> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T: Clone, S> Clone for Buffer<T, S> {
+    fn clone(&self) -> Buffer<T, S> {
+        Buffer {
+            data: self.data.clone(),
+            size_hint: PhantomData,
+        }
+    }
+}
+
+impl<T: Debug, S> Debug for Buffer<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.data.fmt(f)
+    }
+}
+
+impl<T: Arbitrary, S: SizeHint + 'static> Arbitrary for Buffer<T, S> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Buffer<T, S> {
+        let (min, max) = Self::multiples();
+        let max = max.min(min.max(g.size()));
+        let multiples = min + (g.next_u64() % (max - min + 1) as u64) as usize;
+        Buffer::new((0..multiples * S::MULTIPLE).map(|_| T::arbitrary(g)).collect())
+    }
+
+    fn shrink(&self) -> Box<Iterator<Item = Buffer<T, S>>> {
+        let (min, _) = Self::multiples();
+        let multiples = self.data.len() / S::MULTIPLE;
+        // The shortest length first, then half of the length, then one multiple less.
+        let mut lengths = vec![min, multiples / 2, multiples.saturating_sub(1)];
+        lengths.retain(|&length| length >= min && length < multiples);
+        lengths.dedup();
+        let data = self.data.clone();
+        let shorter = lengths.into_iter()
+            .map(move |length| Buffer::new(data[..length * S::MULTIPLE].to_vec()))
+            .collect::<Vec<_>>();
+        let data = self.data.clone();
+        let elements = (0..data.len()).filter_map(move |index| {
+            data[index].shrink().next().map(|element| {
+                let mut data = data.clone();
+                data[index] = element;
+                Buffer::new(data)
+            })
+        });
+        Box::new(shorter.into_iter().chain(elements))
+    }
+}
+
+macro_rules! arbitrary_vectors {
+    ( $( $vector:ident: $len:expr ),* ) => {
+        $(
+            impl<T: Arbitrary + Copy> Arbitrary for $vector<T> {
+                fn arbitrary<G: Gen>(g: &mut G) -> $vector<T> {
+                    let mut components = [T::arbitrary(g); $len];
+                    for component in components.iter_mut().skip(1) {
+                        *component = T::arbitrary(g);
+                    }
+                    $vector(components)
+                }
+
+                fn shrink(&self) -> Box<Iterator<Item = $vector<T>>> {
+                    let vector = *self;
+                    Box::new((0..$len).flat_map(move |index| {
+                        vector.0[index].shrink().map(move |component| {
+                            let mut shrunk = vector;
+                            shrunk.0[index] = component;
+                            shrunk
+                        })
+                    }))
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_vectors!(ArrayVec2: 2, ArrayVec3: 3, ArrayVec4: 4,
+                   ArrayDVec2: 2, ArrayDVec3: 3, ArrayDVec4: 4);
+
+macro_rules! arbitrary_matrices {
+    ( $( $matrix:ident: [$column:ident; $len:expr] ),* ) => {
+        $(
+            impl Arbitrary for $matrix {
+                fn arbitrary<G: Gen>(g: &mut G) -> $matrix {
+                    let mut columns = [$column::<f32>::arbitrary(g); $len];
+                    for column in columns.iter_mut().skip(1) {
+                        *column = $column::arbitrary(g);
+                    }
+                    $matrix(columns)
+                }
+
+                fn shrink(&self) -> Box<Iterator<Item = $matrix>> {
+                    let matrix = *self;
+                    Box::new((0..$len).flat_map(move |index| {
+                        matrix.0[index].shrink().map(move |column| {
+                            let mut shrunk = matrix;
+                            shrunk.0[index] = column;
+                            shrunk
+                        })
+                    }))
+                }
+            }
+        )*
+    };
+}
+
+arbitrary_matrices!(ArrayMat2: [ArrayVec2; 2],
+                    ArrayMat3: [ArrayVec3; 3],
+                    ArrayMat4: [ArrayVec4; 4]);
+
+/// Checks the property `property` on `tests` generated inputs with quickcheck, in which every
+/// `pipeline!` reuses a shared context, see the [`shared`] module. The number of tests can be
+/// overridden with the `QUICKCHECK_TESTS` environment variable.
+///
+/// # Panics
+///
+/// If the property fails, with the shrunk input.
+///
+/// [`shared`]: ../shared/index.html
+pub fn check<A: Testable>(tests: u64, property: A) {
+    let tests = ::std::env::var("QUICKCHECK_TESTS")
+        .ok()
+        .and_then(|tests| tests.parse().ok())
+        .unwrap_or(tests);
+    let _scope = shared::request_scope();
+    QuickCheck::new().tests(tests).quickcheck(property);
+}
@@ -0,0 +1,127 @@
+//! This module contains the table of known driver quirks which affect the comparison helpers.
+//!
+//! Some drivers compute results less precisely than the Vulkan specification requires. Instead of
+//! widening the tolerance of every affected test, a quirk widens the tolerance of the comparison
+//! helpers, or disables them, on the affected drivers only. `pipeline!` records the device of the
+//! current thread with `set_device`, and the helpers in [`compare`] and [`matrix`] look up the
+//! quirks of the device. Every applied quirk is noted on stderr and in the report of the
+//! [`harness`].
+//!
+//! The built-in table is empty; quirks of the drivers in use are added with `register`.
+//!
+//! [`compare`]: ../compare/index.html
+//! [`matrix`]: ../matrix/index.html
+//! [`harness`]: ../harness/index.html
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::Mutex;
+
+use harness;
+
+/// A known precision bug of a driver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quirk {
+    /// The PCI vendor id of the affected devices.
+    pub vendor_id: u32,
+    /// The affected driver versions, as reported by the device.
+    pub driver_versions: Range<u32>,
+    /// The names of the affected comparison helpers, e.g. `"assert_approx_eq"`. If empty, all
+    /// helpers are affected.
+    pub checks: &'static [&'static str],
+    /// The adjustment of the affected helpers.
+    pub effect: Effect,
+    /// The reason for the quirk, which is noted whenever it is applied.
+    pub reason: &'static str,
+}
+
+/// The adjustment of a comparison helper by a quirk.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Effect {
+    /// The tolerance is multiplied by the factor.
+    WidenTolerance(f32),
+    /// The comparison is skipped.
+    Disable,
+}
+
+/// The built-in quirks.
+pub const QUIRKS: &'static [Quirk] = &[];
+
+thread_local! {
+    static DEVICE: Cell<Option<(u32, u32)>> = Cell::new(None);
+}
+
+lazy_static! {
+    /// The quirks which are in effect, starting with the built-in ones.
+    static ref REGISTRY: Mutex<Vec<Quirk>> = Mutex::new(QUIRKS.to_vec());
+}
+
+/// Adds `quirk` to the quirks which are in effect for all threads.
+pub fn register(quirk: Quirk) {
+    REGISTRY.lock().unwrap().push(quirk);
+}
+
+/// Sets the device of the current thread, given by its PCI vendor id and its driver version.
+pub fn set_device(vendor_id: u32, driver_version: u32) {
+    DEVICE.with(|device| device.set(Some((vendor_id, driver_version))));
+}
+
+/// Returns the tolerance of the comparison helper `check` on the device of the current thread,
+/// or `None` if the comparison is disabled by a quirk.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::quirks::{register, set_device, tolerance, Effect, Quirk};
+///
+/// register(Quirk {
+///     vendor_id: 0x1234,
+///     driver_versions: 0..100,
+///     checks: &["assert_approx_eq"],
+///     effect: Effect::WidenTolerance(4.0),
+///     reason: "`inversesqrt` is only accurate to 8 ULP.",
+/// });
+///
+/// assert_eq!(tolerance("assert_approx_eq", 1e-6), Some(1e-6));
+/// set_device(0x1234, 42);
+/// assert_eq!(tolerance("assert_approx_eq", 1e-6), Some(4e-6));
+/// assert_eq!(tolerance("assert_atomic_sum", 1e-6), Some(1e-6));
+/// # }
+/// ```
+///
+pub fn tolerance(check: &str, tolerance: f32) -> Option<f32> {
+    let (vendor_id, driver_version) = match DEVICE.with(|device| device.get()) {
+        Some(device) => device,
+        None => return Some(tolerance),
+    };
+
+    let registry = REGISTRY.lock().unwrap();
+    let mut result = Some(tolerance);
+    for quirk in registry.iter() {
+        let applies = quirk.vendor_id == vendor_id &&
+                      quirk.driver_versions.start <= driver_version &&
+                      driver_version < quirk.driver_versions.end &&
+                      (quirk.checks.is_empty() || quirk.checks.contains(&check));
+        if !applies {
+            continue;
+        }
+
+        let note = match quirk.effect {
+            Effect::WidenTolerance(factor) => {
+                result = result.map(|tolerance| tolerance * factor);
+                format!("`{}` uses a {} times wider tolerance: {}", check, factor, quirk.reason)
+            }
+            Effect::Disable => {
+                result = None;
+                format!("`{}` is disabled: {}", check, quirk.reason)
+            }
+        };
+        let _ = writeln!(io::stderr(), "note: {}", note);
+        harness::record_note(note);
+    }
+    result
+}
@@ -0,0 +1,463 @@
+//! This module contains statistical tests of the quality of random numbers, so the output of a
+//! PRNG shader can be validated as a distribution and not only bit by bit against a CPU port of
+//! the generator, which would share its flaws:
+//!
+//! ```ignore
+//! let output: ReadLock<[u32]> = read_buffer!(result);
+//! randomness::assert_random(&output, randomness::DEFAULT_SIGNIFICANCE);
+//! ```
+//!
+//! Every test computes a statistic of the buffer and its p-value, the probability that truly
+//! random numbers give a statistic at least as extreme. A p-value close to 0 means that the
+//! numbers deviate from the distribution, and a p-value close to 1 that they fit it too well to
+//! be random, e.g. the output of a counter. Truly random numbers give uniformly distributed
+//! p-values, so a test fails by chance with the probability of its significance level.
+//!
+//! `assert_random` runs a battery of tests on the raw bits of `u32` or `u64` outputs:
+//!
+//! - The chi-squared test of the histogram of the highest and of the lowest byte.
+//! - The Kolmogorov-Smirnov test of the outputs as floats from 0 to 1.
+//! - The frequency test of every bit, whose fraction of ones must be close to one half.
+//! - The serial test of non-overlapping pairs of consecutive outputs, which detects correlation
+//!   between them, e.g. between neighbouring invocations.
+//!
+//! `chi_squared`, `chi_squared_uniform` and `kolmogorov_smirnov` test any distribution, e.g.
+//! the floats of a Box-Muller transform against the CDF of the normal distribution.
+
+use std::f64;
+use std::fmt;
+
+use export::Scalar;
+use stats::histogram;
+
+/// The default significance level of `assert_random`. It is low, so a correct generator seldom
+/// fails a test run, while a flawed one fails the tests by many orders of magnitude.
+pub const DEFAULT_SIGNIFICANCE: f64 = 1e-4;
+
+/// The number of outputs which `assert_random` needs at least, so every bin of the tests
+/// expects five outputs.
+pub const MIN_BATTERY_LEN: usize = 2560;
+
+/// The outcome of a statistical test.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Outcome {
+    /// The name of the test.
+    pub name: &'static str,
+    /// The statistic of the test.
+    pub statistic: f64,
+    /// The probability that random numbers give a statistic at least as large.
+    pub p_value: f64,
+}
+
+impl Outcome {
+    /// Returns whether the p-value is outside of the two-sided significance level
+    /// `significance`, i.e. smaller than half of it or larger than one minus half of it.
+    pub fn fails(&self, significance: f64) -> bool {
+        !(self.p_value >= significance / 2.0 && self.p_value <= 1.0 - significance / 2.0)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: statistic {:.4}, p-value {:.3e}", self.name, self.statistic, self.p_value)
+    }
+}
+
+/// Returns the natural logarithm of the gamma function of the positive `x`, with the Lanczos
+/// approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [0.99999999999980993,
+                                    676.5203681218851,
+                                    -1259.1392167224028,
+                                    771.32342877765313,
+                                    -176.61502916214059,
+                                    12.507343278686905,
+                                    -0.13857109526572012,
+                                    9.9843695780195716e-6,
+                                    1.5056327351493116e-7];
+    let x = x - 1.0;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    let t = x + 7.5;
+    0.5 * (2.0 * f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Returns the regularized upper incomplete gamma function `Q(a, x)`, with its series for small
+/// `x` and its continued fraction otherwise.
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..1000 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        1.0 - sum * prefactor
+    } else {
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        prefactor * h
+    }
+}
+
+/// Returns the probability that the chi-squared statistic of `degrees_of_freedom` degrees of
+/// freedom is at least `statistic`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::chi_squared_p_value;
+///
+/// // The critical value of one degree of freedom at a significance level of 5%.
+/// assert!((chi_squared_p_value(3.841, 1) - 0.05).abs() < 1e-4);
+/// # }
+/// ```
+///
+pub fn chi_squared_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    gamma_q(degrees_of_freedom as f64 / 2.0, statistic / 2.0).max(0.0).min(1.0)
+}
+
+/// Returns the Pearson chi-squared test of the counts `observed` against the distribution
+/// `expected`, which gives the probability of every bin. The expected counts are the
+/// probabilities times the total of the observed counts.
+///
+/// # Panics
+///
+/// If `observed` and `expected` do not have the same number of bins.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::chi_squared;
+///
+/// let fair = chi_squared(&[48, 52], &[0.5, 0.5]);
+/// assert!(fair.p_value > 0.5);
+/// let biased = chi_squared(&[30, 70], &[0.5, 0.5]);
+/// assert!(biased.p_value < 1e-4);
+/// # }
+/// ```
+///
+pub fn chi_squared(observed: &[usize], expected: &[f64]) -> Outcome {
+    if observed.len() != expected.len() {
+        panic!("The histogram has {} bins, but the distribution has {}.",
+               observed.len(),
+               expected.len());
+    }
+    let total = observed.iter().sum::<usize>() as f64;
+    let statistic = observed.iter()
+        .zip(expected)
+        .map(|(&observed, &probability)| {
+            let expected = probability * total;
+            let difference = observed as f64 - expected;
+            if expected > 0.0 {
+                difference * difference / expected
+            } else if observed > 0 {
+                f64::INFINITY
+            } else {
+                0.0
+            }
+        })
+        .sum::<f64>();
+    let bins = expected.iter().filter(|&&probability| probability > 0.0).count();
+    Outcome {
+        name: "chi-squared",
+        statistic: statistic,
+        p_value: chi_squared_p_value(statistic, bins.max(2) - 1),
+    }
+}
+
+/// Returns the chi-squared test of the uniform distribution of the elements of `data` from
+/// `min` to `max`, given their histogram of `bins` bins, see `stats::histogram`. The elements
+/// outside of the range are not counted.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::chi_squared_uniform;
+///
+/// let data = (0..10000u32).map(|i| i.wrapping_mul(2654435761)).collect::<Vec<_>>();
+/// let outcome = chi_squared_uniform(&data, 0.0, 4294967296.0, 16);
+/// // The multiplicative hash of a counter is far more uniform than random numbers.
+/// assert!(outcome.p_value > 0.9999);
+/// # }
+/// ```
+///
+pub fn chi_squared_uniform<T: Scalar>(data: &[T], min: f64, max: f64, bins: usize) -> Outcome {
+    chi_squared(&histogram(data, min, max, bins), &vec![1.0 / bins as f64; bins])
+}
+
+/// Returns the probability that the Kolmogorov-Smirnov statistic of `len` samples is at least
+/// `statistic`, with the asymptotic Kolmogorov distribution.
+fn kolmogorov_smirnov_p_value(statistic: f64, len: usize) -> f64 {
+    let root = (len as f64).sqrt();
+    let lambda = (root + 0.12 + 0.11 / root) * statistic;
+    let exponent = -2.0 * lambda * lambda;
+    let mut sum = 0.0;
+    let mut sign = 2.0;
+    let mut previous = 0.0f64;
+    for j in 1..101 {
+        let term = sign * (exponent * (j * j) as f64).exp();
+        sum += term;
+        if term.abs() <= 1e-3 * previous || term.abs() <= 1e-12 * sum.abs() {
+            return sum.max(0.0).min(1.0);
+        }
+        sign = -sign;
+        previous = term.abs();
+    }
+    // The series does not converge for small statistics, whose p-value is close to 1.
+    1.0
+}
+
+/// Returns the Kolmogorov-Smirnov test of the elements of `data` against the continuous
+/// distribution with the cumulative distribution function `cdf`. Its statistic is the largest
+/// distance between `cdf` and the empirical distribution function of the elements.
+///
+/// # Panics
+///
+/// If `data` is empty, or if it contains NaN.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::kolmogorov_smirnov;
+///
+/// let mut state = 88172645463325252u64;
+/// let samples = (0..1000)
+///     .map(|_| {
+///         state ^= state << 13;
+///         state ^= state >> 7;
+///         state ^= state << 17;
+///         (state >> 11) as f64 / (1u64 << 53) as f64
+///     })
+///     .collect::<Vec<_>>();
+/// assert!(kolmogorov_smirnov(&samples, |x| x).p_value > 1e-3);
+/// // The squares of uniform numbers are not uniform.
+/// let squares = samples.iter().map(|x| x * x).collect::<Vec<_>>();
+/// assert!(kolmogorov_smirnov(&squares, |x| x).p_value < 1e-6);
+/// # }
+/// ```
+///
+pub fn kolmogorov_smirnov<T, F>(data: &[T], cdf: F) -> Outcome
+    where T: Scalar,
+          F: Fn(f64) -> f64
+{
+    if data.is_empty() {
+        panic!("The Kolmogorov-Smirnov test of an empty buffer is undefined.");
+    }
+    let mut sorted = data.iter().map(|value| value.to_f64()).collect::<Vec<_>>();
+    if sorted.iter().any(|value| value.is_nan()) {
+        panic!("The buffer of the Kolmogorov-Smirnov test contains NaN.");
+    }
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let len = sorted.len() as f64;
+    let statistic = sorted.iter()
+        .enumerate()
+        .map(|(index, &value)| {
+            let expected = cdf(value);
+            (expected - index as f64 / len).max((index + 1) as f64 / len - expected)
+        })
+        .fold(0.0, f64::max);
+    Outcome {
+        name: "Kolmogorov-Smirnov",
+        statistic: statistic,
+        p_value: kolmogorov_smirnov_p_value(statistic, sorted.len()),
+    }
+}
+
+/// The outputs of random number generators which are tested by `assert_random`.
+pub trait Bits: Copy {
+    /// The number of bits of the output.
+    const BITS: u32;
+
+    /// Returns the bits of the output, in the low bits of a `u64`.
+    fn to_u64(self) -> u64;
+}
+
+impl Bits for u32 {
+    const BITS: u32 = 32;
+
+    fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Bits for u64 {
+    const BITS: u32 = 64;
+
+    fn to_u64(self) -> u64 {
+        self
+    }
+}
+
+/// Returns the highest byte of `value`.
+fn high_byte<T: Bits>(value: T) -> usize {
+    (value.to_u64() >> (T::BITS - 8)) as usize
+}
+
+/// Returns the chi-squared test of the counts of the values of `bytes` against their uniform
+/// distribution.
+fn byte_test<I: Iterator<Item = usize>>(name: &'static str, bytes: I) -> Outcome {
+    let mut counts = vec![0; 256];
+    for byte in bytes {
+        counts[byte] += 1;
+    }
+    Outcome { name: name, ..chi_squared(&counts, &[1.0 / 256.0; 256]) }
+}
+
+/// Returns the frequency test of every bit of `data`, whose statistic is the chi-squared sum of
+/// the squared deviations of the number of ones of every bit from one half.
+fn bit_frequency<T: Bits>(data: &[T]) -> Outcome {
+    let len = data.len() as f64;
+    let statistic = (0..T::BITS)
+        .map(|bit| {
+            let ones = data.iter().filter(|value| value.to_u64() >> bit & 1 == 1).count();
+            let difference = 2.0 * ones as f64 - len;
+            difference * difference / len
+        })
+        .sum::<f64>();
+    Outcome {
+        name: "bit frequency",
+        statistic: statistic,
+        p_value: chi_squared_p_value(statistic, T::BITS as usize),
+    }
+}
+
+/// Returns the outcomes of the battery of `assert_random` on the outputs `data`.
+///
+/// # Panics
+///
+/// If `data` has less than `MIN_BATTERY_LEN` elements.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::battery;
+///
+/// let counter = (0..10000u32).map(|i| i << 16).collect::<Vec<_>>();
+/// for outcome in battery(&counter) {
+///     println!("{}", outcome);
+/// }
+/// assert!(battery(&counter).iter().any(|outcome| outcome.fails(1e-4)));
+/// # }
+/// ```
+///
+pub fn battery<T: Bits>(data: &[T]) -> Vec<Outcome> {
+    if data.len() < MIN_BATTERY_LEN {
+        panic!("The randomness tests need at least {} outputs, but the buffer has {}.",
+               MIN_BATTERY_LEN,
+               data.len());
+    }
+    let scale = 1.0 / (1u64 << 53) as f64;
+    let unit = data.iter()
+        .map(|value| (value.to_u64() << (64 - T::BITS) >> 11) as f64 * scale)
+        .collect::<Vec<_>>();
+    vec![byte_test("chi-squared of the highest byte",
+                   data.iter().map(|&value| high_byte(value))),
+         byte_test("chi-squared of the lowest byte",
+                   data.iter().map(|value| (value.to_u64() & 0xff) as usize)),
+         Outcome { name: "Kolmogorov-Smirnov of the floats", ..kolmogorov_smirnov(&unit, |x| x) },
+         bit_frequency(data),
+         byte_test("serial test of pairs",
+                   data.chunks(2)
+                       .filter(|pair| pair.len() == 2)
+                       .map(|pair| high_byte(pair[0]) >> 4 << 4 | high_byte(pair[1]) >> 4))]
+}
+
+/// Asserts that the outputs `data` of a random number generator pass the battery of tests of
+/// the [`randomness`] module at the two-sided significance level `significance`, e.g.
+/// `DEFAULT_SIGNIFICANCE`. The significance level is shared by the tests, i.e. every test
+/// fails at a fraction of it.
+///
+/// # Panics
+///
+/// If a test fails, with the outcomes of all tests, or if `data` has less than
+/// `MIN_BATTERY_LEN` elements.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::randomness::{assert_random, DEFAULT_SIGNIFICANCE};
+///
+/// // The xorshift64* generator.
+/// let mut state = 1u64;
+/// let outputs = (0..10000)
+///     .map(|_| {
+///         state ^= state >> 12;
+///         state ^= state << 25;
+///         state ^= state >> 27;
+///         state.wrapping_mul(2685821657736338717)
+///     })
+///     .collect::<Vec<_>>();
+/// assert_random(&outputs, DEFAULT_SIGNIFICANCE);
+/// # }
+/// ```
+///
+/// [`randomness`]: index.html
+///
+pub fn assert_random<T: Bits>(data: &[T], significance: f64) {
+    let outcomes = battery(data);
+    let level = significance / outcomes.len() as f64;
+    let failed = outcomes.iter().filter(|outcome| outcome.fails(level)).count();
+    if failed > 0 {
+        let report = outcomes.iter()
+            .map(|outcome| {
+                let marker = if outcome.fails(level) { " <-" } else { "" };
+                format!("  {}{}", outcome, marker)
+            })
+            .collect::<Vec<_>>();
+        panic!("{} of {} randomness tests of {} outputs failed at the significance level {}:\n{}",
+               failed,
+               outcomes.len(),
+               data.len(),
+               significance,
+               report.join("\n"));
+    }
+}
@@ -0,0 +1,603 @@
+//! This module reflects the buffer layouts of SPIR-V modules and compares them against the
+//! element types of the buffers declared in `pipeline!`.
+//!
+//! The reflection runs in the build script, see `build_utils::reflect_buffer_layouts`, which
+//! appends a `BUFFER_LAYOUTS` and a `CAPABILITIES` constant to the generated shader modules. At
+//! pipeline setup every buffer element type is checked against the layout of the shader, every
+//! uniform type against the size of its block, and the capabilities of the shader are checked
+//! against those which can be enabled. If a shader module does not contain the constants, the
+//! empty defaults in [`defaults`] are used and the checks are skipped.
+//!
+//! [`defaults`]: defaults/index.html
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// The layout of a buffer block of a shader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferLayout {
+    /// The descriptor set of the block.
+    pub set: u32,
+    /// The binding of the block.
+    pub binding: u32,
+    /// The name of the block type.
+    pub block: Cow<'static, str>,
+    /// The size of the block in bytes, if it does not end with a runtime array.
+    pub size: Option<u32>,
+    /// The layout of the array elements, if the block contains a single array member.
+    pub element: Option<ElementLayout>,
+}
+
+/// The layout of the elements of an array member.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementLayout {
+    /// The name of the array member.
+    pub member: Cow<'static, str>,
+    /// The distance between two elements in bytes.
+    pub stride: u32,
+    /// The fields of the element, if it is a struct.
+    pub fields: Cow<'static, [FieldLayout]>,
+}
+
+/// The layout of a single field of a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    /// The name of the field.
+    pub name: Cow<'static, str>,
+    /// The offset of the field in bytes.
+    pub offset: u32,
+    /// The size of the field in bytes, if it is known.
+    pub size: Option<u32>,
+}
+
+/// The defaults for shader modules without reflection information.
+pub mod defaults {
+    use super::BufferLayout;
+
+    /// No buffer layouts are known, so no buffer is checked.
+    pub const BUFFER_LAYOUTS: &'static [BufferLayout] = &[];
+
+    /// No capabilities are known, so none is checked.
+    pub const CAPABILITIES: &'static [u32] = &[];
+}
+
+/// The capabilities which require a device extension that cannot be enabled, together with
+/// their names and the extension.
+const UNAVAILABLE_CAPABILITIES: &'static [(u32, &'static str, &'static str)] = &[
+    (9, "Float16", "VK_KHR_shader_float16_int8"),
+    (39, "Int8", "VK_KHR_shader_float16_int8"),
+    (4433, "StorageBuffer16BitAccess", "VK_KHR_16bit_storage"),
+    (4434, "UniformAndStorageBuffer16BitAccess", "VK_KHR_16bit_storage"),
+    (4435, "StoragePushConstant16", "VK_KHR_16bit_storage"),
+    (4448, "StorageBuffer8BitAccess", "VK_KHR_8bit_storage"),
+    (4449, "UniformAndStorageBuffer8BitAccess", "VK_KHR_8bit_storage"),
+    (4450, "StoragePushConstant8", "VK_KHR_8bit_storage"),
+    (4428, "WorkgroupMemoryExplicitLayoutKHR", "VK_KHR_workgroup_memory_explicit_layout"),
+    (4429,
+     "WorkgroupMemoryExplicitLayout8BitAccessKHR",
+     "VK_KHR_workgroup_memory_explicit_layout"),
+    (4430,
+     "WorkgroupMemoryExplicitLayout16BitAccessKHR",
+     "VK_KHR_workgroup_memory_explicit_layout"),
+    (4441, "VariablePointersStorageBuffer", "VK_KHR_variable_pointers"),
+    (4442, "VariablePointers", "VK_KHR_variable_pointers"),
+    (5055, "ShaderClockKHR", "VK_KHR_shader_clock"),
+    (6033, "AtomicFloat32AddEXT", "VK_EXT_shader_atomic_float"),
+    (6034, "AtomicFloat64AddEXT", "VK_EXT_shader_atomic_float"),
+];
+
+/// Panics if the shader `shader` declares one of the SPIR-V `capabilities` which cannot be
+/// enabled on the device.
+///
+/// Without the check, the pipeline creation of such a shader fails without naming the cause.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::reflection::check_capabilities;
+///
+/// // `Shader` and `VariablePointers`.
+/// check_capabilities("select.comp", &[1, 4442]);
+/// # }
+/// ```
+///
+pub fn check_capabilities(shader: &str, capabilities: &[u32]) {
+    let unavailable = UNAVAILABLE_CAPABILITIES.iter()
+        .filter(|&&(capability, _, _)| capabilities.contains(&capability))
+        .map(|&(_, name, extension)| format!("`{}` (requires `{}`)", name, extension))
+        .collect::<Vec<_>>();
+    if !unavailable.is_empty() {
+        panic!("The shader `{}` declares capabilities which cannot be enabled: {}.",
+               shader,
+               unavailable.join(", "));
+    }
+}
+
+/// The capabilities which require an optional device feature of Vulkan 1.0, together with the
+/// name of the feature in `vulkano::instance::Features`.
+const FEATURE_CAPABILITIES: &'static [(u32, &'static str)] = &[
+    (10, "shader_f3264"),
+    (11, "shader_int64"),
+    (22, "shader_int16"),
+];
+
+/// Returns the device features which the SPIR-V `capabilities` require, e.g. `shader_f3264`
+/// for `Float64`.
+///
+/// On devices which do not support one of them, `pipeline!` panics naming the feature, instead
+/// of failing at the pipeline creation without naming the cause.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::reflection::required_features;
+///
+/// // `Shader` and `Float64`.
+/// assert_eq!(required_features(&[1, 10]), vec!["shader_f3264"]);
+/// # }
+/// ```
+///
+pub fn required_features(capabilities: &[u32]) -> Vec<&'static str> {
+    FEATURE_CAPABILITIES.iter()
+        .filter(|&&(capability, _)| capabilities.contains(&capability))
+        .map(|&(_, feature)| feature)
+        .collect()
+}
+
+/// Panics if the element type of the buffer `buffer` does not match the layout of the block
+/// bound to `set` and `binding`.
+///
+/// The buffer is not checked if no layout is known for its binding, or if the block does not
+/// consist of a single array. A buffer of 8-bit or 16-bit scalars matches an array of scalars or
+/// vectors whose stride is a multiple of the scalar size, as the shader unpacks several of its
+/// elements from one element of the array.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::borrow::Cow;
+/// use std::mem;
+/// use vulkanology::reflection::{check_element_type, BufferLayout, ElementLayout, FieldLayout};
+///
+/// // `struct Particle { vec3 position; float mass; }` has a stride of 16 bytes.
+/// let layouts = [BufferLayout {
+///     set: 0,
+///     binding: 0,
+///     block: Cow::Borrowed("Particles"),
+///     size: None,
+///     element: Some(ElementLayout {
+///         member: Cow::Borrowed("particles"),
+///         stride: 16,
+///         fields: Cow::Owned(vec![
+///             FieldLayout { name: Cow::Borrowed("position"), offset: 0, size: Some(12) },
+///             FieldLayout { name: Cow::Borrowed("mass"), offset: 12, size: Some(4) },
+///         ]),
+///     }),
+/// }];
+///
+/// // A `[f32; 3]` only has 12 bytes.
+/// check_element_type(&layouts, "particles", 0, 0, "[f32; 3]", mem::size_of::<[f32; 3]>(),
+///                    mem::align_of::<[f32; 3]>());
+/// # }
+/// ```
+///
+pub fn check_element_type(layouts: &[BufferLayout],
+                          buffer: &str,
+                          set: u32,
+                          binding: u32,
+                          type_name: &str,
+                          size: usize,
+                          align: usize) {
+    let layout = layouts.iter().find(|layout| layout.set == set && layout.binding == binding);
+    let layout = match layout {
+        Some(layout) => layout,
+        None => return,
+    };
+    let element = match layout.element {
+        Some(ref element) => element,
+        None => return,
+    };
+    let packed = element.fields.is_empty() && (size == 1 || size == 2) && align == size &&
+                 element.stride as usize % size == 0;
+    if element.stride as usize == size || packed {
+        return;
+    }
+
+    let mut message = format!("The element type `{}` of the buffer `{}` (set {}, binding {}) does \
+                               not match the shader: `{}.{}[]` has a stride of {} bytes, but `{}` \
+                               has a size of {} bytes and an alignment of {} bytes.",
+                              type_name,
+                              buffer,
+                              set,
+                              binding,
+                              layout.block,
+                              element.member,
+                              element.stride,
+                              type_name,
+                              size,
+                              align);
+    if !element.fields.is_empty() {
+        message.push_str("\nShader element layout:");
+        for field in element.fields.iter() {
+            let size = field.size.map_or("?".to_owned(), |size| size.to_string());
+            message.push_str(&format!("\n    offset {:>4}, size {:>4}: {}",
+                                      field.offset,
+                                      size,
+                                      field.name));
+        }
+    }
+    panic!("{}", message);
+}
+
+/// Panics if the type of the uniform or single-value storage buffer `buffer` is smaller than the
+/// block bound to `set` and `binding`, so that the shader would access memory beyond the end of
+/// the buffer.
+///
+/// The buffer is not checked if no layout or no size is known for its binding. A larger type is
+/// accepted, as Rust pads structs to their alignment.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::borrow::Cow;
+/// use std::mem;
+/// use vulkanology::reflection::{check_block_type, BufferLayout};
+///
+/// // `uniform Parameters { vec3 direction; float speed; }` has 16 bytes.
+/// let layouts = [BufferLayout {
+///     set: 0,
+///     binding: 1,
+///     block: Cow::Borrowed("Parameters"),
+///     size: Some(16),
+///     element: None,
+/// }];
+///
+/// check_block_type(&layouts, "parameters", 0, 1, "[f32; 3]", mem::size_of::<[f32; 3]>());
+/// # }
+/// ```
+///
+pub fn check_block_type(layouts: &[BufferLayout],
+                        buffer: &str,
+                        set: u32,
+                        binding: u32,
+                        type_name: &str,
+                        size: usize) {
+    let layout = layouts.iter().find(|layout| layout.set == set && layout.binding == binding);
+    let layout = match layout {
+        Some(layout) => layout,
+        None => return,
+    };
+    match layout.size {
+        Some(block_size) if size < block_size as usize => {
+            panic!("The type `{}` of the buffer `{}` (set {}, binding {}) does not match \
+                    the shader: `{}` has a size of {} bytes, but `{}` only has {} bytes.",
+                   type_name,
+                   buffer,
+                   set,
+                   binding,
+                   layout.block,
+                   block_size,
+                   type_name,
+                   size)
+        }
+        _ => {}
+    }
+}
+
+/// Returns the layouts of all buffer blocks of a SPIR-V module.
+///
+/// # Panics
+///
+/// If `spirv` is not a SPIR-V module.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::reflection::buffer_layouts;
+///
+/// // layout(set = 0, binding = 1) buffer Data { uint data[]; };
+/// let spirv = [
+///     0x07230203, 0x00010000, 0, 7, 0,
+///     (4 << 16) | 5, 4, 0x61746144, 0,        // OpName %4 "Data"
+///     (5 << 16) | 6, 4, 0, 0x61746164, 0,     // OpMemberName %4 0 "data"
+///     (4 << 16) | 71, 3, 6, 4,                // OpDecorate %3 ArrayStride 4
+///     (5 << 16) | 72, 4, 0, 35, 0,            // OpMemberDecorate %4 0 Offset 0
+///     (4 << 16) | 71, 6, 34, 0,               // OpDecorate %6 DescriptorSet 0
+///     (4 << 16) | 71, 6, 33, 1,               // OpDecorate %6 Binding 1
+///     (4 << 16) | 21, 2, 32, 0,               // %2 = OpTypeInt 32 0
+///     (3 << 16) | 29, 3, 2,                   // %3 = OpTypeRuntimeArray %2
+///     (3 << 16) | 30, 4, 3,                   // %4 = OpTypeStruct %3
+///     (4 << 16) | 32, 5, 2, 4,                // %5 = OpTypePointer Uniform %4
+///     (4 << 16) | 59, 5, 6, 2,                // %6 = OpVariable %5 Uniform
+/// ];
+///
+/// let layouts = buffer_layouts(&spirv);
+/// assert_eq!(layouts.len(), 1);
+/// assert_eq!((layouts[0].set, layouts[0].binding), (0, 1));
+/// assert_eq!(layouts[0].block, "Data");
+///
+/// let element = layouts[0].element.as_ref().unwrap();
+/// assert_eq!(element.member, "data");
+/// assert_eq!(element.stride, 4);
+/// # }
+/// ```
+///
+pub fn buffer_layouts(spirv: &[u32]) -> Vec<BufferLayout> {
+    parse(spirv).buffer_layouts()
+}
+
+/// Returns the capabilities declared by a SPIR-V module.
+///
+/// # Panics
+///
+/// If `spirv` is not a SPIR-V module.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::reflection::capabilities;
+///
+/// let spirv = [
+///     0x07230203, 0x00010000, 0, 1, 0,
+///     (2 << 16) | 17, 1,                      // OpCapability Shader
+///     (2 << 16) | 17, 4441,                   // OpCapability VariablePointersStorageBuffer
+/// ];
+///
+/// assert_eq!(capabilities(&spirv), vec![1, 4441]);
+/// # }
+/// ```
+///
+pub fn capabilities(spirv: &[u32]) -> Vec<u32> {
+    parse(spirv).capabilities
+}
+
+/// Parses the instructions of a SPIR-V module.
+fn parse(spirv: &[u32]) -> Module {
+    const MAGIC: u32 = 0x07230203;
+    let swap = match spirv.first() {
+        Some(&MAGIC) => false,
+        Some(&word) if word.swap_bytes() == MAGIC => true,
+        _ => panic!("The data is not a SPIR-V module."),
+    };
+
+    let mut module = Module::default();
+    let mut words = spirv.iter().skip(5).map(|&word| if swap { word.swap_bytes() } else { word });
+    loop {
+        let first = match words.next() {
+            Some(first) => first,
+            None => break,
+        };
+        let (count, opcode) = ((first >> 16) as usize, first & 0xffff);
+        let operands = words.by_ref().take(count.saturating_sub(1)).collect::<Vec<_>>();
+        module.instruction(opcode, &operands);
+    }
+
+    module
+}
+
+/// The parts of a SPIR-V module which are relevant for buffer layouts and capabilities.
+#[derive(Default)]
+struct Module {
+    capabilities: Vec<u32>,
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_offsets: HashMap<(u32, u32), u32>,
+    types: HashMap<u32, Type>,
+    constants: HashMap<u32, u32>,
+    pointers: HashMap<u32, u32>,
+    variables: Vec<(u32, u32)>,
+}
+
+enum Type {
+    Scalar(u32),
+    Vector(u32, u32),
+    Matrix(u32, u32),
+    Array(u32, Option<u32>),
+    Struct(Vec<u32>),
+}
+
+// The decorations and storage classes of the SPIR-V specification used below.
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+impl Module {
+    fn instruction(&mut self, opcode: u32, operands: &[u32]) {
+        match (opcode, operands.len()) {
+            // OpCapability
+            (17, n) if n >= 1 => {
+                self.capabilities.push(operands[0]);
+            }
+            // OpName
+            (5, n) if n >= 2 => {
+                self.names.insert(operands[0], string(&operands[1..]));
+            }
+            // OpMemberName
+            (6, n) if n >= 3 => {
+                self.member_names.insert((operands[0], operands[1]), string(&operands[2..]));
+            }
+            // OpDecorate
+            (71, n) if n >= 3 => {
+                self.decorations.insert((operands[0], operands[1]), operands[2]);
+            }
+            // OpMemberDecorate
+            (72, n) if n >= 4 && operands[2] == DECORATION_OFFSET => {
+                self.member_offsets.insert((operands[0], operands[1]), operands[3]);
+            }
+            // OpTypeBool
+            (20, n) if n >= 1 => {
+                self.types.insert(operands[0], Type::Scalar(4));
+            }
+            // OpTypeInt, OpTypeFloat
+            (21, n) | (22, n) if n >= 2 => {
+                self.types.insert(operands[0], Type::Scalar(operands[1] / 8));
+            }
+            // OpTypeVector
+            (23, n) if n >= 3 => {
+                self.types.insert(operands[0], Type::Vector(operands[1], operands[2]));
+            }
+            // OpTypeMatrix
+            (24, n) if n >= 3 => {
+                self.types.insert(operands[0], Type::Matrix(operands[1], operands[2]));
+            }
+            // OpTypeArray
+            (28, n) if n >= 3 => {
+                self.types.insert(operands[0], Type::Array(operands[1], Some(operands[2])));
+            }
+            // OpTypeRuntimeArray
+            (29, n) if n >= 2 => {
+                self.types.insert(operands[0], Type::Array(operands[1], None));
+            }
+            // OpTypeStruct
+            (30, n) if n >= 1 => {
+                self.types.insert(operands[0], Type::Struct(operands[1..].to_vec()));
+            }
+            // OpTypePointer
+            (32, n) if n >= 3 => {
+                self.pointers.insert(operands[0], operands[2]);
+            }
+            // OpConstant
+            (43, n) if n >= 3 => {
+                self.constants.insert(operands[1], operands[2]);
+            }
+            // OpVariable
+            (59, n) if n >= 3 => {
+                if operands[2] == STORAGE_CLASS_UNIFORM ||
+                   operands[2] == STORAGE_CLASS_STORAGE_BUFFER {
+                    self.variables.push((operands[0], operands[1]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn name(&self, id: u32) -> String {
+        self.names.get(&id).cloned().unwrap_or_else(|| format!("%{}", id))
+    }
+
+    fn member_name(&self, id: u32, member: u32) -> String {
+        self.member_names.get(&(id, member)).cloned().unwrap_or_else(|| format!("{}", member))
+    }
+
+    /// Returns the size of a type in bytes, if it is sized.
+    fn size(&self, id: u32) -> Option<u32> {
+        match self.types.get(&id) {
+            Some(&Type::Scalar(size)) => Some(size),
+            Some(&Type::Vector(component, count)) => self.size(component).map(|size| size * count),
+            Some(&Type::Matrix(column, count)) => self.size(column).map(|size| size * count),
+            Some(&Type::Array(_, Some(length))) => {
+                let stride = self.decorations.get(&(id, DECORATION_ARRAY_STRIDE));
+                match (stride, self.constants.get(&length)) {
+                    (Some(stride), Some(length)) => Some(stride * length),
+                    _ => None,
+                }
+            }
+            Some(&Type::Struct(ref members)) => {
+                let last = members.len() as u32;
+                match (last.checked_sub(1), members.last()) {
+                    (Some(index), Some(&member)) => {
+                        let offset = self.member_offsets.get(&(id, index)).cloned();
+                        offset.and_then(|offset| self.size(member).map(|size| offset + size))
+                    }
+                    _ => Some(0),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn buffer_layouts(&self) -> Vec<BufferLayout> {
+        let mut layouts = self.variables
+            .iter()
+            .filter_map(|&(pointer, variable)| {
+                let set = self.decorations.get(&(variable, DECORATION_DESCRIPTOR_SET));
+                let binding = self.decorations.get(&(variable, DECORATION_BINDING));
+                let block = self.pointers.get(&pointer).cloned();
+                match (set, binding, block) {
+                    (Some(&set), Some(&binding), Some(block)) => {
+                        Some(BufferLayout {
+                            set: set,
+                            binding: binding,
+                            block: Cow::Owned(self.name(block)),
+                            size: self.size(block),
+                            element: self.element_layout(block),
+                        })
+                    }
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        layouts.sort_by_key(|layout| (layout.set, layout.binding));
+        layouts
+    }
+
+    fn element_layout(&self, block: u32) -> Option<ElementLayout> {
+        let array = match self.types.get(&block) {
+            Some(&Type::Struct(ref members)) if members.len() == 1 => members[0],
+            _ => return None,
+        };
+        let element = match self.types.get(&array) {
+            Some(&Type::Array(element, _)) => element,
+            _ => return None,
+        };
+        let stride = match self.decorations.get(&(array, DECORATION_ARRAY_STRIDE)) {
+            Some(&stride) => stride,
+            None => return None,
+        };
+        let fields = match self.types.get(&element) {
+            Some(&Type::Struct(ref members)) => {
+                members.iter()
+                    .enumerate()
+                    .map(|(index, &member)| {
+                        FieldLayout {
+                            name: Cow::Owned(self.member_name(element, index as u32)),
+                            offset: self.member_offsets
+                                .get(&(element, index as u32))
+                                .cloned()
+                                .unwrap_or(0),
+                            size: self.size(member),
+                        }
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        Some(ElementLayout {
+            member: Cow::Owned(self.member_name(block, 0)),
+            stride: stride,
+            fields: Cow::Owned(fields),
+        })
+    }
+}
+
+/// Decodes a nul-terminated SPIR-V literal string.
+fn string(words: &[u32]) -> String {
+    let bytes = words.iter()
+        .flat_map(|&word| (0..4).map(move |byte| (word >> (byte * 8)) as u8))
+        .take_while(|&byte| byte != 0)
+        .collect::<Vec<_>>();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
@@ -0,0 +1,170 @@
+//! This module triggers RenderDoc captures of the execution command of `pipeline!`.
+//!
+//! If `VULKANOLOGY_RENDERDOC` is set, `pipeline!` loads the in-application API of RenderDoc
+//! before it creates the instance, and every call of the execution command is recorded as a
+//! separate capture, which is commented with the path of the shader. The captures are made
+//! without a window, so a single test should be run at a time:
+//!
+//! `VULKANOLOGY_RENDERDOC=1 renderdoccmd capture -w cargo test test_random_next_u64`
+//!
+//! On Linux, RenderDoc is also loaded from the library search path if the test was not started
+//! from RenderDoc. On Windows, it must have been injected into the test binary. The file names
+//! of the captures are printed to stderr.
+
+use std::env;
+use std::ffi::CString;
+use std::io::{self, Write};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+
+/// The environment variable which enables the captures.
+pub const CAPTURE_VAR: &'static str = "VULKANOLOGY_RENDERDOC";
+
+/// `eRENDERDOC_API_Version_1_2_0`, the version which added the comments of captures.
+const API_VERSION: c_int = 10200;
+
+type GetApi = extern "C" fn(c_int, *mut *mut c_void) -> c_int;
+
+/// The prefix of `RENDERDOC_API_1_2_0` up to the function which comments a capture.
+#[repr(C)]
+#[allow(dead_code)]
+struct Api {
+    unused: [*const c_void; 13],
+    get_num_captures: extern "C" fn() -> u32,
+    get_capture: extern "C" fn(u32, *mut c_char, *mut u32, *mut u64) -> u32,
+    unused_control: [*const c_void; 4],
+    start_frame_capture: extern "C" fn(*mut c_void, *mut c_void),
+    is_frame_capturing: extern "C" fn() -> u32,
+    end_frame_capture: extern "C" fn(*mut c_void, *mut c_void) -> u32,
+    unused_trigger: *const c_void,
+    set_capture_file_comments: extern "C" fn(*const c_char, *const c_char),
+}
+
+/// The RenderDoc API, which records the executions of the pipeline of a shader.
+pub struct Capture {
+    api: &'static Api,
+    shader: String,
+}
+
+impl Capture {
+    /// Loads the RenderDoc API, if `VULKANOLOGY_RENDERDOC` is set. It must be loaded before the
+    /// instance is created.
+    ///
+    /// # Panics
+    ///
+    /// If the variable is set, but RenderDoc cannot be loaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use std::env;
+    /// use vulkanology::renderdoc::{Capture, CAPTURE_VAR};
+    ///
+    /// env::remove_var(CAPTURE_VAR);
+    /// assert!(Capture::from_env("tests/shaders/example.comp").is_none());
+    /// # }
+    /// ```
+    ///
+    pub fn from_env(shader: &str) -> Option<Capture> {
+        if env::var_os(CAPTURE_VAR).is_none() {
+            return None;
+        }
+        let get_api = load_get_api().expect("Failed to load RenderDoc, run the test from \
+                                             RenderDoc or add it to the library search path.");
+        let mut api = ptr::null_mut();
+        if get_api(API_VERSION, &mut api) != 1 || api.is_null() {
+            panic!("Failed to get version 1.2.0 of the RenderDoc API.");
+        }
+        Some(Capture {
+            api: unsafe { &*(api as *const Api) },
+            shader: shader.to_owned(),
+        })
+    }
+
+    /// Records the commands submitted by `execution` in a capture of the active device.
+    ///
+    /// # Panics
+    ///
+    /// If a capture is already in progress, or if RenderDoc failed to finish the capture.
+    pub fn record<F: FnOnce()>(&self, execution: F) {
+        if (self.api.is_frame_capturing)() != 0 {
+            panic!("A RenderDoc capture is already in progress.");
+        }
+        // Null pointers select the active device and no window.
+        (self.api.start_frame_capture)(ptr::null_mut(), ptr::null_mut());
+        execution();
+        if (self.api.end_frame_capture)(ptr::null_mut(), ptr::null_mut()) != 1 {
+            panic!("Failed to capture the execution of `{}` with RenderDoc.", self.shader);
+        }
+
+        // A null path refers to the latest capture.
+        let comments = CString::new(self.shader.as_str())
+            .expect("The shader path contains a null byte.");
+        (self.api.set_capture_file_comments)(ptr::null(), comments.as_ptr());
+        let _ = writeln!(io::stderr(),
+                         "note: captured the execution of `{}` with RenderDoc: {}",
+                         self.shader,
+                         self.latest_capture());
+    }
+
+    /// Returns the file name of the latest capture.
+    fn latest_capture(&self) -> String {
+        let index = (self.api.get_num_captures)().saturating_sub(1);
+        let mut len = 0;
+        if (self.api.get_capture)(index, ptr::null_mut(), &mut len, ptr::null_mut()) != 1 {
+            return String::from("<unknown>");
+        }
+        // The length includes the terminating null byte.
+        let mut path = vec![0u8; len as usize];
+        let buffer = path.as_mut_ptr() as *mut c_char;
+        (self.api.get_capture)(index, buffer, &mut len, ptr::null_mut());
+        path.retain(|&byte| byte != 0);
+        String::from_utf8_lossy(&path).into_owned()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn load_get_api() -> Option<GetApi> {
+    const RTLD_NOW: c_int = 2;
+    const RTLD_NOLOAD: c_int = 4;
+    extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+    unsafe {
+        let library = b"librenderdoc.so\0".as_ptr() as *const c_char;
+        let mut handle = dlopen(library, RTLD_NOW | RTLD_NOLOAD);
+        if handle.is_null() {
+            handle = dlopen(library, RTLD_NOW);
+        }
+        if handle.is_null() {
+            return None;
+        }
+        let get_api = dlsym(handle, b"RENDERDOC_GetAPI\0".as_ptr() as *const c_char);
+        if get_api.is_null() { None } else { Some(::std::mem::transmute(get_api)) }
+    }
+}
+
+#[cfg(windows)]
+fn load_get_api() -> Option<GetApi> {
+    extern "system" {
+        fn GetModuleHandleA(module: *const c_char) -> *mut c_void;
+        fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *mut c_void;
+    }
+    unsafe {
+        let module = GetModuleHandleA(b"renderdoc.dll\0".as_ptr() as *const c_char);
+        if module.is_null() {
+            return None;
+        }
+        let get_api = GetProcAddress(module, b"RENDERDOC_GetAPI\0".as_ptr() as *const c_char);
+        if get_api.is_null() { None } else { Some(::std::mem::transmute(get_api)) }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn load_get_api() -> Option<GetApi> {
+    None
+}
@@ -0,0 +1,1186 @@
+//! This module runs a compute shader without a test, for quick experiments with a kernel. It
+//! backs the `vulkanology-run` binary, which requires the optional `runner` feature:
+//!
+//! ```text
+//! cargo run --features runner --bin vulkanology-run -- kernel.comp kernel.json
+//! ```
+//!
+//! `pipeline!` assembles its pipeline at compile time from its declaration. The runner assembles
+//! it at runtime instead, from a SPIR-V module and a JSON description of the workgroup count, the
+//! push constants and the buffers:
+//!
+//! ```json
+//! {
+//!     "workgroup_count": [100, 100, 1],
+//!     "push_constants": [{ "type": "f32", "value": 4.0 }, { "type": "f32", "value": 10.0 }],
+//!     "buffers": [
+//!         { "name": "data", "type": "u32", "input": "data.npy" },
+//!         { "name": "result", "type": "f32", "len": 640000, "output": "result.npy",
+//!           "print": [0, 8], "expect": [10.0, 14.0, 18.0] }
+//!     ]
+//! }
+//! ```
+//!
+//! The shader is read as a SPIR-V module if its file name ends with `.spv`, e.g. one compiled
+//! with `glslangValidator -V`. Any other file is compiled from GLSL at runtime, which requires
+//! the optional `glsl-to-spirv` feature, enabled by `runner`.
+//!
+//! The buffers are storage buffers of the scalars `u8` to `u64`, `i8` to `i64`, `f32` or `f64`.
+//! A buffer of vectors or structs is described by its scalars, e.g. a buffer of `vec4` by four
+//! `f32` per element. Like in `pipeline!`, the buffers are bound to set 0 at the binding of their
+//! position, unless `set` and `binding` are given. Before the shader runs, the buffers are checked
+//! against the layouts reflected from it, see the [`reflection`] module.
+//!
+//! A buffer takes its contents from the numbers of its `input`, or loads them from the file
+//! `input` with the [`import`] module, or is zeroed without an `input`. Its length is `len`, or
+//! that of its input. After the shader ran, the elements of a buffer are written to the file
+//! `output` with the [`export`] module, the elements in the range `print` are printed, and its
+//! first elements are compared with the numbers of `expect`.
+//!
+//! The push constants are scalars of the types `u32`, `i32`, `u64`, `i64`, `f32` or `f64`, which
+//! are laid out by the `std430` rules and take at most 128 bytes.
+//!
+//! With `--watch`, the binary keeps the device, the buffers and their inputs, and runs the shader
+//! again whenever its file changes: it recompiles the shader, writes the inputs to the buffers
+//! and reports the results. A shader which fails to compile or to run is reported, and the binary
+//! waits for its next change.
+//!
+//! [`export`]: ../export/index.html
+//! [`import`]: ../import/index.html
+//! [`reflection`]: ../reflection/index.html
+
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use export::Scalar;
+use import::load;
+use reflection;
+
+#[cfg(feature = "vulkano")]
+use std::ffi::CStr;
+#[cfg(feature = "vulkano")]
+use std::io::{self, Write};
+#[cfg(feature = "vulkano")]
+use std::mem;
+#[cfg(feature = "vulkano")]
+use std::sync::Arc;
+#[cfg(feature = "vulkano")]
+use std::time::Duration;
+#[cfg(feature = "vulkano")]
+use std::vec::IntoIter as VecIntoIter;
+
+#[cfg(feature = "vulkano")]
+use vulkano::buffer::{BufferUsage, CpuAccessibleBuffer};
+#[cfg(feature = "vulkano")]
+use vulkano::command_buffer::{submit, PrimaryCommandBufferBuilder};
+#[cfg(feature = "vulkano")]
+use vulkano::descriptor::descriptor::{DescriptorBufferDesc, DescriptorDesc, DescriptorDescTy,
+                                      ShaderStages};
+#[cfg(feature = "vulkano")]
+use vulkano::descriptor::descriptor_set::{DescriptorPool, DescriptorSet, DescriptorSetDesc,
+                                          DescriptorSetsCollection, DescriptorWrite,
+                                          UnsafeDescriptorSet, UnsafeDescriptorSetLayout};
+#[cfg(feature = "vulkano")]
+use vulkano::descriptor::pipeline_layout::{EmptyPipelineDesc, PipelineLayout,
+                                           PipelineLayoutDesc, UnsafePipelineLayout};
+#[cfg(feature = "vulkano")]
+use vulkano::device::{Device, DeviceExtensions, Queue};
+#[cfg(feature = "vulkano")]
+use vulkano::instance::{Instance, InstanceExtensions, PhysicalDevice};
+#[cfg(feature = "vulkano")]
+use vulkano::pipeline::ComputePipeline;
+#[cfg(feature = "vulkano")]
+use vulkano::pipeline::shader::ShaderModule;
+#[cfg(feature = "vulkano")]
+use vulkano::sync::FenceWaitError;
+
+#[cfg(feature = "vulkano")]
+use checked;
+#[cfg(feature = "vulkano")]
+use export::export;
+#[cfg(feature = "vulkano")]
+use context;
+#[cfg(feature = "vulkano")]
+use hang;
+#[cfg(feature = "vulkano")]
+use overrides;
+
+/// The size of the push constants in bytes, which every device supports.
+pub const MAX_PUSH_CONSTANTS_SIZE: usize = 128;
+
+macro_rules! scalar_types {
+    ( $( $(#[$attr:meta])* $variant:ident: $scalar:ident; )* ) => {
+        /// The scalar types of the buffer elements and of the push constants.
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        pub enum ScalarType {
+            $( $(#[$attr])* $variant, )*
+        }
+
+        impl ScalarType {
+            /// Returns the type of the name `name`, e.g. `f32`.
+            pub fn from_name(name: &str) -> Option<ScalarType> {
+                $(
+                    if name == stringify!($scalar) {
+                        return Some(ScalarType::$variant);
+                    }
+                )*
+                None
+            }
+
+            /// Returns the name of the type, e.g. `f32`.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $( ScalarType::$variant => stringify!($scalar), )*
+                }
+            }
+
+            /// Returns the size of the type in bytes.
+            pub fn size(self) -> usize {
+                match self {
+                    $( ScalarType::$variant => <$scalar as Scalar>::SIZE, )*
+                }
+            }
+
+            /// Appends the little endian bytes of the number `text` to `out`.
+            pub fn encode(self, text: &str, out: &mut Vec<u8>) -> Result<(), String> {
+                match self {
+                    $(
+                        ScalarType::$variant => {
+                            let value = <$scalar as FromStr>::from_str(text)
+                                .map_err(|_| format!("`{}` is not a `{}`", text, self.name()))?;
+                            value.write_le(out);
+                        }
+                    )*
+                }
+                Ok(())
+            }
+
+            /// Returns the number of the little endian `bytes` as text.
+            pub fn decode(self, bytes: &[u8]) -> String {
+                match self {
+                    $( ScalarType::$variant => <$scalar as Scalar>::read_le(bytes).to_string(), )*
+                }
+            }
+
+            /// Returns whether the numbers of the little endian bytes `a` and `b` are equal,
+            /// which they are not if one of them is NaN.
+            pub fn equal(self, a: &[u8], b: &[u8]) -> bool {
+                match self {
+                    $(
+                        ScalarType::$variant => {
+                            <$scalar as Scalar>::read_le(a) == <$scalar as Scalar>::read_le(b)
+                        }
+                    )*
+                }
+            }
+
+            /// Returns the little endian bytes of the elements of the file `path`.
+            fn load(self, path: &Path) -> Vec<u8> {
+                let mut out = Vec::new();
+                match self {
+                    $(
+                        ScalarType::$variant => {
+                            for value in load::<$scalar>(path) {
+                                value.write_le(&mut out);
+                            }
+                        }
+                    )*
+                }
+                out
+            }
+
+            /// Writes the elements of the little endian `bytes` to the file `path`.
+            #[cfg(feature = "vulkano")]
+            fn export(self, path: &Path, name: &str, bytes: &[u8]) {
+                match self {
+                    $(
+                        ScalarType::$variant => {
+                            let values = bytes.chunks(<$scalar as Scalar>::SIZE)
+                                .map(<$scalar as Scalar>::read_le)
+                                .collect::<Vec<$scalar>>();
+                            export(path, name, &values);
+                        }
+                    )*
+                }
+            }
+        }
+    };
+}
+
+scalar_types!{
+    /// A GLSL `uint8_t`.
+    U8: u8;
+    /// A GLSL `int8_t`.
+    I8: i8;
+    /// A GLSL `uint16_t`.
+    U16: u16;
+    /// A GLSL `int16_t`.
+    I16: i16;
+    /// A GLSL `uint`.
+    U32: u32;
+    /// A GLSL `int`.
+    I32: i32;
+    /// A GLSL `uint64_t`.
+    U64: u64;
+    /// A GLSL `int64_t`.
+    I64: i64;
+    /// A GLSL `float`.
+    F32: f32;
+    /// A GLSL `double`.
+    F64: f64;
+}
+
+/// The contents of a buffer before the shader runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// The buffer is zeroed.
+    Zeroed,
+    /// The elements are loaded from the file with the [`import`] module.
+    ///
+    /// [`import`]: ../import/index.html
+    File(PathBuf),
+    /// The little endian bytes of the elements given in the description.
+    Values(Vec<u8>),
+}
+
+/// A storage buffer of a description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferDescription {
+    /// The name of the buffer, which names it in the messages and the output files.
+    pub name: String,
+    /// The descriptor set of the buffer.
+    pub set: u32,
+    /// The binding of the buffer.
+    pub binding: u32,
+    /// The type of the elements.
+    pub ty: ScalarType,
+    /// The number of elements, or `None` to take the length of the input.
+    pub len: Option<usize>,
+    /// The contents of the buffer before the shader runs.
+    pub input: Input,
+    /// The file which the elements are written to after the shader ran.
+    pub output: Option<PathBuf>,
+    /// The elements which are printed after the shader ran.
+    pub print: Option<Range<usize>>,
+    /// The little endian bytes of the expected first elements.
+    pub expect: Vec<u8>,
+}
+
+impl BufferDescription {
+    /// Returns the little endian bytes of the elements before the shader runs.
+    ///
+    /// # Panics
+    ///
+    /// If the input file cannot be loaded, or if the length of the input differs from `len`.
+    pub fn input_bytes(&self) -> Vec<u8> {
+        let bytes = match self.input {
+            Input::Zeroed => vec![0; self.len.unwrap_or(0) * self.ty.size()],
+            Input::File(ref path) => self.ty.load(path),
+            Input::Values(ref bytes) => bytes.clone(),
+        };
+        let len = bytes.len() / self.ty.size();
+        if self.len.map_or(false, |expected| expected != len) {
+            panic!("The input of the buffer `{}` has {} elements instead of {}.",
+                   self.name,
+                   len,
+                   self.len.unwrap());
+        }
+        if len == 0 {
+            panic!("The buffer `{}` has no elements.", self.name);
+        }
+        bytes
+    }
+}
+
+/// The description of a shader run: the workgroup count, the push constants and the buffers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Description {
+    /// The workgroup count of the dispatch.
+    pub workgroup_count: [u32; 3],
+    /// The little endian bytes of the push constants, laid out by the `std430` rules.
+    pub push_constants: Vec<u8>,
+    /// The buffers, in the order of their description.
+    pub buffers: Vec<BufferDescription>,
+}
+
+impl Description {
+    /// Parses the JSON description `text`, see the [module documentation](index.html).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use vulkanology::runner::{Description, Input, ScalarType};
+    ///
+    /// let description = Description::parse(r#"{
+    ///     "workgroup_count": [4, 1, 1],
+    ///     "push_constants": [{ "type": "u32", "value": 3 }, { "type": "f64", "value": 0.5 }],
+    ///     "buffers": [
+    ///         { "name": "data", "type": "u32", "input": [1, 2, 3, 4] },
+    ///         { "name": "result", "binding": 3, "type": "f32", "len": 4, "print": [0, 2] }
+    ///     ]
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(description.workgroup_count, [4, 1, 1]);
+    /// // The `f64` is aligned to 8 bytes.
+    /// assert_eq!(description.push_constants.len(), 16);
+    /// assert_eq!(description.buffers[0].input_bytes().len(), 16);
+    /// assert_eq!((description.buffers[1].binding, description.buffers[1].ty), (3, ScalarType::F32));
+    /// assert_eq!(description.buffers[1].input, Input::Zeroed);
+    ///
+    /// let error = Description::parse(r#"{ "workgroup_count": [4, 1, 1], "bufers": [] }"#);
+    /// assert_eq!(error, Err("Unknown key `bufers` in the description".to_owned()));
+    /// # }
+    /// ```
+    ///
+    pub fn parse(text: &str) -> Result<Description, String> {
+        let json = Parser::new(text).document()?;
+        let object = as_object(&json, "the description")?;
+        check_keys(object, &["workgroup_count", "push_constants", "buffers"], "the description")?;
+
+        let workgroup_count = match field(object, "workgroup_count") {
+            Some(&Json::Array(ref counts)) if counts.len() == 3 => {
+                [as_number::<u32>(&counts[0], "the workgroup count")?,
+                 as_number::<u32>(&counts[1], "the workgroup count")?,
+                 as_number::<u32>(&counts[2], "the workgroup count")?]
+            }
+            _ => return Err("The description needs a `workgroup_count` of three numbers".to_owned()),
+        };
+
+        let mut push_constants = Vec::new();
+        for constant in as_array(field(object, "push_constants"), "`push_constants`")? {
+            let constant = as_object(constant, "a push constant")?;
+            check_keys(constant, &["type", "value"], "a push constant")?;
+            let ty = scalar_type(constant, "a push constant")?;
+            if ty.size() < 4 {
+                return Err(format!("Push constants of the type `{}` are not supported",
+                                   ty.name()));
+            }
+            let value = match field(constant, "value") {
+                Some(&Json::Number(ref value)) => value,
+                _ => return Err("A push constant needs a numeric `value`".to_owned()),
+            };
+            // Every scalar is aligned to its size.
+            while push_constants.len() % ty.size() != 0 {
+                push_constants.push(0);
+            }
+            ty.encode(value, &mut push_constants)
+                .map_err(|error| format!("{} in a push constant", error))?;
+        }
+        if push_constants.len() > MAX_PUSH_CONSTANTS_SIZE {
+            return Err(format!("The push constants take {} bytes, more than {}",
+                               push_constants.len(),
+                               MAX_PUSH_CONSTANTS_SIZE));
+        }
+
+        let mut buffers = Vec::<BufferDescription>::new();
+        let described = as_array(field(object, "buffers"), "`buffers`")?;
+        for (index, buffer) in described.iter().enumerate() {
+            let buffer = buffer_description(buffer, index as u32)?;
+            let duplicate = buffers.iter()
+                .find(|other| other.set == buffer.set && other.binding == buffer.binding);
+            if let Some(other) = duplicate {
+                return Err(format!("The buffers `{}` and `{}` are both bound to set {}, \
+                                    binding {}",
+                                   other.name,
+                                   buffer.name,
+                                   buffer.set,
+                                   buffer.binding));
+            }
+            buffers.push(buffer);
+        }
+
+        Ok(Description {
+            workgroup_count: workgroup_count,
+            push_constants: push_constants,
+            buffers: buffers,
+        })
+    }
+
+    /// Reads and parses the JSON description in the file `path`.
+    ///
+    /// # Panics
+    ///
+    /// If the file cannot be read, or if it is not a valid description.
+    pub fn load(path: &Path) -> Description {
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .expect(format!("Failed to read the description: {}", path.display()).as_ref());
+        Description::parse(&text)
+            .unwrap_or_else(|error| panic!("Invalid description {}: {}.", path.display(), error))
+    }
+
+    /// Checks the SPIR-V module `spirv` of the shader `shader` against the description: its
+    /// capabilities must be available, see `reflection::check_capabilities`, every buffer block
+    /// of the shader must be described, and the stride of the array in a block must be a
+    /// multiple of the size of the described scalars.
+    ///
+    /// # Panics
+    ///
+    /// If `spirv` is not a SPIR-V module, or if it does not match the description.
+    pub fn check_shader(&self, shader: &str, spirv: &[u32]) {
+        reflection::check_capabilities(shader, &reflection::capabilities(spirv));
+        let layouts = reflection::buffer_layouts(spirv);
+        for layout in &layouts {
+            let buffer = self.buffers
+                .iter()
+                .find(|buffer| buffer.set == layout.set && buffer.binding == layout.binding);
+            let buffer = match buffer {
+                Some(buffer) => buffer,
+                None => {
+                    panic!("The block `{}` of the shader `{}` at set {}, binding {} is not \
+                            described.",
+                           layout.block,
+                           shader,
+                           layout.set,
+                           layout.binding)
+                }
+            };
+            // A buffer of vectors or structs is described by its scalars.
+            if let Some(ref element) = layout.element {
+                if element.stride as usize % buffer.ty.size() != 0 {
+                    panic!("The elements of `{}.{}[]` have a stride of {} bytes, which is not \
+                            a multiple of the size of the `{}` elements of the buffer `{}`.",
+                           layout.block,
+                           element.member,
+                           element.stride,
+                           buffer.ty.name(),
+                           buffer.name);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the SPIR-V module of the shader `path`, which is read from a `.spv` file or compiled
+/// from any other file.
+///
+/// # Panics
+///
+/// If the file cannot be read, or the shader cannot be compiled.
+pub fn load_shader(path: &Path) -> Vec<u32> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .expect(format!("Failed to read the shader: {}", path.display()).as_ref());
+    if path.extension().map_or(true, |extension| extension != "spv") {
+        bytes = compile_glsl(path, &bytes);
+    }
+    if bytes.len() % 4 != 0 {
+        panic!("`{}` is not a SPIR-V module, its size is not a multiple of 4 bytes.",
+               path.display());
+    }
+    bytes.chunks(4).map(<u32 as Scalar>::read_le).collect()
+}
+
+/// Compiles the GLSL compute shader `source` of the file `path` to SPIR-V.
+#[cfg(feature = "glsl-to-spirv")]
+fn compile_glsl(path: &Path, source: &[u8]) -> Vec<u8> {
+    use glsl_to_spirv::{compile, ShaderType};
+
+    let source = String::from_utf8_lossy(source);
+    let mut bytes = Vec::new();
+    compile(&source, ShaderType::Compute)
+        .unwrap_or_else(|error| panic!("Failed to compile {}:\n{}", path.display(), error))
+        .read_to_end(&mut bytes)
+        .expect("Failed to read the compiled shader.");
+    bytes
+}
+
+/// Compiling GLSL requires the optional `glsl-to-spirv` feature.
+#[cfg(not(feature = "glsl-to-spirv"))]
+fn compile_glsl(path: &Path, _: &[u8]) -> Vec<u8> {
+    panic!("`{}` is not a `.spv` file. Compile it with `glslangValidator -V`, or enable the \
+            `glsl-to-spirv` feature to compile it at runtime.",
+           path.display())
+}
+
+/// A device with the buffers of a description, which runs shaders with them.
+#[cfg(feature = "vulkano")]
+pub struct Runner {
+    description: Description,
+    inputs: Vec<Vec<u8>>,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    buffers: Vec<Arc<CpuAccessibleBuffer<[u8]>>>,
+    layout: Arc<Layout>,
+    sets: Sets,
+}
+
+#[cfg(feature = "vulkano")]
+impl Runner {
+    /// Creates a device and allocates the buffers of `description` on it. The physical device can
+    /// be selected like that of `pipeline!`, see the [`overrides`] module.
+    ///
+    /// # Panics
+    ///
+    /// If no device can be created, or if the input of a buffer cannot be loaded.
+    ///
+    /// [`overrides`]: ../overrides/index.html
+    pub fn new(description: Description) -> Runner {
+        let inputs = description.buffers
+            .iter()
+            .map(|buffer| buffer.input_bytes())
+            .collect::<Vec<_>>();
+
+        let instance = Instance::new(None, &InstanceExtensions::none(), None)
+            .expect("Failed to initialize vulkano.");
+        let names = PhysicalDevice::enumerate(&instance)
+            .map(|device| device.name())
+            .collect::<Vec<_>>();
+        let index = overrides::device(&names).unwrap_or(0);
+        let physical_device = PhysicalDevice::from_index(&instance, index)
+            .expect("No physical devices are available.");
+        context::set(context::DeviceContext {
+            name: physical_device.name(),
+            vendor_id: physical_device.pci_vendor_id(),
+            device_id: physical_device.pci_device_id(),
+            driver_version: physical_device.driver_version(),
+            api_version: {
+                let version = physical_device.api_version();
+                [version.major, version.minor, version.patch]
+            },
+        });
+        let _ = writeln!(io::stderr(), "note: running on `{}`.", physical_device.name());
+
+        let queue_family = physical_device.queue_families()
+            .find(|queue_family| queue_family.supports_compute())
+            .expect("Couldn't find a compute queue family.");
+        let (device, mut queues) = Device::new(&physical_device,
+                                               physical_device.supported_features(),
+                                               &DeviceExtensions::none(),
+                                               [(queue_family, 0.5)].iter().cloned())
+            .expect("Failed to create device.");
+        let queue = queues.next().unwrap();
+
+        let buffers = inputs.iter()
+            .map(|input| {
+                CpuAccessibleBuffer::from_iter(&device,
+                                               &BufferUsage::all(),
+                                               Some(queue.family()),
+                                               input.iter().cloned())
+                    .expect("Failed to create a cpu accessible buffer.")
+            })
+            .collect::<Vec<_>>();
+
+        let descriptors = description.buffers
+            .iter()
+            .map(|buffer| {
+                (buffer.set,
+                 DescriptorDesc {
+                     binding: buffer.binding,
+                     ty: DescriptorDescTy::Buffer(DescriptorBufferDesc {
+                         dynamic: Some(false),
+                         storage: true,
+                     }),
+                     array_count: 1,
+                     stages: ShaderStages { compute: true, ..ShaderStages::none() },
+                     readonly: false,
+                 })
+            })
+            .collect::<Vec<_>>();
+        let push_constants_size = if description.push_constants.is_empty() {
+            0
+        } else {
+            MAX_PUSH_CONSTANTS_SIZE
+        };
+        checked::push_constants_size(push_constants_size,
+                                     physical_device.limits().max_push_constants_size());
+        let layout = Layout::new(&device, &descriptors, push_constants_size);
+
+        let pool = DescriptorPool::new(&device);
+        let writes = description.buffers
+            .iter()
+            .zip(&buffers)
+            .map(|(buffer, allocated)| {
+                (buffer.set, DescriptorWrite::storage_buffer(buffer.binding, allocated))
+            })
+            .collect();
+        let sets = Sets::new(&pool, &layout, writes);
+
+        Runner {
+            description: description,
+            inputs: inputs,
+            device: device,
+            queue: queue,
+            buffers: buffers,
+            layout: layout,
+            sets: sets,
+        }
+    }
+
+    /// Writes the inputs to the buffers, runs the SPIR-V module `spirv` of the shader `shader`
+    /// with them and waits for it. The workgroup count and the timeout can be overridden like
+    /// those of `pipeline!`.
+    ///
+    /// # Panics
+    ///
+    /// If the shader does not match the description, see `Description::check_shader`, if the
+    /// device does not support the features which it requires, if its pipeline cannot be created,
+    /// or if it does not finish in time, see the [`hang`] module.
+    ///
+    /// [`hang`]: ../hang/index.html
+    pub fn run(&self, shader: &str, spirv: &[u32]) {
+        self.description.check_shader(shader, spirv);
+        let supported = self.device.enabled_features();
+        let missing = reflection::required_features(&reflection::capabilities(spirv))
+            .into_iter()
+            .filter(|&feature| {
+                !match feature {
+                    "shader_f3264" => supported.shader_f3264,
+                    "shader_int64" => supported.shader_int64,
+                    "shader_int16" => supported.shader_int16,
+                    _ => true,
+                }
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            panic!("The device does not support the features {:?}, which `{}` requires.",
+                   missing,
+                   shader);
+        }
+
+        for (buffer, input) in self.buffers.iter().zip(&self.inputs) {
+            let mut contents = buffer.write(Duration::new(1, 0))
+                .expect("Failed to write the inputs.");
+            contents.copy_from_slice(input);
+        }
+
+        let mut bytes = Vec::with_capacity(spirv.len() * 4);
+        for word in spirv {
+            word.write_le(&mut bytes);
+        }
+        let module = unsafe { ShaderModule::new(self.device.clone(), &bytes) }
+            .expect("Failed to create shader module.");
+        let entry_point = unsafe {
+            module.compute_shader_entry_point::<(), _>(CStr::from_bytes_with_nul(b"main\0")
+                                                           .unwrap(),
+                                                       EmptyPipelineDesc)
+        };
+        let pipeline = ComputePipeline::new(&self.device, &self.layout, &entry_point, &())
+            .unwrap_or_else(|error| {
+                panic!("Failed to create the compute pipeline of `{}`: {:?}", shader, error)
+            });
+
+        let workgroup_count = overrides::workgroup_count(self.description.workgroup_count);
+        let builder = PrimaryCommandBufferBuilder::new(&self.device, self.queue.family());
+        let command = if self.description.push_constants.is_empty() {
+            builder.dispatch(&pipeline, self.sets.clone(), workgroup_count, &()).build()
+        } else {
+            let mut push_constants = [0u32; MAX_PUSH_CONSTANTS_SIZE / 4];
+            for (word, bytes) in push_constants.iter_mut()
+                .zip(self.description.push_constants.chunks(4)) {
+                let mut padded = bytes.to_vec();
+                padded.resize(4, 0);
+                *word = <u32 as Scalar>::read_le(&padded);
+            }
+            builder.dispatch(&pipeline, self.sets.clone(), workgroup_count, &push_constants)
+                .build()
+        };
+
+        let submission = submit(&command, &self.queue).expect("Failed to submit the shader.");
+        let default_timeout = Duration::from_secs(hang::DEFAULT_TIMEOUT_SECS);
+        let timeout = overrides::timeout(default_timeout);
+        match submission.wait(timeout) {
+            Ok(()) => {}
+            Err(FenceWaitError::Timeout) => {
+                // Dropping the submission would wait for the shader forever.
+                mem::forget(submission);
+                hang::probable_hang(shader, workgroup_count, timeout);
+            }
+            Err(error) => panic!("Failed to wait for the execution of `{}`: {:?}", shader, error),
+        }
+    }
+
+    /// Writes the buffers with an `output` to their files, prints the elements in the `print`
+    /// ranges, and returns the elements which differ from the `expect` numbers, or nothing if all
+    /// of them match.
+    ///
+    /// # Panics
+    ///
+    /// If an output file cannot be written.
+    pub fn report(&self) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        for (buffer, allocated) in self.description.buffers.iter().zip(&self.buffers) {
+            let contents = allocated.read(Duration::new(1, 0))
+                .expect("Failed to read the outputs.");
+            let size = buffer.ty.size();
+
+            if let Some(ref path) = buffer.output {
+                buffer.ty.export(path, &buffer.name, &contents);
+            }
+
+            if let Some(ref print) = buffer.print {
+                let len = contents.len() / size;
+                let (start, end) = (print.start.min(len), print.end.min(len));
+                let elements = contents[start * size..end * size]
+                    .chunks(size)
+                    .map(|bytes| buffer.ty.decode(bytes))
+                    .collect::<Vec<_>>();
+                println!("{}[{}..{}]: [{}]", buffer.name, start, end, elements.join(", "));
+            }
+
+            let expected = buffer.expect.chunks(size);
+            for (index, (actual, expected)) in contents.chunks(size).zip(expected).enumerate() {
+                if !buffer.ty.equal(actual, expected) {
+                    mismatches.push(format!("{}[{}] is {}, expected {}",
+                                            buffer.name,
+                                            index,
+                                            buffer.ty.decode(actual),
+                                            buffer.ty.decode(expected)));
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+/// The pipeline layout of a description, which is created at runtime like the layout of
+/// `pipeline!`.
+#[cfg(feature = "vulkano")]
+struct Layout {
+    inner: UnsafePipelineLayout,
+    num_sets: usize,
+}
+
+#[cfg(feature = "vulkano")]
+impl Layout {
+    /// Creates a layout from the `(set, descriptor)` pairs of all descriptors, and the size of
+    /// the push constants. Sets which contain no descriptor are left empty.
+    fn new(device: &Arc<Device>,
+           descriptors: &[(u32, DescriptorDesc)],
+           push_constants_size: usize)
+           -> Arc<Layout> {
+        let num_sets = descriptors.iter()
+            .map(|&(set, _)| set as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let layouts = (0..num_sets)
+            .map(|set_num| {
+                let set_descriptors = descriptors.iter()
+                    .filter(|&&(set, _)| set as usize == set_num)
+                    .map(|&(_, desc)| desc);
+                Arc::new(UnsafeDescriptorSetLayout::raw(device.clone(), set_descriptors)
+                    .expect("Failed to create a descriptor set layout."))
+            })
+            .collect::<Vec<_>>();
+        let push_constants = if push_constants_size >= 1 {
+            Some((0, push_constants_size, ShaderStages { compute: true, ..ShaderStages::none() }))
+        } else {
+            None
+        };
+        let inner = UnsafePipelineLayout::new(device, layouts.iter(), push_constants)
+            .expect("Failed to create pipeline layout.");
+        Arc::new(Layout {
+            inner: inner,
+            num_sets: num_sets,
+        })
+    }
+}
+
+#[cfg(feature = "vulkano")]
+unsafe impl PipelineLayout for Layout {
+    #[inline]
+    fn inner(&self) -> &UnsafePipelineLayout {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "vulkano")]
+unsafe impl PipelineLayoutDesc for Layout {
+    type SetsIter = VecIntoIter<Self::DescIter>;
+    type DescIter = VecIntoIter<DescriptorDesc>;
+
+    fn descriptors_desc(&self) -> Self::SetsIter {
+        // Like vulkano's `pipeline_layout!`, the layout does not describe itself.
+        vec![].into_iter()
+    }
+}
+
+/// A descriptor set of a `Layout`.
+#[cfg(feature = "vulkano")]
+struct Set {
+    inner: UnsafeDescriptorSet,
+}
+
+#[cfg(feature = "vulkano")]
+unsafe impl DescriptorSet for Set {
+    #[inline]
+    fn inner(&self) -> &UnsafeDescriptorSet {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "vulkano")]
+unsafe impl DescriptorSetDesc for Set {
+    type Iter = VecIntoIter<DescriptorDesc>;
+
+    #[inline]
+    fn desc(&self) -> Self::Iter {
+        vec![].into_iter()
+    }
+}
+
+/// One descriptor set for every set of a `Layout`, starting with set 0.
+#[cfg(feature = "vulkano")]
+#[derive(Clone)]
+struct Sets(Vec<Arc<Set>>);
+
+#[cfg(feature = "vulkano")]
+impl Sets {
+    /// Allocates the sets and writes the `(set, write)` pairs into them.
+    fn new(pool: &Arc<DescriptorPool>,
+           layout: &Arc<Layout>,
+           writes: Vec<(u32, DescriptorWrite)>)
+           -> Sets {
+        let mut set_writes = (0..layout.num_sets).map(|_| Vec::new()).collect::<Vec<_>>();
+        for (set, write) in writes {
+            set_writes[set as usize].push(write);
+        }
+
+        let sets = set_writes.into_iter()
+            .enumerate()
+            .map(|(set_num, writes)| unsafe {
+                let set_layout = layout.inner.descriptor_set_layout(set_num).unwrap();
+                let mut set = UnsafeDescriptorSet::uninitialized_raw(pool, set_layout)
+                    .expect("Failed to allocate a descriptor set.");
+                set.write(writes);
+                Arc::new(Set { inner: set })
+            })
+            .collect();
+        Sets(sets)
+    }
+}
+
+#[cfg(feature = "vulkano")]
+unsafe impl DescriptorSetsCollection for Sets {
+    type ListIter = VecIntoIter<Arc<DescriptorSet>>;
+    type SetsIter = VecIntoIter<Self::DescIter>;
+    type DescIter = VecIntoIter<DescriptorDesc>;
+
+    fn list(&self) -> Self::ListIter {
+        self.0
+            .iter()
+            .map(|set| set.clone() as Arc<DescriptorSet>)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn description(&self) -> Self::SetsIter {
+        self.0.iter().map(|set| set.desc()).collect::<Vec<_>>().into_iter()
+    }
+}
+
+/// A JSON value of a description. Numbers are kept as text, so that they are parsed by the
+/// scalar type which they are given for, without rounding 64-bit integers. `true`, `false` and
+/// `null` are parsed as a `Literal`, as no key of a description takes them.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Literal,
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// A parser of JSON documents.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {
+            bytes: text.as_bytes(),
+            position: 0,
+        }
+    }
+
+    /// Parses the whole text as a single value.
+    fn document(&mut self) -> Result<Json, String> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.position < self.bytes.len() {
+            return Err(self.error("Unexpected characters after the value"));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at byte {}", message, self.position)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.position).map_or(false, |&byte| {
+            byte == b' ' || byte == b'\t' || byte == b'\n' || byte == b'\r'
+        }) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips the whitespace and returns the next byte without consuming it.
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.position).cloned()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{}`", byte as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(byte) if byte == b'-' || (b'0' <= byte && byte <= b'9') => Ok(self.number()),
+            _ => Err(self.error("Expected a value")),
+        }
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<Json, String> {
+        if self.bytes[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(Json::Literal)
+        } else {
+            Err(self.error("Expected a value"))
+        }
+    }
+
+    fn number(&mut self) -> Json {
+        let start = self.position;
+        while self.bytes.get(self.position).map_or(false, |&byte| {
+            (b'0' <= byte && byte <= b'9') || b"+-.eE".contains(&byte)
+        }) {
+            self.position += 1;
+        }
+        Json::Number(String::from_utf8_lossy(&self.bytes[start..self.position]).into_owned())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = match self.bytes.get(self.position) {
+                Some(&byte) => byte,
+                None => return Err(self.error("Unterminated string")),
+            };
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self.bytes.get(self.position).cloned();
+                    self.position += 1;
+                    let unescaped = match escaped {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let digits = self.bytes
+                                .get(self.position..self.position + 4)
+                                .and_then(|digits| ::std::str::from_utf8(digits).ok())
+                                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                                .and_then(::std::char::from_u32);
+                            self.position += 4;
+                            match digits {
+                                Some(character) => character,
+                                None => return Err(self.error("Invalid `\\u` escape")),
+                            }
+                        }
+                        _ => return Err(self.error("Invalid escape")),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut buffer).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+        // The text is valid UTF-8 and escapes are only split at ASCII characters.
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("Expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected a key"));
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            members.push((key, self.value()?));
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("Expected `,` or `}`")),
+            }
+        }
+    }
+}
+
+/// Returns the value of the key `key` of the object `object`.
+fn field<'a>(object: &'a [(String, Json)], key: &str) -> Option<&'a Json> {
+    object.iter().find(|&&(ref name, _)| name == key).map(|&(_, ref value)| value)
+}
+
+/// Returns an error if the object `what` has a key which is not one of `keys`.
+fn check_keys(object: &[(String, Json)], keys: &[&str], what: &str) -> Result<(), String> {
+    match object.iter().find(|&&(ref key, _)| !keys.contains(&key.as_str())) {
+        Some(&(ref key, _)) => Err(format!("Unknown key `{}` in {}", key, what)),
+        None => Ok(()),
+    }
+}
+
+fn as_object<'a>(json: &'a Json, what: &str) -> Result<&'a [(String, Json)], String> {
+    match *json {
+        Json::Object(ref members) => Ok(members),
+        _ => Err(format!("Expected an object for {}", what)),
+    }
+}
+
+/// Returns the elements of the array `json`, which is empty if it is missing.
+fn as_array<'a>(json: Option<&'a Json>, what: &str) -> Result<&'a [Json], String> {
+    match json {
+        Some(&Json::Array(ref values)) => Ok(values),
+        None => Ok(&[]),
+        Some(_) => Err(format!("Expected an array for {}", what)),
+    }
+}
+
+fn as_number<T: FromStr>(json: &Json, what: &str) -> Result<T, String> {
+    match *json {
+        Json::Number(ref number) => {
+            T::from_str(number).map_err(|_| format!("Invalid number `{}` in {}", number, what))
+        }
+        _ => Err(format!("Expected a number for {}", what)),
+    }
+}
+
+fn as_path(json: &Json, what: &str) -> Result<PathBuf, String> {
+    match *json {
+        Json::String(ref path) => Ok(PathBuf::from(path)),
+        _ => Err(format!("Expected a path for {}", what)),
+    }
+}
+
+/// Returns the little endian bytes of the numbers of the array `json`.
+fn as_values(json: &Json, ty: ScalarType, what: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    for value in as_array(Some(json), what)? {
+        match *value {
+            Json::Number(ref number) => {
+                ty.encode(number, &mut bytes).map_err(|error| format!("{} in {}", error, what))?
+            }
+            _ => return Err(format!("Expected a number in {}", what)),
+        }
+    }
+    Ok(bytes)
+}
+
+fn scalar_type(object: &[(String, Json)], what: &str) -> Result<ScalarType, String> {
+    match field(object, "type") {
+        Some(&Json::String(ref name)) => {
+            ScalarType::from_name(name).ok_or_else(|| format!("Unknown type `{}` of {}", name, what))
+        }
+        _ => Err(format!("Expected a `type` for {}", what)),
+    }
+}
+
+/// Parses the description of the buffer at the position `index`.
+fn buffer_description(json: &Json, index: u32) -> Result<BufferDescription, String> {
+    let object = as_object(json, "a buffer")?;
+    let name = match field(object, "name") {
+        Some(&Json::String(ref name)) => name.clone(),
+        _ => return Err("A buffer needs a `name`".to_owned()),
+    };
+    let what = format!("the buffer `{}`", name);
+    check_keys(object,
+               &["name", "set", "binding", "type", "len", "input", "output", "print", "expect"],
+               &what)?;
+    let ty = scalar_type(object, &what)?;
+
+    let input = match field(object, "input") {
+        None => Input::Zeroed,
+        Some(&Json::String(ref path)) => Input::File(PathBuf::from(path)),
+        Some(values) => Input::Values(as_values(values, ty, &what)?),
+    };
+    let len = match field(object, "len") {
+        Some(len) => Some(as_number::<usize>(len, &what)?),
+        None if input == Input::Zeroed => {
+            return Err(format!("The buffer `{}` needs a `len` or an `input`", name))
+        }
+        None => None,
+    };
+    let print = match field(object, "print") {
+        Some(&Json::Array(ref range)) if range.len() == 2 => {
+            Some(as_number(&range[0], &what)?..as_number(&range[1], &what)?)
+        }
+        Some(_) => return Err(format!("Expected a `print` range `[start, end]` for {}", what)),
+        None => None,
+    };
+
+    Ok(BufferDescription {
+        set: field(object, "set").map_or(Ok(0), |set| as_number(set, &what))?,
+        binding: field(object, "binding").map_or(Ok(index), |binding| as_number(binding, &what))?,
+        ty: ty,
+        len: len,
+        input: input,
+        output: match field(object, "output") {
+            Some(path) => Some(as_path(path, &what)?),
+            None => None,
+        },
+        print: print,
+        expect: match field(object, "expect") {
+            Some(values) => as_values(values, ty, &what)?,
+            None => Vec::new(),
+        },
+        name: name,
+    })
+}
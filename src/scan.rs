@@ -0,0 +1,215 @@
+//! This module verifies the results of prefix sum (scan) shaders.
+//!
+//! A bug in a scan shader usually breaks the running sum at a single index, e.g. at the boundary
+//! between two workgroups, and every following element inherits the error. An elementwise
+//! comparison reports all of these elements, while the helpers of this module report where the
+//! prefix first diverges and whether the error is carried on. All sums wrap around like `uint`
+//! arithmetic in GLSL.
+
+/// The kind of a scan.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scan {
+    /// Element `i` is the sum of the elements `0..=i`.
+    Inclusive,
+    /// Element `i` is the sum of the elements `0..i`.
+    Exclusive,
+}
+
+impl Scan {
+    fn name(self) -> &'static str {
+        match self {
+            Scan::Inclusive => "inclusive",
+            Scan::Exclusive => "exclusive",
+        }
+    }
+}
+
+/// Returns `len` deterministic pseudo-random elements smaller than 256, which is small enough to
+/// recognize the prefix sums in a failure message.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::input;
+///
+/// let data = input(1000, 42);
+/// assert_eq!(data, input(1000, 42));
+/// assert!(data.iter().all(|&x| x < 256));
+/// # }
+/// ```
+///
+pub fn input(len: usize, seed: u32) -> Vec<u32> {
+    (0..len as u32)
+        .map(|index| {
+            // The finalizer of MurmurHash3, like the `hash` segment.
+            let mut x = index ^ seed.wrapping_mul(0x9e3779b9);
+            x ^= x >> 16;
+            x = x.wrapping_mul(0x85ebca6b);
+            x ^= x >> 13;
+            x = x.wrapping_mul(0xc2b2ae35);
+            x ^= x >> 16;
+            x & 0xff
+        })
+        .collect()
+}
+
+/// Returns the scan of `input`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::{reference, Scan};
+///
+/// assert_eq!(reference(Scan::Inclusive, &[1, 2, 3]), vec![1, 3, 6]);
+/// assert_eq!(reference(Scan::Exclusive, &[1, 2, 3]), vec![0, 1, 3]);
+/// # }
+/// ```
+///
+pub fn reference(scan: Scan, input: &[u32]) -> Vec<u32> {
+    segmented_reference(scan, input, &[])
+}
+
+/// Returns the segmented scan of `input`. A new segment, whose sum starts at 0, begins at every
+/// index `i` for which `heads[i]` is true. Missing flags are false.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::{segmented_reference, Scan};
+///
+/// let heads = [true, false, true, false];
+/// assert_eq!(segmented_reference(Scan::Inclusive, &[1, 2, 3, 4], &heads), vec![1, 3, 3, 7]);
+/// assert_eq!(segmented_reference(Scan::Exclusive, &[1, 2, 3, 4], &heads), vec![0, 1, 0, 3]);
+/// # }
+/// ```
+///
+pub fn segmented_reference(scan: Scan, input: &[u32], heads: &[bool]) -> Vec<u32> {
+    let mut sum = 0u32;
+    input.iter()
+        .enumerate()
+        .map(|(index, &x)| {
+            if heads.get(index).cloned().unwrap_or(false) {
+                sum = 0;
+            }
+            let exclusive = sum;
+            sum = sum.wrapping_add(x);
+            match scan {
+                Scan::Inclusive => sum,
+                Scan::Exclusive => exclusive,
+            }
+        })
+        .collect()
+}
+
+/// Compares `actual` against the scan of `input`.
+///
+/// # Panics
+///
+/// If the lengths differ, or if the scans diverge. The message names the first index at which
+/// `actual` diverges, and whether all following elements are off by the same amount, which means
+/// that the error was carried on by the running sum.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::{assert_scan_eq, Scan};
+///
+/// assert_scan_eq(Scan::Inclusive, &[1, 2, 3, 4], &[1, 3, 6, 10]);
+/// # }
+/// ```
+///
+/// The element at index 2 was added twice:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::{assert_scan_eq, Scan};
+///
+/// assert_scan_eq(Scan::Inclusive, &[1, 2, 3, 4], &[1, 3, 9, 13]);
+/// # }
+/// ```
+///
+pub fn assert_scan_eq(scan: Scan, input: &[u32], actual: &[u32]) {
+    assert_segmented_scan_eq(scan, input, &[], actual);
+}
+
+/// Compares `actual` against the segmented scan of `input`, see `segmented_reference`.
+///
+/// # Panics
+///
+/// Like `assert_scan_eq`. The message additionally names the segment of the first divergent
+/// index.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::scan::{assert_segmented_scan_eq, Scan};
+///
+/// let heads = [true, false, true, false];
+/// assert_segmented_scan_eq(Scan::Exclusive, &[1, 2, 3, 4], &heads, &[0, 1, 0, 3]);
+/// # }
+/// ```
+///
+pub fn assert_segmented_scan_eq(scan: Scan, input: &[u32], heads: &[bool], actual: &[u32]) {
+    if input.len() != actual.len() {
+        panic!("The {} scan has {} elements, expected {}.",
+               scan.name(),
+               actual.len(),
+               input.len());
+    }
+
+    let expected = segmented_reference(scan, input, heads);
+    let diverged = expected.iter().zip(actual).position(|(expected, actual)| expected != actual);
+    let index = match diverged {
+        Some(index) => index,
+        None => return,
+    };
+
+    let is_head = |index: usize| heads.get(index).cloned().unwrap_or(false);
+    let segment_start = (0..index + 1).rev().find(|&i| is_head(i)).unwrap_or(0);
+    let segment_end = (index + 1..input.len()).find(|&i| is_head(i)).unwrap_or(input.len());
+    let mismatches = expected.iter().zip(actual).filter(|&(expected, actual)| expected != actual);
+
+    // If the rest of the segment is off by the same amount, the running sum is wrong from this
+    // index on, otherwise the elements are wrong individually.
+    let offset = actual[index].wrapping_sub(expected[index]);
+    let carried = (index + 1..segment_end).all(|i| actual[i].wrapping_sub(expected[i]) == offset);
+    let diagnosis = if index + 1 == segment_end {
+        "it is the last element of its segment".to_owned()
+    } else if carried {
+        format!("the remaining {} elements of its segment are off by the same amount {}, so the \
+                 running sum is wrong from this index on",
+                segment_end - index - 1,
+                offset as i32)
+    } else {
+        "the following elements of its segment are not off by the same amount".to_owned()
+    };
+
+    panic!("The {} scan diverges at index {} in the segment {}..{}: {} != {} (input {}), {}. {} \
+            mismatches in total.",
+           scan.name(),
+           index,
+           segment_start,
+           segment_end,
+           actual[index],
+           expected[index],
+           input[index],
+           diagnosis,
+           mismatches.count());
+}
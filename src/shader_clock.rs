@@ -0,0 +1,90 @@
+//! This module decodes the timing records written by the `shader_clock` GLSL segment, see
+//! `build_utils::builtin_segment`.
+//!
+//! The segment measures sections of a shader with `clockRealtime2x32EXT()`. Every record
+//! consists of four `u32`: the low and high bits of the start timestamp, followed by those of the
+//! end timestamp. The timestamps are in ticks of the device-wide clock, whose period in
+//! nanoseconds is the `timestampPeriod` limit of the device.
+//!
+//! Note that `VK_KHR_shader_clock` cannot be enabled with the version of `vulkano` used by
+//! `pipeline!`, which therefore rejects shaders declaring the `ShaderClockKHR` capability.
+
+/// The number of `u32` of a single timing record.
+pub const RECORD_LEN: usize = 4;
+
+/// A section measured in a shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClockRecord {
+    /// The timestamp at the start of the section.
+    pub start: u64,
+    /// The timestamp at the end of the section.
+    pub end: u64,
+}
+
+impl ClockRecord {
+    /// Returns the duration of the section in clock ticks.
+    pub fn ticks(&self) -> u64 {
+        self.end.wrapping_sub(self.start)
+    }
+}
+
+/// Decodes the timing records in `data`.
+///
+/// # Panics
+///
+/// If the length of `data` is not a multiple of `RECORD_LEN`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::shader_clock::decode;
+///
+/// let records = decode(&[0xffff_fff0, 1, 0x10, 2]);
+/// assert_eq!(records[0].start, 0x1_ffff_fff0);
+/// assert_eq!(records[0].ticks(), 0x20);
+/// # }
+/// ```
+///
+pub fn decode(data: &[u32]) -> Vec<ClockRecord> {
+    if data.len() % RECORD_LEN != 0 {
+        panic!("The timing data has {} elements, which is not a multiple of {}.",
+               data.len(),
+               RECORD_LEN);
+    }
+    data.chunks(RECORD_LEN)
+        .map(|record| {
+            ClockRecord {
+                start: (record[1] as u64) << 32 | record[0] as u64,
+                end: (record[3] as u64) << 32 | record[2] as u64,
+            }
+        })
+        .collect()
+}
+
+/// Returns the minimum, the median and the maximum duration of `records` in clock ticks, or
+/// `None` if there are no records.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::shader_clock::{decode, summary};
+///
+/// let records = decode(&[0, 0, 10, 0, 5, 0, 35, 0, 100, 0, 120, 0]);
+/// assert_eq!(summary(&records), Some((10, 20, 30)));
+/// # }
+/// ```
+///
+pub fn summary(records: &[ClockRecord]) -> Option<(u64, u64, u64)> {
+    let mut ticks = records.iter().map(ClockRecord::ticks).collect::<Vec<_>>();
+    ticks.sort();
+    match (ticks.first(), ticks.last()) {
+        (Some(&min), Some(&max)) => Some((min, ticks[ticks.len() / 2], max)),
+        _ => None,
+    }
+}
@@ -0,0 +1,196 @@
+//! This module checks that a shader computes the same outputs for every shape of the dispatch
+//! which covers the same problem. Many synchronization bugs, e.g. a missing `barrier()` or a
+//! race between workgroups, only show with particular workgroup sizes or numbers of workgroups
+//! in flight.
+//!
+//! `assert_shape_independent!` creates the pipeline for every `Shape`, whose workgroup count
+//! and local size it passes to the parameters of `pipeline!`, executes it once, and compares the
+//! output buffers against those of the first shape. The local size is set with a
+//! specialization constant, declared with `layout(local_size_x_id = 0) in;` in the shader:
+//!
+//! ```ignore
+//! assert_shape_independent!{
+//!     shader_path: "tests/shaders/shape_independent.comp",
+//!     shapes: shapes::covering([4096, 1, 1], &[[32, 1, 1], [64, 1, 1], [256, 1, 1]]),
+//!     pipeline: |shape| {
+//!         workgroup_count: shape.workgroup_count,
+//!         spec_constants: { local_size: u32 = shape.local_size[0] },
+//!         buffers: {
+//!             data: [u32] from input_vec,
+//!             result: [u32; 4096]
+//!         }
+//!     },
+//!     outputs: [result]
+//! };
+//! ```
+//!
+//! The inputs must be given in the `buffers` with their contents, e.g. with `from`, like for
+//! `ab_compare!`. The outputs are compared exactly, with the `ab` module.
+
+use std::fmt;
+
+use ab::Divergence;
+
+/// The shape of a dispatch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Shape {
+    /// The number of workgroups in every dimension.
+    pub workgroup_count: [u32; 3],
+    /// The size of a workgroup in every dimension.
+    pub local_size: [u32; 3],
+}
+
+impl Shape {
+    /// Returns the number of invocations in every dimension, i.e. the workgroup count times the
+    /// local size.
+    pub fn extent(&self) -> [u32; 3] {
+        [self.workgroup_count[0] * self.local_size[0],
+         self.workgroup_count[1] * self.local_size[1],
+         self.workgroup_count[2] * self.local_size[2]]
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "workgroup count {:?} with local size {:?}",
+               self.workgroup_count,
+               self.local_size)
+    }
+}
+
+/// Returns the shapes of the dispatches with the local sizes `local_sizes` which cover the
+/// `extent` invocations exactly.
+///
+/// # Panics
+///
+/// If a local size does not divide the extent in every dimension.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::shapes::covering;
+///
+/// let shapes = covering([256, 16, 1], &[[64, 1, 1], [8, 8, 1]]);
+/// assert_eq!(shapes[0].workgroup_count, [4, 16, 1]);
+/// assert_eq!(shapes[1].workgroup_count, [32, 2, 1]);
+/// assert_eq!(shapes[1].extent(), [256, 16, 1]);
+/// # }
+/// ```
+///
+pub fn covering(extent: [u32; 3], local_sizes: &[[u32; 3]]) -> Vec<Shape> {
+    local_sizes.iter()
+        .map(|&local_size| {
+            if (0..3).any(|dim| local_size[dim] == 0 || extent[dim] % local_size[dim] != 0) {
+                panic!("The local size {:?} does not divide the extent {:?}.",
+                       local_size,
+                       extent);
+            }
+            Shape {
+                workgroup_count: [extent[0] / local_size[0],
+                                  extent[1] / local_size[1],
+                                  extent[2] / local_size[2]],
+                local_size: local_size,
+            }
+        })
+        .collect()
+}
+
+/// An output buffer of a shape which differs from the output of the first shape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeDivergence {
+    /// The shape of the dispatch.
+    pub shape: Shape,
+    /// The difference of the buffer from the first shape, whose `reference` is the first shape
+    /// and whose `candidate` is `shape`.
+    pub divergence: Divergence,
+}
+
+impl fmt::Display for ShapeDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "`{}` differs with the {} in {} elements, first at index {}: {} != {}",
+               self.divergence.buffer,
+               self.shape,
+               self.divergence.mismatches,
+               self.divergence.first,
+               self.divergence.reference,
+               self.divergence.candidate)
+    }
+}
+
+/// Asserts that no shape of the dispatches of the shader `shader_path` computed outputs which
+/// differ from the first shape `first`.
+///
+/// # Panics
+///
+/// If any output differs, with every difference.
+pub fn assert_shape_independent(shader_path: &str, first: Shape, found: &[ShapeDivergence]) {
+    if !found.is_empty() {
+        let found = found.iter().map(|found| format!("  {}", found)).collect::<Vec<_>>();
+        panic!("The outputs of `{}` depend on the shape of the dispatch, which suggests a \
+                missing synchronization. Compared to the {}:\n{}",
+               shader_path,
+               first,
+               found.join("\n"));
+    }
+}
+
+/// Executes a shader with several shapes of the dispatch and asserts that its outputs are the
+/// same for every shape, see the [`shapes`] module. `shapes` is a list of `shapes::Shape`, e.g.
+/// from `shapes::covering`. The `pipeline` parameters are passed to [`pipeline!`] for every
+/// shape, which is bound to the name given between the bars, together with the `shader_path`
+/// and an execution command. The buffers named in `outputs` are compared after every execution.
+///
+/// # Panics
+///
+/// If an output of a shape differs from the first shape, with every difference, or if fewer
+/// than two shapes are given.
+///
+/// [`shapes`]: shapes/index.html
+/// [`pipeline!`]: macro.pipeline.html
+///
+#[macro_export]
+macro_rules! assert_shape_independent {
+    {
+        shader_path: $shader_path:expr,
+        shapes: $shapes:expr,
+        pipeline: |$shape:ident| { $( $pipeline_args:tt )* },
+        outputs: [ $( $output:ident ),* $(,)* ] $(,)*
+    } => ({
+        use vulkanology::{ab, shapes};
+
+        let all_shapes: Vec<shapes::Shape> = $shapes.into_iter().collect();
+        if all_shapes.len() < 2 {
+            panic!("The shape independence of a shader needs at least 2 shapes, not {}.",
+                   all_shapes.len());
+        }
+        let first_outputs: Vec<_> = {
+            let $shape = all_shapes[0];
+            pipeline!{ shader_path: $shader_path, $( $pipeline_args )*, execution_command: execute }
+            execute();
+            // The locks must be released before the buffers of the block are dropped.
+            let outputs = vec![ $( ab::record_output(&read_buffer!($output)) ),* ];
+            outputs
+        };
+        let mut found = Vec::new();
+        for &shape in &all_shapes[1..] {
+            let $shape = shape;
+            pipeline!{ shader_path: $shader_path, $( $pipeline_args )*, execution_command: execute }
+            execute();
+            let mut first_outputs = first_outputs.iter();
+            $(
+                let divergence = ab::compare_output(stringify!($output),
+                                                    &**first_outputs.next().unwrap(),
+                                                    &read_buffer!($output));
+                if let Some(divergence) = divergence {
+                    found.push(shapes::ShapeDivergence { shape: shape, divergence: divergence });
+                }
+            )*
+        }
+        shapes::assert_shape_independent($shader_path, all_shapes[0], &found);
+    });
+}
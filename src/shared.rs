@@ -0,0 +1,108 @@
+//! This module keeps the Vulkan objects which the tests of a binary share.
+//!
+//! By default, every `pipeline!` creates its own instance, device and queue. With
+//! `context: shared`, it instead reuses the objects which the first such test of the binary
+//! created, so the device is created once per test binary. A context is shared between the tests
+//! which have the same `validation` and run on the same physical device, and it is never
+//! destroyed, so drivers which leak resources per instance only leak them once.
+//!
+//! Tests which run concurrently on a shared context submit to the same queue, and with
+//! `validation: strict`, every one of them reports the messages of the validation layers caused
+//! by the others. Run them on a single thread, e.g. with the [`harness`], to keep their reports
+//! apart.
+//!
+//! [`harness`]: ../harness/index.html
+
+use std::any::Any;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+thread_local! {
+    static REQUESTED: Cell<bool> = Cell::new(false);
+    static SCOPES: Cell<usize> = Cell::new(0);
+}
+
+lazy_static! {
+    /// The shared objects, by their key.
+    static ref REGISTRY: Mutex<HashMap<String, Box<Any + Send>>> = Mutex::new(HashMap::new());
+}
+
+/// Requests a shared context for the next `pipeline!` of the current thread. `pipeline!` calls
+/// this function with `context: shared`, and keeps the request until the end of the test, so it
+/// does not carry over to the next test of the thread if the test returns before the context is
+/// created.
+pub fn request() -> Request {
+    REQUESTED.with(|requested| requested.set(true));
+    Request(())
+}
+
+/// A request for a shared context, which is withdrawn when it is dropped.
+#[derive(Debug)]
+pub struct Request(());
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        // The thread local may already be destroyed while the thread exits.
+        let _ = REQUESTED.try_with(|requested| requested.set(false));
+    }
+}
+
+/// Requests a shared context for every `pipeline!` of the current thread until the returned
+/// scope is dropped, e.g. for all cases of a property test, see `quickcheck_adapter::check`.
+pub fn request_scope() -> Scope {
+    SCOPES.with(|scopes| scopes.set(scopes.get() + 1));
+    Scope(())
+}
+
+/// A request for a shared context of all pipelines, which is withdrawn when it is dropped.
+#[derive(Debug)]
+pub struct Scope(());
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let _ = SCOPES.try_with(|scopes| scopes.set(scopes.get() - 1));
+    }
+}
+
+/// Returns whether a shared context was requested, and resets the request of the next
+/// `pipeline!`.
+pub fn take_request() -> bool {
+    REQUESTED.with(|requested| requested.replace(false)) || SCOPES.with(|scopes| scopes.get() > 0)
+}
+
+/// Returns the object stored under `key`, which is created with `init` if there is none yet.
+/// Other threads wait until it is created.
+///
+/// # Panics
+///
+/// If the object stored under `key` is not of the type `T`, or if `init` panics.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::sync::Arc;
+/// use vulkanology::shared::get_or_init;
+///
+/// let first = get_or_init("example", || Arc::new(42));
+/// let second = get_or_init("example", || Arc::new(0));
+/// assert!(Arc::ptr_eq(&first, &second));
+/// # }
+/// ```
+///
+pub fn get_or_init<T, F>(key: &str, init: F) -> T
+    where T: Any + Clone + Send + Sync,
+          F: FnOnce() -> T
+{
+    // A panic of `init` leaves the registry unchanged, so the next test retries the creation and
+    // fails with its own error.
+    let mut objects = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let object = objects.entry(key.to_owned()).or_insert_with(|| Box::new(init()));
+    match object.downcast_ref::<T>() {
+        Some(object) => object.clone(),
+        None => panic!("The shared object `{}` has a different type.", key),
+    }
+}
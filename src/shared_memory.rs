@@ -0,0 +1,47 @@
+//! This module contains helpers for checking the shared memory layouts of shaders on the host.
+//!
+//! Shared memory is divided into banks, and the accesses of a subgroup to distinct words of the
+//! same bank are serialized. Padded layouts avoid these bank conflicts. Since the conflicts only
+//! affect the performance, a test cannot observe them in the results. Instead, the offsets which
+//! the layout assigns to the invocations can be computed on the host and checked here.
+
+use std::collections::HashSet;
+
+/// Returns the degree of the bank conflict of a single shared memory access, i.e. the maximum
+/// number of distinct words accessed in the same bank.
+///
+/// `offsets` are the byte offsets accessed by the invocations of a subgroup. Shared memory is
+/// assumed to consist of `num_banks` banks of `bank_width` bytes. Invocations accessing the same
+/// word do not conflict, as the word is broadcast to them. A degree of 1 means that the access is
+/// free of conflicts.
+///
+/// # Panics
+///
+/// If `num_banks` or `bank_width` is 0.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::shared_memory::bank_conflict_degree;
+///
+/// // Reading a column of a 32x32 `float` tile, which is padded to 33 columns or not.
+/// let column = |width: u32| (0..32).map(|row| row * width * 4).collect::<Vec<_>>();
+/// assert_eq!(bank_conflict_degree(&column(32), 32, 4), 32);
+/// assert_eq!(bank_conflict_degree(&column(33), 32, 4), 1);
+/// # }
+/// ```
+///
+pub fn bank_conflict_degree(offsets: &[u32], num_banks: u32, bank_width: u32) -> usize {
+    if num_banks == 0 || bank_width == 0 {
+        panic!("The number of banks and their width must not be 0.");
+    }
+    let words = offsets.iter().map(|&offset| offset / bank_width).collect::<HashSet<_>>();
+    let mut words_per_bank = vec![0; num_banks as usize];
+    for word in words {
+        words_per_bank[(word % num_banks) as usize] += 1;
+    }
+    words_per_bank.into_iter().max().unwrap_or(0)
+}
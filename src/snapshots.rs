@@ -0,0 +1,314 @@
+//! This module records the buffer contents of a test run and replays them in later runs.
+//!
+//! Snapshots are enabled by setting `VULKANOLOGY_SNAPSHOTS` to a directory. The first run records
+//! the inputs and outputs of a test, e.g. with a passing shader build. Every following run
+//! replays the recorded inputs instead of generating new ones and compares its outputs against
+//! the recorded outputs. Thus a change of the results can be bisected across shader changes,
+//! even if the inputs are random:
+//!
+//! `VULKANOLOGY_SNAPSHOTS=target/snapshots cargo test <name>`
+//!
+//! Delete the directory to record new snapshots. Without the variable, inputs are generated and
+//! outputs are not checked.
+//!
+//! Golden files are snapshots of outputs which are committed with the tests, so the results of
+//! a shader can be checked without reimplementing it on the CPU.
+//! `assert_buffer_snapshot!(result, "name")` records the buffer `result` to
+//! `tests/snapshots/name.bin` in the crate of the test if the file does not exist yet, and
+//! otherwise compares the buffer against it, exactly or within a tolerance like
+//! `assert_buffers_approx_eq!`. Golden files are rewritten with the current outputs by setting
+//! `VULKANOLOGY_UPDATE_SNAPSHOTS`:
+//!
+//! `VULKANOLOGY_UPDATE_SNAPSHOTS=1 cargo test <name>`
+//!
+//! The snapshots store the raw bytes of the elements, so they are only valid for types without
+//! pointers, and only on machines with the same endianness.
+
+use std::env;
+use std::fmt::Debug;
+use std::fs::{create_dir_all, File};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+
+/// The environment variable which sets the snapshot directory and enables the snapshots.
+pub const SNAPSHOTS_VAR: &'static str = "VULKANOLOGY_SNAPSHOTS";
+
+const MAGIC: &'static [u8; 8] = b"VKSNAP01";
+
+/// Returns the input `name`, which is either replayed from the snapshot directory or generated by
+/// `generate`. Generated inputs are recorded if snapshots are enabled.
+///
+/// # Panics
+///
+/// * If the snapshot cannot be read or written.
+/// * If the snapshot was recorded with an element type of a different size.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use vulkanology::snapshots::{input, SNAPSHOTS_VAR};
+///
+/// env::set_var(SNAPSHOTS_VAR, "target/doc_snapshots");
+/// let recorded = input("doc_input", || vec![1u32, 2, 3]);
+/// let replayed = input("doc_input", || vec![4u32, 5, 6]);
+/// assert_eq!(replayed, recorded);
+/// # }
+/// ```
+///
+pub fn input<T, F>(name: &str, generate: F) -> Vec<T>
+    where T: Copy,
+          F: FnOnce() -> Vec<T>
+{
+    let path = match snapshot_path(name) {
+        Some(path) => path,
+        None => return generate(),
+    };
+    if path.exists() {
+        read(&path)
+    } else {
+        let data = generate();
+        write(&path, &data);
+        data
+    }
+}
+
+/// Checks the output `name` against the snapshot directory. The output is recorded if there is no
+/// snapshot yet, and nothing happens if snapshots are disabled.
+///
+/// # Panics
+///
+/// * If the output differs from the snapshot.
+/// * If the snapshot cannot be read or written.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::env;
+/// use std::fs::remove_file;
+/// use vulkanology::snapshots::{output, SNAPSHOTS_VAR};
+///
+/// env::set_var(SNAPSHOTS_VAR, "target/doc_snapshots");
+/// let _ = remove_file("target/doc_snapshots/doc_output.bin");
+/// output("doc_output", &[1u32, 2, 3]);
+/// output("doc_output", &[1u32, 2, 4]);
+/// # }
+/// ```
+///
+pub fn output<T>(name: &str, data: &[T])
+    where T: Copy + PartialEq + Debug
+{
+    let path = match snapshot_path(name) {
+        Some(path) => path,
+        None => return,
+    };
+    if !path.exists() {
+        write(&path, data);
+        return;
+    }
+
+    let recorded = read::<T>(&path);
+    if recorded.len() != data.len() {
+        panic!("The output `{}` has {} elements, but the snapshot {} has {}.",
+               name,
+               data.len(),
+               path.display(),
+               recorded.len());
+    }
+    let mut mismatches = recorded.iter().zip(data).enumerate().filter(|&(_, (a, b))| a != b);
+    if let Some((index, (recorded, actual))) = mismatches.next() {
+        panic!("The output `{}` differs from the snapshot {} at index {}: {:?} != {:?}, {} \
+                mismatches in total.",
+               name,
+               path.display(),
+               index,
+               actual,
+               recorded,
+               mismatches.count() + 1);
+    }
+}
+
+/// The environment variable which makes `assert_buffer_snapshot!` rewrite the golden files.
+pub const UPDATE_VAR: &'static str = "VULKANOLOGY_UPDATE_SNAPSHOTS";
+
+/// The directory of the golden files, relative to the crate of the test.
+pub const GOLDEN_DIR: &'static str = "tests/snapshots";
+
+/// Checks `data` against the golden file `name` in `dir` with `diff`, which is given the output
+/// and the golden file, and returns a report of their differences, e.g. `compare::buffer_diff`.
+/// The golden file is recorded if it does not exist yet, or if `UPDATE_VAR` is set.
+///
+/// # Panics
+///
+/// * If `diff` reports a difference.
+/// * If the golden file cannot be read or written.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use std::fs::remove_file;
+/// use std::path::Path;
+/// use vulkanology::compare::buffer_diff;
+/// use vulkanology::snapshots::golden;
+///
+/// let dir = Path::new("target/doc_golden");
+/// let _ = remove_file(dir.join("doc_golden.bin"));
+/// let diff = |actual: &[u32], golden: &[u32]| buffer_diff("output", actual, "golden", golden, 8);
+/// golden(dir, "doc_golden", &[1u32, 2, 3], &diff);
+/// golden(dir, "doc_golden", &[1u32, 2, 3], &diff);
+/// # }
+/// ```
+///
+pub fn golden<T, F>(dir: &Path, name: &str, data: &[T], diff: F)
+    where T: Copy,
+          F: Fn(&[T], &[T]) -> Option<String>
+{
+    let path = dir.join(name).with_extension("bin");
+    if !path.exists() || env::var_os(UPDATE_VAR).is_some() {
+        write(&path, data);
+        let _ = writeln!(io::stderr(), "note: Recorded the golden file {}.", path.display());
+        return;
+    }
+    if let Some(report) = diff(data, &read::<T>(&path)) {
+        panic!("{}\nThe golden file is {}, set {} to update it.",
+               report,
+               path.display(),
+               UPDATE_VAR);
+    }
+}
+
+/// Returns the path of the snapshot `name`, or `None` if snapshots are disabled.
+fn snapshot_path(name: &str) -> Option<PathBuf> {
+    env::var_os(SNAPSHOTS_VAR).map(|dir| PathBuf::from(dir).join(name).with_extension("bin"))
+}
+
+fn write<T: Copy>(path: &Path, data: &[T]) {
+    create_dir_all(path.parent().unwrap()).expect("Failed to create the snapshot directory.");
+    let bytes = unsafe {
+        slice::from_raw_parts(data.as_ptr() as *const u8, data.len() * mem::size_of::<T>())
+    };
+    let element_size = mem::size_of::<T>() as u32;
+    let element_size = [element_size as u8,
+                        (element_size >> 8) as u8,
+                        (element_size >> 16) as u8,
+                        (element_size >> 24) as u8];
+    File::create(path)
+        .and_then(|mut file| {
+            file.write_all(MAGIC)?;
+            file.write_all(&element_size)?;
+            file.write_all(bytes)
+        })
+        .expect(format!("Failed to write snapshot: {}", path.display()).as_ref());
+}
+
+/// Returns whether `bytes` start like a snapshot file.
+pub fn is_snapshot(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Reads the snapshot file `path` with elements of the type `T`.
+///
+/// # Panics
+///
+/// * If the file cannot be read or is not a snapshot.
+/// * If the snapshot was recorded with an element type of a different size.
+pub fn read<T: Copy>(path: &Path) -> Vec<T> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .expect(format!("Failed to read snapshot: {}", path.display()).as_ref());
+    if bytes.len() < 12 || &bytes[..8] != MAGIC {
+        panic!("The file {} is not a snapshot.", path.display());
+    }
+
+    let element_size = mem::size_of::<T>();
+    let recorded_size = bytes[8..12].iter().rev().fold(0, |size, &byte| size << 8 | byte as usize);
+    let data = &bytes[12..];
+    if recorded_size != element_size || data.len() % element_size != 0 {
+        panic!("The snapshot {} was recorded with elements of {} bytes, expected {}.",
+               path.display(),
+               recorded_size,
+               element_size);
+    }
+
+    let len = data.len() / element_size;
+    let mut result = Vec::with_capacity(len);
+    unsafe {
+        ptr::copy_nonoverlapping(data.as_ptr(), result.as_mut_ptr() as *mut u8, data.len());
+        result.set_len(len);
+    }
+    result
+}
+
+/// Asserts that the buffer `$buffer` equals the golden file `$name`, see the [`snapshots`]
+/// module. The buffer is given like to `assert_buffers_eq!`. Float buffers can be compared
+/// within a tolerance, given like to `assert_buffers_approx_eq!` as `ulps = $ulps`,
+/// `epsilon = $epsilon` or `relative = $relative`.
+///
+/// # Panics
+///
+/// If the buffer differs from the golden file, with the report of `compare::buffer_diff` or
+/// `compare::buffer_approx_diff`.
+///
+/// # Example
+///
+/// ```ignore
+/// let output: ReadLock<[f32]> = read_buffer!(result);
+/// assert_buffer_snapshot!(output, "blur_result", ulps = 4);
+/// ```
+///
+/// [`snapshots`]: snapshots/index.html
+///
+#[macro_export]
+macro_rules! assert_buffer_snapshot {
+    ($buffer:expr, $name:expr) => {{
+        use vulkanology::compare;
+        assert_buffer_snapshot!(__check__ $buffer, $name, |actual, golden| {
+            compare::buffer_diff(stringify!($buffer),
+                                 actual,
+                                 $name,
+                                 golden,
+                                 compare::DEFAULT_DIFF_LIMIT)
+        })
+    }};
+    ($buffer:expr, $name:expr, ulps = $ulps:expr) => {
+        assert_buffer_snapshot!(__approx__ $buffer, $name, Ulps($ulps as u64))
+    };
+    ($buffer:expr, $name:expr, epsilon = $epsilon:expr) => {
+        assert_buffer_snapshot!(__approx__ $buffer, $name, Epsilon($epsilon as f64))
+    };
+    ($buffer:expr, $name:expr, relative = $relative:expr) => {
+        assert_buffer_snapshot!(__approx__ $buffer, $name, Relative($relative as f64))
+    };
+    (__approx__ $buffer:expr, $name:expr, $kind:ident($value:expr)) => {{
+        use vulkanology::compare::{self, Tolerance};
+        if let Some(tolerance) = Tolerance::$kind($value).with_quirks("assert_buffer_snapshot") {
+            assert_buffer_snapshot!(__check__ $buffer, $name, |actual, golden| {
+                compare::buffer_approx_diff(stringify!($buffer),
+                                            actual,
+                                            $name,
+                                            golden,
+                                            tolerance,
+                                            compare::DEFAULT_DIFF_LIMIT)
+            })
+        }
+    }};
+    (__check__ $buffer:expr, $name:expr, $diff:expr) => {{
+        use std::path::Path;
+        use vulkanology::snapshots;
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(snapshots::GOLDEN_DIR);
+        snapshots::golden(&dir, $name, &$buffer[..], $diff)
+    }};
+}
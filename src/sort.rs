@@ -0,0 +1,191 @@
+//! This module verifies the results of sorting shaders, e.g. bitonic and radix sorts.
+//!
+//! The output of a sort is correct if it is sorted and a permutation of the input. For key-value
+//! sorts, every value must stay with its key, and stable sorts must additionally keep the order
+//! of equal keys. The permutation is checked with an order-independent hash of the elements, so
+//! the input does not need to be sorted on the host.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+
+/// Returns an order-independent hash of the multiset of `values`: two slices have the same hash
+/// if they are permutations of each other, and different hashes with a high probability
+/// otherwise.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::multiset_hash;
+///
+/// assert_eq!(multiset_hash(&[3, 1, 2, 1]), multiset_hash(&[1, 1, 2, 3]));
+/// assert!(multiset_hash(&[3, 1, 2, 1]) != multiset_hash(&[3, 1, 2, 2]));
+/// # }
+/// ```
+///
+pub fn multiset_hash<T: Hash>(values: &[T]) -> u64 {
+    // The sum of the element hashes does not depend on the order. The elements are hashed
+    // individually, so equal elements do not cancel out like with xor.
+    values.iter().fold(values.len() as u64, |sum, value| {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        sum.wrapping_add(hasher.finish())
+    })
+}
+
+/// Checks that `keys` is sorted in ascending order.
+///
+/// # Panics
+///
+/// If two neighbouring keys are out of order. The message names the first such index and the
+/// number of such indices.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::assert_sorted;
+///
+/// assert_sorted(&[1, 1, 2, 3]);
+/// # }
+/// ```
+///
+/// The keys 3 and 2 are out of order:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::assert_sorted;
+///
+/// assert_sorted(&[1, 3, 2, 4]);
+/// # }
+/// ```
+///
+pub fn assert_sorted<T: Ord + Debug>(keys: &[T]) {
+    let mut inversions = keys.windows(2).enumerate().filter(|&(_, pair)| pair[0] > pair[1]);
+    if let Some((index, pair)) = inversions.next() {
+        panic!("The keys are not sorted at index {}: {:?} > {:?}, {} unsorted neighbours in total.",
+               index,
+               pair[0],
+               pair[1],
+               inversions.count() + 1);
+    }
+}
+
+/// Checks that `output` is a permutation of `input` by comparing their `multiset_hash`.
+///
+/// # Panics
+///
+/// If the lengths or the hashes differ.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::assert_permutation;
+///
+/// assert_permutation(&[3, 1, 2], &[1, 2, 3]);
+/// # }
+/// ```
+///
+pub fn assert_permutation<T: Hash>(input: &[T], output: &[T]) {
+    if input.len() != output.len() {
+        panic!("The output has {} elements, expected {}.",
+               output.len(),
+               input.len());
+    }
+    if multiset_hash(input) != multiset_hash(output) {
+        panic!("The output is not a permutation of the input: elements were lost, duplicated or \
+                changed.");
+    }
+}
+
+/// Checks the output of a key-value sort: the output keys are sorted, and the output pairs are a
+/// permutation of the input pairs, so every value stayed with its key. If `stable` is true, the
+/// values of equal keys must additionally be in the order of the input.
+///
+/// # Panics
+///
+/// * If the lengths of the slices differ.
+/// * If the keys are not sorted, or if a pair of the input is missing in the output.
+/// * If `stable` is true and the values of equal keys are reordered. The message names the first
+///   reordered index.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::assert_sorted_pairs;
+///
+/// let (keys, values) = ([2, 1, 2], [10, 11, 12]);
+/// assert_sorted_pairs(&keys, &values, &[1, 2, 2], &[11, 12, 10], false);
+/// # }
+/// ```
+///
+/// The values of the key 2 are reordered, so the sort is not stable:
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::sort::assert_sorted_pairs;
+///
+/// let (keys, values) = ([2, 1, 2], [10, 11, 12]);
+/// assert_sorted_pairs(&keys, &values, &[1, 2, 2], &[11, 12, 10], true);
+/// # }
+/// ```
+///
+pub fn assert_sorted_pairs<K, V>(input_keys: &[K],
+                                 input_values: &[V],
+                                 output_keys: &[K],
+                                 output_values: &[V],
+                                 stable: bool)
+    where K: Ord + Hash + Debug,
+          V: PartialEq + Hash + Debug
+{
+    if input_keys.len() != input_values.len() || output_keys.len() != output_values.len() {
+        panic!("The keys and values differ in length: {} and {} in the input, {} and {} in the \
+                output.",
+               input_keys.len(),
+               input_values.len(),
+               output_keys.len(),
+               output_values.len());
+    }
+    assert_sorted(output_keys);
+
+    let input = input_keys.iter().zip(input_values).collect::<Vec<_>>();
+    let output = output_keys.iter().zip(output_values).collect::<Vec<_>>();
+    if input.len() != output.len() {
+        panic!("The output has {} pairs, expected {}.", output.len(), input.len());
+    }
+    if multiset_hash(&input) != multiset_hash(&output) {
+        panic!("The output pairs are not a permutation of the input pairs: values were separated \
+                from their keys, lost, duplicated or changed.");
+    }
+    if !stable {
+        return;
+    }
+
+    // A stable sort of the input on the host is the only correct output.
+    let mut expected = input;
+    expected.sort_by(|a, b| a.0.cmp(b.0));
+    let reordered = expected.iter().zip(&output).position(|(expected, actual)| expected != actual);
+    if let Some(index) = reordered {
+        panic!("The sort is not stable at index {}: the key {:?} has the value {:?}, but the value \
+                {:?} comes first in the input.",
+               index,
+               output[index].0,
+               output[index].1,
+               expected[index].1);
+    }
+}
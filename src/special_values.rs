@@ -0,0 +1,323 @@
+//! This module seeds float input buffers with special values, to probe a kernel for the way it
+//! propagates NaNs and infinities.
+//!
+//! `inject` places NaN, both infinities, negative zero, denormals and the extreme magnitudes at
+//! chosen positions of an input, and records the elements and the invocations which received
+//! them. The result dereferences to the elements, so it can be the data of a buffer of
+//! `pipeline!` with `from`. After the execution, `assert_contained` checks that the non-finite
+//! outputs were only written by the invocations which received a special value:
+//!
+//! ```ignore
+//! let layout = Layout::new([NUM_ELEMENTS as u32 / 64, 1, 1], [64, 1, 1], 1);
+//! let input = special_values::inject(&input_vec, Placement::Every(97), Some(layout));
+//! pipeline!{
+//!     // ...
+//!     buffers: {
+//!         data: [f32] from input,
+//!         result: [f32; NUM_ELEMENTS]
+//!     },
+//!     execution_command: execute_shader
+//! };
+//! execute_shader();
+//! input.assert_contained(&read_buffer!(result), Some(layout));
+//! ```
+//!
+//! A kernel which combines neighbouring elements, e.g. a blur, legitimately spreads a NaN to the
+//! invocations around it. `unexplained` lists the non-finite outputs for such a kernel to check
+//! against its own expectations.
+
+use std::fmt;
+use std::ops::Deref;
+
+use non_finite::{self, Float, Layout, NonFinite};
+
+/// The special values which `inject` places, in the order of `Special::ALL`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Special {
+    /// A quiet NaN.
+    Nan,
+    /// The positive infinity.
+    Infinity,
+    /// The negative infinity.
+    NegativeInfinity,
+    /// The negative zero, which compares equal to zero but not in its sign.
+    NegativeZero,
+    /// The smallest positive denormal, which devices may flush to zero.
+    SmallestDenormal,
+    /// The largest denormal, just below the smallest normal value.
+    LargestDenormal,
+    /// The smallest positive normal value.
+    SmallestNormal,
+    /// The largest finite value, which overflows when it is doubled.
+    Largest,
+    /// The smallest finite value.
+    Lowest,
+}
+
+impl Special {
+    /// All special values.
+    pub const ALL: [Special; 9] = [Special::Nan,
+                                   Special::Infinity,
+                                   Special::NegativeInfinity,
+                                   Special::NegativeZero,
+                                   Special::SmallestDenormal,
+                                   Special::LargestDenormal,
+                                   Special::SmallestNormal,
+                                   Special::Largest,
+                                   Special::Lowest];
+
+    /// Returns whether the value is NaN or an infinity.
+    pub fn is_non_finite(self) -> bool {
+        match self {
+            Special::Nan | Special::Infinity | Special::NegativeInfinity => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Special {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Special::Nan => "NaN",
+            Special::Infinity => "+inf",
+            Special::NegativeInfinity => "-inf",
+            Special::NegativeZero => "-0.0",
+            Special::SmallestDenormal => "the smallest denormal",
+            Special::LargestDenormal => "the largest denormal",
+            Special::SmallestNormal => "the smallest normal",
+            Special::Largest => "the largest finite value",
+            Special::Lowest => "the lowest finite value",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The float element types which `inject` seeds with special values.
+pub trait SpecialFloat: Float {
+    /// Returns the special value `special` of the type.
+    fn special(special: Special) -> Self;
+}
+
+macro_rules! special_floats {
+    ( $( $float:ident ),* ) => {
+        $(
+            impl SpecialFloat for $float {
+                fn special(special: Special) -> $float {
+                    match special {
+                        Special::Nan => ::std::$float::NAN,
+                        Special::Infinity => ::std::$float::INFINITY,
+                        Special::NegativeInfinity => ::std::$float::NEG_INFINITY,
+                        Special::NegativeZero => -0.0,
+                        Special::SmallestDenormal => <$float>::from_bits(1),
+                        Special::LargestDenormal => {
+                            <$float>::from_bits(::std::$float::MIN_POSITIVE.to_bits() - 1)
+                        }
+                        Special::SmallestNormal => ::std::$float::MIN_POSITIVE,
+                        Special::Largest => ::std::$float::MAX,
+                        Special::Lowest => ::std::$float::MIN,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+special_floats!(f32, f64);
+
+/// The positions of the special values in an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placement {
+    /// Every `n`th element, starting at the index `n - 1`.
+    Every(usize),
+    /// The elements at the given indices, which are skipped if they are outside of the input.
+    Indices(Vec<usize>),
+    /// `count` distinct elements, chosen by a pseudorandom generator seeded with `seed`, so the
+    /// same seed gives the same positions.
+    Scattered {
+        /// The number of special values.
+        count: usize,
+        /// The seed of the positions.
+        seed: u64,
+    },
+}
+
+impl Placement {
+    /// Returns the sorted indices of the special values in an input of `len` elements.
+    fn indices(&self, len: usize) -> Vec<usize> {
+        let mut indices = match *self {
+            Placement::Every(0) => Vec::new(),
+            Placement::Every(n) => (1..len / n + 1).map(|k| k * n - 1).collect(),
+            Placement::Indices(ref indices) => {
+                indices.iter().cloned().filter(|&index| index < len).collect()
+            }
+            Placement::Scattered { count, seed } => {
+                // SplitMix64, which gives the same sequence on every platform.
+                let mut state = seed;
+                let mut chosen = vec![false; len];
+                let mut indices = Vec::new();
+                while indices.len() < count.min(len) {
+                    state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+                    let index = ((z ^ (z >> 31)) % len as u64) as usize;
+                    if !chosen[index] {
+                        chosen[index] = true;
+                        indices.push(index);
+                    }
+                }
+                indices
+            }
+        };
+        indices.sort();
+        indices.dedup();
+        indices
+    }
+}
+
+/// A special value placed in an input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Injection {
+    /// The index of the element.
+    pub index: usize,
+    /// The special value.
+    pub special: Special,
+    /// The global invocation id which reads the element, if the layout of the input is known.
+    pub invocation: Option<[u32; 3]>,
+}
+
+impl fmt::Display for Injection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index {}", self.index)?;
+        if let Some(invocation) = self.invocation {
+            write!(f, " (invocation {:?})", invocation)?;
+        }
+        write!(f, ": {}", self.special)
+    }
+}
+
+/// An input seeded with special values by `inject`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Injected<T> {
+    /// The elements of the input.
+    pub data: Vec<T>,
+    /// The special values in the input, by their index.
+    pub injections: Vec<Injection>,
+}
+
+impl<T> Deref for Injected<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.data
+    }
+}
+
+impl<T: SpecialFloat> Injected<T> {
+    /// Returns whether the invocation `invocation` or the element `index` of an output received
+    /// a non-finite special value. The invocations are compared if both layouts are known, and
+    /// the indices otherwise.
+    fn explains(&self, index: usize, invocation: Option<[u32; 3]>) -> bool {
+        self.injections
+            .iter()
+            .filter(|injection| injection.special.is_non_finite())
+            .any(|injection| match (injection.invocation, invocation) {
+                (Some(input), Some(output)) => input == output,
+                _ => injection.index == index,
+            })
+    }
+
+    /// Returns the NaNs and infinities in `output` which were written by invocations that did
+    /// not receive a NaN or an infinity, given the `layout` of the output.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use std::f32;
+    /// use vulkanology::special_values::{inject, Placement};
+    ///
+    /// let input = inject(&[1.0f32; 8], Placement::Indices(vec![2]), None);
+    /// // The NaN at index 2 spread to index 3.
+    /// let output = [1.0, 1.0, f32::NAN, f32::NAN, 1.0, 1.0, 1.0, 1.0];
+    /// let unexplained = input.unexplained(&output, None);
+    /// assert_eq!(unexplained.len(), 1);
+    /// assert_eq!(unexplained[0].index, 3);
+    /// # }
+    /// ```
+    ///
+    pub fn unexplained<F: Float>(&self, output: &[F], layout: Option<Layout>) -> Vec<NonFinite> {
+        non_finite::scan(output, layout)
+            .into_iter()
+            .filter(|non_finite| !self.explains(non_finite.index, non_finite.invocation))
+            .collect()
+    }
+
+    /// Asserts that the NaNs and infinities in `output` were only written by invocations which
+    /// received a NaN or an infinity, see `unexplained`.
+    ///
+    /// # Panics
+    ///
+    /// If other invocations wrote a NaN or an infinity, with them and the injected values.
+    pub fn assert_contained<F: Float>(&self, output: &[F], layout: Option<Layout>) {
+        let unexplained = self.unexplained(output, layout);
+        if !unexplained.is_empty() {
+            let listed = |lines: Vec<String>| lines.join("\n");
+            panic!("{} non-finite outputs were written by invocations without a non-finite \
+                    input:\n{}\nThe special values of the input:\n{}",
+                   unexplained.len(),
+                   listed(unexplained.iter().map(|output| format!("  {}", output)).collect()),
+                   listed(self.injections
+                       .iter()
+                       .map(|injection| format!("  {}", injection))
+                       .collect()));
+        }
+    }
+}
+
+/// Returns a copy of `data` in which the elements at the positions of `placement` are replaced
+/// by the special values of `Special::ALL` in turn, with the invocations which read them given
+/// the `layout` of the input.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::non_finite::Layout;
+/// use vulkanology::special_values::{inject, Placement, Special};
+///
+/// let layout = Layout::new([2, 1, 1], [8, 1, 1], 1);
+/// let input = inject(&[0.5f32; 16], Placement::Every(4), Some(layout));
+/// assert!(input[3].is_nan());
+/// assert_eq!(input[7], ::std::f32::INFINITY);
+/// assert_eq!(input.injections[3].special, Special::NegativeZero);
+/// assert_eq!(input.injections[3].to_string(), "index 15 (invocation [15, 0, 0]): -0.0");
+/// # }
+/// ```
+///
+pub fn inject<T: SpecialFloat>(data: &[T],
+                               placement: Placement,
+                               layout: Option<Layout>)
+                               -> Injected<T> {
+    let mut data = data.to_vec();
+    let injections = placement.indices(data.len())
+        .into_iter()
+        .zip(Special::ALL.iter().cycle())
+        .map(|(index, &special)| {
+            data[index] = T::special(special);
+            Injection {
+                index: index,
+                special: special,
+                invocation: layout.and_then(|layout| layout.invocation(index)),
+            }
+        })
+        .collect();
+    Injected {
+        data: data,
+        injections: injections,
+    }
+}
@@ -0,0 +1,276 @@
+//! This module contains statistical assertions on buffers, for shaders whose results can only be
+//! validated as a distribution, e.g. random number generators, noise or Monte Carlo estimators.
+//!
+//! The assertions compare the statistics of a buffer against the expected ones within a
+//! tolerance, which must account for the sampling error: the mean of `n` samples of a
+//! distribution with the standard deviation `σ` deviates from the expected mean by about
+//! `σ / sqrt(n)`. A tolerance of several such standard errors keeps the tests from failing by
+//! chance. The elements are any scalars of the [`export`] module, converted to `f64`.
+//!
+//! [`export`]: ../export/index.html
+
+use std::f64;
+
+use export::Scalar;
+
+/// The summary statistics of a buffer.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Summary {
+    /// The number of elements.
+    pub len: usize,
+    /// The mean of the elements.
+    pub mean: f64,
+    /// The population variance of the elements.
+    pub variance: f64,
+    /// The smallest element.
+    pub min: f64,
+    /// The largest element.
+    pub max: f64,
+}
+
+/// Returns the summary statistics of `data`.
+///
+/// # Panics
+///
+/// If `data` is empty.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::summarize;
+///
+/// let summary = summarize(&[2u32, 4, 4, 4, 5, 5, 7, 9]);
+/// assert_eq!(summary.mean, 5.0);
+/// assert_eq!(summary.variance, 4.0);
+/// assert_eq!((summary.min, summary.max), (2.0, 9.0));
+/// # }
+/// ```
+///
+pub fn summarize<T: Scalar>(data: &[T]) -> Summary {
+    if data.is_empty() {
+        panic!("The statistics of an empty buffer are undefined.");
+    }
+    let len = data.len() as f64;
+    let mean = data.iter().map(|value| value.to_f64()).sum::<f64>() / len;
+    let variance = data.iter().map(|value| (value.to_f64() - mean).powi(2)).sum::<f64>() / len;
+    let (min, max) = data.iter()
+        .map(|value| value.to_f64())
+        .fold((f64::INFINITY, f64::NEG_INFINITY),
+              |(min, max), value| (min.min(value), max.max(value)));
+    Summary {
+        len: data.len(),
+        mean: mean,
+        variance: variance,
+        min: min,
+        max: max,
+    }
+}
+
+/// Asserts that the mean of `data` is `expected` within `tolerance`.
+///
+/// # Panics
+///
+/// If the mean differs by more than `tolerance`, or if it is NaN.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::assert_mean;
+///
+/// let uniform = (0..1000).map(|i| i as f32 / 1000.0).collect::<Vec<_>>();
+/// assert_mean(&uniform, 0.5, 0.01);
+/// # }
+/// ```
+///
+pub fn assert_mean<T: Scalar>(data: &[T], expected: f64, tolerance: f64) {
+    let summary = summarize(data);
+    if !((summary.mean - expected).abs() <= tolerance) {
+        panic!("The mean of {} elements is {}, expected {} within {}.",
+               summary.len,
+               summary.mean,
+               expected,
+               tolerance);
+    }
+}
+
+/// Asserts that the population variance of `data` is `expected` within `tolerance`.
+///
+/// # Panics
+///
+/// If the variance differs by more than `tolerance`, or if it is NaN.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::assert_variance;
+///
+/// // The variance of a uniform distribution on [0, 1) is 1 / 12.
+/// let uniform = (0..1000).map(|i| i as f32 / 1000.0).collect::<Vec<_>>();
+/// assert_variance(&uniform, 1.0 / 12.0, 0.001);
+/// # }
+/// ```
+///
+pub fn assert_variance<T: Scalar>(data: &[T], expected: f64, tolerance: f64) {
+    let summary = summarize(data);
+    if !((summary.variance - expected).abs() <= tolerance) {
+        panic!("The variance of {} elements is {}, expected {} within {}.",
+               summary.len,
+               summary.variance,
+               expected,
+               tolerance);
+    }
+}
+
+/// Asserts that every element of `data` is in the range from `min` to `max`, inclusively.
+///
+/// # Panics
+///
+/// If an element is outside of the range or NaN, naming the first such element.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::assert_range;
+///
+/// assert_range(&[0.0f32, 0.25, 1.0], 0.0, 1.0);
+/// # }
+/// ```
+///
+pub fn assert_range<T: Scalar>(data: &[T], min: f64, max: f64) {
+    let mut outside = data.iter()
+        .map(|value| value.to_f64())
+        .enumerate()
+        .filter(|&(_, value)| !(value >= min && value <= max));
+    if let Some((index, value)) = outside.next() {
+        panic!("The element {} at index {} is outside of the range [{}, {}], {} elements in \
+                total.",
+               value,
+               index,
+               min,
+               max,
+               outside.count() + 1);
+    }
+}
+
+/// Returns the number of elements of `data` in each of `bins` bins of the same width from `min`
+/// to `max`. The last bin includes `max`, and the elements outside of the range are not
+/// counted.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::histogram;
+///
+/// assert_eq!(histogram(&[0.1f32, 0.2, 0.6, 1.0, 1.5], 0.0, 1.0, 2), [2, 2]);
+/// # }
+/// ```
+///
+pub fn histogram<T: Scalar>(data: &[T], min: f64, max: f64, bins: usize) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    let width = (max - min) / bins as f64;
+    for value in data.iter().map(|value| value.to_f64()) {
+        if value >= min && value <= max {
+            let bin = ((value - min) / width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+    }
+    counts
+}
+
+/// Asserts that the histogram of `data` from `min` to `max` has the shape of the distribution
+/// `expected`, which gives the probability of every bin. The fraction of the elements in each
+/// bin must match its probability within `tolerance`.
+///
+/// # Panics
+///
+/// If the fraction of a bin differs by more than `tolerance`, with the whole histogram.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::assert_histogram;
+///
+/// // The sum of two dice.
+/// let sums = (1..7).flat_map(|a| (1..7).map(move |b| a + b)).collect::<Vec<u32>>();
+/// let triangle = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 5.0, 4.0, 3.0, 2.0, 1.0];
+/// let expected = triangle.iter().map(|weight| weight / 36.0).collect::<Vec<_>>();
+/// assert_histogram(&sums, 1.5, 12.5, &expected, 1e-9);
+/// # }
+/// ```
+///
+pub fn assert_histogram<T: Scalar>(data: &[T],
+                                   min: f64,
+                                   max: f64,
+                                   expected: &[f64],
+                                   tolerance: f64) {
+    let fractions = histogram(data, min, max, expected.len())
+        .iter()
+        .map(|&count| count as f64 / data.len() as f64)
+        .collect::<Vec<_>>();
+    let deviating = fractions.iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|&(_, (fraction, expected))| !((fraction - expected).abs() <= tolerance))
+        .map(|(bin, _)| bin)
+        .collect::<Vec<_>>();
+    if !deviating.is_empty() {
+        let bins = fractions.iter()
+            .zip(expected)
+            .enumerate()
+            .map(|(bin, (fraction, expected))| {
+                let marker = if deviating.contains(&bin) { " <-" } else { "" };
+                format!("  bin {}: {:.4} (expected {:.4}){}", bin, fraction, expected, marker)
+            })
+            .collect::<Vec<_>>();
+        panic!("The histogram of {} elements from {} to {} deviates from the expected \
+                distribution by more than {} in {} bins:\n{}",
+               data.len(),
+               min,
+               max,
+               tolerance,
+               deviating.len(),
+               bins.join("\n"));
+    }
+}
+
+/// Asserts that the elements of `data` are distributed uniformly from `min` to `max`, given
+/// their histogram of `bins` bins as in `assert_histogram`.
+///
+/// # Panics
+///
+/// If the fraction of a bin differs from `1 / bins` by more than `tolerance`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::stats::assert_uniform;
+///
+/// let data = (0..10000u32).map(|i| i.wrapping_mul(2654435761)).collect::<Vec<_>>();
+/// assert_uniform(&data, 0.0, 4294967296.0, 16, 0.01);
+/// # }
+/// ```
+///
+pub fn assert_uniform<T: Scalar>(data: &[T], min: f64, max: f64, bins: usize, tolerance: f64) {
+    assert_histogram(data, min, max, &vec![1.0 / bins as f64; bins], tolerance);
+}
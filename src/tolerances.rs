@@ -0,0 +1,241 @@
+//! This module contains the registry of tolerance profiles, which give the tolerance of a
+//! comparison per device instead of a single tolerance for all devices.
+//!
+//! The precision of float operations differs widely between vendors, and mobile GPUs often use
+//! the full error which the Vulkan specification allows. A single epsilon is therefore either too
+//! lax for desktop GPUs or too strict for mobile drivers. A `Profile` lists the tolerances of
+//! the devices, and a default for all others:
+//!
+//! ```ignore
+//! tolerances::register("exp", Profile::new(Tolerance::Ulps(4))
+//!     .on(Device::Vendor(tolerances::NVIDIA), Tolerance::Ulps(2))
+//!     .on(Device::Vendor(tolerances::ARM), Tolerance::Ulps(8)));
+//!
+//! assert_buffers_approx_eq!(result, expected, profile = "exp");
+//! ```
+//!
+//! The devices are matched against the device of the current thread, which `pipeline!` records
+//! in the [`context`] module, and the first matching entry applies. Like every tolerance of the
+//! comparison helpers, the tolerance of the profile is then adjusted by the [`quirks`] of the
+//! driver. Every entry which applies is noted on stderr and in the report of the [`harness`].
+//!
+//! [`context`]: ../context/index.html
+//! [`quirks`]: ../quirks/index.html
+//! [`harness`]: ../harness/index.html
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use compare::Tolerance;
+use context::{self, DeviceContext};
+use harness;
+
+/// The PCI vendor id of AMD.
+pub const AMD: u32 = 0x1002;
+/// The PCI vendor id of Apple.
+pub const APPLE: u32 = 0x106b;
+/// The PCI vendor id of ARM, whose GPUs are the Mali series.
+pub const ARM: u32 = 0x13b5;
+/// The PCI vendor id of Imagination Technologies, whose GPUs are the PowerVR series.
+pub const IMAGINATION: u32 = 0x1010;
+/// The PCI vendor id of Intel.
+pub const INTEL: u32 = 0x8086;
+/// The PCI vendor id of NVIDIA.
+pub const NVIDIA: u32 = 0x10de;
+/// The PCI vendor id of Qualcomm, whose GPUs are the Adreno series.
+pub const QUALCOMM: u32 = 0x5143;
+
+/// The devices to which an entry of a `Profile` applies.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Device {
+    /// All devices with the PCI vendor id.
+    Vendor(u32),
+    /// The device with the PCI vendor id and the PCI device id.
+    Id(u32, u32),
+    /// All devices whose name contains the string, e.g. `"Mali-G78"`.
+    Name(&'static str),
+}
+
+impl Device {
+    /// Returns whether the device `context` is one of the devices.
+    pub fn matches(&self, context: &DeviceContext) -> bool {
+        match *self {
+            Device::Vendor(vendor_id) => context.vendor_id == vendor_id,
+            Device::Id(vendor_id, device_id) => {
+                context.vendor_id == vendor_id && context.device_id == device_id
+            }
+            Device::Name(name) => context.name.contains(name),
+        }
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Device::Vendor(vendor_id) => write!(f, "the devices of vendor {:#06x}", vendor_id),
+            Device::Id(vendor_id, device_id) => {
+                write!(f, "the device {:#06x} of vendor {:#06x}", device_id, vendor_id)
+            }
+            Device::Name(name) => write!(f, "the devices named `{}`", name),
+        }
+    }
+}
+
+/// The tolerances of a comparison on different devices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// The tolerance on the devices without an entry, or if the device is unknown.
+    pub default: Tolerance,
+    /// The tolerances of the devices, in the order in which they are matched.
+    pub devices: Vec<(Device, Tolerance)>,
+}
+
+impl Profile {
+    /// Returns the profile with the tolerance `default` on all devices.
+    pub fn new(default: Tolerance) -> Profile {
+        Profile {
+            default: default,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Adds the tolerance `tolerance` on the devices `device`, after the previous entries.
+    pub fn on(mut self, device: Device, tolerance: Tolerance) -> Profile {
+        self.devices.push((device, tolerance));
+        self
+    }
+
+    /// Returns the entry of the profile which applies to the device `context`.
+    fn entry(&self, context: &DeviceContext) -> Option<&(Device, Tolerance)> {
+        self.devices.iter().find(|&&(ref device, _)| device.matches(context))
+    }
+
+    /// Returns the tolerance of the profile on the device `context`, or the default if it is
+    /// `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate vulkanology;
+    /// #
+    /// # fn main() {
+    /// use vulkanology::compare::Tolerance;
+    /// use vulkanology::context::DeviceContext;
+    /// use vulkanology::tolerances::{Device, Profile, ARM, NVIDIA};
+    ///
+    /// let profile = Profile::new(Tolerance::Ulps(4))
+    ///     .on(Device::Name("Mali-G71"), Tolerance::Ulps(16))
+    ///     .on(Device::Vendor(ARM), Tolerance::Ulps(8))
+    ///     .on(Device::Vendor(NVIDIA), Tolerance::Ulps(2));
+    ///
+    /// let mali = DeviceContext {
+    ///     name: "Mali-G78".to_owned(),
+    ///     vendor_id: ARM,
+    ///     device_id: 0x9200_0010,
+    ///     driver_version: 32 << 22,
+    ///     api_version: [1, 1, 0],
+    /// };
+    /// assert_eq!(profile.tolerance_on(Some(&mali)), Tolerance::Ulps(8));
+    /// let old_mali = DeviceContext { name: "Mali-G71".to_owned(), ..mali.clone() };
+    /// assert_eq!(profile.tolerance_on(Some(&old_mali)), Tolerance::Ulps(16));
+    /// let intel = DeviceContext { name: "Intel(R) UHD Graphics".to_owned(), vendor_id: 0x8086,
+    ///                             ..mali };
+    /// assert_eq!(profile.tolerance_on(Some(&intel)), Tolerance::Ulps(4));
+    /// assert_eq!(profile.tolerance_on(None), Tolerance::Ulps(4));
+    /// # }
+    /// ```
+    ///
+    pub fn tolerance_on(&self, context: Option<&DeviceContext>) -> Tolerance {
+        context.and_then(|context| self.entry(context))
+            .map(|&(_, tolerance)| tolerance)
+            .unwrap_or(self.default)
+    }
+
+    /// Returns the tolerance of the profile on the device of the current thread, and notes the
+    /// entry which applies, see the [`tolerances`] module.
+    ///
+    /// [`tolerances`]: index.html
+    pub fn tolerance(&self) -> Tolerance {
+        let context = match context::current() {
+            Some(context) => context,
+            None => return self.default,
+        };
+        match self.entry(&context) {
+            Some(&(ref device, tolerance)) => {
+                let note = format!("The tolerance on `{}` is {}, as given for {}.",
+                                   context.name,
+                                   tolerance,
+                                   device);
+                let _ = writeln!(io::stderr(), "note: {}", note);
+                harness::record_note(note);
+                tolerance
+            }
+            None => self.default,
+        }
+    }
+}
+
+lazy_static! {
+    /// The registered profiles by their names.
+    static ref REGISTRY: Mutex<Vec<(&'static str, Profile)>> = Mutex::new(Vec::new());
+}
+
+/// Registers `profile` under the name `name` for all threads, replacing a previous profile of
+/// the same name.
+pub fn register(name: &'static str, profile: Profile) {
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.retain(|&(registered, _)| registered != name);
+    registry.push((name, profile));
+}
+
+/// Returns the profile registered under the name `name`.
+pub fn profile(name: &str) -> Option<Profile> {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|&&(registered, _)| registered == name)
+        .map(|&(_, ref profile)| profile.clone())
+}
+
+/// Returns the tolerance of the profile `name` on the device of the current thread, see
+/// `Profile::tolerance`.
+///
+/// # Panics
+///
+/// If no profile is registered under the name.
+///
+/// # Example
+///
+/// ```
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::compare::Tolerance;
+/// use vulkanology::context::{self, DeviceContext};
+/// use vulkanology::tolerances::{self, Device, Profile, NVIDIA, QUALCOMM};
+///
+/// tolerances::register("inversesqrt",
+///                      Profile::new(Tolerance::Ulps(4))
+///                          .on(Device::Vendor(NVIDIA), Tolerance::Ulps(2))
+///                          .on(Device::Vendor(QUALCOMM), Tolerance::Ulps(8)));
+///
+/// assert_eq!(tolerances::tolerance("inversesqrt"), Tolerance::Ulps(4));
+/// context::set(DeviceContext {
+///     name: "Adreno (TM) 660".to_owned(),
+///     vendor_id: QUALCOMM,
+///     device_id: 0x0606_0001,
+///     driver_version: 512 << 22 | 530,
+///     api_version: [1, 1, 128],
+/// });
+/// assert_eq!(tolerances::tolerance("inversesqrt"), Tolerance::Ulps(8));
+/// # }
+/// ```
+///
+pub fn tolerance(name: &str) -> Tolerance {
+    match profile(name) {
+        Some(profile) => profile.tolerance(),
+        None => panic!("The tolerance profile `{}` is not registered.", name),
+    }
+}
@@ -0,0 +1,128 @@
+//! This module collects the messages of the validation layers during a test.
+//!
+//! With `validation: strict`, `pipeline!` enables `VK_LAYER_KHRONOS_validation` together with
+//! `VK_EXT_debug_report`, and installs a debug callback which records every error and warning in
+//! a `ValidationLog`. When the log goes out of scope at the end of the test, it panics with the
+//! recorded messages, so invalid API usage fails the test instead of going unnoticed.
+//!
+//! The messages refer to objects by their handles. `pipeline!` names the instance, the device,
+//! the pipelines and the buffers in the log, which adds the names after the handles in the
+//! reported messages. `vkSetDebugUtilsObjectNameEXT` of `VK_EXT_debug_utils` would give the names
+//! to the driver and to tools like RenderDoc, but neither it nor `VK_EXT_debug_marker` is known to
+//! the version of `vulkano` used by `pipeline!`, and the command buffers do not expose their
+//! handles.
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The validation layer enabled by `validation: strict`.
+pub const VALIDATION_LAYER: &'static str = "VK_LAYER_KHRONOS_validation";
+
+/// The messages of the validation layers about the pipeline of a shader.
+///
+/// # Panics
+///
+/// When it is dropped, if a message was recorded and the thread is not already panicking.
+///
+/// # Example
+///
+/// ```should_panic
+/// # extern crate vulkanology;
+/// #
+/// # fn main() {
+/// use vulkanology::validation::ValidationLog;
+///
+/// let mut log = ValidationLog::new("example.comp");
+/// log.name(0x2a, "result");
+/// // Panics, with the buffer reported as "0x2a (`result`)".
+/// log.sink().record("Validation", "vkCmdDispatch: the buffer 0x2a is not bound.");
+/// # }
+/// ```
+///
+#[derive(Debug)]
+pub struct ValidationLog {
+    shader: String,
+    sink: MessageSink,
+    names: Vec<(u64, String)>,
+}
+
+impl ValidationLog {
+    /// Creates an empty log for the pipeline of the shader `shader`.
+    pub fn new(shader: &str) -> ValidationLog {
+        ValidationLog {
+            shader: shader.to_owned(),
+            sink: MessageSink { messages: Arc::new(Mutex::new(Vec::new())) },
+            names: Vec::new(),
+        }
+    }
+
+    /// Returns a sink which records messages into this log, e.g. from a debug callback.
+    pub fn sink(&self) -> MessageSink {
+        self.sink.clone()
+    }
+
+    /// Names the object with the handle `handle` in the reported messages.
+    pub fn name(&mut self, handle: u64, name: &str) {
+        self.names.push((handle, name.to_owned()));
+    }
+
+    /// Panics with the recorded messages, if there are any, and clears them.
+    pub fn check(&self) {
+        let messages = self.sink.messages.lock().map(|mut messages| messages.split_off(0));
+        let messages = messages.expect("Failed to lock the validation messages.");
+        if messages.is_empty() {
+            return;
+        }
+        let mut report = format!("The validation layers reported {} messages about `{}`:",
+                                 messages.len(),
+                                 self.shader);
+        for message in &messages {
+            let _ = write!(report, "\n    {}", self.annotate(message));
+        }
+        panic!("{}", report);
+    }
+
+    /// Adds the names of the named objects after their handles in `message`.
+    fn annotate(&self, message: &str) -> String {
+        let mut annotated = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find("0x") {
+            let digits = &rest[start + 2..];
+            let end = start + 2 + digits.find(|c: char| !c.is_digit(16)).unwrap_or(digits.len());
+            annotated.push_str(&rest[..end]);
+            if let Ok(handle) = u64::from_str_radix(&rest[start + 2..end], 16) {
+                if let Some(&(_, ref name)) = self.names.iter().find(|name| name.0 == handle) {
+                    let _ = write!(annotated, " (`{}`)", name);
+                }
+            }
+            rest = &rest[end..];
+        }
+        annotated.push_str(rest);
+        annotated
+    }
+}
+
+impl Drop for ValidationLog {
+    fn drop(&mut self) {
+        // A second panic would abort the test binary without reporting the first one.
+        if !thread::panicking() {
+            self.check();
+        }
+    }
+}
+
+/// Records messages into a `ValidationLog`.
+#[derive(Debug, Clone)]
+pub struct MessageSink {
+    messages: Arc<Mutex<Vec<String>>>,
+}
+
+impl MessageSink {
+    /// Records the message `description` of the layer `layer`.
+    pub fn record(&self, layer: &str, description: &str) {
+        if let Ok(mut messages) = self.messages.lock() {
+            messages.push(format!("[{}] {}", layer, description));
+        }
+    }
+}
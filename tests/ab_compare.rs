@@ -0,0 +1,34 @@
+//! This is an example of a comparison of two kernels which compute the same result.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test compares a variant of the example shader against the example shader.
+#[test]
+fn test_ab_compare_example() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32)
+        .map(|i| i.wrapping_mul(2654435761))
+        .collect();
+
+    let comparison = ab_compare!{
+        reference: "tests/shaders/example.comp",
+        candidate: "tests/shaders/example_variant.comp",
+        pipeline: {
+            workgroup_count: [100, 100, 1],
+            buffers: {
+                data: [u32] from input_vec,
+                result: [u32; NUM_INVOCATIONS]
+            }
+        },
+        outputs: [result],
+        warm_up: 3,
+        samples: 10
+    };
+
+    comparison.assert_equal_outputs();
+    assert_eq!(comparison.reference.samples, 10);
+    assert!(comparison.speedup() > 0.0);
+}
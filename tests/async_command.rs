@@ -0,0 +1,41 @@
+//! This is an example of a shader test which computes the expected results while the shader
+//! runs.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test submits the example shader without waiting, computes the expected results and then
+/// waits for the shader.
+#[test]
+fn test_async_command() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader,
+        async_command: execute_shader_async
+    };
+
+    let submission = execute_shader_async();
+    let expected = input_vec.iter()
+        .enumerate()
+        .map(|(index, item)| item.wrapping_mul(index as u32))
+        .collect::<Vec<_>>();
+    submission.wait(Duration::new(10, 0)).expect("Failed to wait for the shader.");
+    assert!(submission.finished());
+
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    assert_buffers_eq!(mapping, expected);
+}
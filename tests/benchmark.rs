@@ -0,0 +1,52 @@
+//! This is an example of a benchmark of a shader.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test measures the example shader and reports its throughput.
+#[test]
+fn test_benchmark_example() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS],
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let stats = benchmark!(execute_shader, warm_up: 3, samples: 10, elements: NUM_INVOCATIONS);
+    assert_eq!(stats.samples, 10);
+    assert!(stats.min <= stats.median);
+    assert!(stats.throughput.unwrap() > 0.0);
+}
+
+/// This test warms the example shader up until its executions are steady before measuring it.
+#[test]
+fn test_benchmark_steady_warm_up() {
+    use vulkanology::benchmark::WarmUp;
+
+    const NUM_INVOCATIONS: usize = 640000;
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS],
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let warm_up = WarmUp::Steady {
+        window: 5,
+        tolerance: 0.1,
+        max_runs: 50,
+    };
+    let stats = benchmark!(execute_shader, warm_up: warm_up, samples: 10);
+    assert_eq!(stats.samples, 10);
+}
@@ -0,0 +1,73 @@
+//! This is an example of a shader test with buffers initialized from host data.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+
+use std::time::Duration;
+
+/// This test shows how to create buffers from a `Vec`.
+#[test]
+fn test_buffer_from_vec() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    // Generate the input data on the host.
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS).map(|_| rand::random::<u32>()).collect();
+
+    // Create the environment. The `data` buffer takes its length and contents from `input_vec`.
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; input_vec.len()]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        let zipped = input_vec.iter().zip(result_buffer.iter());
+
+        for (invocation_uid, (item_in, item_out)) in zipped.enumerate() {
+            assert_eq!(*item_out, (*item_in).wrapping_mul(invocation_uid as u32));
+        }
+    }
+}
+
+/// This test shows how to initialize buffers of a given length when they are created.
+#[test]
+fn test_buffer_initializers() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let zeros = vec![0u32; NUM_INVOCATIONS];
+
+    // Create the environment. `data` is filled with the element at every index, `result` with
+    // the contents of `zeros`, whose length must match the buffer.
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS] = |i| i as u32 * 3,
+            result: [u32; NUM_INVOCATIONS] = zeros
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        for (invocation_uid, item_out) in result_buffer.iter().enumerate() {
+            let item_in = invocation_uid as u32 * 3;
+            assert_eq!(*item_out, item_in.wrapping_mul(invocation_uid as u32));
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! This is an example of a test of the buffer layout check at pipeline setup. The build script
+//! reflects the layouts of the shader, which `pipeline!` compares with the declared buffers.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// The shader reads `vec4` elements with a stride of 16 bytes, but the buffer is declared with
+/// `f32` elements, so the pipeline setup panics before the shader reads past the elements.
+#[test]
+#[should_panic(expected = "does not match the shader: `Data.data[]` has a stride of 16 bytes")]
+fn test_mismatched_element_type() {
+    const NUM_ELEMENTS: usize = 256;
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/buffer_layouts.comp",
+        workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+        buffers: {
+            data: [f32; NUM_ELEMENTS]
+        },
+        execution_command: execute_shader
+    };
+
+    // The pipeline setup panics before the shader can be executed.
+    execute_shader();
+}
@@ -0,0 +1,35 @@
+//! This is an example of a conformance test of the GLSL built-in math functions.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use vulkanology::conformance;
+
+/// This test sweeps the inputs of every described built-in function through a shader which
+/// selects the function with a specialization constant, and checks the results against the
+/// precision which Vulkan requires.
+#[test]
+fn test_builtins_conformant() {
+    const NUM_ELEMENTS: usize = 64 * 1024;
+
+    for (index, builtin) in conformance::ALL.iter().enumerate() {
+        let input_vec = builtin.inputs(NUM_ELEMENTS);
+
+        let conformance = assert_conformant!{
+            builtin: builtin,
+            pipeline: {
+                shader_path: "tests/shaders/builtins.comp",
+                workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+                spec_constants: { builtin: u32 = index as u32 },
+                buffers: {
+                    data: [f32] from input_vec,
+                    result: [f32; NUM_ELEMENTS]
+                }
+            },
+            input: data,
+            output: result
+        };
+        assert!(conformance.checked > 0, "{}", builtin.name);
+    }
+}
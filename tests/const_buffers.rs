@@ -0,0 +1,50 @@
+//! This is an example of a shader test with buffers whose length is encoded in their type.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::{ReadLock, WriteLock};
+
+/// The number of invocations of a single workgroup of the example shader. The length of a
+/// `const` buffer is a module-level item, so that the buffer type is valid in every scope, see
+/// `cpu_array_buffer!`.
+const NUM_INVOCATIONS: usize = 8 * 8;
+
+/// This test shows how to encode the length of the buffers in their types.
+#[test]
+fn test_const_buffers() {
+    // Create the environment. The contents of both buffers are `[u32; NUM_INVOCATIONS]`.
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [1, 1, 1],
+        buffers: {
+            data: [u32; const NUM_INVOCATIONS],
+            result: [u32; const NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Fill the input buffer. Assigning an array of a different length would not compile.
+    {
+        let mut mapping: WriteLock<[u32; NUM_INVOCATIONS]> = data.write(Duration::new(1, 0))
+            .unwrap();
+        *mapping = [3; NUM_INVOCATIONS];
+    }
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer: ReadLock<[u32; NUM_INVOCATIONS]> = result.read(Duration::new(1, 0))
+            .unwrap();
+        let mut expected = [0; NUM_INVOCATIONS];
+        for (invocation_uid, item) in expected.iter_mut().enumerate() {
+            *item = 3 * invocation_uid as u32;
+        }
+        assert_eq!(&result_buffer[..], &expected[..]);
+    }
+}
@@ -0,0 +1,48 @@
+//! This is an example of a shader test which compares the results of two devices.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+
+use std::time::Duration;
+use vulkano::instance::PhysicalDevice;
+use vulkanology::compare::assert_approx_eq;
+
+/// Runs the shader on the physical device `device` and returns the results.
+fn run_on_device(device: usize, input_vec: &[f32]) -> Vec<f32> {
+    pipeline!{
+        device: device,
+        shader_path: "tests/shaders/cross_device.comp",
+        workgroup_count: [input_vec.len() / 64, 1, 1],
+        buffers: {
+            data: [f32] from input_vec,
+            result: [f32; input_vec.len()]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+    result_buffer.to_vec()
+}
+
+/// This test runs the same pipeline on the first and the last device and compares the results
+/// within the precision guaranteed for `inversesqrt`. With a single device, the device is
+/// compared against itself.
+#[test]
+fn test_cross_device() {
+    const NUM_INVOCATIONS: usize = 64 * 1024;
+
+    let instance = instance!();
+    let num_devices = PhysicalDevice::enumerate(&instance).count();
+
+    let input_vec: Vec<f32> = (0..NUM_INVOCATIONS)
+        .map(|_| 1.0 + rand::random::<f32>() * 1000.0)
+        .collect();
+    let first = run_on_device(0, &input_vec);
+    let last = run_on_device(num_devices - 1, &input_vec);
+
+    assert_approx_eq(&first, &last, 1e-6);
+}
@@ -0,0 +1,28 @@
+//! This is an example of a check that a shader computes the same outputs in every run.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test executes the example shader four times on the same input.
+#[test]
+fn test_deterministic_example() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32)
+        .map(|i| i.wrapping_mul(2654435761))
+        .collect();
+
+    assert_deterministic!{
+        shader_path: "tests/shaders/example.comp",
+        pipeline: {
+            workgroup_count: [100, 100, 1],
+            buffers: {
+                data: [u32] from input_vec,
+                result: [u32; NUM_INVOCATIONS]
+            }
+        },
+        outputs: [result],
+        runs: 4
+    };
+}
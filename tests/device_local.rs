@@ -0,0 +1,38 @@
+//! This is an example of a shader test with buffers in device local memory.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test runs the example shader on device local buffers. The buffers are read and written
+/// through their staging buffers like host visible buffers.
+#[test]
+fn test_device_local_buffers() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: device_local [u32] from input_vec,
+            result: device_local [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
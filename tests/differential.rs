@@ -0,0 +1,32 @@
+//! This is an example of a shader test against a reference implementation in Rust.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test compares the example shader with a closure which computes every result element.
+#[test]
+fn test_shader_matches_example() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32)
+        .map(|i| i.wrapping_mul(2654435761))
+        .collect();
+
+    assert_shader_matches!{
+        pipeline: {
+            shader_path: "tests/shaders/example.comp",
+            workgroup_count: [100, 100, 1],
+            buffers: {
+                data: [u32] from input_vec,
+                result: [u32; NUM_INVOCATIONS]
+            }
+        },
+        inputs: [data],
+        output: result,
+        expected: |inputs, i| inputs.data[i].wrapping_mul(i as u32),
+        tolerance: 0,
+        workgroup_count: [100, 100, 1],
+        local_size: [8, 8, 1]
+    };
+}
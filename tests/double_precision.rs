@@ -0,0 +1,40 @@
+//! This is an example of a shader test with buffers of doubles, which is skipped on devices
+//! without the `shaderFloat64` feature.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test doubles the input and adds a value which is lost in single precision.
+#[test]
+fn test_double_precision() {
+    const NUM_ELEMENTS: usize = 4096;
+
+    let input_vec: Vec<f64> = (0..NUM_ELEMENTS).map(|index| index as f64 + 0.1).collect();
+
+    // Create the environment.
+    pipeline!{
+        features: [shader_f3264],
+        shader_path: "tests/shaders/double_precision.comp",
+        workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+        buffers: {
+            data: [f64] from input_vec,
+            result: [f64; NUM_ELEMENTS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results. Doubling is exact, and the addition is rounded
+    // correctly on every device, so the results match exactly.
+    let mapping: ReadLock<[f64]> = result.read(Duration::new(1, 0)).unwrap();
+    for (item, input) in mapping.iter().zip(&input_vec) {
+        assert_eq!(*item, input * 2.0 + 1e-12);
+    }
+}
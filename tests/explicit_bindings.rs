@@ -0,0 +1,112 @@
+//! This is an example of a shader test with explicit descriptor sets and binding numbers.
+
+// The `sets` section of `test_descriptor_sets` is expanded in more steps than the default
+// recursion limit of older compilers allows.
+#![recursion_limit = "128"]
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+
+use std::time::Duration;
+
+/// This test binds the buffers to the non-contiguous bindings declared in the shader.
+#[test]
+fn test_explicit_bindings() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS).map(|_| rand::random::<u32>()).collect();
+
+    // Create the environment. The buffers are declared in a different order than their bindings.
+    pipeline!{
+        shader_path: "tests/shaders/explicit_bindings.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            result @ set 0, binding 7: [u32; NUM_INVOCATIONS],
+            data @ set 0, binding 3: [u32] from input_vec
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        let zipped = input_vec.iter().zip(result_buffer.iter());
+
+        for (invocation_uid, (item_in, item_out)) in zipped.enumerate() {
+            assert_eq!(*item_out, (*item_in).wrapping_add(invocation_uid as u32));
+        }
+    }
+}
+
+/// This test groups the buffers by the descriptor sets declared in the shader.
+#[test]
+fn test_descriptor_sets() {
+    const NUM_INVOCATIONS: usize = 64 * 1024;
+    const FACTOR: u32 = 3;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS).map(|_| rand::random::<u32>()).collect();
+    let offsets_vec: Vec<u32> = (0..16).collect();
+
+    // Create the environment. The bindings of every set start at 0.
+    pipeline!{
+        shader_path: "tests/shaders/descriptor_sets.comp",
+        workgroup_count: [NUM_INVOCATIONS / 64, 1, 1],
+        sets: {
+            0: {
+                data: [u32] from input_vec,
+                offsets: [u32] from offsets_vec
+            },
+            1: {
+                result: [u32; NUM_INVOCATIONS]
+            },
+            2: {
+                factor: [u32] from [FACTOR]
+            }
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        for (id, (item_in, item_out)) in input_vec.iter().zip(result_buffer.iter()).enumerate() {
+            assert_eq!(*item_out, item_in.wrapping_mul(FACTOR).wrapping_add(offsets_vec[id % 16]));
+        }
+    }
+}
+
+/// This test binds a range in the middle of a larger buffer. The shader only sees the range, so
+/// the elements around it are left untouched.
+#[test]
+fn test_buffer_range() {
+    const ARENA_LEN: usize = 1024;
+    const OFFSET: usize = 256;
+
+    // Create the environment. A single workgroup of 64 invocations writes the range.
+    pipeline!{
+        shader_path: "tests/shaders/workgroup_count.comp",
+        workgroup_count: [1, 1, 1],
+        buffers: {
+            result @ offset OFFSET, len 64: [u32; ARENA_LEN] = vec![0; ARENA_LEN]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Only the bound range was written.
+    let mapping = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        let expected = if OFFSET <= index && index < OFFSET + 64 { 1 } else { 0 };
+        assert_eq!(*item, expected);
+    }
+}
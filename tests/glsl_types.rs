@@ -0,0 +1,54 @@
+//! This is an example of a shader test with buffers of GLSL vectors and matrices.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+use vulkanology::glsl::{ArrayMat3, ArrayMat4, ArrayVec3};
+
+std430_buffer_struct!{
+    /// The `Transforms` block of the shader.
+    #[derive(Debug, Copy, Clone)]
+    struct Transforms {
+        transform: ArrayMat4,
+        rotation: ArrayMat3,
+    }
+}
+
+/// This test translates every position with a `mat4` and rotates it with a `mat3`, which reads
+/// the padded `vec3` elements and columns.
+#[test]
+fn test_glsl_types() {
+    const NUM_POSITIONS: usize = 1024;
+
+    let translation = ArrayMat4::from([[1.0, 0.0, 0.0, 0.0],
+                                      [0.0, 1.0, 0.0, 0.0],
+                                      [0.0, 0.0, 1.0, 0.0],
+                                      [1.0, 2.0, 3.0, 1.0]]);
+    // A rotation by 90 degrees around the z axis.
+    let rotation = ArrayMat3::from([[0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]]);
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/glsl_types.comp",
+        workgroup_count: [NUM_POSITIONS / 64, 1, 1],
+        buffers: {
+            transforms: Transforms = Transforms { transform: translation, rotation: rotation },
+            positions: [ArrayVec3<f32>; NUM_POSITIONS] = |i| ArrayVec3([i as f32, 0.0, 0.0]),
+            results: [ArrayVec3<f32>; NUM_POSITIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[ArrayVec3<f32>]> = results.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, ArrayVec3([-2.0, index as f32 + 1.0, 3.0]));
+    }
+}
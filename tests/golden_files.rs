@@ -0,0 +1,30 @@
+//! This is an example of a shader test which compares its output against a golden file.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test compares the result of the example shader against `tests/snapshots/example.bin`.
+#[test]
+fn test_golden_file_example() {
+    const NUM_INVOCATIONS: usize = 6400;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).map(|i| i % 1000).collect();
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [10, 10, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let output: ReadLock<[u32]> = read_buffer!(result);
+    assert_buffer_snapshot!(output, "example");
+}
@@ -0,0 +1,43 @@
+//! This is an example of a shader test with buffers of half floats.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+use vulkanology::compare::assert_approx_eq;
+use vulkanology::half::{self, from_f16, to_f16};
+
+/// This test computes `a * x + y` of half floats, which the shader reads and writes in pairs.
+#[test]
+fn test_half_floats() {
+    const NUM_ELEMENTS: usize = 2048;
+
+    let x_vec: Vec<f32> = (0..NUM_ELEMENTS).map(|index| index as f32 / 7.0).collect();
+    let y_vec: Vec<f32> = (0..NUM_ELEMENTS).map(|index| 1.0 - index as f32 / 3.0).collect();
+    let x_halves = to_f16(&x_vec);
+    let y_halves = to_f16(&y_vec);
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/half_floats.comp",
+        workgroup_count: [NUM_ELEMENTS / 2 / 64, 1, 1],
+        buffers: {
+            x: [u16] from x_halves,
+            y: [u16] from y_halves,
+            result: [u16; NUM_ELEMENTS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results. The inputs are rounded to half floats, and so are the
+    // product and the sum.
+    let expected = x_vec.iter().zip(&y_vec).map(|(x, y)| 0.5 * x + y).collect::<Vec<_>>();
+    let mapping: ReadLock<[u16]> = result.read(Duration::new(1, 0)).unwrap();
+    assert_approx_eq(&expected, &from_f16(&mapping), 4.0 * half::EPSILON);
+}
@@ -0,0 +1,118 @@
+//! This is an example of a test target with `harness = false`, whose `main` is generated by
+//! `test_harness!`. Its tests run the harness on a set of inner tests and check which of them it
+//! selects and how it reports them.
+
+#[macro_use]
+extern crate vulkanology;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+
+use vulkanology::harness::{self, run, Test};
+use vulkanology::junit::JUNIT_VAR;
+
+thread_local! {
+    static RAN: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+}
+
+fn passes() {
+    RAN.with(|ran| ran.borrow_mut().push("passes"));
+}
+
+fn fails() {
+    RAN.with(|ran| ran.borrow_mut().push("fails"));
+    panic!("failed");
+}
+
+fn skipped() {
+    RAN.with(|ran| ran.borrow_mut().push("skipped"));
+    harness::note_skipped("tests/shaders/double_precision.comp", "llvmpipe", &["shader_f3264"]);
+}
+
+fn unavailable() {
+    RAN.with(|ran| ran.borrow_mut().push("unavailable"));
+    harness::note_unavailable("tests/harness.rs", "no Vulkan driver was found");
+}
+
+const TESTS: [Test; 4] = [Test { name: "passes", run: passes },
+                          Test { name: "fails", run: fails },
+                          Test { name: "skipped", run: skipped },
+                          Test { name: "unavailable", run: unavailable }];
+
+/// Runs the inner tests with the arguments `args`, and returns the exit code and the names of
+/// the tests which ran.
+fn run_with(args: &[&str]) -> (i32, Vec<&'static str>) {
+    RAN.with(|ran| ran.borrow_mut().clear());
+    let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let code = run(&TESTS, &args);
+    (code, RAN.with(|ran| ran.borrow_mut().split_off(0)))
+}
+
+/// This test checks that all tests run without a filter, and the matching ones with filters.
+fn test_filters() {
+    assert_eq!(run_with(&[]), (101, vec!["passes", "fails", "skipped", "unavailable"]));
+    assert_eq!(run_with(&["passes"]), (0, vec!["passes"]));
+    assert_eq!(run_with(&["ail"]), (101, vec!["fails", "unavailable"]));
+    assert_eq!(run_with(&["passes", "skip"]), (0, vec!["passes", "skipped"]));
+}
+
+/// This test checks that `--exact` only selects the tests whose names equal a filter.
+fn test_exact() {
+    assert_eq!(run_with(&["skipped", "--exact"]), (0, vec!["skipped"]));
+    assert_eq!(run_with(&["skip", "--exact"]), (0, vec![]));
+}
+
+/// This test checks that `--skip` drops the matching tests, also with `--exact`.
+fn test_skip() {
+    assert_eq!(run_with(&["--skip", "ail"]), (0, vec!["passes", "skipped"]));
+    assert_eq!(run_with(&["--skip", "fails", "--skip", "passes"]),
+               (0, vec!["skipped", "unavailable"]));
+    assert_eq!(run_with(&["--exact", "--skip", "fail"]),
+               (101, vec!["passes", "fails", "skipped", "unavailable"]));
+    assert_eq!(run_with(&["--skip", "unavailable", "--exact"]),
+               (101, vec!["passes", "fails", "skipped"]));
+}
+
+/// This test checks that `--list` and `--ignored` run no tests, and that the options of libtest
+/// with a value do not become filters.
+fn test_options() {
+    assert_eq!(run_with(&["--list", "--format", "terse"]), (0, vec![]));
+    assert_eq!(run_with(&["--list", "--ignored"]), (0, vec![]));
+    assert_eq!(run_with(&["--ignored"]), (0, vec![]));
+    assert_eq!(run_with(&["--test-threads", "1", "--color", "never", "passes"]),
+               (0, vec!["passes"]));
+}
+
+/// This test checks that skipped tests and tests without Vulkan are reported as skipped in the
+/// JUnit report, together with their notes.
+fn test_skipped_and_unavailable_report() {
+    let path = "target/test_harness/junit.xml";
+    fs::create_dir_all("target/test_harness").unwrap();
+    env::set_var(JUNIT_VAR, path);
+    let result = run_with(&["skipped", "unavailable"]);
+    env::remove_var(JUNIT_VAR);
+    assert_eq!(result, (0, vec!["skipped", "unavailable"]));
+
+    let mut report = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut report)).unwrap();
+    assert!(report.contains("<testsuite name=\"harness"));
+    assert!(report.contains("tests=\"2\" failures=\"0\" errors=\"0\" skipped=\"2\""));
+    assert!(report.contains("<skipped message=\"Skipped `tests/shaders/double_precision.comp`: \
+                             the device `llvmpipe` does not support `shader_f3264`.\"/>"));
+    assert!(report.contains("<skipped message=\"Skipped the test at tests/harness.rs: Vulkan is \
+                             not available, no Vulkan driver was found.\"/>"));
+    assert!(report.contains("<system-out>Skipped `tests/shaders/double_precision.comp`: the \
+                             device `llvmpipe` does not support `shader_f3264`.</system-out>"));
+    assert!(report.contains("<system-out>Skipped the test at tests/harness.rs: Vulkan is not \
+                             available, no Vulkan driver was found.</system-out>"));
+}
+
+test_harness!{
+    test_filters,
+    test_exact,
+    test_skip,
+    test_options,
+    test_skipped_and_unavailable_report,
+}
@@ -0,0 +1,175 @@
+//! This is an example of buffers in host memory with chosen properties, which requires the
+//! `vulkano` feature: `cargo test --features vulkano`.
+#![cfg(feature = "vulkano")]
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::command_buffer::{PrimaryCommandBufferBuilder, submit};
+use vulkanology::harness;
+use vulkanology::host_buffer::{HostBuffer, MemoryProperties};
+
+/// This test creates buffers in host coherent memory, which every device has.
+#[test]
+fn test_host_coherent_buffers() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (ref device, ref queue) = device_and_queue!(physical_device);
+
+    let coherent = MemoryProperties { host_coherent: true, ..MemoryProperties::none() };
+    let input_vec: Vec<u32> = (0..1024).collect();
+    let buffer = cpu_array_buffer!(device, queue, memory: coherent, u32, from input_vec);
+    assert!(buffer.memory_type().is_host_coherent());
+    assert_eq!(&buffer.read(Duration::new(1, 0)).unwrap()[..], &input_vec[..]);
+
+    let squares = cpu_array_buffer!(device, queue, memory: coherent, u32, 64, |i| (i * i) as u32);
+    assert_eq!(squares.read(Duration::new(1, 0)).unwrap()[7], 49);
+
+    let counter = cpu_array_buffer!(device, queue, memory: coherent, [u32; 2], single from [3, 4]);
+    assert_eq!(*counter.read(Duration::new(1, 0)).unwrap(), [3, 4]);
+}
+
+/// This test writes and reads a buffer in host cached memory, whose accesses are flushed and
+/// invalidated unless the memory is also coherent.
+#[test]
+fn test_host_cached_buffer() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (ref device, ref queue) = device_and_queue!(physical_device);
+
+    let cached = MemoryProperties { host_cached: true, ..MemoryProperties::none() };
+    if !physical_device.memory_types().any(|memory_type| cached.matches(memory_type)) {
+        harness::note_skipped(concat!(file!(), ":", line!()),
+                              &physical_device.name(),
+                              &["host cached memory"]);
+        return;
+    }
+
+    const LEN: usize = 333;
+    let buffer = cpu_array_buffer!(device, queue, memory: cached, u32, const LEN);
+    assert!(buffer.memory_type().is_host_cached());
+    {
+        let mut mapping = buffer.write(Duration::new(1, 0)).unwrap();
+        for (index, element) in mapping.iter_mut().enumerate() {
+            *element = index as u32 * 3;
+        }
+    }
+    let mapping = buffer.read(Duration::new(1, 0)).unwrap();
+    assert_eq!(mapping[LEN - 1], (LEN as u32 - 1) * 3);
+}
+
+/// This test copies a buffer into another one on the device. The lock of the destination waits
+/// for the copy, which writes it, and the lock of the source waits for the copy, which reads it.
+#[test]
+fn test_host_buffer_submissions() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (ref device, ref queue) = device_and_queue!(physical_device);
+
+    let coherent = MemoryProperties { host_coherent: true, ..MemoryProperties::none() };
+    let input_vec: Vec<u32> = (0..1024).collect();
+    let source = cpu_array_buffer!(device, queue, memory: coherent, u32, from input_vec);
+    let destination = cpu_array_buffer!(device, queue, memory: coherent, u32, from vec![0; 1024]);
+
+    let command_buffer = PrimaryCommandBufferBuilder::new(device, queue.family())
+        .copy_buffer(&source, &destination)
+        .build();
+    let submission = submit(&command_buffer, queue).unwrap();
+    {
+        let mapping = destination.read(Duration::new(1, 0)).unwrap();
+        assert!(submission.finished());
+        assert_eq!(&mapping[..], &input_vec[..]);
+    }
+    {
+        let mut mapping = source.write(Duration::new(1, 0)).unwrap();
+        assert!(submission.finished());
+        for element in mapping.iter_mut() {
+            *element = 0;
+        }
+    }
+    assert_eq!(&destination.read(Duration::new(1, 0)).unwrap()[..], &input_vec[..]);
+}
+
+/// This test submits a copy into a buffer from another thread while the buffer is locked for
+/// reading. The submission waits until the lock is dropped, so the host never reads a buffer
+/// which the device writes.
+#[test]
+fn test_host_buffer_lock_blocks_submission() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (ref device, ref queue) = device_and_queue!(physical_device);
+
+    let coherent = MemoryProperties { host_coherent: true, ..MemoryProperties::none() };
+    let input_vec: Vec<u32> = (0..1024).collect();
+    let source = cpu_array_buffer!(device, queue, memory: coherent, u32, from input_vec);
+    let destination = cpu_array_buffer!(device, queue, memory: coherent, u32, from vec![0; 1024]);
+
+    let mapping = destination.read(Duration::new(1, 0)).unwrap();
+    let (submitted, submissions) = mpsc::channel();
+    let copy = {
+        let (device, queue) = (device.clone(), queue.clone());
+        let (source, destination) = (source.clone(), destination.clone());
+        thread::spawn(move || {
+            let command_buffer = PrimaryCommandBufferBuilder::new(&device, queue.family())
+                .copy_buffer(&source, &destination)
+                .build();
+            let submission = submit(&command_buffer, &queue).unwrap();
+            submitted.send(()).unwrap();
+            submission.wait(Duration::new(1, 0)).unwrap();
+        })
+    };
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(submissions.try_recv().is_err());
+    assert!(mapping.iter().all(|&element| element == 0));
+    drop(mapping);
+
+    copy.join().unwrap();
+    assert!(submissions.try_recv().is_ok());
+    assert_eq!(&destination.read(Duration::new(1, 0)).unwrap()[..], &input_vec[..]);
+}
+
+/// An iterator which yields one element less than its length.
+struct ShortIter(usize);
+
+impl Iterator for ShortIter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.0 == 0 {
+            None
+        } else {
+            self.0 -= 1;
+            Some(self.0 as u32)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0 + 1, Some(self.0 + 1))
+    }
+}
+
+impl ExactSizeIterator for ShortIter {}
+
+/// This test creates a buffer from an iterator which is shorter than its length, which would
+/// leave the last element uninitialized.
+#[test]
+#[should_panic(expected = "The iterator yielded 16 elements, but its length is 17.")]
+fn test_host_buffer_from_short_iterator() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (ref device, ref queue) = device_and_queue!(physical_device);
+
+    let coherent = MemoryProperties { host_coherent: true, ..MemoryProperties::none() };
+    let _ = HostBuffer::from_iter(device,
+                                  &BufferUsage::all(),
+                                  &coherent,
+                                  Some(queue.family()),
+                                  ShortIter(16));
+}
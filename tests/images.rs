@@ -0,0 +1,106 @@
+//! This is an example of a shader test with storage and sampled images.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::{ReadLock, WriteLock};
+
+/// This test reads one storage image filled by the test and writes another one.
+#[test]
+fn test_images() {
+    const WIDTH: usize = 64;
+    const HEIGHT: usize = 32;
+    const SCALE: f32 = 0.5;
+
+    // Create the environment. The images are bound after `scale`, in the order of declaration.
+    pipeline!{
+        shader_path: "tests/shaders/images.comp",
+        workgroup_count: [WIDTH / 8, HEIGHT / 8, 1],
+        buffers: {
+            scale: [f32;1]
+        },
+        images: {
+            source: [R32Uint; WIDTH, HEIGHT],
+            result: [R32G32B32A32Sfloat; WIDTH, HEIGHT]
+        },
+        execution_command: execute_shader
+    };
+
+    // Fill the scale buffer and the staging buffer of the source image, whose pixels are in
+    // row-major order.
+    {
+        let mut mapping: WriteLock<[f32]> = scale.write(Duration::new(1, 0)).unwrap();
+        mapping[0] = SCALE;
+    }
+    {
+        let mut mapping: WriteLock<[u32]> = source.write(Duration::new(1, 0)).unwrap();
+        for (index, pixel) in mapping.iter_mut().enumerate() {
+            *pixel = index as u32;
+        }
+    }
+
+    // Execute the shader, which copies the staging buffers from and back into the images.
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let mapping: ReadLock<[[f32; 4]]> = result.read(Duration::new(1, 0)).unwrap();
+        assert_eq!(mapping.len(), WIDTH * HEIGHT);
+        for (index, pixel) in mapping.iter().enumerate() {
+            let (x, y) = (index % WIDTH, index / WIDTH);
+            assert_eq!(*pixel, [index as f32 * SCALE, x as f32, y as f32, 1.0]);
+        }
+    }
+}
+
+/// This test samples two images with different filters and address modes.
+#[test]
+fn test_sampled_images() {
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 8;
+
+    pipeline!{
+        shader_path: "tests/shaders/sampled_images.comp",
+        workgroup_count: [WIDTH / 8, HEIGHT / 8, 1],
+        buffers: {
+            result: [[f32; 4]; 2 * WIDTH * HEIGHT]
+        },
+        images: {
+            nearest: sampled [R8G8B8A8Unorm; WIDTH, HEIGHT],
+            linear: sampled (Linear, Repeat) [R8G8B8A8Unorm; WIDTH, HEIGHT]
+        },
+        execution_command: execute_shader
+    };
+
+    // Both images hold the same pixels, whose red channel grows along the rows.
+    let pixel = |x: usize, y: usize| [(x * 16) as u8, (y * 32) as u8, 0, 255];
+    for image in &[&nearest, &linear] {
+        let mut mapping: WriteLock<[[u8; 4]]> = image.write(Duration::new(1, 0)).unwrap();
+        for (index, value) in mapping.iter_mut().enumerate() {
+            *value = pixel(index % WIDTH, index / WIDTH);
+        }
+    }
+
+    execute_shader();
+
+    // `nearest` returns the pixels unchanged. `linear` averages every pixel with its right
+    // neighbour, which wraps around at the end of the row.
+    let normalize = |value: [u8; 4]| {
+        [value[0] as f32 / 255.0, value[1] as f32 / 255.0, value[2] as f32 / 255.0, 1.0]
+    };
+    let mapping: ReadLock<[[f32; 4]]> = result.read(Duration::new(1, 0)).unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let id = y * WIDTH + x;
+            let (left, right) = (normalize(pixel(x, y)), normalize(pixel((x + 1) % WIDTH, y)));
+            for channel in 0..4 {
+                let average = (left[channel] + right[channel]) / 2.0;
+                assert!((mapping[2 * id][channel] - left[channel]).abs() < 0.01);
+                assert!((mapping[2 * id + 1][channel] - average).abs() < 0.01);
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! This is an example of a shader test whose input is loaded from a file.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::path::Path;
+
+use vulkano::buffer::cpu_access::ReadLock;
+use vulkanology::import::load;
+
+/// This test runs the example shader on the contents of `tests/data/example_input.npy`.
+#[test]
+fn test_input_from_file() {
+    const NUM_INVOCATIONS: usize = 6400;
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [10, 10, 1],
+        buffers: {
+            data: [u32] from_file "tests/data/example_input.npy",
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let input = load::<u32>(Path::new("tests/data/example_input.npy"));
+    let expected = input.iter()
+        .enumerate()
+        .map(|(index, item)| item.wrapping_mul(index as u32))
+        .collect::<Vec<_>>();
+    let output: ReadLock<[u32]> = read_buffer!(result);
+    assert_buffers_eq!(output, expected);
+}
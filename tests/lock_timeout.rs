@@ -0,0 +1,37 @@
+//! This is an example of a shader test which reads its results while the shader may still run.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test reads the results right after submitting the shader. The lock waits for the shader,
+/// and is retried with a longer timeout if it takes longer than 10 milliseconds.
+#[test]
+fn test_lock_timeout() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    pipeline!{
+        lock_timeout: Duration::from_millis(10),
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader,
+        async_command: execute_shader_async
+    };
+
+    let _submission = execute_shader_async();
+
+    let mapping: ReadLock<[u32]> = read_buffer!(result);
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
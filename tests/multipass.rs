@@ -0,0 +1,60 @@
+//! This is an example of a shader test with several passes, whose dispatches are recorded into a
+//! single command buffer.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::{ReadLock, WriteLock};
+
+/// This test reduces every workgroup of the input, and then scatters the sums back to the
+/// elements in a second pass.
+#[test]
+fn test_reduce_then_scatter() {
+    const NUM_WORKGROUPS: usize = 256;
+    const NUM_INVOCATIONS: usize = NUM_WORKGROUPS * 64;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).map(|i| i % 1000).collect();
+
+    // Create the environment. The pipeline dispatches `multipass_reduce.comp`, followed by the
+    // passes in the order of declaration.
+    pipeline!{
+        shader_path: "tests/shaders/multipass_reduce.comp",
+        workgroup_count: [NUM_WORKGROUPS, 1, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            sums: [u32; NUM_WORKGROUPS],
+            result: [u32; NUM_INVOCATIONS]
+        },
+        passes: {
+            scatter: {
+                shader_path: "tests/shaders/multipass_scatter.comp",
+                workgroup_count: [NUM_WORKGROUPS, 1, 1]
+            }
+        },
+        execution_command: execute_shader
+    };
+
+    {
+        let mut mapping: WriteLock<[u32]> = sums.write(Duration::new(1, 0)).unwrap();
+        for sum in mapping.iter_mut() {
+            *sum = 0;
+        }
+    }
+
+    // Execute both passes
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+        for (workgroup, chunk) in input_vec.chunks(64).enumerate() {
+            let sum = chunk.iter().sum::<u32>();
+            for (index, item_in) in chunk.iter().enumerate() {
+                assert_eq!(result_buffer[workgroup * 64 + index], item_in + sum);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! This is an example of a shader test which does not provide all bindings of the shader.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+/// This test leaves the `trace` binding to a dummy buffer, as the shader only writes to it when
+/// tracing is enabled.
+#[test]
+fn test_optional_buffers() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/optional_buffers.comp",
+        workgroup_count: [100, 100, 1],
+        push_constants: {
+            trace: u32 = 0
+        },
+        buffers: {
+            result: [u32; NUM_INVOCATIONS],
+            trace: optional [u32]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+
+        for (invocation_uid, item_out) in result_buffer.iter().enumerate() {
+            assert_eq!(*item_out, invocation_uid as u32 * 2);
+        }
+    }
+}
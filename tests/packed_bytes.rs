@@ -0,0 +1,38 @@
+//! This is an example of a shader test with a buffer of bytes, which the shader reads from a
+//! `uint` array without 8-bit storage.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test sums every four consecutive bytes, which the shader unpacks from one `uint`.
+#[test]
+fn test_packed_bytes() {
+    const NUM_WORDS: usize = 4096;
+
+    let bytes: Vec<u8> = (0..NUM_WORDS * 4).map(|index| (index * 7 % 256) as u8).collect();
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/packed_bytes.comp",
+        workgroup_count: [NUM_WORDS / 64, 1, 1],
+        buffers: {
+            data: [u8] from bytes,
+            result: [u32; NUM_WORDS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results. The bytes of a `uint` are in little endian order.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (item, word) in mapping.iter().zip(bytes.chunks(4)) {
+        assert_eq!(*item, word.iter().map(|&byte| byte as u32).sum::<u32>());
+    }
+}
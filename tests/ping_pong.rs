@@ -0,0 +1,53 @@
+//! This is an example of a shader test which iterates a shader on two ping-pong buffers.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::{ReadLock, WriteLock};
+
+/// Every dispatch adds the state of the left neighbour to every cell, so starting from a single
+/// cell, the state after `n` dispatches is the `n`th row of Pascal's triangle. Both odd and even
+/// numbers of dispatches leave the newest state in `state`.
+#[test]
+fn test_ping_pong() {
+    const LEN: usize = 256;
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/ping_pong.comp",
+        workgroup_count: [LEN / 64, 1, 1],
+        buffers: {
+            state: [u32; LEN],
+            next_state: [u32; LEN]
+        },
+        ping_pong: { state, next_state },
+        execution_command: execute_shader(dispatches)
+    };
+
+    for dispatches in 1..6 {
+        {
+            let mut mapping: WriteLock<[u32]> = state.write(Duration::new(1, 0)).unwrap();
+            for (index, item) in mapping.iter_mut().enumerate() {
+                *item = if index == 0 { 1 } else { 0 };
+            }
+        }
+
+        // Execute the shader
+        execute_shader(dispatches);
+
+        // Iterate the same rule on the host.
+        let mut expected = vec![0u32; LEN];
+        expected[0] = 1;
+        for _ in 0..dispatches {
+            expected = (0..LEN)
+                .map(|index| expected[index] + expected[(index + LEN - 1) % LEN])
+                .collect();
+        }
+
+        let mapping: ReadLock<[u32]> = state.read(Duration::new(1, 0)).unwrap();
+        assert_buffers_eq!(mapping, expected);
+    }
+}
@@ -0,0 +1,78 @@
+//! This is an example of shader tests pinned to the first version of `pipeline!`.
+
+#[macro_use]
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+
+use std::time::Duration;
+
+/// This test shows the first DSL, with the input written into its buffer.
+#[test]
+fn test_pipeline_v1() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    // Create the environment.
+    pipeline_v1!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS],
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Fill the input buffer.
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS).map(|_| rand::random::<u32>()).collect();
+    data.write(Duration::new(1, 0)).unwrap().copy_from_slice(&input_vec);
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        let zipped = input_vec.iter().zip(result_buffer.iter());
+
+        for (invocation_uid, (item_in, item_out)) in zipped.enumerate() {
+            assert_eq!(*item_out, (*item_in).wrapping_mul(invocation_uid as u32));
+        }
+    }
+}
+
+/// This test shows the first DSL with push constants.
+#[test]
+fn test_pipeline_v1_with_push_constants() {
+    const NUM_INVOCATIONS: usize = 640000;
+    const A: f32 = 4.0;
+    const B: f32 = 10.0;
+
+    // Create the environment.
+    pipeline_v1!{
+        shader_path: "tests/shaders/push_constants.comp",
+        workgroup_count: [100, 100, 1],
+        push_constants: {
+            a: f32 = A,
+            b: f32 = B
+        },
+        buffers: {
+            result: [f32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+
+        for (invocation_uid, remote_result) in result_buffer.iter().enumerate() {
+            let local_result = A * invocation_uid as f32 + B;
+            assert!((local_result - remote_result).abs() < 0.0001);
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! This is an example of a shader test which measures the precision of a shader against a
+//! double-precision reference.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test reports the error of `exp` on a range of inputs, and checks it against the
+/// precision which the Vulkan specification requires, `3 + 2 * |x|` ULP.
+#[test]
+fn test_precision_exp() {
+    const NUM_ELEMENTS: usize = 4096;
+
+    let input_vec: Vec<f32> = (0..NUM_ELEMENTS)
+        .map(|index| -10.0 + 20.0 * index as f32 / NUM_ELEMENTS as f32)
+        .collect();
+
+    let report = precision_report!{
+        pipeline: {
+            shader_path: "tests/shaders/precision.comp",
+            workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+            buffers: {
+                data: [f32] from input_vec,
+                result: [f32; NUM_ELEMENTS]
+            }
+        },
+        input: data,
+        output: result,
+        reference: |&x| (x as f64).exp(),
+        workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+        local_size: [64, 1, 1]
+    };
+
+    assert_eq!(report.elements, NUM_ELEMENTS);
+    report.assert_within(3.0 + 2.0 * 10.0);
+}
@@ -1,6 +1,5 @@
 //! This is an example of an elaborate shader test.
 
-#[macro_use]
 extern crate vulkano;
 #[macro_use]
 extern crate vulkanology;
@@ -8,6 +7,8 @@ extern crate rand;
 
 use std::time::Duration;
 
+use vulkanology::push_constants::{Mat3, Vec2, Vec3};
+
 /// This test shows how to use push constants in tests.
 #[test]
 fn test_with_push_constants() {
@@ -45,3 +46,99 @@ fn test_with_push_constants() {
         }
     }
 }
+
+/// This test shows how to place push constants at explicit offsets.
+#[test]
+fn test_with_push_constant_ranges() {
+    const NUM_INVOCATIONS: usize = 640000;
+    const A: f32 = 4.0;
+    const B: f32 = 10.0;
+    const C: f32 = 0.5;
+
+    // Create the environment. `b` and `c` start at offset 16, as declared in the shader.
+    pipeline!{
+        shader_path: "tests/shaders/push_constant_ranges.comp",
+        workgroup_count: [100, 100, 1],
+        push_constants: {
+            offset 0: {
+                a: f32 = A
+            },
+            offset 16, size 8: {
+                b: f32 = B,
+                c: f32 = C
+            }
+        },
+        buffers: {
+            result: [f32;NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+
+        for (invocation_uid, remote_result) in result_buffer.iter().enumerate() {
+            let local_result = A * invocation_uid as f32 + B * C;
+            assert!((local_result - remote_result).abs() < 0.0001);
+        }
+    }
+}
+
+std430_struct!{
+    /// A light of the shader, which declares the lights as `vec4`s of the same layout.
+    #[derive(Copy, Clone)]
+    struct Light {
+        position: Vec3<f32>,
+        intensity: f32,
+    }
+}
+
+/// This test shows how to push vectors, matrices, arrays and structs, which are laid out by the
+/// `std430` rules.
+#[test]
+fn test_with_push_constant_structs() {
+    const LIGHTS: [Light; 2] = [Light {
+                                    position: Vec3([12.0, 13.0, 14.0]),
+                                    intensity: 15.0,
+                                },
+                                Light {
+                                    position: Vec3([16.0, 17.0, 18.0]),
+                                    intensity: 19.0,
+                                }];
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/push_constant_structs.comp",
+        workgroup_count: [1, 1, 1],
+        push_constants: {
+            transform: Mat3 = Mat3([[0.0, 1.0, 2.0], [3.0, 4.0, 5.0], [6.0, 7.0, 8.0]]),
+            scale: Vec2<f32> = Vec2([9.0, 10.0]),
+            lights_len: u32 = LIGHTS.len() as u32,
+            lights: [Light; 2] = LIGHTS,
+            weights: [f32; 3] = [20.0, 21.0, 22.0]
+        },
+        buffers: {
+            result: [f32;23]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // The shader copies the members in the order of declaration, so `result` counts up, except
+    // for the number of lights.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        let expected = (0..11)
+            .chain(Some(LIGHTS.len()))
+            .chain(12..23)
+            .map(|value| value as f32)
+            .collect::<Vec<_>>();
+        assert_eq!(&result_buffer[..], &expected[..]);
+    }
+}
@@ -0,0 +1,34 @@
+//! This is an example of a test which creates several queues of one device.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::sync::Arc;
+
+/// This test requests a queue of every kind, and checks that the families of the compute queues
+/// support compute operations.
+#[test]
+fn test_multiple_queues() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (device, (compute, compute_only, transfer)) =
+        device_and_queue!(physical_device, queues: [compute, compute_only, transfer]);
+
+    assert!(compute.family().supports_compute());
+    assert!(compute_only.family().supports_compute());
+    for queue in &[compute, compute_only, transfer] {
+        assert!(Arc::ptr_eq(queue.device(), &device));
+    }
+}
+
+/// This test requests a single queue of a compute-only family with the highest priority.
+#[test]
+fn test_queue_family_and_priority() {
+    let instance = instance!();
+    let physical_device = physical_device!(instance);
+    let (device, queue) = device_and_queue!(physical_device, priority: 1.0, family: compute_only);
+
+    assert!(queue.family().supports_compute());
+    assert!(Arc::ptr_eq(queue.device(), &device));
+}
@@ -0,0 +1,51 @@
+//! This is an example of a property test of a shader on generated buffers, which requires the
+//! `quickcheck` feature: `cargo test --features quickcheck`.
+
+#![cfg(feature = "quickcheck")]
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use vulkanology::quickcheck_adapter::{check, Buffer, SizeHint};
+
+/// The local size of the example shader.
+const WORKGROUP_SIZE: usize = 8 * 8;
+
+/// Whole workgroups of the example shader.
+struct Workgroups;
+
+impl SizeHint for Workgroups {
+    const MIN: usize = WORKGROUP_SIZE;
+    const MAX: usize = WORKGROUP_SIZE * 1024;
+    const MULTIPLE: usize = WORKGROUP_SIZE;
+}
+
+/// The example shader multiplies every element with the id of its invocation.
+fn multiplies_by_invocation(input: Buffer<u32, Workgroups>) -> bool {
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [(input.len() / WORKGROUP_SIZE) as u32, 1, 1],
+        buffers: {
+            data: [u32] from input,
+            result: [u32; input.len()]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let result = read_buffer!(result);
+    input.iter()
+        .zip(result.iter())
+        .enumerate()
+        .all(|(invocation_uid, (&item_in, &item_out))| {
+            item_out == item_in.wrapping_mul(invocation_uid as u32)
+        })
+}
+
+/// This test checks the example shader on generated inputs, which share one device.
+#[test]
+fn test_quickcheck_example() {
+    check(20, multiplies_by_invocation as fn(Buffer<u32, Workgroups>) -> bool);
+}
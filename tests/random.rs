@@ -1,15 +1,15 @@
 //! This is an example of an elaborate shader test.
 
-#[macro_use]
 extern crate vulkano;
 #[macro_use]
 extern crate vulkanology;
 extern crate rand;
 
 use std::time::Duration;
-use rand::{Rng, StdRng};
+use rand::{Rng, SeedableRng, StdRng};
 
 use vulkano::buffer::cpu_access::WriteLock;
+use vulkanology::randomness::{assert_random, DEFAULT_SIGNIFICANCE};
 
 /// Implementation of the xoroshiro128+ PRNG.
 /// For reference see: http://xoroshiro.di.unimi.it/xoroshiro128plus.c
@@ -78,3 +78,41 @@ fn test_random_next_u64() {
         }
     }
 }
+
+/// This test checks the distribution of the outputs of the GLSL xoroshiro128+ PRNG. The bit-exact
+/// port shares the flaws of the generator, so its outputs are tested as well. The seeds are
+/// generated from a fixed seed, so that a failure can be reproduced.
+#[test]
+fn test_random_next_u64_distribution() {
+    const NUM_INVOCATIONS: usize = 640000;
+    const PRNG_XOROSHIRO128PLUS_NUM_U64: usize = 2;
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/random.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            prng: [u64;NUM_INVOCATIONS*PRNG_XOROSHIRO128PLUS_NUM_U64],
+            result: [u64;NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Fill the shader buffer with seeds generated from a fixed seed.
+    let mut seed_generator = StdRng::from_seed(&[0x5eed, 0x0123_4567, 0x89ab_cdef][..]);
+    {
+        let mut mapping: WriteLock<[u64]> = prng.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = seed_generator.next_u64();
+        }
+    }
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the distribution of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+        assert_random(&result_buffer, DEFAULT_SIGNIFICANCE);
+    }
+}
@@ -0,0 +1,36 @@
+//! This is an example of a shader test which submits its command buffer several times.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test submits the example shader ten times and waits for all of them at once. The shader
+/// does not depend on its previous results, so they are the same as after a single execution.
+#[test]
+fn test_repeat_command() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader,
+        repeat_command: execute_shader_n
+    };
+
+    execute_shader_n(10);
+
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
@@ -0,0 +1,71 @@
+//! This is an example of shaders run from a description instead of `pipeline!`, which requires
+//! the `runner` feature: `cargo test --features runner`.
+
+#![cfg(feature = "runner")]
+
+extern crate vulkanology;
+
+use std::path::Path;
+
+use vulkanology::import::load;
+use vulkanology::runner::{load_shader, Description, Runner};
+
+/// This test runs the example shader on an input given in the description.
+#[test]
+fn test_runner() {
+    let input = (0..64).map(|uid| uid.to_string()).collect::<Vec<_>>().join(", ");
+    let expected = (0..64).map(|uid| (uid * uid).to_string()).collect::<Vec<_>>().join(", ");
+    let description = Description::parse(&format!(r#"{{
+        "workgroup_count": [1, 1, 1],
+        "buffers": [
+            {{ "name": "data", "type": "u32", "input": [{}] }},
+            {{ "name": "result", "type": "u32", "len": 64, "expect": [{}],
+               "output": "target/test_runner/result.npy" }}
+        ]
+    }}"#,
+                                                  input,
+                                                  expected))
+        .unwrap();
+
+    let shader = "tests/shaders/example.comp";
+    let runner = Runner::new(description);
+    runner.run(shader, &load_shader(Path::new(shader)));
+    assert_eq!(runner.report(), Vec::<String>::new());
+
+    let result = load::<u32>(Path::new("target/test_runner/result.npy"));
+    assert_eq!(result, (0..64).map(|uid| uid * uid).collect::<Vec<u32>>());
+}
+
+/// This test runs a shader with push constants, and reports the elements which differ from the
+/// expected ones.
+#[test]
+fn test_runner_with_push_constants() {
+    let description = Description::parse(r#"{
+        "workgroup_count": [1, 1, 1],
+        "push_constants": [{ "type": "f32", "value": 4.0 }, { "type": "f32", "value": 10.0 }],
+        "buffers": [
+            { "name": "result", "type": "f32", "len": 64, "expect": [10.0, 14.0, 17.0] }
+        ]
+    }"#)
+        .unwrap();
+
+    let shader = "tests/shaders/push_constants.comp";
+    let runner = Runner::new(description);
+    runner.run(shader, &load_shader(Path::new(shader)));
+    assert_eq!(runner.report(), ["result[2] is 18, expected 17"]);
+}
+
+/// This test checks that every buffer of the shader has to be described.
+#[test]
+#[should_panic(expected = "of the shader `tests/shaders/example.comp` at set 0, binding 1 is not \
+                           described.")]
+fn test_runner_with_undescribed_buffer() {
+    let description = Description::parse(r#"{
+        "workgroup_count": [1, 1, 1],
+        "buffers": [{ "name": "data", "type": "u32", "len": 64 }]
+    }"#)
+        .unwrap();
+
+    let shader = "tests/shaders/example.comp";
+    Runner::new(description).run(shader, &load_shader(Path::new(shader)));
+}
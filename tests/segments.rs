@@ -0,0 +1,299 @@
+//! Tests of the built-in GLSL segments in `segments/`.
+//!
+//! The build script concatenates each segment with a test header and a test `main` from
+//! `tests/shaders/segments` into `<OUT_DIR>/test_shaders/segment_<name>.comp`. The results of the
+//! shaders are compared with Rust implementations of the segments.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+
+use std::time::Duration;
+use rand::{Rng, StdRng};
+
+use vulkano::buffer::cpu_access::WriteLock;
+use vulkanology::debug_log::{self, Values};
+use vulkanology::invocations::{self, assert_invocations};
+use vulkanology::scan::{assert_segmented_scan_eq, Scan};
+
+const WORKGROUP_SIZE: usize = 64;
+const NUM_WORKGROUPS: usize = 256;
+const NUM_INVOCATIONS: usize = WORKGROUP_SIZE * NUM_WORKGROUPS;
+const RESULTS_PER_INVOCATION: usize = 4;
+
+fn pcg_hash(x: u32) -> u32 {
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn wang_hash(mut x: u32) -> u32 {
+    x = (x ^ 61) ^ (x >> 16);
+    x = x.wrapping_mul(9);
+    x ^= x >> 4;
+    x = x.wrapping_mul(0x27d4eb2d);
+    x ^= x >> 15;
+    x
+}
+
+fn murmur3_fmix32(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85ebca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2ae35);
+    x ^= x >> 16;
+    x
+}
+
+/// Implementation of the PCG32 PRNG.
+/// For reference see: https://www.pcg-random.org/download.html
+fn pcg32_next(state: &mut u64) -> u32 {
+    let old = *state;
+    *state = old.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+    let rot = (old >> 59) as u32;
+    xorshifted.rotate_right(rot)
+}
+
+/// Implementation of the xoroshiro128+ PRNG.
+/// For reference see: http://xoroshiro.di.unimi.it/xoroshiro128plus.c
+fn xoroshiro128plus_next(state: &mut [u64; 2]) -> u64 {
+    let s0 = state[0];
+    let mut s1 = state[1];
+    let result = s0.wrapping_add(s1);
+
+    s1 ^= s0;
+    state[0] = s0.rotate_left(55) ^ s1 ^ (s1 << 14);
+    state[1] = s1.rotate_left(36);
+
+    result
+}
+
+/// Tests the integer hash functions of `segments/hash.comp`.
+#[test]
+fn test_segment_hash() {
+    pipeline!{
+        shader_path: "test_shaders/segment_hash.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            data: [u32;NUM_INVOCATIONS],
+            result: [u32;3*NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let mut rng = StdRng::new().unwrap();
+    {
+        let mut mapping: WriteLock<[u32]> = data.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = rng.next_u32();
+        }
+    }
+
+    execute_shader();
+
+    let data = data.read(Duration::new(1, 0)).unwrap();
+    let result = result.read(Duration::new(1, 0)).unwrap();
+    for (id, &x) in data.iter().enumerate() {
+        assert_eq!(result[3 * id], pcg_hash(x));
+        assert_eq!(result[3 * id + 1], wang_hash(x));
+        assert_eq!(result[3 * id + 2], murmur3_fmix32(x));
+    }
+}
+
+/// Tests the PCG32 PRNG of `segments/pcg.comp`, including the state which is left behind.
+#[test]
+fn test_segment_pcg() {
+    pipeline!{
+        shader_path: "test_shaders/segment_pcg.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            state: [u64;NUM_INVOCATIONS],
+            result: [u32;RESULTS_PER_INVOCATION*NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let mut rng = StdRng::new().unwrap();
+    let mut seeds = Vec::with_capacity(NUM_INVOCATIONS);
+    {
+        let mut mapping: WriteLock<[u64]> = state.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = rng.next_u64();
+            seeds.push(*item);
+        }
+    }
+
+    execute_shader();
+
+    let state = state.read(Duration::new(1, 0)).unwrap();
+    let result = result.read(Duration::new(1, 0)).unwrap();
+    for (id, &seed) in seeds.iter().enumerate() {
+        let mut local_state = seed;
+        for i in 0..RESULTS_PER_INVOCATION {
+            assert_eq!(result[RESULTS_PER_INVOCATION * id + i], pcg32_next(&mut local_state));
+        }
+        assert_eq!(state[id], local_state);
+    }
+}
+
+/// Tests the xoroshiro128+ PRNG of `segments/xoroshiro.comp`, including the state which is left
+/// behind.
+#[test]
+fn test_segment_xoroshiro() {
+    pipeline!{
+        shader_path: "test_shaders/segment_xoroshiro.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            state: [[u64; 2];NUM_INVOCATIONS],
+            result: [u64;RESULTS_PER_INVOCATION*NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let mut rng = StdRng::new().unwrap();
+    let mut seeds = Vec::with_capacity(NUM_INVOCATIONS);
+    {
+        let mut mapping: WriteLock<[[u64; 2]]> = state.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = [rng.next_u64(), rng.next_u64()];
+            seeds.push(*item);
+        }
+    }
+
+    execute_shader();
+
+    let state = state.read(Duration::new(1, 0)).unwrap();
+    let result = result.read(Duration::new(1, 0)).unwrap();
+    for (id, &seed) in seeds.iter().enumerate() {
+        let mut local_state = seed;
+        for i in 0..RESULTS_PER_INVOCATION {
+            assert_eq!(result[RESULTS_PER_INVOCATION * id + i],
+                       xoroshiro128plus_next(&mut local_state));
+        }
+        assert_eq!(state[id], local_state);
+    }
+}
+
+/// Tests the workgroup reduction of `segments/reduce.comp` against per-workgroup sums.
+#[test]
+fn test_segment_reduce() {
+    pipeline!{
+        shader_path: "test_shaders/segment_reduce.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            data: [u32;NUM_INVOCATIONS],
+            result: [u32;NUM_WORKGROUPS]
+        },
+        execution_command: execute_shader
+    };
+
+    let mut rng = StdRng::new().unwrap();
+    {
+        let mut mapping: WriteLock<[u32]> = data.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = rng.next_u32();
+        }
+    }
+
+    execute_shader();
+
+    let data = data.read(Duration::new(1, 0)).unwrap();
+    let result = result.read(Duration::new(1, 0)).unwrap();
+    for (workgroup, chunk) in data.chunks(WORKGROUP_SIZE).enumerate() {
+        let sum = chunk.iter().fold(0u32, |sum, &x| sum.wrapping_add(x));
+        assert_eq!(result[workgroup], sum, "Workgroup {}", workgroup);
+    }
+}
+
+/// Tests the inclusive and exclusive workgroup scans of `segments/scan.comp`.
+#[test]
+fn test_segment_scan() {
+    pipeline!{
+        shader_path: "test_shaders/segment_scan.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            data: [u32;NUM_INVOCATIONS],
+            inclusive: [u32;NUM_INVOCATIONS],
+            exclusive: [u32;NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    let mut rng = StdRng::new().unwrap();
+    {
+        let mut mapping: WriteLock<[u32]> = data.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = rng.next_u32();
+        }
+    }
+
+    execute_shader();
+
+    // Every workgroup scans its own segment of the input.
+    let heads = (0..NUM_INVOCATIONS).map(|id| id % WORKGROUP_SIZE == 0).collect::<Vec<_>>();
+    let data = data.read(Duration::new(1, 0)).unwrap();
+    let inclusive = inclusive.read(Duration::new(1, 0)).unwrap();
+    let exclusive = exclusive.read(Duration::new(1, 0)).unwrap();
+    assert_segmented_scan_eq(Scan::Inclusive, &data, &heads, &inclusive);
+    assert_segmented_scan_eq(Scan::Exclusive, &data, &heads, &exclusive);
+}
+
+/// Tests the records of `segments/debug_log.comp`, whose order within an invocation is kept.
+#[test]
+fn test_segment_debug_log() {
+    // Every other invocation writes three records.
+    const NUM_RECORDS: usize = 3 * NUM_INVOCATIONS / 2;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+    let log_vec = vec![0; debug_log::buffer_len(NUM_RECORDS)];
+
+    pipeline!{
+        shader_path: "test_shaders/segment_debug_log.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            log: [u32] from log_vec
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let log = debug_log::decode(&log.read(Duration::new(1, 0)).unwrap());
+    assert_eq!(log.records.len(), NUM_RECORDS);
+    assert_eq!(log.dropped, 0);
+    for records in log.records.chunks(3) {
+        let value = records[0].invocation[0];
+        assert_eq!(value % 2, 0);
+        assert_eq!(records.iter().map(|record| record.tag).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(records[0].values, Values::Uint([value, value + 1, 0, 0]));
+        assert_eq!(records[1].values, Values::Int([-(value as i32), 0, 0, 0]));
+        assert_eq!(records[2].values, Values::Float([value as f32 * 0.5, 0.0, 0.0, 0.0]));
+    }
+}
+
+/// Tests the counts of `segments/invocation_count.comp`, which covers every invocation of the
+/// dispatch exactly once.
+#[test]
+fn test_segment_invocation_count() {
+    let counts_vec = vec![0; invocations::BUFFER_LEN];
+
+    pipeline!{
+        shader_path: "test_shaders/segment_invocation_count.comp",
+        workgroup_count: [NUM_WORKGROUPS as u32, 1, 1],
+        buffers: {
+            result: [u32; NUM_INVOCATIONS],
+            counts: [u32] from counts_vec
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let counts = counts.read(Duration::new(1, 0)).unwrap();
+    assert_invocations(&counts,
+                       [NUM_WORKGROUPS as u32, 1, 1],
+                       [WORKGROUP_SIZE as u32, 1, 1]);
+}
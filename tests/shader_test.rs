@@ -0,0 +1,40 @@
+//! This is an example of a shader test and a benchmark generated from one definition.
+#![feature(test)]
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+extern crate rand;
+extern crate test;
+
+const NUM_INVOCATIONS: usize = 640000;
+
+shader_test!{
+    test: test_example,
+    bench: bench_example,
+    execution_command: execute_shader,
+    pipeline: {
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32; NUM_INVOCATIONS],
+            result: [u32; NUM_INVOCATIONS]
+        }
+    },
+    setup: {
+        use std::time::Duration;
+        let mut mapping = data.write(Duration::new(1, 0)).unwrap();
+        for item in mapping.iter_mut() {
+            *item = rand::random::<u32>();
+        }
+    },
+    verify: {
+        use std::time::Duration;
+        let input = data.read(Duration::new(1, 0)).unwrap();
+        let output = result.read(Duration::new(1, 0)).unwrap();
+
+        for (invocation_uid, (item_in, item_out)) in input.iter().zip(output.iter()).enumerate() {
+            assert_eq!(*item_out, (*item_in).wrapping_mul(invocation_uid as u32));
+        }
+    }
+}
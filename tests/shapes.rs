@@ -0,0 +1,32 @@
+//! This is an example of a check that a shader computes the same outputs for every shape of the
+//! dispatch.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+/// This test executes a shader which stages its input in shared memory with three workgroup
+/// sizes.
+#[test]
+fn test_shape_independent() {
+    const NUM_INVOCATIONS: usize = 4096;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32)
+        .map(|i| i.wrapping_mul(2654435761))
+        .collect();
+
+    assert_shape_independent!{
+        shader_path: "tests/shaders/shape_independent.comp",
+        shapes: shapes::covering([NUM_INVOCATIONS as u32, 1, 1],
+                                 &[[32, 1, 1], [64, 1, 1], [256, 1, 1]]),
+        pipeline: |shape| {
+            workgroup_count: shape.workgroup_count,
+            spec_constants: { local_size: u32 = shape.local_size[0] },
+            buffers: {
+                data: [u32] from input_vec,
+                result: [u32; NUM_INVOCATIONS]
+            }
+        },
+        outputs: [result]
+    };
+}
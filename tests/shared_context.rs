@@ -0,0 +1,46 @@
+//! This is an example of shader tests which share their instance, device and queue.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// Runs the example shader on the shared context with `rows` rows of workgroups, and checks
+/// the results.
+fn run_example_shader(rows: u32) {
+    let num_invocations = (rows * 100 * 8 * 8) as usize;
+    let input_vec: Vec<u32> = (0..num_invocations as u32).collect();
+
+    pipeline!{
+        context: shared,
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, rows, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; num_invocations]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
+
+/// This test creates the shared context, if the other test did not create it yet.
+#[test]
+fn test_shared_context_first() {
+    run_example_shader(100);
+}
+
+/// This test reuses the shared context of the other test, if it already ran.
+#[test]
+fn test_shared_context_second() {
+    run_example_shader(10);
+}
@@ -0,0 +1,49 @@
+//! This is an example of a shader test with specialization constants.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+/// This test shows how to set specialization constants, including the workgroup size.
+#[test]
+fn test_with_spec_constants() {
+    const WORKGROUP_SIZE: u32 = 32;
+    const NUM_INVOCATIONS: usize = 32 * 1024;
+    const MULTIPLIER: u32 = 3;
+    const OFFSET: f32 = 0.5;
+
+    let input = (0..NUM_INVOCATIONS as u32).collect::<Vec<_>>();
+
+    // Create the environment. `local_size` and `multiplier` get the ids 0 and 1 from their
+    // position, the ids of `negate` and `offset` are given explicitly.
+    pipeline!{
+        shader_path: "tests/shaders/spec_constants.comp",
+        workgroup_count: [NUM_INVOCATIONS as u32 / WORKGROUP_SIZE, 1, 1],
+        spec_constants: {
+            local_size: u32 = WORKGROUP_SIZE,
+            multiplier: u32 = MULTIPLIER,
+            negate @ constant_id 3: u32 = 1,
+            offset @ constant_id 2: f32 = OFFSET
+        },
+        buffers: {
+            data: [u32] from input,
+            result: [f32;NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+
+        for (invocation_uid, remote_result) in result_buffer.iter().enumerate() {
+            let local_result = -((invocation_uid as u32 * MULTIPLIER) as f32 + OFFSET);
+            assert!((local_result - remote_result).abs() < 0.0001);
+        }
+    }
+}
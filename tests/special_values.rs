@@ -0,0 +1,39 @@
+//! This is an example of a shader test which seeds its input with NaNs, infinities and other
+//! special float values.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use vulkanology::non_finite::Layout;
+use vulkanology::special_values::{inject, Placement};
+
+/// This test checks that an elementwise kernel keeps the non-finite values in the invocations
+/// which read them.
+#[test]
+fn test_special_values_contained() {
+    const NUM_ELEMENTS: usize = 4096;
+
+    let input_vec: Vec<f32> = (0..NUM_ELEMENTS).map(|index| index as f32 * 0.25).collect();
+    let layout = Layout::new([NUM_ELEMENTS as u32 / 64, 1, 1], [64, 1, 1], 1);
+    let input = inject(&input_vec, Placement::Scattered { count: 64, seed: 7 }, Some(layout));
+
+    pipeline!{
+        shader_path: "tests/shaders/special_values.comp",
+        workgroup_count: [NUM_ELEMENTS / 64, 1, 1],
+        buffers: {
+            data: [f32] from input,
+            result: [f32; NUM_ELEMENTS]
+        },
+        execution_command: execute_shader
+    };
+
+    execute_shader();
+
+    let output = read_buffer!(result);
+    input.assert_contained(&output, Some(layout));
+    // Every NaN and infinity of the input reaches the output.
+    for injection in input.injections.iter().filter(|injection| injection.special.is_non_finite()) {
+        assert!(!output[injection.index].is_finite(), "{}", injection);
+    }
+}
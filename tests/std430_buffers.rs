@@ -0,0 +1,65 @@
+//! This is an example of a shader test with a buffer of structs with the `std430` layout.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+use vulkanology::push_constants::Vec3;
+
+std430_buffer_struct!{
+    /// The `Particle` struct of the shader. Rust does not align the vectors to 16 bytes, so
+    /// the padding is explicit.
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    struct Particle {
+        mass: f32,
+        _padding: [f32; 3],
+        position: Vec3<f32>,
+        _padding_1: f32,
+        velocity: Vec3<f32>,
+        id: u32,
+    }
+}
+
+/// This test moves every particle by its velocity, scaled by its mass.
+#[test]
+fn test_std430_buffers() {
+    const NUM_PARTICLES: usize = 1024;
+
+    let particle = |index: usize| {
+        Particle {
+            mass: 2.0,
+            _padding: [0.0; 3],
+            position: Vec3([index as f32, 0.0, 1.0]),
+            _padding_1: 0.0,
+            velocity: Vec3([1.0, 2.0, 3.0]),
+            id: 0,
+        }
+    };
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/std430_buffers.comp",
+        workgroup_count: [NUM_PARTICLES / 64, 1, 1],
+        buffers: {
+            particles: [Particle; NUM_PARTICLES] = |i| particle(i)
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[Particle]> = particles.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        let expected = Particle {
+            position: Vec3([index as f32 + 2.0, 4.0, 7.0]),
+            id: index as u32,
+            ..particle(index)
+        };
+        assert_eq!(*item, expected);
+    }
+}
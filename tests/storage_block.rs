@@ -0,0 +1,50 @@
+//! This is an example of a shader test with a storage buffer holding a single struct.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// The `Counters` block of the shader.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Counters {
+    count: u32,
+    sum: u32,
+    max_value: u32,
+}
+
+/// This test counts, sums and maximizes the input with atomics on the members of a block, which
+/// is bound to a buffer holding a single `Counters`.
+#[test]
+fn test_storage_block() {
+    const NUM_INVOCATIONS: usize = 4096;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).map(|index| index % 1000).collect();
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/storage_block.comp",
+        workgroup_count: [NUM_INVOCATIONS / 64, 1, 1],
+        buffers: {
+            counters: Counters = Counters { count: 0, sum: 0, max_value: 0 },
+            data: [u32] from input_vec
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let expected = Counters {
+        count: NUM_INVOCATIONS as u32,
+        sum: input_vec.iter().sum(),
+        max_value: 999,
+    };
+    let mapping: ReadLock<Counters> = counters.read(Duration::new(1, 0)).unwrap();
+    assert_eq!(*mapping, expected);
+}
@@ -0,0 +1,38 @@
+//! This is an example of a shader test with texel buffers.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test fetches floats from a uniform texel buffer and stores them doubled into a storage
+/// texel buffer of unsigned integers.
+#[test]
+fn test_texel_buffers() {
+    const LEN: usize = 1024;
+
+    let weights_vec: Vec<f32> = (0..LEN).map(|index| index as f32).collect();
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/texel_buffers.comp",
+        workgroup_count: [LEN / 64, 1, 1],
+        buffers: {
+            weights: uniform_texel [R32Sfloat] from weights_vec,
+            result: storage_texel [R32Uint; LEN]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // The pixels of the storage texel buffer are read like the elements of a buffer.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, index as u32 * 2);
+    }
+}
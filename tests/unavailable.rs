@@ -0,0 +1,69 @@
+//! These are examples of shader tests which are skipped on machines without Vulkan.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test runs the example shader if a Vulkan implementation is available, and otherwise
+/// returns after noting on stderr that it was skipped.
+#[test]
+fn test_skip_unavailable() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    // Create the environment.
+    pipeline!{
+        unavailable: skip,
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
+
+/// This test guards the example shader with `require_gpu!` instead.
+#[test]
+fn test_require_gpu() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    require_gpu!();
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    // Create the environment.
+    pipeline!{
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
@@ -0,0 +1,71 @@
+//! This is an example of a shader test with uniform buffers.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::WriteLock;
+
+/// The `Parameters` block of the shader in the `std140` layout.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Parameters {
+    scale: [f32; 4],
+    offset: f32,
+    count: u32,
+}
+
+/// This test reads one uniform buffer initialized by `pipeline!` and one written by the test.
+#[test]
+fn test_uniforms() {
+    const NUM_INVOCATIONS: usize = 64 * 1024;
+    const COUNT: u32 = 60000;
+    const WEIGHTS: [f32; 4] = [0.0, 0.25, 0.5, 0.75];
+
+    let parameters_value = Parameters {
+        scale: [1.0, 2.0, 3.0, 4.0],
+        offset: 0.5,
+        count: COUNT,
+    };
+
+    // Create the environment. `parameters` is bound after `result`, `weights` is bound to the
+    // set and binding declared in the shader.
+    pipeline!{
+        shader_path: "tests/shaders/uniforms.comp",
+        workgroup_count: [NUM_INVOCATIONS / 64, 1, 1],
+        buffers: {
+            result: [f32;NUM_INVOCATIONS]
+        },
+        uniforms: {
+            parameters: Parameters = parameters_value,
+            weights @ set 1, binding 0: [[f32; 4]; 4]
+        },
+        execution_command: execute_shader
+    };
+
+    // Fill the uniform buffer which was not initialized by `pipeline!`. Only the first component
+    // of every `vec4` is read by the shader.
+    {
+        let mut mapping: WriteLock<[[f32; 4]; 4]> = weights.write(Duration::new(1, 0)).unwrap();
+        for (weight, &value) in mapping.iter_mut().zip(WEIGHTS.iter()) {
+            *weight = [value, 0.0, 0.0, 0.0];
+        }
+    }
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    {
+        let parameters = parameters.read(Duration::new(1, 0)).unwrap();
+        let result_buffer = result.read(Duration::new(1, 0)).unwrap();
+
+        for (id, remote_result) in result_buffer.iter().enumerate().take(COUNT as usize) {
+            let local_result = parameters.scale[id % 4] * id as f32 + parameters.offset +
+                               WEIGHTS[id % 4];
+            assert!((local_result - remote_result).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//! This is an example of a shader test which fails if the validation layers report a message.
+//! It requires `VK_LAYER_KHRONOS_validation` to be installed.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::ReadLock;
+
+/// This test runs the example shader with strict validation, which panics at the end of the
+/// test if the layer reported an error or a warning about the pipeline.
+#[test]
+fn test_strict_validation() {
+    const NUM_INVOCATIONS: usize = 640000;
+
+    let input_vec: Vec<u32> = (0..NUM_INVOCATIONS as u32).collect();
+
+    // Create the environment.
+    pipeline!{
+        validation: strict,
+        shader_path: "tests/shaders/example.comp",
+        workgroup_count: [100, 100, 1],
+        buffers: {
+            data: [u32] from input_vec,
+            result: [u32; NUM_INVOCATIONS]
+        },
+        execution_command: execute_shader
+    };
+
+    // Execute the shader
+    execute_shader();
+
+    // Assert the validity of the results.
+    let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+    for (index, item) in mapping.iter().enumerate() {
+        assert_eq!(*item, (index as u32).wrapping_mul(index as u32));
+    }
+}
+
+/// The log names the objects after their handles in the reported messages.
+#[test]
+#[should_panic(expected = "the buffer 0x2a (`result`) is bound to 0x2ab.")]
+fn test_named_objects() {
+    use vulkanology::validation::ValidationLog;
+
+    let mut log = ValidationLog::new("tests/shaders/example.comp");
+    log.name(0x2a, "result");
+    log.sink().record("Validation", "the buffer 0x2a is bound to 0x2ab.");
+}
@@ -0,0 +1,46 @@
+//! This is an example of a shader test which dispatches a pipeline with different workgroup
+//! counts.
+
+extern crate vulkano;
+#[macro_use]
+extern crate vulkanology;
+
+use std::time::Duration;
+
+use vulkano::buffer::cpu_access::{ReadLock, WriteLock};
+
+/// This test dispatches the shader with a growing number of workgroups, each of which writes
+/// the workgroup count into its part of the buffer.
+#[test]
+fn test_runtime_workgroup_count() {
+    const MAX_WORKGROUPS: usize = 8;
+
+    // Create the environment. `workgroup_count` is the default, which is not used here.
+    pipeline!{
+        shader_path: "tests/shaders/workgroup_count.comp",
+        workgroup_count: [MAX_WORKGROUPS, 1, 1],
+        buffers: {
+            result: [u32; MAX_WORKGROUPS * 64]
+        },
+        execution_command: execute_shader(workgroup_count)
+    };
+
+    for workgroups in 1..MAX_WORKGROUPS + 1 {
+        {
+            let mut mapping: WriteLock<[u32]> = result.write(Duration::new(1, 0)).unwrap();
+            for item in mapping.iter_mut() {
+                *item = 0;
+            }
+        }
+
+        // Execute the shader
+        execute_shader([workgroups as u32, 1, 1]);
+
+        // Only the invocations of the dispatched workgroups wrote to the buffer.
+        let mapping: ReadLock<[u32]> = result.read(Duration::new(1, 0)).unwrap();
+        for (index, item) in mapping.iter().enumerate() {
+            let expected = if index < workgroups * 64 { workgroups as u32 } else { 0 };
+            assert_eq!(*item, expected);
+        }
+    }
+}